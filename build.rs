@@ -0,0 +1,53 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Writes the table of embedded assets `asset_loading` includes: every
+/// file under `assets/` by its path from the crate root, when the
+/// `embedded-assets` feature is on, and an empty table otherwise.
+fn main() {
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("embedded_assets.rs");
+
+    let mut files = Vec::new();
+    if env::var_os("CARGO_FEATURE_EMBEDDED_ASSETS").is_some() {
+        println!("cargo:rerun-if-changed=assets");
+        collect(&root.join("assets"), &mut files);
+    } else {
+        println!("cargo:rerun-if-changed=build.rs");
+    }
+    let mut keyed: Vec<(String, PathBuf)> = files
+        .into_iter()
+        .map(|file| {
+            let key = file.strip_prefix(&root).unwrap().to_string_lossy();
+            (key.replace('\\', "/"), file)
+        })
+        .collect();
+    // Sorted by key, so lookups can binary search.
+    keyed.sort();
+
+    let mut table = String::from("&[\n");
+    for (key, file) in keyed {
+        table.push_str(&format!(
+            "    ({key:?}, include_bytes!({:?})),\n",
+            file.to_string_lossy()
+        ));
+    }
+    table.push(']');
+
+    fs::write(out, table).unwrap();
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match path.is_dir() {
+            true => collect(&path, files),
+            false => files.push(path),
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use macroquad::{
     color::WHITE,
-    math::{Rect, Vec2},
+    math::{vec2, Rect, Vec2},
     texture::{draw_texture_ex, DrawTextureParams},
 };
 
-use crate::sprites::Sprite;
+use crate::{
+    flash::FlashTimer,
+    shaders::draw_flashing,
+    sprites::{Sprite, SpriteFrameSpan},
+};
 
 enum Direction {
     Up,
@@ -13,9 +17,64 @@ enum Direction {
     Right,
 }
 
+impl Direction {
+    /// The direction a movement delta reads as. Horizontal wins ties.
+    fn from_delta(delta: Vec2) -> Option<Self> {
+        if delta == Vec2::ZERO {
+            return None;
+        }
+
+        let delta_abs = delta.abs();
+        return Some(match delta_abs.x >= delta_abs.y {
+            true => match delta.x > 0.0 {
+                true => Direction::Right,
+                false => Direction::Left,
+            },
+            false => match delta.y > 0.0 {
+                true => Direction::Down,
+                false => Direction::Up,
+            },
+        });
+    }
+
+    fn vector(&self) -> Vec2 {
+        match self {
+            Direction::Up => vec2(0.0, -1.0),
+            Direction::Down => vec2(0.0, 1.0),
+            Direction::Left => vec2(-1.0, 0.0),
+            Direction::Right => vec2(1.0, 0.0),
+        }
+    }
+}
+
+/// Unit vector a body moving by `delta` would face, zero if not moving.
+pub fn facing_for(delta: Vec2) -> Vec2 {
+    return Direction::from_delta(delta).map_or(Vec2::ZERO, |dir| dir.vector());
+}
+
+/// Which set of frames the owner wants shown. Anything the sprite has no
+/// span for falls back to the directional walk frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Animation {
+    Walk,
+    Attack,
+    Swim,
+    Hit,
+    Death,
+    /// An ambusher passing for scenery.
+    Disguise,
+    /// An ambusher dropping its disguise. Plays once.
+    Reveal,
+}
+
 pub struct Animator {
     direction: Direction,
     time_moving: f32,
+    animation: Animation,
+    animation_time: f32,
+    last_frame: usize,
+    stepped: bool,
+    flash: FlashTimer,
     sprite: Sprite,
 }
 
@@ -24,66 +83,173 @@ impl Animator {
         Self {
             direction: Direction::Down,
             time_moving: 0.0,
+            animation: Animation::Walk,
+            animation_time: 0.0,
+            last_frame: 0,
+            stepped: false,
+            flash: FlashTimer::new(),
             sprite,
         }
     }
 
+    pub fn set_animation(&mut self, animation: Animation) {
+        if self.animation != animation {
+            self.animation = animation;
+            self.animation_time = 0.0;
+        }
+    }
+
+    pub fn set_sprite(&mut self, sprite: Sprite) {
+        self.sprite = sprite;
+    }
+
+    /// Draws the sprite solid white for the next `seconds`.
+    pub fn flash(&mut self, seconds: f32) {
+        self.flash.start(seconds);
+    }
+
+    pub fn facing(&self) -> Vec2 {
+        self.direction.vector()
+    }
+
+    /// Turns toward `delta` without moving or advancing the walk cycle.
+    pub fn face(&mut self, delta: Vec2) {
+        if let Some(direction) = Direction::from_delta(delta) {
+            self.direction = direction;
+        }
+    }
+
     pub fn apply_delta(&mut self, delta: Vec2, dt: f32) {
-        if delta.x != 0.0 || delta.y != 0.0 {
-            let delta_abs = delta.abs();
-            if delta_abs.x >= delta_abs.y {
-                self.direction = match delta.x > 0.0 {
-                    true => Direction::Right,
-                    false => Direction::Left,
-                }
-            } else if delta_abs.y > delta_abs.x {
-                self.direction = match delta.y > 0.0 {
-                    true => Direction::Down,
-                    false => Direction::Up,
-                }
-            }
+        self.animation_time += dt;
+        self.flash.update(dt);
 
+        if let Some(direction) = Direction::from_delta(delta) {
+            self.direction = direction;
             self.time_moving += dt;
         } else {
             self.time_moving = 0.0;
         }
+
+        let frame = self.current_frame();
+        if frame != self.last_frame && self.time_moving > 0.0 && self.animation == Animation::Walk {
+            let (span, _, _) = self.current_span();
+            let half_cycle = (span.number_of_frames / 2).max(1);
+            if (frame - span.start_frame).is_multiple_of(half_cycle) {
+                self.stepped = true;
+            }
+        }
+        self.last_frame = frame;
+    }
+
+    /// Whether a foot touched the ground since the last call. Steps land on
+    /// the first and middle frames of the walk cycle.
+    pub fn take_step(&mut self) -> bool {
+        std::mem::take(&mut self.stepped)
     }
 
-    pub fn render(&self, r#box: &Rect) {
-        let (frame_span, flip_x) = match self.direction {
-            Direction::Up => (&self.sprite.up, false),
-            Direction::Down => (&self.sprite.down, false),
-            Direction::Left => (&self.sprite.side, true),
-            Direction::Right => (&self.sprite.side, false),
+    /// The span to show, the time to play it at, and whether it loops.
+    fn current_span(&self) -> (&SpriteFrameSpan, f32, bool) {
+        let special = match self.animation {
+            Animation::Walk => None,
+            Animation::Attack => self
+                .sprite
+                .attack
+                .as_ref()
+                .map(|s| (s, self.animation_time, true)),
+            Animation::Swim => self
+                .sprite
+                .swim
+                .as_ref()
+                .map(|s| (s, self.time_moving, true)),
+            Animation::Hit => self
+                .sprite
+                .hit
+                .as_ref()
+                .map(|s| (s, self.animation_time, true)),
+            Animation::Death => self
+                .sprite
+                .death
+                .as_ref()
+                .map(|s| (s, self.animation_time, false)),
+            Animation::Disguise => self
+                .sprite
+                .disguise
+                .as_ref()
+                .map(|s| (s, self.animation_time, true)),
+            Animation::Reveal => self
+                .sprite
+                .reveal
+                .as_ref()
+                .map(|s| (s, self.animation_time, false)),
         };
 
-        let frame;
-        if self.time_moving > 0.0 || frame_span.duration_seconds == 0.0 {
-            let prog = self.time_moving / frame_span.duration_seconds;
-            frame = frame_span.start_frame
+        return match special {
+            Some(span) => span,
+            None => match self.direction {
+                Direction::Up => (&self.sprite.up, self.time_moving, true),
+                Direction::Down => (&self.sprite.down, self.time_moving, true),
+                Direction::Left | Direction::Right => (&self.sprite.side, self.time_moving, true),
+            },
+        };
+    }
+
+    /// Whether a one-shot animation has played through. Looping animations
+    /// never hold anything up, so they always count as finished.
+    pub fn is_finished(&self) -> bool {
+        let (span, time, looping) = self.current_span();
+        return looping || time >= span.duration_seconds;
+    }
+
+    fn current_frame(&self) -> usize {
+        let (frame_span, time, looping) = self.current_span();
+
+        if !looping {
+            let prog = time / frame_span.duration_seconds;
+            let frame = (prog * frame_span.number_of_frames as f32).floor() as usize;
+            return frame_span.start_frame + frame.min(frame_span.number_of_frames - 1);
+        }
+
+        return if time > 0.0 || frame_span.duration_seconds == 0.0 {
+            let prog = time / frame_span.duration_seconds;
+            frame_span.start_frame
                 + (prog * frame_span.number_of_frames as f32 + 1.0).floor() as usize
-                    % frame_span.number_of_frames;
+                    % frame_span.number_of_frames
         } else {
-            frame = frame_span.start_frame;
-        }
+            frame_span.start_frame
+        };
+    }
 
-        let frame = self.sprite.frames[frame];
-
-        draw_texture_ex(
-            &self.sprite.tex,
-            r#box.center().x - self.sprite.frame_w / 2.0,
-            r#box.bottom() - self.sprite.frame_h,
-            WHITE,
-            DrawTextureParams {
-                source: Some(Rect::new(
-                    frame.0,
-                    frame.1,
-                    self.sprite.frame_w,
-                    self.sprite.frame_h,
-                )),
-                flip_x,
-                ..Default::default()
-            },
-        );
+    /// Draws the current frame standing on the bottom of `box`, lifted by `z`.
+    pub fn render(&self, r#box: &Rect, z: f32) {
+        let flip_x = matches!(self.direction, Direction::Left);
+        let frame = self.sprite.frames[self.current_frame()];
+        let bob = match self.animation {
+            Animation::Swim => (self.animation_time * 4.0).sin() * 1.5,
+            _ => 0.0,
+        };
+
+        let draw = || {
+            draw_texture_ex(
+                &self.sprite.tex,
+                r#box.center().x - self.sprite.frame_w / 2.0,
+                r#box.bottom() - self.sprite.frame_h + bob - z,
+                WHITE,
+                DrawTextureParams {
+                    source: Some(Rect::new(
+                        frame.0,
+                        frame.1,
+                        self.sprite.frame_w,
+                        self.sprite.frame_h,
+                    )),
+                    flip_x,
+                    ..Default::default()
+                },
+            );
+        };
+
+        match self.flash.is_active() {
+            true => draw_flashing(WHITE, draw),
+            false => draw(),
+        }
     }
 }
@@ -0,0 +1,249 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    asset_loading::{check_saving, meta_path, serialize, AssetManageError, AssetManageResult},
+    sprites::{SpriteFrameSpan, SpriteSerializable},
+};
+
+/// A sheet exported from Aseprite as JSON, with only what the importer
+/// reads.
+#[derive(Deserialize)]
+struct AsepriteSheet {
+    /// An array in Aseprite's array layout, or an object in its hash one.
+    frames: Value,
+    meta: AsepriteMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: String,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    #[serde(default)]
+    rotated: bool,
+    /// Milliseconds the frame shows for.
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+struct AsepriteRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+}
+
+/// An Aseprite sheet brought over as sprite meta.
+pub struct AsepriteImport {
+    pub sprite: SpriteSerializable,
+    /// The image exported with the sheet, relative to the sheet.
+    pub image: String,
+}
+
+/// Reads Aseprite's JSON export `json`, from `file`, as sprite meta. Tags
+/// named after the sprite's animations, like `up` or `attack`, become
+/// their frame spans, lasting as long as their frames add up to. Other
+/// tags are ignored. Every tagged frame must be the same size.
+pub fn import_aseprite(json: &[u8], file: &str) -> AssetManageResult<AsepriteImport> {
+    let sheet: AsepriteSheet = serde_json::from_slice(json)?;
+    if sheet.frames.is_object() {
+        return Err(AssetManageError::Unsupported {
+            file: file.to_owned(),
+            features: vec!["the hash layout, export it as an array".to_owned()],
+        });
+    }
+    let frames: Vec<AsepriteFrame> = serde_json::from_value(sheet.frames)?;
+    if frames.iter().any(|frame| frame.rotated) {
+        return Err(AssetManageError::Unsupported {
+            file: file.to_owned(),
+            features: vec!["rotated frames".to_owned()],
+        });
+    }
+
+    let mut sprite = SpriteSerializable {
+        frames: frames
+            .iter()
+            .map(|frame| (frame.frame.x, frame.frame.y))
+            .collect(),
+        ..Default::default()
+    };
+
+    // The tag the frame size was taken from, to name in a mismatch.
+    let mut sized_by: Option<&str> = None;
+    let (mut has_up, mut has_down, mut has_side) = (false, false, false);
+    for tag in &sheet.meta.frame_tags {
+        let Some(tagged) = frames.get(tag.from..=tag.to) else {
+            return Err(AssetManageError::corrupt(
+                file,
+                &format!("tag {:?} runs past the last frame", tag.name),
+            ));
+        };
+
+        let span = match tag.name.as_str() {
+            "up" => {
+                has_up = true;
+                &mut sprite.up
+            }
+            "down" => {
+                has_down = true;
+                &mut sprite.down
+            }
+            "side" => {
+                has_side = true;
+                &mut sprite.side
+            }
+            "attack" => sprite.attack.insert(SpriteFrameSpan::default()),
+            "swim" => sprite.swim.insert(SpriteFrameSpan::default()),
+            "hit" => sprite.hit.insert(SpriteFrameSpan::default()),
+            "death" => sprite.death.insert(SpriteFrameSpan::default()),
+            "disguise" => sprite.disguise.insert(SpriteFrameSpan::default()),
+            "reveal" => sprite.reveal.insert(SpriteFrameSpan::default()),
+            _ => continue,
+        };
+        span.start_frame = tag.from;
+        span.number_of_frames = tagged.len();
+        span.duration_seconds =
+            tagged.iter().map(|frame| frame.duration).sum::<u32>() as f32 / 1000.0;
+
+        for frame in tagged {
+            let size = (frame.frame.w, frame.frame.h);
+            match sized_by {
+                None => {
+                    (sprite.frame_w, sprite.frame_h) = size;
+                    sized_by = Some(&tag.name);
+                }
+                Some(other) if size != (sprite.frame_w, sprite.frame_h) => {
+                    return Err(AssetManageError::corrupt(
+                        file,
+                        &format!(
+                            "tag {:?} has {}x{} frames but tag {:?} has {}x{}, export without trimming",
+                            tag.name, size.0, size.1, other, sprite.frame_w, sprite.frame_h
+                        ),
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    let missing: Vec<&str> = [("up", has_up), ("down", has_down), ("side", has_side)]
+        .into_iter()
+        .filter(|(_, has)| !has)
+        .map(|(name, _)| name)
+        .collect();
+    if !missing.is_empty() {
+        return Err(AssetManageError::Missing(format!(
+            "tags {} in {file}",
+            missing.join(", ")
+        )));
+    }
+
+    return Ok(AsepriteImport {
+        sprite,
+        image: sheet.meta.image,
+    });
+}
+
+/// Imports the Aseprite export at `path` and writes it as the meta of the
+/// image it was exported with. Returns where the meta was written.
+pub fn import_aseprite_file(path: &str) -> AssetManageResult<String> {
+    check_saving()?;
+    let import = import_aseprite(&std::fs::read(path)?, path)?;
+
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let meta = meta_path(&dir.join(import.image).to_string_lossy());
+    serialize(&import.sprite, &meta)?;
+    return Ok(meta);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_loading::{deserialize, tests::scratch_dir};
+
+    fn import(name: &str) -> AssetManageResult<AsepriteImport> {
+        let path = format!("tests/fixtures/{name}");
+        return import_aseprite(&std::fs::read(&path).unwrap(), &path);
+    }
+
+    #[test]
+    fn tags_become_frame_spans() {
+        let import = import("aseprite_sheet.json").unwrap();
+        assert_eq!(import.image, "player.png");
+
+        let sprite = import.sprite;
+        assert_eq!(sprite.frames.len(), 7);
+        assert_eq!(sprite.frames[3], (48.0, 0.0));
+        assert_eq!((sprite.frame_w, sprite.frame_h), (16.0, 24.0));
+
+        let spans = [&sprite.up, &sprite.down, &sprite.side];
+        for (span, start) in spans.into_iter().zip([0, 2, 4]) {
+            assert_eq!((span.start_frame, span.number_of_frames), (start, 2));
+            assert_eq!(span.duration_seconds, 0.25);
+        }
+        let attack = sprite.attack.unwrap();
+        assert_eq!((attack.start_frame, attack.number_of_frames), (6, 1));
+        assert_eq!(attack.duration_seconds, 0.1);
+        assert!(sprite.swim.is_none());
+    }
+
+    #[test]
+    fn mismatched_frame_sizes_name_both_tags() {
+        let err = import("aseprite_mismatch.json").err().unwrap();
+        let AssetManageError::Corrupt { reason, .. } = err else {
+            panic!("expected a corrupt sheet, got {err}");
+        };
+        assert_eq!(
+            reason,
+            "tag \"side\" has 16x16 frames but tag \"up\" has 16x24, export without trimming"
+        );
+    }
+
+    #[test]
+    fn broken_sheets_are_refused() {
+        let err = import("aseprite_hash.json").err().unwrap();
+        assert!(matches!(err, AssetManageError::Unsupported { .. }), "{err}");
+
+        let err = import("aseprite_missing_tag.json").err().unwrap();
+        assert!(matches!(err, AssetManageError::Missing(_)), "{err}");
+        assert!(err.to_string().contains("tags side in"));
+
+        let err = import("aseprite_past_end.json").err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("tag \"down\" runs past the last frame"));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "embedded-assets",
+        ignore = "saving is refused with the assets built in"
+    )]
+    fn writes_the_meta_beside_the_image() {
+        let dir = scratch_dir("aseprite_import");
+        let sheet = dir.join("player.json");
+        std::fs::copy("tests/fixtures/aseprite_sheet.json", &sheet).unwrap();
+
+        let meta = import_aseprite_file(&sheet.to_string_lossy()).unwrap();
+        assert_eq!(meta, meta_path(&dir.join("player.png").to_string_lossy()));
+        let written: SpriteSerializable = deserialize(&meta).unwrap();
+        assert_eq!(written.side.start_frame, 4);
+        assert_eq!(written.frames.len(), 7);
+    }
+}
@@ -1,13 +1,77 @@
-use std::{fmt::Display, path::Path};
+use std::{
+    borrow::Cow,
+    cell::{OnceCell, RefCell},
+    collections::BTreeMap,
+    fmt::Display,
+    io::{Read, Write},
+    path::Path,
+};
 
-use macroquad::texture::{load_texture, Texture2D};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use macroquad::{
+    color::{BLACK, MAGENTA},
+    logging::{info, warn},
+    texture::{load_texture, FilterMode, Image, Texture2D},
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::asset_root::AssetRoot;
 
 #[derive(Debug)]
 pub enum AssetManageError {
     Macro(macroquad::Error),
     Serde(serde_json::Error),
     Io(std::io::Error),
+    /// An asset loaded but is missing an entry the game needs.
+    Missing(String),
+    /// A file is there but can't be read as what it should hold.
+    Corrupt {
+        file: String,
+        reason: String,
+    },
+    /// A file uses features the game can't handle.
+    Unsupported {
+        file: String,
+        features: Vec<String>,
+    },
+    /// A file was written by a newer version of the game.
+    TooNew {
+        file: String,
+        version: u32,
+        supported: u32,
+    },
+    /// The build can't write files back, like web builds.
+    ReadOnly,
+    /// A gzipped file couldn't be unzipped, before its contents were even
+    /// read.
+    Decompress {
+        file: String,
+        err: std::io::Error,
+    },
+    /// Another error, with what was being done when it happened.
+    Context {
+        what: String,
+        err: Box<AssetManageError>,
+    },
+}
+
+impl AssetManageError {
+    pub fn corrupt(file: &str, reason: &str) -> Self {
+        return Self::Corrupt {
+            file: file.to_owned(),
+            reason: reason.to_owned(),
+        };
+    }
+
+    /// Wraps the error with `what` was being done, to show the player.
+    pub fn context(self, what: String) -> Self {
+        return Self::Context {
+            what,
+            err: Box::new(self),
+        };
+    }
 }
 
 impl Display for AssetManageError {
@@ -16,6 +80,28 @@ impl Display for AssetManageError {
             AssetManageError::Macro(error) => write!(f, "{error}"),
             AssetManageError::Serde(error) => write!(f, "{error}"),
             AssetManageError::Io(error) => write!(f, "{error}"),
+            AssetManageError::Missing(what) => write!(f, "missing {what}"),
+            AssetManageError::Corrupt { file, reason } => write!(f, "{file} is corrupt: {reason}"),
+            AssetManageError::Unsupported { file, features } => {
+                write!(
+                    f,
+                    "{file} uses what the game can't handle: {}",
+                    features.join(", ")
+                )
+            }
+            AssetManageError::TooNew {
+                file,
+                version,
+                supported,
+            } => write!(
+                f,
+                "{file} is version {version}, but this game only reads up to version {supported}"
+            ),
+            AssetManageError::ReadOnly => write!(f, "saving not available in web builds"),
+            AssetManageError::Decompress { file, err } => {
+                write!(f, "{file} could not be unzipped: {err}")
+            }
+            AssetManageError::Context { what, err } => write!(f, "{what}: {err}"),
         }
     }
 }
@@ -40,12 +126,73 @@ impl From<std::io::Error> for AssetManageError {
 
 pub type AssetManageResult<T> = Result<T, AssetManageError>;
 
+/// Every file under `assets/`, by its path, in builds with the assets
+/// embedded. Sorted by path.
+#[cfg(feature = "embedded-assets")]
+static EMBEDDED: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+#[cfg(feature = "embedded-assets")]
+fn embedded(path: &Path) -> Option<&'static [u8]> {
+    let path = path.to_string_lossy();
+    let path = path.trim_start_matches("./");
+    return EMBEDDED
+        .binary_search_by_key(&path, |(file, _)| file)
+        .ok()
+        .map(|idx| EMBEDDED[idx].1);
+}
+
+#[cfg(not(feature = "embedded-assets"))]
+fn embedded(_path: &Path) -> Option<&'static [u8]> {
+    return None;
+}
+
+/// The bytes of the asset at `path`, from the ones built in if there are
+/// any, otherwise from disk.
+pub fn read_asset<P: AsRef<Path>>(path: P) -> AssetManageResult<Cow<'static, [u8]>> {
+    let path = path.as_ref();
+    return match embedded(path) {
+        Some(bytes) => Ok(Cow::Borrowed(bytes)),
+        None => Ok(Cow::Owned(std::fs::read(AssetRoot::resolve(path))?)),
+    };
+}
+
+pub fn asset_exists<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+    return embedded(path).is_some() || AssetRoot::resolve(path).exists();
+}
+
+/// Fails in builds that can't write assets back, where files the editor
+/// saved would be shadowed by the built-in ones or couldn't be written at
+/// all.
+pub fn check_saving() -> AssetManageResult<()> {
+    return match cfg!(any(feature = "embedded-assets", target_arch = "wasm32")) {
+        true => Err(AssetManageError::ReadOnly),
+        false => Ok(()),
+    };
+}
+
+/// Whether the file at `path` is gzipped, told by a `.gz` extension.
+pub fn is_gzipped(path: &Path) -> bool {
+    return path.extension().is_some_and(|ext| ext == "gz");
+}
+
+/// Writes `obj` to `path` as JSON, gzipped if `path` ends in `.gz` and
+/// pretty-printed otherwise.
 pub fn serialize<T, P>(obj: &T, path: P) -> AssetManageResult<()>
 where
     T: Serialize,
     P: AsRef<Path>,
 {
-    std::fs::write(path, serde_json::to_string_pretty(obj)?)?;
+    let path = path.as_ref();
+    let bytes = match is_gzipped(path) {
+        true => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&serde_json::to_vec(obj)?)?;
+            encoder.finish()?
+        }
+        false => serde_json::to_vec_pretty(obj)?,
+    };
+    std::fs::write(AssetRoot::resolve(path), bytes)?;
     return Ok(());
 }
 
@@ -54,7 +201,156 @@ where
     T: for<'de> Deserialize<'de>,
     P: AsRef<Path>,
 {
-    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    Ok(serde_json::from_slice(&read_json(path.as_ref())?)?)
+}
+
+/// The JSON in the file at `path`, unzipped if it's gzipped.
+fn read_json(path: &Path) -> AssetManageResult<Cow<'static, [u8]>> {
+    let bytes = read_asset(path)?;
+    if !is_gzipped(path) {
+        return Ok(bytes);
+    }
+
+    let mut json = Vec::new();
+    return match GzDecoder::new(&bytes[..]).read_to_end(&mut json) {
+        Ok(_) => Ok(Cow::Owned(json)),
+        Err(err) => Err(AssetManageError::Decompress {
+            file: path.to_string_lossy().into_owned(),
+            err,
+        }),
+    };
+}
+
+/// A file format that has changed over time. Files record the version they
+/// were written at and older ones are upgraded a version at a time before
+/// they're read, so old assets keep loading as fields are added.
+pub trait Versioned: for<'de> Deserialize<'de> {
+    /// The version this code reads and writes.
+    const VERSION: u32;
+
+    /// Upgrades `doc` from version `from` to the one after it.
+    fn migrate(from: u32, doc: &mut Value) -> AssetManageResult<()>;
+}
+
+/// Reads the file at `path` as `T`, upgrading it from whatever version it
+/// was written at.
+pub fn deserialize_versioned<T, P>(path: P) -> AssetManageResult<T>
+where
+    T: Versioned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let doc = serde_json::from_slice(&read_json(path)?)?;
+    return upgrade(doc, &path.to_string_lossy());
+}
+
+/// Upgrades `doc`, read from `file`, to `T`'s version and reads it. Files
+/// from before versions were recorded are version 0.
+fn upgrade<T: Versioned>(mut doc: Value, file: &str) -> AssetManageResult<T> {
+    let version = match doc.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| AssetManageError::corrupt(file, "its version isn't a whole number"))?,
+    };
+    if version > T::VERSION {
+        return Err(AssetManageError::TooNew {
+            file: file.to_owned(),
+            version,
+            supported: T::VERSION,
+        });
+    }
+
+    for from in version..T::VERSION {
+        T::migrate(from, &mut doc)?;
+    }
+    if let Some(fields) = doc.as_object_mut() {
+        fields.insert("version".to_owned(), T::VERSION.into());
+    }
+
+    return Ok(serde_json::from_value(doc)?);
+}
+
+/// Sets `field` of the object `doc` to `value` unless it's already there,
+/// for migrations adding a field.
+pub fn fill_field<T: Serialize>(doc: &mut Value, field: &str, value: T) -> AssetManageResult<()> {
+    if let Some(fields) = doc.as_object_mut() {
+        if !fields.contains_key(field) {
+            fields.insert(field.to_owned(), serde_json::to_value(value)?);
+        }
+    }
+    return Ok(());
+}
+
+thread_local! {
+    static PLACEHOLDER: OnceCell<Texture2D> = const { OnceCell::new() };
+    /// What's wrong with each asset standing in as a placeholder, by path.
+    static PROBLEMS: RefCell<BTreeMap<String, String>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// A magenta and black checkerboard a tile across, drawn in place of
+/// missing textures so they stand out.
+pub fn placeholder_tex() -> Texture2D {
+    return PLACEHOLDER.with(|cell| {
+        cell.get_or_init(|| {
+            let mut image = Image::gen_image_color(16, 16, BLACK);
+            for y in 0..16 {
+                for x in 0..16 {
+                    if (x / 8 + y / 8) % 2 == 0 {
+                        image.set_pixel(x, y, MAGENTA);
+                    }
+                }
+            }
+            let tex = Texture2D::from_image(&image);
+            tex.set_filter(FilterMode::Nearest);
+            tex
+        })
+        .clone()
+    });
+}
+
+/// The first of `files` that isn't there, for loaders that stand in a
+/// placeholder rather than fail.
+pub fn first_missing<'a>(files: &[&'a str]) -> Option<&'a str> {
+    return files.iter().copied().find(|file| !asset_exists(file));
+}
+
+/// Records that the asset at `path` is a placeholder, and why.
+pub fn report_problem(path: &str, problem: String) {
+    warn!("Using a placeholder for {}: {}", path, problem);
+    PROBLEMS.with(|problems| problems.borrow_mut().insert(path.to_owned(), problem));
+}
+
+/// Forgets the problem with the asset at `path`, once it loads.
+pub fn clear_problem(path: &str) {
+    PROBLEMS.with(|problems| problems.borrow_mut().remove(path));
+}
+
+/// The assets standing in as placeholders, each with what's wrong.
+pub fn asset_problems() -> Vec<String> {
+    return PROBLEMS.with(|problems| {
+        problems
+            .borrow()
+            .iter()
+            .map(|(path, problem)| format!("{path}: {problem}"))
+            .collect()
+    });
+}
+
+/// Loads a texture for pixel art, with no smoothing when scaled.
+pub async fn load_tex(path: &str) -> AssetManageResult<Texture2D> {
+    let tex = match embedded(Path::new(path)) {
+        Some(bytes) => Texture2D::from_file_with_format(bytes, None),
+        None => load_texture(&AssetRoot::resolve(path).to_string_lossy()).await?,
+    };
+    tex.set_filter(macroquad::texture::FilterMode::Nearest);
+    return Ok(tex);
+}
+
+/// Where the meta for the texture at `path` is kept.
+pub fn meta_path(path: &str) -> String {
+    return format!("{path}.meta.json");
 }
 
 pub async fn load_tex_with_meta<T, P>(path: P) -> AssetManageResult<(T, Texture2D)>
@@ -63,10 +359,122 @@ where
     P: AsRef<Path>,
 {
     let path = &path.as_ref().to_string_lossy();
-    let tex = load_texture(path).await?;
-    tex.set_filter(macroquad::texture::FilterMode::Nearest);
+    let tex = load_tex(path).await?;
 
-    let meta = deserialize(format!("{path}.meta.json"))?;
+    let meta = deserialize(meta_path(path))?;
 
     return Ok((meta, tex));
 }
+
+/// Writes `T::default()` as the meta of the texture at `path` if the
+/// texture is there without one, so a new image opens without a stub
+/// written by hand. Returns whether it wrote one. Does nothing where
+/// assets can't be saved.
+pub fn create_default_meta<T: Serialize + Default>(path: &str) -> AssetManageResult<bool> {
+    let meta = meta_path(path);
+    if check_saving().is_err() || !asset_exists(path) || asset_exists(&meta) {
+        return Ok(false);
+    }
+
+    serialize(&T::default(), &meta)?;
+    info!("Wrote a default meta for {}", path);
+    return Ok(true);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use macroquad::miniquad::{RawId, TextureId};
+
+    use super::*;
+
+    /// A texture for tests to hand things that want one. Tests have no
+    /// window, so it's never uploaded and must never be drawn or measured.
+    pub fn stand_in_tex() -> Texture2D {
+        return Texture2D::from_miniquad_texture(TextureId::from_raw_id(RawId::OpenGl(0)));
+    }
+
+    #[test]
+    fn saving_is_refused_only_with_the_assets_built_in() {
+        let refused = matches!(check_saving(), Err(AssetManageError::ReadOnly));
+        assert_eq!(refused, cfg!(feature = "embedded-assets"));
+    }
+
+    #[test]
+    fn every_error_says_what_went_wrong() {
+        let io = || std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        let errors = [
+            AssetManageError::Serde(serde_json::from_str::<u32>("x").unwrap_err()),
+            AssetManageError::Io(io()),
+            AssetManageError::Missing("a sheet".to_owned()),
+            AssetManageError::corrupt("a.bin", "it's short"),
+            AssetManageError::Unsupported {
+                file: "a.tmx".to_owned(),
+                features: vec!["XML".to_owned()],
+            },
+            AssetManageError::TooNew {
+                file: "a.json".to_owned(),
+                version: 9,
+                supported: 1,
+            },
+            AssetManageError::ReadOnly,
+            AssetManageError::Decompress {
+                file: "a.json.gz".to_owned(),
+                err: io(),
+            },
+            AssetManageError::Missing("a sheet".to_owned()).context("Loading".to_owned()),
+        ];
+        for err in errors {
+            assert!(!err.to_string().is_empty(), "{:?}", err);
+        }
+        assert_eq!(
+            AssetManageError::corrupt("a.bin", "it's short").to_string(),
+            "a.bin is corrupt: it's short"
+        );
+    }
+
+    #[test]
+    fn gzipped_files_round_trip() {
+        let path = scratch_dir("gzip_round_trip").join("numbers.json.gz");
+        let numbers: Vec<u32> = (0..500).collect();
+        serialize(&numbers, &path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.len() < serde_json::to_vec(&numbers).unwrap().len());
+        assert_eq!(deserialize::<Vec<u32>, _>(&path).unwrap(), numbers);
+    }
+
+    #[test]
+    fn broken_gzip_fails_to_decompress() {
+        let dir = scratch_dir("gzip_broken");
+        let whole = dir.join("whole.json.gz");
+        let numbers: Vec<u32> = (0..500).collect();
+        serialize(&numbers, &whole).unwrap();
+        let bytes = std::fs::read(&whole).unwrap();
+
+        let truncated = dir.join("truncated.json.gz");
+        std::fs::write(&truncated, &bytes[..bytes.len() / 2]).unwrap();
+        let garbage = dir.join("garbage.json.gz");
+        std::fs::write(&garbage, b"[1, 2, 3] is not gzip").unwrap();
+
+        for path in [truncated, garbage] {
+            match deserialize::<Vec<u32>, _>(&path) {
+                Err(AssetManageError::Decompress { file, .. }) => {
+                    assert_eq!(file, path.to_string_lossy());
+                }
+                Err(err) => panic!("expected a decompress error, got {err}"),
+                Ok(_) => panic!("read {} as gzip", path.display()),
+            }
+        }
+    }
+
+    /// An empty folder for the test `name` to write files in, cleared of
+    /// anything an earlier run left.
+    pub fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir()
+            .join("island_past_infinity_tests")
+            .join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        return dir;
+    }
+}
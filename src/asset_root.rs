@@ -0,0 +1,104 @@
+use std::{
+    cell::OnceCell,
+    path::{Path, PathBuf},
+};
+
+use macroquad::logging::info;
+
+use crate::asset_loading::{AssetManageError, AssetManageResult};
+
+thread_local! {
+    static ROOT: OnceCell<PathBuf> = const { OnceCell::new() };
+}
+
+/// Where the `assets` folder is, found once at startup. Asset paths are
+/// written from the working directory, as `assets/...`, and moved onto the
+/// root as they're read or written.
+pub struct AssetRoot;
+
+impl AssetRoot {
+    /// The folder name asset paths start with.
+    const DIR: &str = "assets";
+    const ARG: &str = "--assets";
+    const ENV: &str = "IPI_ASSETS";
+
+    /// Finds the assets folder from, in order, the `--assets` argument, the
+    /// `IPI_ASSETS` variable, next to the executable and the working
+    /// directory, and uses the first with levels in it. Without one, fails
+    /// listing where it looked, unless the assets are built in.
+    pub fn install() -> AssetManageResult<()> {
+        let mut tried = Vec::new();
+
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(idx) = args.iter().position(|arg| arg == Self::ARG) {
+            if let Some(path) = args.get(idx + 1) {
+                tried.push((PathBuf::from(path), "from --assets"));
+            }
+        }
+        if let Some(path) = std::env::var_os(Self::ENV) {
+            tried.push((PathBuf::from(path), "from IPI_ASSETS"));
+        }
+        if let Some(dir) = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        {
+            tried.push((dir.join(Self::DIR), "next to the game"));
+        }
+        let working = std::env::current_dir().unwrap_or_default();
+        tried.push((working.join(Self::DIR), "in the working directory"));
+
+        let found = tried
+            .iter()
+            .find(|(path, _)| path.join("levels").is_dir())
+            .map(|(path, _)| path.clone());
+        let root = match found {
+            Some(root) => root,
+            None if cfg!(any(feature = "embedded-assets", target_arch = "wasm32")) => {
+                PathBuf::from(Self::DIR)
+            }
+            None => {
+                let places: Vec<String> = tried
+                    .iter()
+                    .map(|(path, how)| format!("{} ({how})", path.display()))
+                    .collect();
+                return Err(AssetManageError::Missing(format!(
+                    "the assets folder, looked in: {}",
+                    places.join(", ")
+                )));
+            }
+        };
+
+        info!("Using assets from {}", root.display());
+        ROOT.with(|cell| {
+            let _ = cell.set(root);
+        });
+        return Ok(());
+    }
+
+    /// Where the asset at `path` really is. Paths outside `assets/`, like
+    /// the settings, are left as they are.
+    pub fn resolve<P: AsRef<Path>>(path: P) -> PathBuf {
+        let path = path.as_ref();
+        let Ok(rest) = path.strip_prefix(Self::DIR) else {
+            return path.to_owned();
+        };
+
+        return ROOT.with(|cell| match cell.get() {
+            Some(root) => root.join(rest),
+            None => path.to_owned(),
+        });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Reads assets from `root` for the rest of the test, which runs on its
+    /// own thread.
+    pub fn use_root<P: AsRef<Path>>(root: P) {
+        ROOT.with(|cell| {
+            cell.set(root.as_ref().to_owned()).unwrap();
+        });
+    }
+}
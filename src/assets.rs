@@ -0,0 +1,152 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    asset_loading::AssetManageResult,
+    atlas::TileAtlas,
+    level_file::LevelFormat,
+    levels::{Level, LevelSerializable},
+    tilesets::TilesetAsset,
+};
+
+/// Assets kept loaded between levels, so levels sharing them don't read,
+/// decode and upload them again.
+pub struct Assets {
+    tilesets: HashMap<String, Rc<TilesetAsset>>,
+    /// Tilesets loaded up front from the manifest, never evicted.
+    preloaded: HashSet<String>,
+    /// Level files read up front from the manifest, by name.
+    levels: HashMap<String, LevelSerializable>,
+    /// Every kept tileset's image, packed together.
+    atlas: Rc<TileAtlas>,
+}
+
+impl Assets {
+    /// Tilesets kept before `trim` lets go of those the level doesn't use.
+    const MAX_TILESETS: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            tilesets: HashMap::new(),
+            preloaded: HashSet::new(),
+            levels: HashMap::new(),
+            atlas: Rc::new(TileAtlas::empty()),
+        }
+    }
+
+    /// The tileset `name`, loaded the first time it's asked for. Returns
+    /// whether it was already loaded along with it.
+    pub async fn tileset(&mut self, name: &str) -> AssetManageResult<(Rc<TilesetAsset>, bool)> {
+        if let Some(tileset) = self.tilesets.get(name) {
+            return Ok((tileset.clone(), true));
+        }
+
+        let tileset = Rc::new(TilesetAsset::load(name).await?);
+        self.tilesets.insert(name.to_owned(), tileset.clone());
+        return Ok((tileset, false));
+    }
+
+    /// Loads the tileset `name` now and keeps it for good.
+    pub async fn preload_tileset(&mut self, name: &str) -> AssetManageResult<()> {
+        self.tileset(name).await?;
+        self.preloaded.insert(name.to_owned());
+        return Ok(());
+    }
+
+    /// Reads the file of the level `name` now, for `Level::read`. Chunked
+    /// levels are left to stream in.
+    pub fn preload_level(&mut self, name: &str) -> AssetManageResult<()> {
+        let path = LevelFormat::find(name);
+        let format = LevelFormat::of(&path);
+        if format == LevelFormat::Chunked {
+            return Ok(());
+        }
+        let level = format.read(&path)?;
+        self.levels.insert(name.to_owned(), level);
+        return Ok(());
+    }
+
+    /// The file of the level `name`, if it was preloaded.
+    pub fn level_file(&self, name: &str) -> Option<&LevelSerializable> {
+        return self.levels.get(name);
+    }
+
+    /// The atlas of every kept tileset, packed again if one was loaded or
+    /// reloaded since it was last packed.
+    pub fn atlas(&mut self) -> Rc<TileAtlas> {
+        if !self.atlas.covers(&self.tilesets) {
+            self.atlas = Rc::new(TileAtlas::pack(&self.tilesets));
+        }
+        return self.atlas.clone();
+    }
+
+    /// Takes `level`'s tilesets as the shared copies. The editor changes a
+    /// level's tilesets copy-on-write, so call this before leaving a level
+    /// to carry its changes into the next.
+    pub fn keep_tilesets(&mut self, level: &Level) {
+        for (name, tileset) in level.tilesets() {
+            self.tilesets.insert(name.clone(), tileset.clone());
+        }
+        // Its preloaded file too, or edits would be lost coming back.
+        if self.levels.contains_key(level.name()) {
+            self.levels
+                .insert(level.name().to_owned(), level.to_serializable());
+        }
+    }
+
+    /// Lets go of every tileset `level` doesn't use, for leaving a region
+    /// behind. Levels still holding one keep it alive.
+    pub fn evict_unused(&mut self, level: &Level) {
+        self.tilesets
+            .retain(|name, _| level.tilesets().contains_key(name) || self.preloaded.contains(name));
+        // Levels still drawing from the old atlas keep it alive.
+        if self.atlas.packs_other_than(&self.tilesets) {
+            self.atlas = Rc::new(TileAtlas::empty());
+        }
+    }
+
+    /// Evicts the tilesets `level` doesn't use once too many are kept.
+    pub fn trim(&mut self, level: &Level) {
+        if self.tilesets.len() > Self::MAX_TILESETS {
+            self.evict_unused(level);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{levels::tests::level_using, object::tests::block_on};
+
+    fn kept(assets: &Assets) -> HashSet<&str> {
+        return assets.tilesets.keys().map(String::as_str).collect();
+    }
+
+    #[test]
+    fn levels_sharing_tilesets_share_the_loaded_copies() {
+        let mut assets = Assets::new();
+        let first = level_using(&["sand", "rock", "palm"]);
+        assets.keep_tilesets(&first);
+
+        let second = level_using(&["palm", "sand", "rock"]);
+        for name in second.tileset_names() {
+            let (tileset, cached) = block_on(assets.tileset(&name)).unwrap();
+            assert!(cached, "{name} was loaded again");
+            assert!(Rc::ptr_eq(&tileset, &first.tilesets()[&name]));
+        }
+        assert_eq!(kept(&assets), HashSet::from(["sand", "rock", "palm"]));
+    }
+
+    #[test]
+    fn eviction_keeps_what_the_new_level_uses() {
+        let mut assets = Assets::new();
+        assets.keep_tilesets(&level_using(&["sand", "rock", "palm"]));
+        assets.keep_tilesets(&level_using(&["reef", "title"]));
+        assets.preloaded.insert("title".to_owned());
+
+        assets.evict_unused(&level_using(&["rock", "reef", "cave"]));
+        assert_eq!(kept(&assets), HashSet::from(["rock", "reef", "title"]));
+    }
+}
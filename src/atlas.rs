@@ -0,0 +1,186 @@
+use std::{collections::HashMap, rc::Rc};
+
+use macroquad::{
+    color::BLANK,
+    logging::info,
+    math::{vec2, Vec2},
+    texture::{FilterMode, Image, Texture2D},
+};
+
+use crate::tilesets::TilesetAsset;
+
+/// Where a tileset's image was packed.
+struct Placement {
+    page: usize,
+    offset: Vec2,
+    /// The texture the image was copied from, to tell when the tileset has
+    /// been reloaded since.
+    source: Texture2D,
+}
+
+/// A row of images as tall as its first, filled left to right.
+struct Shelf {
+    page: usize,
+    y: u32,
+    h: u32,
+    x: u32,
+}
+
+/// Tileset images packed onto a few big pages at load time, so tiles from
+/// different tilesets batch into one draw. Only drawing uses it; tile
+/// sources stay in their own image's pixels everywhere else.
+pub struct TileAtlas {
+    pages: Vec<Texture2D>,
+    placements: HashMap<String, Placement>,
+}
+
+impl TileAtlas {
+    /// Widest and tallest a page gets. Images bigger than this aren't
+    /// packed and draw from their own texture.
+    pub const SIZE: u32 = 2048;
+    /// Blank pixels between images, so neighbours can't bleed in.
+    const PADDING: u32 = 1;
+
+    pub fn empty() -> Self {
+        Self {
+            pages: Vec::new(),
+            placements: HashMap::new(),
+        }
+    }
+
+    /// Packs the images of `tilesets` onto shelves, tallest first, spilling
+    /// onto another page once one is full. Pages are cut down to the
+    /// shelves they use.
+    pub fn pack(tilesets: &HashMap<String, Rc<TilesetAsset>>) -> Self {
+        let mut images: Vec<(&String, Image)> = tilesets
+            .iter()
+            .map(|(name, tileset)| (name, tileset.tex.get_texture_data()))
+            .filter(|(_, image)| {
+                image.width as u32 <= Self::SIZE && image.height as u32 <= Self::SIZE
+            })
+            .collect();
+        images.sort_by(|(a_name, a), (b_name, b)| {
+            b.height.cmp(&a.height).then_with(|| a_name.cmp(b_name))
+        });
+
+        let mut shelves: Vec<Shelf> = Vec::new();
+        // How far down each page's shelves reach.
+        let mut page_heights: Vec<u32> = Vec::new();
+        let mut placed = Vec::new();
+        for (name, image) in &images {
+            let (w, h) = (image.width as u32, image.height as u32);
+            let fits = shelves
+                .iter()
+                .position(|shelf| h <= shelf.h && shelf.x + w <= Self::SIZE);
+            let idx = match fits {
+                Some(idx) => idx,
+                None => {
+                    let page = match page_heights.last() {
+                        Some(&used) if used + h <= Self::SIZE => page_heights.len() - 1,
+                        _ => {
+                            page_heights.push(0);
+                            page_heights.len() - 1
+                        }
+                    };
+                    shelves.push(Shelf {
+                        page,
+                        y: page_heights[page],
+                        h,
+                        x: 0,
+                    });
+                    page_heights[page] += h + Self::PADDING;
+                    shelves.len() - 1
+                }
+            };
+            let shelf = &mut shelves[idx];
+
+            placed.push((*name, image, shelf.page, shelf.x, shelf.y));
+            shelf.x += w + Self::PADDING;
+        }
+
+        // The padding under a page's last shelf can run one past its foot.
+        let mut pages: Vec<Image> = page_heights
+            .iter()
+            .map(|&used| {
+                let h = used.min(Self::SIZE) as u16;
+                Image::gen_image_color(Self::SIZE as u16, h, BLANK)
+            })
+            .collect();
+        let mut placements = HashMap::new();
+        for (name, image, page, x, y) in placed {
+            let target = &mut pages[page];
+            let target_w = target.width as usize;
+            let row_bytes = image.width as usize * 4;
+            for row in 0..image.height as usize {
+                let from = row * row_bytes;
+                let to = ((y as usize + row) * target_w + x as usize) * 4;
+                target.bytes[to..to + row_bytes]
+                    .copy_from_slice(&image.bytes[from..from + row_bytes]);
+            }
+
+            placements.insert(
+                name.clone(),
+                Placement {
+                    page,
+                    offset: vec2(x as f32, y as f32),
+                    source: tilesets[name].tex.clone(),
+                },
+            );
+        }
+
+        let pages: Vec<Texture2D> = pages
+            .iter()
+            .map(|page| {
+                let tex = Texture2D::from_image(page);
+                tex.set_filter(FilterMode::Nearest);
+                tex
+            })
+            .collect();
+
+        info!(
+            "Packed {} of {} tilesets onto {} atlas pages",
+            placements.len(),
+            tilesets.len(),
+            pages.len()
+        );
+        return Self { pages, placements };
+    }
+
+    /// Whether every one of `tilesets` small enough to pack is packed as it
+    /// is now.
+    pub fn covers(&self, tilesets: &HashMap<String, Rc<TilesetAsset>>) -> bool {
+        return tilesets.iter().all(|(name, tileset)| {
+            let too_big =
+                tileset.tex.width() as u32 > Self::SIZE || tileset.tex.height() as u32 > Self::SIZE;
+            too_big
+                || self
+                    .placements
+                    .get(name)
+                    .is_some_and(|placement| placement.source == tileset.tex)
+        });
+    }
+
+    /// Whether any image packed belongs to a tileset not in `tilesets`.
+    pub fn packs_other_than(&self, tilesets: &HashMap<String, Rc<TilesetAsset>>) -> bool {
+        return self
+            .placements
+            .keys()
+            .any(|name| !tilesets.contains_key(name));
+    }
+
+    /// The texture to draw `tileset`, called `name`, from, and where its
+    /// image starts on it. Falls back to the tileset's own texture when it
+    /// isn't packed or was reloaded since.
+    pub fn locate<'a>(&'a self, name: &str, tileset: &'a TilesetAsset) -> (&'a Texture2D, Vec2) {
+        return match self.placements.get(name) {
+            Some(placement) if placement.source == tileset.tex => {
+                (&self.pages[placement.page], placement.offset)
+            }
+            _ => (&tileset.tex, Vec2::ZERO),
+        };
+    }
+
+    pub fn pages(&self) -> &[Texture2D] {
+        &self.pages
+    }
+}
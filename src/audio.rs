@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+
+use macroquad::{
+    audio::{load_sound_from_bytes, play_sound, PlaySoundParams, Sound},
+    logging::{info, warn},
+    rand::gen_range,
+};
+use serde::Deserialize;
+
+use crate::{
+    asset_loading::{asset_exists, deserialize, read_asset, AssetManageResult},
+    events::GameEvent,
+};
+
+/// The sound effects the game plays by itself. Any others in the manifest
+/// can be played by name.
+pub struct Sfx;
+
+impl Sfx {
+    pub const ATTACK_SWING: &str = "attack_swing";
+    pub const PLAYER_HURT: &str = "player_hurt";
+    pub const ENEMY_HURT: &str = "enemy_hurt";
+    pub const ENEMY_DEATH: &str = "enemy_death";
+    pub const CHEST_OPEN: &str = "chest_open";
+    pub const UI_CONFIRM: &str = "ui_confirm";
+
+    const ALL: [&str; 6] = [
+        Self::ATTACK_SWING,
+        Self::PLAYER_HURT,
+        Self::ENEMY_HURT,
+        Self::ENEMY_DEATH,
+        Self::CHEST_OPEN,
+        Self::UI_CONFIRM,
+    ];
+}
+
+/// A sound effect as the manifest lists it.
+#[derive(Deserialize)]
+#[serde(default)]
+struct SfxEntry {
+    /// Files in the sfx folder, one picked at random each time it plays.
+    /// Left empty, it's the file named after the effect.
+    variations: Vec<String>,
+    volume: f32,
+}
+
+impl Default for SfxEntry {
+    fn default() -> Self {
+        Self {
+            variations: Vec::new(),
+            volume: 1.0,
+        }
+    }
+}
+
+struct LoadedSfx {
+    variations: Vec<Sound>,
+    volume: f32,
+}
+
+/// The sound effects, loaded at startup from the sfx folder. Effects
+/// without a sound stay silent.
+pub struct AudioAssets {
+    sounds: HashMap<String, LoadedSfx>,
+    /// Effects known to have no sound, so each is only warned about once.
+    silent: HashSet<String>,
+}
+
+impl AudioAssets {
+    pub const DIR: &str = "assets/audio/sfx";
+    const MANIFEST: &str = "assets/audio/sfx/sounds.json";
+    /// What an effect without variations is looked for as.
+    const EXTENSIONS: [&str; 2] = ["ogg", "wav"];
+    /// How far each play's volume strays either way, so repeats don't all
+    /// sound the same. macroquad can't change the pitch.
+    const VOLUME_JITTER: f32 = 0.1;
+
+    /// Loads every effect in the manifest and every one the game plays by
+    /// itself. Builds without the `audio` feature load nothing.
+    pub async fn load() -> Self {
+        let mut audio = Self {
+            sounds: HashMap::new(),
+            silent: HashSet::new(),
+        };
+        if !cfg!(feature = "audio") {
+            info!("Built without audio, so the game is silent");
+            return audio;
+        }
+
+        let mut entries: HashMap<String, SfxEntry> = match asset_exists(Self::MANIFEST) {
+            true => deserialize(Self::MANIFEST).unwrap_or_else(|err| {
+                warn!("Could not read the sound manifest: {}", err);
+                HashMap::new()
+            }),
+            false => HashMap::new(),
+        };
+        for name in Sfx::ALL {
+            entries.entry(name.to_owned()).or_default();
+        }
+
+        for (name, entry) in entries {
+            let files: Vec<String> = match entry.variations.is_empty() {
+                true => Self::EXTENSIONS
+                    .iter()
+                    .map(|ext| format!("{}/{name}.{ext}", Self::DIR))
+                    .filter(|path| asset_exists(path))
+                    .take(1)
+                    .collect(),
+                false => entry
+                    .variations
+                    .iter()
+                    .map(|file| format!("{}/{file}", Self::DIR))
+                    .collect(),
+            };
+
+            let mut variations = Vec::new();
+            for path in files {
+                match load_sound(&path).await {
+                    Ok(sound) => variations.push(sound),
+                    Err(err) => warn!("Could not load {}: {}", path, err),
+                }
+            }
+            if variations.is_empty() {
+                warn!("No sound for {}, so it stays silent", name);
+                audio.silent.insert(name);
+                continue;
+            }
+
+            let volume = entry.volume;
+            audio.sounds.insert(name, LoadedSfx { variations, volume });
+        }
+
+        return audio;
+    }
+
+    /// Plays a random variation of the effect `name`, or nothing if it has
+    /// no sound.
+    pub fn play_sfx(&mut self, name: &str) {
+        let Some(sfx) = self.sounds.get(name) else {
+            if cfg!(feature = "audio") && self.silent.insert(name.to_owned()) {
+                warn!("No sound effect called {}", name);
+            }
+            return;
+        };
+
+        let sound = &sfx.variations[gen_range(0, sfx.variations.len())];
+        let jitter = gen_range(-Self::VOLUME_JITTER, Self::VOLUME_JITTER);
+        play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume: (sfx.volume * (1.0 + jitter)).clamp(0.0, 1.0),
+            },
+        );
+    }
+
+    /// Plays the effect a gameplay event calls for, if any.
+    pub fn react(&mut self, event: &GameEvent) {
+        let name = match event {
+            GameEvent::PlayerAttacked => Sfx::ATTACK_SWING,
+            GameEvent::PlayerDamaged(_) => Sfx::PLAYER_HURT,
+            GameEvent::EnemyDamaged { .. } => Sfx::ENEMY_HURT,
+            GameEvent::EnemyDied { .. } => Sfx::ENEMY_DEATH,
+            GameEvent::ChestOpened => Sfx::CHEST_OPEN,
+            GameEvent::LinkToggled { .. }
+            | GameEvent::WeatherChanged(_)
+            | GameEvent::ScreenFlash { .. } => return,
+        };
+        self.play_sfx(name);
+    }
+}
+
+async fn load_sound(path: &str) -> AssetManageResult<Sound> {
+    let bytes = read_asset(path)?;
+    return Ok(load_sound_from_bytes(&bytes).await?);
+}
@@ -1,32 +1,65 @@
 use macroquad::{
-    color::BLUE,
+    color::{Color, BLUE},
     math::{vec2, Rect, Vec2},
-    shapes::draw_rectangle,
+    shapes::{draw_ellipse, draw_rectangle},
 };
 
 use crate::{
-    animator::Animator, levels::Level, sprites::Sprite, world::World, TILE_COLLISION_SECTIONS, TILE_SIZE
+    animator::{Animation, Animator},
+    levels::Level,
+    sprites::Sprite,
+    world::World,
+    TILE_COLLISION_SECTIONS, TILE_SIZE,
 };
 
+/// Which axes a call to `Body::r#move` was stopped on by collision.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MoveResult {
+    pub blocked_x: bool,
+    pub blocked_y: bool,
+}
+
 pub struct Body {
     pub hitbox: Rect,
+    impulse: Vec2,
+    /// Visual height above the ground. While above zero the body hops over
+    /// low collision sections.
+    z: f32,
+    z_velocity: f32,
     animator: Option<Animator>,
+    /// Skip the drop shadow, for things that aren't standing on the ground.
+    shadowless: bool,
 }
 
 impl Body {
+    const IMPULSE_DECAY: f32 = 12.0;
+    const JUMP_SECONDS: f32 = 0.35;
+    const JUMP_HEIGHT: f32 = 8.0;
+    const GRAVITY: f32 = 8.0 * Self::JUMP_HEIGHT / (Self::JUMP_SECONDS * Self::JUMP_SECONDS);
+    const SHADOW: Color = Color::new(0.0, 0.0, 0.0, 0.3);
+    /// Shadow height as a fraction of its width.
+    const SHADOW_SQUASH: f32 = 0.35;
+
     pub fn new(x: f32, y: f32, w: f32, h: f32, sprite: Option<Sprite>) -> Self {
         let x = x - w / 2.0;
         let y = y - h / 2.0;
 
         return Self {
             hitbox: Rect::new(x, y, w, h),
-            animator: match sprite {
-                Some(sprite) => Some(Animator::new(sprite)),
-                None => None,
-            },
+            impulse: Vec2::ZERO,
+            z: 0.0,
+            z_velocity: 0.0,
+            animator: sprite.map(Animator::new),
+            shadowless: false,
         };
     }
 
+    /// The same body without a drop shadow.
+    pub fn without_shadow(mut self) -> Self {
+        self.shadowless = true;
+        return self;
+    }
+
     pub fn screen_x(&self, world: &World) -> f32 {
         self.hitbox.x - world.x
     }
@@ -35,16 +68,138 @@ impl Body {
         self.hitbox.y - world.y
     }
 
+    pub fn set_center(&mut self, center: Vec2) {
+        self.hitbox.x = center.x - self.hitbox.w / 2.0;
+        self.hitbox.y = center.y - self.hitbox.h / 2.0;
+    }
+
+    pub fn set_animation(&mut self, animation: Animation) {
+        if let Some(ref mut animator) = self.animator {
+            animator.set_animation(animation);
+        }
+    }
+
+    /// Whether the current one-shot animation is done. Bodies without a
+    /// sprite have nothing to wait for.
+    pub fn animation_finished(&self) -> bool {
+        match &self.animator {
+            Some(animator) => animator.is_finished(),
+            None => true,
+        }
+    }
+
+    /// Adds a velocity that is carried through collision with the body's own
+    /// movement and decays on its own, e.g. knockback.
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.impulse += impulse;
+    }
+
+    pub fn is_airborne(&self) -> bool {
+        self.z > 0.0
+    }
+
+    /// Starts a fixed-height hop. Does nothing if already in the air.
+    pub fn jump(&mut self) {
+        if !self.is_airborne() {
+            self.z_velocity = Self::GRAVITY * Self::JUMP_SECONDS / 2.0;
+            self.z = f32::EPSILON;
+        }
+    }
+
+    pub fn clear_impulse(&mut self) {
+        self.impulse = Vec2::ZERO;
+    }
+
+    /// Swaps the sprite drawn for the body, keeping where its animation is.
+    pub fn set_sprite(&mut self, sprite: Sprite) {
+        if let Some(ref mut animator) = self.animator {
+            animator.set_sprite(sprite);
+        }
+    }
+
+    pub fn flash(&mut self, seconds: f32) {
+        if let Some(ref mut animator) = self.animator {
+            animator.flash(seconds);
+        }
+    }
+
+    /// Whether the body's walk cycle planted a foot since the last call.
+    /// Steps taken in the air don't count.
+    pub fn take_step(&mut self) -> bool {
+        let stepped = match self.animator {
+            Some(ref mut animator) => animator.take_step(),
+            None => false,
+        };
+
+        return stepped && !self.is_airborne();
+    }
+
+    pub fn face(&mut self, delta: Vec2) {
+        if let Some(ref mut animator) = self.animator {
+            animator.face(delta);
+        }
+    }
+
+    /// Unit vector the body's sprite is facing, down for bodies without one.
+    pub fn facing(&self) -> Vec2 {
+        match &self.animator {
+            Some(animator) => animator.facing(),
+            None => vec2(0.0, 1.0),
+        }
+    }
+
     pub fn get_y_sort_key(&self) -> i32 {
         (self.hitbox.bottom() * 100.0) as i32
     }
 
-    pub fn r#move(&mut self, delta: Vec2, level: &Level, dt: f32) {
+    pub fn r#move(&mut self, delta: Vec2, level: &Level, dt: f32) -> MoveResult {
         if let Some(ref mut animator) = self.animator {
             animator.apply_delta(delta, dt);
         }
 
-        let delta = delta * dt;
+        if self.is_airborne() {
+            self.z_velocity -= Self::GRAVITY * dt;
+            self.z += self.z_velocity * dt;
+        }
+
+        let airborne = self.is_airborne();
+        let delta = (delta + self.impulse) * dt;
+        self.impulse *= (-Self::IMPULSE_DECAY * dt).exp();
+        if self.impulse.length_squared() < 1.0 {
+            self.impulse = Vec2::ZERO;
+        }
+
+        // Never move further than one collision section at a time, so a
+        // hard shove can't carry the body through a thin wall.
+        let section = TILE_SIZE / TILE_COLLISION_SECTIONS;
+        let steps = (delta.abs().max_element() / section).ceil().max(1.0) as u32;
+        let mut result = MoveResult::default();
+        for _ in 0..steps {
+            let step = self.step(delta / steps as f32, level, airborne);
+            result.blocked_x |= step.blocked_x;
+            result.blocked_y |= step.blocked_y;
+        }
+
+        if result.blocked_x {
+            self.impulse.x = 0.0;
+        }
+        if result.blocked_y {
+            self.impulse.y = 0.0;
+        }
+
+        if airborne && self.z <= 0.0 {
+            self.z = 0.0;
+            self.z_velocity = 0.0;
+            self.resolve_overlap(level);
+        }
+
+        return result;
+    }
+
+    /// Moves by `delta`, which must be no longer than one collision section,
+    /// stopping flush against anything solid.
+    fn step(&mut self, delta: Vec2, level: &Level, airborne: bool) -> MoveResult {
+        let mut result = MoveResult::default();
         self.hitbox.x += delta.x;
         let mut vert_check_point = self.hitbox.y;
         loop {
@@ -55,15 +210,19 @@ impl Body {
 
             if delta.x > 0.0 {
                 let right = self.hitbox.x + self.hitbox.w;
-                if let Some(collision_point) = level.check_for_collision(right, vert_check_point) {
+                if let Some(collision_point) =
+                    level.check_for_collision(right, vert_check_point, airborne)
+                {
                     self.hitbox.x = collision_point.from_left() - self.hitbox.w;
+                    result.blocked_x = true;
                     break;
                 }
             } else {
                 if let Some(collision_point) =
-                    level.check_for_collision(self.hitbox.x, vert_check_point)
+                    level.check_for_collision(self.hitbox.x, vert_check_point, airborne)
                 {
                     self.hitbox.x = collision_point.from_right();
+                    result.blocked_x = delta.x != 0.0;
                     break;
                 }
             }
@@ -87,16 +246,18 @@ impl Body {
 
             if delta.y > 0.0 {
                 if let Some(collision_info) =
-                    level.check_for_collision(horizontal_check_point, bottom)
+                    level.check_for_collision(horizontal_check_point, bottom, airborne)
                 {
                     self.hitbox.y = collision_info.from_top() - self.hitbox.h;
+                    result.blocked_y = true;
                     break;
                 }
             } else {
                 if let Some(collision_info) =
-                    level.check_for_collision(horizontal_check_point, self.hitbox.y)
+                    level.check_for_collision(horizontal_check_point, self.hitbox.y, airborne)
                 {
                     self.hitbox.y = collision_info.from_bottom();
+                    result.blocked_y = delta.y != 0.0;
                     break;
                 }
             }
@@ -107,20 +268,92 @@ impl Body {
                 horizontal_check_point += TILE_SIZE / TILE_COLLISION_SECTIONS;
             }
         }
+
+        return result;
+    }
+
+    /// Whether any solid section, low or not, touches the hitbox.
+    pub fn overlaps_solid(&self, level: &Level) -> bool {
+        let step = TILE_SIZE / TILE_COLLISION_SECTIONS;
+        let mut y = self.hitbox.y;
+        loop {
+            let mut x = self.hitbox.x;
+            loop {
+                if level.check_for_collision(x, y, false).is_some() {
+                    return true;
+                }
+
+                if x == self.hitbox.right() {
+                    break;
+                }
+                x = (x + step).min(self.hitbox.right());
+            }
+
+            if y == self.hitbox.bottom() {
+                return false;
+            }
+            y = (y + step).min(self.hitbox.bottom());
+        }
+    }
+
+    /// Moves the body to the nearest spot clear of every solid section, e.g.
+    /// after landing a hop on top of a low obstacle.
+    fn resolve_overlap(&mut self, level: &Level) {
+        if !self.overlaps_solid(level) {
+            return;
+        }
+
+        let step = TILE_SIZE / TILE_COLLISION_SECTIONS;
+        let origin = self.hitbox.point();
+        for ring in 1..=(TILE_COLLISION_SECTIONS as i32 * 2) {
+            let mut candidates = Vec::new();
+            for dy in -ring..=ring {
+                for dx in -ring..=ring {
+                    if dx.abs() == ring || dy.abs() == ring {
+                        candidates.push(vec2(dx as f32, dy as f32) * step);
+                    }
+                }
+            }
+            candidates.sort_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+
+            for offset in candidates {
+                self.hitbox.move_to(origin + offset);
+                if !self.overlaps_solid(level) {
+                    return;
+                }
+            }
+        }
+
+        self.hitbox.move_to(origin);
+    }
+
+    /// Draws the drop shadow under `screen_box`, the hitbox already moved
+    /// to the screen, so it lines up with the sprite drawn there. It stays
+    /// on the ground and shrinks while the body is in the air.
+    fn render_shadow(&self, screen_box: &Rect) {
+        if self.shadowless {
+            return;
+        }
+
+        let scale = 1.0 / (1.0 + self.z / Self::JUMP_HEIGHT);
+        let radius = screen_box.w / 2.0 * scale;
+        draw_ellipse(
+            screen_box.center().x,
+            screen_box.bottom(),
+            radius,
+            radius * Self::SHADOW_SQUASH,
+            0.0,
+            Self::SHADOW,
+        );
     }
 
     pub fn render(&self, world: &World) {
-        if let Some(animator) = &self.animator {
-            let screen_box = self.hitbox.offset(-vec2(world.x, world.y));
-            animator.render(&screen_box)
-        } else {
-            draw_rectangle(
-                self.screen_x(world),
-                self.screen_y(world),
-                self.hitbox.w,
-                self.hitbox.h,
-                BLUE,
-            );
+        let screen_box = self.hitbox.offset(-vec2(world.x, world.y));
+        self.render_shadow(&screen_box);
+
+        match &self.animator {
+            Some(animator) => animator.render(&screen_box, self.z),
+            None => draw_rectangle(screen_box.x, screen_box.y, screen_box.w, screen_box.h, BLUE),
         }
     }
 }
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use macroquad::{
+    color::{Color, BROWN, DARKBROWN, DARKGREEN, GREEN},
+    math::{vec2, Vec2},
+    rand::RandGenerator,
+    shapes::{draw_circle, draw_rectangle},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{deserialize, AssetManageError, AssetManageResult},
+    body::Body,
+    loot::{self, LootDrop},
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    world::World,
+    TILE_SIZE,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BreakableKind {
+    Bush,
+    Pot,
+}
+
+impl BreakableKind {
+    pub const ALL: [BreakableKind; 2] = [BreakableKind::Bush, BreakableKind::Pot];
+
+    /// Main and shaded color, shared by the prop and its pieces.
+    fn colors(&self) -> (Color, Color) {
+        match self {
+            BreakableKind::Bush => (GREEN, DARKGREEN),
+            BreakableKind::Pot => (BROWN, DARKBROWN),
+        }
+    }
+}
+
+pub struct BreakableDropsTable {
+    drops: HashMap<BreakableKind, Vec<LootDrop>>,
+}
+
+impl BreakableDropsTable {
+    const PATH: &str = "assets/config/breakables.json";
+
+    /// Fails if the file is unreadable or leaves out any kind.
+    pub fn load() -> AssetManageResult<Self> {
+        let drops: HashMap<BreakableKind, Vec<LootDrop>> = deserialize(Self::PATH)?;
+        if let Some(missing) = BreakableKind::ALL
+            .iter()
+            .find(|kind| !drops.contains_key(kind))
+        {
+            return Err(AssetManageError::Missing(format!(
+                "drops for {:?} in {}",
+                missing,
+                Self::PATH
+            )));
+        }
+
+        return Ok(Self { drops });
+    }
+
+    pub fn get(&self, kind: BreakableKind) -> &[LootDrop] {
+        &self.drops[&kind]
+    }
+}
+
+enum BreakableState {
+    Intact,
+    /// Hit this frame; drops are rolled on the next update.
+    Struck,
+    /// Pieces flying apart before it's removed.
+    Breaking(f32),
+}
+
+/// A bush or pot that fills its cell until a single swing breaks it.
+pub struct Breakable {
+    pub body: Body,
+    kind: BreakableKind,
+    drops: Vec<LootDrop>,
+    state: BreakableState,
+    rng: RandGenerator,
+}
+
+impl Breakable {
+    const BREAK_SECONDS: f32 = 0.35;
+    const PIECES: usize = 6;
+    const PIECE_SPEED: f32 = 40.0;
+    const PIECE_GRAVITY: f32 = 120.0;
+
+    /// `seed` drives the drop rolls.
+    pub fn new(kind: BreakableKind, x: f32, y: f32, drops: Vec<LootDrop>, seed: u64) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+
+        Self {
+            body: Body::new(x, y, TILE_SIZE, TILE_SIZE, None),
+            kind,
+            drops,
+            state: BreakableState::Intact,
+            rng,
+        }
+    }
+
+    fn render_intact(&self, center: Vec2) {
+        let (main, shade) = self.kind.colors();
+        match self.kind {
+            BreakableKind::Bush => {
+                draw_circle(center.x, center.y + 1.0, 7.0, shade);
+                draw_circle(center.x - 3.0, center.y - 1.0, 4.0, main);
+                draw_circle(center.x + 3.0, center.y, 4.0, main);
+            }
+            BreakableKind::Pot => {
+                draw_rectangle(center.x - 5.0, center.y - 4.0, 10.0, 11.0, main);
+                draw_rectangle(center.x - 3.0, center.y - 7.0, 6.0, 3.0, shade);
+                draw_rectangle(center.x - 5.0, center.y + 1.0, 10.0, 2.0, shade);
+            }
+        }
+    }
+
+    /// Pieces thrown outward in a fan, falling and fading as they go.
+    fn render_pieces(&self, center: Vec2, time: f32) {
+        let (main, shade) = self.kind.colors();
+        let alpha = 1.0 - time / Self::BREAK_SECONDS;
+        for idx in 0..Self::PIECES {
+            let angle = idx as f32 / Self::PIECES as f32 * std::f32::consts::TAU;
+            let offset = Vec2::from_angle(angle) * Self::PIECE_SPEED * time
+                + vec2(0.0, Self::PIECE_GRAVITY * time * time / 2.0);
+            let color = match idx % 2 {
+                0 => main,
+                _ => shade,
+            };
+            draw_rectangle(
+                center.x + offset.x - 1.5,
+                center.y + offset.y - 1.5,
+                3.0,
+                3.0,
+                Color { a: alpha, ..color },
+            );
+        }
+    }
+}
+
+impl GameObject for Breakable {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Breakable
+    }
+
+    /// Drops loot the frame after it is struck, then plays out the break.
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        match self.state {
+            BreakableState::Intact => {}
+            BreakableState::Struck => {
+                let center = self.body.hitbox.center();
+                for (loot, Vec2 { x, y }) in loot::roll(&self.drops, &self.rng, ctx.level, center) {
+                    ctx.spawns.push(loot.spawn(x, y, ctx.item_sheet));
+                }
+                self.state = BreakableState::Breaking(0.0);
+            }
+            BreakableState::Breaking(time) => {
+                if time + ctx.dt >= Self::BREAK_SECONDS {
+                    return ObjectStatus::Dead;
+                }
+                self.state = BreakableState::Breaking(time + ctx.dt);
+            }
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    /// Plays out a break already underway. A struck one waits to drop its
+    /// loot until it wakes.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        if let BreakableState::Breaking(time) = self.state {
+            if time + dt >= Self::BREAK_SECONDS {
+                return ObjectStatus::Dead;
+            }
+            self.state = BreakableState::Breaking(time + dt);
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    /// Any swing that reaches it breaks it, whatever else the swing hits.
+    fn touch_player(&mut self, player: &mut Player) {
+        if !matches!(self.state, BreakableState::Intact) {
+            return;
+        }
+
+        if let Some(attack) = player.attack() {
+            if attack.hitbox.overlaps(&self.body.hitbox) {
+                self.state = BreakableState::Struck;
+            }
+        }
+    }
+
+    fn render(&self, world: &World) {
+        let center = self.body.hitbox.center() - vec2(world.x, world.y);
+        match self.state {
+            BreakableState::Intact => self.render_intact(center),
+            BreakableState::Struck => self.render_pieces(center, 0.0),
+            BreakableState::Breaking(time) => self.render_pieces(center, time),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use macroquad::{
+    color::GREEN,
+    math::{vec2, Rect, Vec2},
+    shapes::draw_rectangle_lines,
+};
+
+use crate::world::World;
+
+/// Where the camera heads each frame: centered on the player, but held
+/// inside the level's camera zone while the player is in one. Moving into,
+/// out of, or between zones eases over `TRANSITION_SECONDS`.
+pub struct CameraFollow {
+    zone: Option<Rect>,
+    /// Offset from the free-follow target, eased from `from` to the current
+    /// zone's clamp.
+    offset: Vec2,
+    from: Vec2,
+    transition: f32,
+}
+
+impl CameraFollow {
+    const TRANSITION_SECONDS: f32 = 0.5;
+
+    pub fn new() -> Self {
+        Self {
+            zone: None,
+            offset: Vec2::ZERO,
+            from: Vec2::ZERO,
+            transition: 1.0,
+        }
+    }
+
+    /// Forgets the zone, for a camera that was just snapped somewhere else.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// The top-left corner `world` should head toward this frame. `zone` is
+    /// the camera zone the player's `center` is in, if any.
+    pub fn target(&mut self, zone: Option<Rect>, center: Vec2, world: &World, dt: f32) -> Vec2 {
+        if zone != self.zone {
+            self.zone = zone;
+            self.from = self.offset;
+            self.transition = 0.0;
+        }
+        self.transition = (self.transition + dt / Self::TRANSITION_SECONDS).min(1.0);
+
+        let free = center - vec2(world.w, world.h) / 2.0;
+        let goal = match zone {
+            Some(zone) => clamp_view(free, world, zone) - free,
+            None => Vec2::ZERO,
+        };
+        let t = self.transition;
+        let eased = t * t * (3.0 - 2.0 * t);
+        self.offset = self.from.lerp(goal, eased);
+
+        return free + self.offset;
+    }
+
+    /// Outlines the zone holding the camera, for the debug overlay.
+    pub fn render_debug(&self, world: &World) {
+        if let Some(zone) = self.zone {
+            draw_rectangle_lines(
+                zone.x - world.x,
+                zone.y - world.y,
+                zone.w,
+                zone.h,
+                2.0,
+                GREEN,
+            );
+        }
+    }
+}
+
+/// Moves the view's top-left corner `corner` so the view stays inside
+/// `zone`, centering it on any side the zone is too small to fill.
+fn clamp_view(corner: Vec2, world: &World, zone: Rect) -> Vec2 {
+    let axis = |corner: f32, size: f32, start: f32, len: f32| match len > size {
+        true => corner.clamp(start, start + len - size),
+        false => start + (len - size) / 2.0,
+    };
+    return vec2(
+        axis(corner.x, world.w, zone.x, zone.w),
+        axis(corner.y, world.h, zone.y, zone.h),
+    );
+}
@@ -0,0 +1,70 @@
+use macroquad::math::{vec2, Vec2};
+
+use crate::{events::GameEvent, lights::smooth_noise, world::World};
+
+/// Screen shake driven by trauma, which hits add to and time wears off. The
+/// shake grows with the square of trauma, so small knocks stay subtle.
+pub struct CameraShake {
+    trauma: f32,
+    time: f32,
+}
+
+impl CameraShake {
+    /// Offset in pixels at full trauma.
+    const MAX_OFFSET: f32 = 6.0;
+    /// Trauma lost per second.
+    const DECAY: f32 = 1.5;
+    /// Noise steps per second; higher shakes faster.
+    const RATE: f32 = 25.0;
+
+    pub fn new() -> Self {
+        Self {
+            trauma: 0.0,
+            time: 0.0,
+        }
+    }
+
+    /// Adds to the trauma, which tops out at 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    /// Adds the trauma a gameplay event calls for, if any.
+    pub fn react(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::PlayerDamaged(_) => self.add_trauma(0.3),
+            GameEvent::EnemyDied { .. } => self.add_trauma(0.1),
+            GameEvent::LinkToggled { .. }
+            | GameEvent::EnemyDamaged { .. }
+            | GameEvent::PlayerAttacked
+            | GameEvent::ChestOpened
+            | GameEvent::WeatherChanged(_)
+            | GameEvent::ScreenFlash { .. } => {}
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.trauma = (self.trauma - Self::DECAY * dt).max(0.0);
+        self.time += dt;
+    }
+
+    fn offset(&self) -> Vec2 {
+        let t = self.time * Self::RATE;
+        let x = smooth_noise(t, 1) * 2.0 - 1.0;
+        let y = smooth_noise(t, 2) * 2.0 - 1.0;
+        return vec2(x, y) * self.trauma * self.trauma * Self::MAX_OFFSET;
+    }
+
+    /// `world` moved by this frame's shake, in whole steps of
+    /// `sub_pixels` per pixel so a rounded view stays rounded. Only for
+    /// drawing; logic and the editor keep the steady view.
+    pub fn apply(&self, world: &World, sub_pixels: f32) -> World {
+        let offset = (self.offset() * sub_pixels).round() / sub_pixels;
+        return World {
+            x: world.x + offset.x,
+            y: world.y + offset.y,
+            w: world.w,
+            h: world.h,
+        };
+    }
+}
@@ -0,0 +1,153 @@
+use macroquad::{
+    color::{Color, BLACK, DARKGRAY, GRAY, GREEN},
+    math::{vec2, Vec2},
+    shapes::{draw_rectangle, draw_triangle},
+    time::get_time,
+};
+
+use crate::{
+    body::Body,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+/// Where the player comes back after dying.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RespawnPoint {
+    pub level: String,
+    pub position: Vec2,
+}
+
+pub struct Checkpoint {
+    pub body: Body,
+    pub active: bool,
+}
+
+impl Checkpoint {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            body: Body::new(x, y, 12.0, 12.0, None),
+            active: false,
+        }
+    }
+
+    pub fn respawn_point(&self, level: &str) -> RespawnPoint {
+        RespawnPoint {
+            level: level.to_owned(),
+            position: self.body.hitbox.center(),
+        }
+    }
+}
+
+impl GameObject for Checkpoint {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Checkpoint
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn render(&self, world: &World) {
+        let base_x = self.body.hitbox.center().x - world.x;
+        let base_y = self.body.hitbox.bottom() - world.y;
+
+        let flag_color = match self.active {
+            true => {
+                let pulse = (get_time() as f32 * 4.0).sin() * 0.2 + 0.8;
+                Color::new(GREEN.r * pulse, GREEN.g * pulse, GREEN.b * pulse, 1.0)
+            }
+            false => GRAY,
+        };
+
+        draw_rectangle(base_x - 3.0, base_y - 2.0, 6.0, 2.0, DARKGRAY);
+        draw_rectangle(base_x - 0.5, base_y - 16.0, 1.0, 14.0, DARKGRAY);
+        draw_triangle(
+            vec2(base_x + 0.5, base_y - 16.0),
+            vec2(base_x + 0.5, base_y - 10.0),
+            vec2(base_x + 7.0, base_y - 13.0),
+            flag_color,
+        );
+    }
+}
+
+enum RespawnState {
+    Idle,
+    FadingOut(f32),
+    Black,
+    FadingIn(f32),
+}
+
+/// Drives the fade to black after the player dies and the fade back in once
+/// they retry.
+pub struct Respawner {
+    state: RespawnState,
+}
+
+impl Respawner {
+    const FADE_OUT_SECONDS: f32 = 1.0;
+    const FADE_IN_SECONDS: f32 = 0.6;
+
+    pub fn new() -> Self {
+        Self {
+            state: RespawnState::Idle,
+        }
+    }
+
+    /// Advances the fade, returning true on the frame the screen turns fully
+    /// black. The screen then stays black until `fade_in` is called.
+    pub fn update(&mut self, player_dead: bool, dt: f32) -> bool {
+        match self.state {
+            RespawnState::Idle => {
+                if player_dead {
+                    self.state = RespawnState::FadingOut(0.0);
+                }
+            }
+            RespawnState::FadingOut(time) => {
+                if time >= Self::FADE_OUT_SECONDS {
+                    self.state = RespawnState::Black;
+                    return true;
+                }
+                self.state = RespawnState::FadingOut(time + dt);
+            }
+            RespawnState::Black => {}
+            RespawnState::FadingIn(time) => {
+                self.state = match time >= Self::FADE_IN_SECONDS {
+                    true => RespawnState::Idle,
+                    false => RespawnState::FadingIn(time + dt),
+                };
+            }
+        }
+
+        return false;
+    }
+
+    pub fn fade_in(&mut self) {
+        self.state = RespawnState::FadingIn(0.0);
+    }
+
+    pub fn render(&self) {
+        let alpha = match self.state {
+            RespawnState::Idle => return,
+            RespawnState::FadingOut(time) => time / Self::FADE_OUT_SECONDS,
+            RespawnState::Black => 1.0,
+            RespawnState::FadingIn(time) => 1.0 - time / Self::FADE_IN_SECONDS,
+        };
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            VIRTUAL_W,
+            VIRTUAL_H,
+            Color {
+                a: alpha.clamp(0.0, 1.0),
+                ..BLACK
+            },
+        );
+    }
+}
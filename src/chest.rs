@@ -0,0 +1,171 @@
+use macroquad::{
+    color::WHITE,
+    math::{vec2, Rect},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{load_tex_with_meta, AssetManageResult},
+    body::Body,
+    items::ItemId,
+    object::{in_reach, GameObject, Interaction, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    popup::Popup,
+    world::World,
+    TILE_SIZE,
+};
+
+#[derive(Serialize, Deserialize)]
+struct ChestSheetSerializable {
+    frame_w: f32,
+    frame_h: f32,
+    closed: (f32, f32),
+    open: (f32, f32),
+}
+
+/// The closed and open frames every chest is drawn from.
+#[derive(Clone)]
+pub struct ChestSheet {
+    tex: Texture2D,
+    frame_w: f32,
+    frame_h: f32,
+    closed: (f32, f32),
+    open: (f32, f32),
+}
+
+impl ChestSheet {
+    const PATH: &str = "assets/art/sprites/chest.png";
+
+    pub async fn load() -> AssetManageResult<Self> {
+        let (serializable, tex): (ChestSheetSerializable, _) =
+            load_tex_with_meta(Self::PATH).await?;
+
+        return Ok(Self {
+            tex,
+            frame_w: serializable.frame_w,
+            frame_h: serializable.frame_h,
+            closed: serializable.closed,
+            open: serializable.open,
+        });
+    }
+}
+
+/// A chest that hands its contents to the player the first time they
+/// interact with it, then stays open. It fills its whole cell, and the
+/// level treats that cell as solid.
+pub struct Chest {
+    pub body: Body,
+    contents: Vec<(ItemId, u8)>,
+    opened: bool,
+    sheet: ChestSheet,
+    /// What was granted, shown for a moment after opening.
+    popup: Option<Popup>,
+}
+
+impl Chest {
+    pub fn new(
+        x: f32,
+        y: f32,
+        contents: Vec<(ItemId, u8)>,
+        opened: bool,
+        sheet: &ChestSheet,
+    ) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE, TILE_SIZE, None),
+            contents,
+            opened,
+            sheet: sheet.clone(),
+            popup: None,
+        }
+    }
+}
+
+impl GameObject for Chest {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Chest
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        return self.sleep(ctx.dt);
+    }
+
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        if let Some(popup) = &mut self.popup {
+            if !popup.update(dt) {
+                self.popup = None;
+            }
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    fn can_interact(&self, player: &Player) -> bool {
+        return !self.opened && in_reach(player, &self.body.hitbox);
+    }
+
+    /// Opens the chest and moves its contents into the player's inventory.
+    fn interact(&mut self, player: &mut Player) -> Interaction {
+        self.opened = true;
+        let granted: Vec<String> = self
+            .contents
+            .iter()
+            .map(|&(item, count)| {
+                player.inventory.add(item, count as u32);
+                format!("+{} {}", count, item.name())
+            })
+            .collect();
+
+        let text = match granted.is_empty() {
+            true => "Empty".to_owned(),
+            false => granted.join(", "),
+        };
+        self.popup = Some(Popup::new(text));
+        return Interaction::Looted;
+    }
+
+    fn render(&self, world: &World) {
+        let (x, y) = match self.opened {
+            true => self.sheet.open,
+            false => self.sheet.closed,
+        };
+
+        draw_texture_ex(
+            &self.sheet.tex,
+            self.body.hitbox.center().x - self.sheet.frame_w / 2.0 - world.x,
+            self.body.hitbox.bottom() - self.sheet.frame_h - world.y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(Rect::new(x, y, self.sheet.frame_w, self.sheet.frame_h)),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn render_indicator(&self, world: &World) {
+        if let Some(popup) = &self.popup {
+            popup.render(vec2(self.body.hitbox.center().x, self.body.hitbox.y), world);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::asset_loading::tests::stand_in_tex;
+
+    /// A one-frame sheet, for anything that spawns chests in tests.
+    pub fn chest_sheet() -> ChestSheet {
+        return ChestSheet {
+            tex: stand_in_tex(),
+            frame_w: 16.0,
+            frame_h: 16.0,
+            closed: (0.0, 0.0),
+            open: (0.0, 0.0),
+        };
+    }
+}
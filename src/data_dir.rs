@@ -0,0 +1,89 @@
+use std::{
+    cell::OnceCell,
+    path::{Path, PathBuf},
+};
+
+use macroquad::logging::{info, warn};
+
+thread_local! {
+    static DIR: OnceCell<PathBuf> = const { OnceCell::new() };
+}
+
+/// The folder name under the platform's data folder.
+const NAME: &str = "island_past_infinity";
+const PORTABLE_ARG: &str = "--portable";
+/// Files kept in the working directory before there was a data folder,
+/// moved over the first time it's made.
+const LEGACY_FILES: [&str; 2] = ["settings.json", "save.json"];
+
+/// Where the game writes what the player makes, like saves and settings,
+/// worked out the first time it's asked for and made if it isn't there.
+/// Assets the editor saves stay in the asset folder.
+///
+/// That's the platform's own place for it: `XDG_DATA_HOME` or
+/// `~/.local/share`, `%APPDATA%`, or `~/Library/Application Support`. With
+/// `--portable`, or if there's no such place or it can't be made, it's
+/// next to the game or the working directory.
+pub fn data_dir() -> PathBuf {
+    return DIR.with(|cell| cell.get_or_init(find).clone());
+}
+
+/// Where the data file `name` is kept.
+pub fn data_path(name: &str) -> PathBuf {
+    return data_dir().join(name);
+}
+
+fn find() -> PathBuf {
+    let working = std::env::current_dir().unwrap_or_default();
+    if std::env::args().any(|arg| arg == PORTABLE_ARG) {
+        let beside_exe = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        let dir = beside_exe.unwrap_or(working);
+        info!("Portable, keeping data in {}", dir.display());
+        return dir;
+    }
+
+    let Some(dir) = platform_dir() else {
+        return working;
+    };
+    if dir.is_dir() {
+        return dir;
+    }
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        warn!(
+            "Could not make {}, keeping data in the working directory: {}",
+            dir.display(),
+            err
+        );
+        return working;
+    }
+
+    info!("Keeping data in {}", dir.display());
+    for name in LEGACY_FILES {
+        let old = working.join(name);
+        if old.is_file() {
+            if let Err(err) = std::fs::copy(&old, dir.join(name)) {
+                warn!("Could not move {} over: {}", old.display(), err);
+            }
+        }
+    }
+    return dir;
+}
+
+/// The data folder the platform expects, if it has one.
+fn platform_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    let base = if cfg!(target_arch = "wasm32") {
+        None
+    } else if cfg!(windows) {
+        var("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    return base.map(|base| base.join(NAME));
+}
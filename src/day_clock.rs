@@ -0,0 +1,70 @@
+use macroquad::{
+    color::{Color, WHITE},
+    text::draw_text,
+};
+
+use crate::{VIRTUAL_H, VIRTUAL_W};
+
+/// The in-game time of day, and the ambient light it gives outdoor levels.
+pub struct DayClock {
+    /// Hours since midnight, from 0 up to 24.
+    hour: f32,
+}
+
+impl DayClock {
+    /// Real seconds each in-game hour lasts.
+    const SECONDS_PER_HOUR: f32 = 30.0;
+    const START_HOUR: f32 = 8.0;
+
+    /// Tints keyed by hour, wrapping from the last back to the first.
+    const KEYS: [(f32, Color); 6] = [
+        // Night
+        (2.0, Color::new(0.35, 0.4, 0.65, 1.0)),
+        // Dawn
+        (6.0, Color::new(0.95, 0.75, 0.7, 1.0)),
+        // Noon
+        (10.0, WHITE),
+        (16.0, WHITE),
+        // Dusk
+        (19.0, Color::new(1.0, 0.7, 0.55, 1.0)),
+        (22.0, Color::new(0.35, 0.4, 0.65, 1.0)),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            hour: Self::START_HOUR,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.hour = (self.hour + dt / Self::SECONDS_PER_HOUR).rem_euclid(24.0);
+    }
+
+    /// The daylight for the current hour, eased between the keys either
+    /// side.
+    pub fn tint(&self) -> Color {
+        let after = Self::KEYS
+            .iter()
+            .position(|(hour, _)| *hour > self.hour)
+            .unwrap_or(0);
+        let before = (after + Self::KEYS.len() - 1) % Self::KEYS.len();
+        let (from_hour, from) = Self::KEYS[before];
+        let (to_hour, to) = Self::KEYS[after];
+
+        let span = (to_hour - from_hour).rem_euclid(24.0);
+        let t = (self.hour - from_hour).rem_euclid(24.0) / span;
+        return Color::new(
+            from.r + (to.r - from.r) * t,
+            from.g + (to.g - from.g) * t,
+            from.b + (to.b - from.b) * t,
+            1.0,
+        );
+    }
+
+    /// The time, for the debug overlay.
+    pub fn render_debug(&self) {
+        let minutes = (self.hour * 60.0) as u32;
+        let text = format!("{:02}:{:02}", minutes / 60, minutes % 60);
+        draw_text(&text, VIRTUAL_W - 24.0, VIRTUAL_H - 12.0, 8.0, WHITE);
+    }
+}
@@ -0,0 +1,144 @@
+use macroquad::{
+    color::{Color, BLACK, WHITE, YELLOW},
+    math::vec2,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    texture::{draw_texture_ex, DrawTextureParams},
+    time::get_fps,
+    window::{screen_height, screen_width},
+};
+
+use crate::{
+    atlas::TileAtlas,
+    pixel_font::{draw_pixel_text_scaled, measure_pixel_text, pixel_line_height},
+};
+
+/// Frame stats drawn over the whole window, at its own size so the text
+/// stays sharp however the game is scaled.
+pub struct DebugOverlay {
+    open: bool,
+    /// Shows the tile atlas pages across the window.
+    atlas_open: bool,
+    /// Smoothed milliseconds spent on logic and rendering each frame.
+    logic_ms: f32,
+    render_ms: f32,
+}
+
+impl DebugOverlay {
+    /// Window pixels to a font pixel.
+    const SCALE: f32 = 2.0;
+    const MARGIN: f32 = 4.0;
+    /// Share of each new timing that goes into the smoothed value.
+    const SMOOTHING: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            atlas_open: false,
+            logic_ms: 0.0,
+            render_ms: 0.0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle_atlas(&mut self) {
+        self.atlas_open = !self.atlas_open;
+    }
+
+    pub fn is_atlas_open(&self) -> bool {
+        self.atlas_open
+    }
+
+    /// Takes how long this frame's logic and render took, in seconds.
+    pub fn record(&mut self, logic: f64, render: f64) {
+        self.logic_ms += (logic as f32 * 1000.0 - self.logic_ms) * Self::SMOOTHING;
+        self.render_ms += (render as f32 * 1000.0 - self.render_ms) * Self::SMOOTHING;
+    }
+
+    /// Draws the frame stats, then `lines`, down the top left of the
+    /// window. Call after the render target is drawn to the screen.
+    pub fn render(&self, dt: f32, lines: &[String]) {
+        let stats = [
+            format!("FPS: {}", get_fps()),
+            format!(
+                "Frame: {:.2}ms (logic {:.2}ms, render {:.2}ms)",
+                dt * 1000.0,
+                self.logic_ms,
+                self.render_ms
+            ),
+        ];
+        let lines: Vec<&String> = stats.iter().chain(lines).collect();
+
+        let width = lines
+            .iter()
+            .map(|line| measure_pixel_text(line) * Self::SCALE)
+            .fold(0.0, f32::max);
+        let line_height = pixel_line_height() * Self::SCALE;
+        let height = lines.len() as f32 * line_height;
+        draw_rectangle(
+            Self::MARGIN,
+            Self::MARGIN,
+            width + Self::MARGIN * 2.0,
+            height + Self::MARGIN * 2.0,
+            Color { a: 0.6, ..BLACK },
+        );
+
+        for (idx, line) in lines.iter().enumerate() {
+            draw_pixel_text_scaled(
+                line,
+                Self::MARGIN * 2.0,
+                Self::MARGIN + (idx + 1) as f32 * line_height - 4.0 * Self::SCALE,
+                Self::SCALE,
+                WHITE,
+            );
+        }
+    }
+
+    /// Draws the pages of `atlas` side by side, shrunk to fit the window,
+    /// each outlined and labelled with its size.
+    pub fn render_atlas(&self, atlas: &TileAtlas) {
+        let pages = atlas.pages();
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), BLACK);
+        if pages.is_empty() {
+            draw_pixel_text_scaled(
+                "No tilesets packed",
+                Self::MARGIN * 2.0,
+                Self::MARGIN * 2.0 + pixel_line_height() * Self::SCALE,
+                Self::SCALE,
+                WHITE,
+            );
+            return;
+        }
+
+        let label_h = pixel_line_height() * Self::SCALE;
+        let slot_w = (screen_width() - Self::MARGIN) / pages.len() as f32 - Self::MARGIN;
+        let scale = (slot_w / TileAtlas::SIZE as f32)
+            .min((screen_height() - label_h - Self::MARGIN * 3.0) / TileAtlas::SIZE as f32);
+
+        for (idx, page) in pages.iter().enumerate() {
+            let x = Self::MARGIN + idx as f32 * (slot_w + Self::MARGIN);
+            let y = Self::MARGIN * 2.0 + label_h;
+            let (w, h) = (page.width() * scale, page.height() * scale);
+            draw_texture_ex(
+                page,
+                x,
+                y,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(w, h)),
+                    ..Default::default()
+                },
+            );
+            draw_rectangle_lines(x, y, TileAtlas::SIZE as f32 * scale, h, 1.0, YELLOW);
+
+            let label = format!("Page {}: {}x{}", idx + 1, page.width(), page.height());
+            draw_pixel_text_scaled(&label, x, y - 4.0 * Self::SCALE, Self::SCALE, WHITE);
+        }
+    }
+}
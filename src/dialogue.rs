@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use macroquad::{
+    color::{Color, WHITE, YELLOW},
+    logging::warn,
+    math::vec2,
+    shapes::{draw_rectangle, draw_rectangle_lines, draw_triangle},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::load_tex,
+    input::Input,
+    pixel_font::{draw_pixel_text, measure_pixel_text, pixel_line_height},
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+/// One box of text, optionally labelled with who is speaking.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DialoguePage {
+    #[serde(default)]
+    pub speaker: Option<String>,
+    /// Name of an image in `assets/art/portraits` shown beside the text.
+    #[serde(default)]
+    pub portrait: Option<String>,
+    pub text: String,
+}
+
+/// A conversation in progress. Each page types itself out a letter at a
+/// time, faster while interact is held; interact turns a finished page.
+pub struct Dialogue {
+    pages: Vec<DialoguePage>,
+    page: usize,
+    /// Letters of the current page shown so far.
+    revealed: f32,
+    /// Seconds the dialogue has been open, for bouncing the arrow.
+    time: f32,
+    /// Whether interact has been let go since the dialogue opened, so the
+    /// press that opened it doesn't fast-forward the first page.
+    released: bool,
+    portraits: HashMap<String, Texture2D>,
+}
+
+impl Dialogue {
+    const LETTERS_PER_SECOND: f32 = 40.0;
+    /// How many times faster text types out while interact is held.
+    const FAST_FORWARD: f32 = 4.0;
+    const PORTRAIT_DIR: &str = "assets/art/portraits";
+    const PORTRAIT_SIZE: f32 = 32.0;
+    const MARGIN: f32 = 8.0;
+    const PADDING: f32 = 6.0;
+    const HEIGHT: f32 = 64.0;
+    const BACKGROUND: Color = Color::new(0.08, 0.04, 0.12, 0.9);
+
+    /// `None` if there is nothing to say. Pages too long for the box are
+    /// split into as many boxes as they need.
+    pub fn new(pages: Vec<DialoguePage>) -> Option<Self> {
+        let pages: Vec<DialoguePage> = pages.into_iter().flat_map(Self::paginate).collect();
+        if pages.is_empty() {
+            return None;
+        }
+
+        return Some(Self {
+            pages,
+            page: 0,
+            revealed: 0.0,
+            time: 0.0,
+            released: false,
+            portraits: HashMap::new(),
+        });
+    }
+
+    /// Like `new`, with the pages' portraits loaded. Portraits that fail
+    /// to load are left out.
+    pub async fn talk(pages: Vec<DialoguePage>) -> Option<Self> {
+        let mut dialogue = Self::new(pages)?;
+        for page in &dialogue.pages {
+            let Some(name) = &page.portrait else {
+                continue;
+            };
+            if dialogue.portraits.contains_key(name) {
+                continue;
+            }
+
+            match load_tex(&format!("{}/{name}.png", Self::PORTRAIT_DIR)).await {
+                Ok(tex) => {
+                    dialogue.portraits.insert(name.clone(), tex);
+                }
+                Err(err) => warn!("Could not load portrait {}: {}", name, err),
+            }
+        }
+
+        return Some(dialogue);
+    }
+
+    /// A single unattributed page, for system messages.
+    pub fn message(text: impl Into<String>) -> Option<Self> {
+        return Self::new(vec![DialoguePage {
+            speaker: None,
+            portrait: None,
+            text: text.into(),
+        }]);
+    }
+
+    /// Space to the left of the text, which a portrait takes up.
+    fn text_indent(page: &DialoguePage) -> f32 {
+        return match page.portrait {
+            Some(_) => Self::PADDING + Self::PORTRAIT_SIZE + Self::PADDING,
+            None => Self::PADDING,
+        };
+    }
+
+    fn text_width(page: &DialoguePage) -> f32 {
+        VIRTUAL_W - Self::MARGIN * 2.0 - Self::text_indent(page) - Self::PADDING
+    }
+
+    /// Splits a page into pages that each fit in the box. Every piece keeps
+    /// the speaker, whose name takes up a line of its own.
+    fn paginate(page: DialoguePage) -> Vec<DialoguePage> {
+        let rows = ((Self::HEIGHT - Self::PADDING * 2.0) / pixel_line_height()) as usize;
+        let rows = match page.speaker {
+            Some(_) => rows - 1,
+            None => rows,
+        };
+
+        return Self::wrap(&page.text, Self::text_width(&page))
+            .chunks(rows.max(1))
+            .map(|lines| DialoguePage {
+                speaker: page.speaker.clone(),
+                portrait: page.portrait.clone(),
+                text: lines.join(" "),
+            })
+            .collect();
+    }
+
+    fn page_len(&self) -> usize {
+        self.pages[self.page].text.chars().count()
+    }
+
+    fn page_done(&self) -> bool {
+        self.revealed as usize >= self.page_len()
+    }
+
+    /// Types out the current page and handles interact presses. Returns
+    /// false once the last page is closed.
+    pub fn update(&mut self, input: &Input, dt: f32) -> bool {
+        self.time += dt;
+        self.released |= !input.interact_held;
+
+        let speed = match self.released && input.interact_held {
+            true => Self::LETTERS_PER_SECOND * Self::FAST_FORWARD,
+            false => Self::LETTERS_PER_SECOND,
+        };
+        self.revealed += speed * dt;
+
+        if !input.interact || !self.page_done() {
+            return true;
+        }
+
+        self.page += 1;
+        self.revealed = 0.0;
+        return self.page < self.pages.len();
+    }
+
+    /// Splits `text` into lines no wider than `width`, breaking between
+    /// words.
+    pub fn wrap(text: &str, width: f32) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            let candidate = match line.is_empty() {
+                true => word.to_owned(),
+                false => format!("{line} {word}"),
+            };
+
+            let fits = measure_pixel_text(&candidate) <= width;
+            if fits || line.is_empty() {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, word.to_owned()));
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        return lines;
+    }
+
+    /// Draws the box along the bottom of the screen. The text is wrapped
+    /// before it is cut off at the typewriter position, so words don't jump
+    /// between lines as they appear.
+    pub fn render(&self) {
+        let x = Self::MARGIN;
+        let y = VIRTUAL_H - Self::MARGIN - Self::HEIGHT;
+        let w = VIRTUAL_W - Self::MARGIN * 2.0;
+        draw_rectangle(x, y, w, Self::HEIGHT, Self::BACKGROUND);
+        draw_rectangle_lines(x, y, w, Self::HEIGHT, 1.0, WHITE);
+
+        let page = &self.pages[self.page];
+        let portrait = page
+            .portrait
+            .as_ref()
+            .and_then(|name| self.portraits.get(name));
+        if let Some(tex) = portrait {
+            draw_texture_ex(
+                tex,
+                x + Self::PADDING,
+                y + (Self::HEIGHT - Self::PORTRAIT_SIZE) / 2.0,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(Self::PORTRAIT_SIZE, Self::PORTRAIT_SIZE)),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let text_x = x + Self::text_indent(page);
+        let line_height = pixel_line_height();
+        let mut text_y = y + Self::PADDING + line_height - 4.0;
+        if let Some(speaker) = &page.speaker {
+            draw_pixel_text(speaker, text_x, text_y, YELLOW);
+            text_y += line_height;
+        }
+
+        let mut left = self.revealed as usize;
+        for line in Self::wrap(&page.text, Self::text_width(page)) {
+            let shown: String = line.chars().take(left).collect();
+            draw_pixel_text(&shown, text_x, text_y, WHITE);
+
+            // The wrapped-away space between lines counts as a letter.
+            left = left.saturating_sub(line.chars().count() + 1);
+            text_y += line_height;
+        }
+
+        if self.page_done() {
+            let bounce = (self.time * 6.0).sin().abs() * 2.0;
+            let corner = vec2(
+                x + w - Self::PADDING,
+                y + Self::HEIGHT - Self::PADDING - bounce,
+            );
+            draw_triangle(
+                corner - vec2(6.0, 4.0),
+                corner - vec2(0.0, 4.0),
+                corner - vec2(3.0, 0.0),
+                WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use macroquad::{
+    color::WHITE,
+    math::{vec2, Rect},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{load_tex_with_meta, AssetManageResult},
+    body::Body,
+    items::ItemId,
+    object::{in_reach, GameObject, Interaction, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    popup::Popup,
+    world::World,
+    TILE_SIZE,
+};
+
+#[derive(Serialize, Deserialize)]
+struct DoorSheetSerializable {
+    frame_w: f32,
+    frame_h: f32,
+    frames: usize,
+}
+
+/// A strip of frames running from shut to fully open.
+#[derive(Clone)]
+pub struct DoorSheet {
+    tex: Texture2D,
+    frame_w: f32,
+    frame_h: f32,
+    frames: usize,
+}
+
+impl DoorSheet {
+    const PATH: &str = "assets/art/sprites/door.png";
+
+    pub async fn load() -> AssetManageResult<Self> {
+        let (serializable, tex): (DoorSheetSerializable, _) =
+            load_tex_with_meta(Self::PATH).await?;
+
+        return Ok(Self {
+            tex,
+            frame_w: serializable.frame_w,
+            frame_h: serializable.frame_h,
+            frames: serializable.frames.max(1),
+        });
+    }
+}
+
+enum DoorState {
+    Closed,
+    Opening(f32),
+    Open,
+}
+
+/// Fills its cell until opened. Doors with a `key` only open for a player
+/// carrying it, and use it up if `consume_key` is set.
+pub struct Door {
+    pub body: Body,
+    key: Option<ItemId>,
+    consume_key: bool,
+    state: DoorState,
+    sheet: DoorSheet,
+    popup: Option<Popup>,
+}
+
+impl Door {
+    const OPEN_SECONDS: f32 = 0.3;
+
+    pub fn new(
+        x: f32,
+        y: f32,
+        key: Option<ItemId>,
+        consume_key: bool,
+        opened: bool,
+        sheet: &DoorSheet,
+    ) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE, TILE_SIZE, None),
+            key,
+            consume_key,
+            state: match opened {
+                true => DoorState::Open,
+                false => DoorState::Closed,
+            },
+            sheet: sheet.clone(),
+            popup: None,
+        }
+    }
+
+    fn frame(&self) -> usize {
+        let last = self.sheet.frames - 1;
+        return match self.state {
+            DoorState::Closed => 0,
+            DoorState::Opening(time) => {
+                ((time / Self::OPEN_SECONDS * last as f32) as usize).min(last)
+            }
+            DoorState::Open => last,
+        };
+    }
+}
+
+impl GameObject for Door {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Door
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        return self.sleep(ctx.dt);
+    }
+
+    /// A door only has timers, so sleeping plays out the same as updating.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        if let DoorState::Opening(time) = self.state {
+            self.state = match time + dt >= Self::OPEN_SECONDS {
+                true => DoorState::Open,
+                false => DoorState::Opening(time + dt),
+            };
+        }
+
+        if let Some(popup) = &mut self.popup {
+            if !popup.update(dt) {
+                self.popup = None;
+            }
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    fn can_interact(&self, player: &Player) -> bool {
+        return matches!(self.state, DoorState::Closed) && in_reach(player, &self.body.hitbox);
+    }
+
+    fn interact(&mut self, player: &mut Player) -> Interaction {
+        if let Some(key) = self.key {
+            if !player.inventory.has(key, 1) {
+                self.popup = Some(Popup::new("It's locked."));
+                return Interaction::Nothing;
+            }
+            if self.consume_key {
+                player.inventory.remove(key, 1);
+            }
+        }
+
+        self.state = DoorState::Opening(0.0);
+        return Interaction::Opened;
+    }
+
+    fn render(&self, world: &World) {
+        draw_texture_ex(
+            &self.sheet.tex,
+            self.body.hitbox.center().x - self.sheet.frame_w / 2.0 - world.x,
+            self.body.hitbox.bottom() - self.sheet.frame_h - world.y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(Rect::new(
+                    self.frame() as f32 * self.sheet.frame_w,
+                    0.0,
+                    self.sheet.frame_w,
+                    self.sheet.frame_h,
+                )),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn render_indicator(&self, world: &World) {
+        if let Some(popup) = &self.popup {
+            popup.render(vec2(self.body.hitbox.center().x, self.body.hitbox.y), world);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::asset_loading::tests::stand_in_tex;
+
+    /// A one-frame sheet, for anything that spawns doors in tests.
+    pub fn door_sheet() -> DoorSheet {
+        return DoorSheet {
+            tex: stand_in_tex(),
+            frame_w: 16.0,
+            frame_h: 16.0,
+            frames: 1,
+        };
+    }
+}
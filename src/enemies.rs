@@ -1,8 +1,32 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use macroquad::{
+    color::{GREEN, RED, WHITE, YELLOW},
+    logging::warn,
+    math::{vec2, Vec2},
+    rand::RandGenerator,
+    shapes::{draw_line, draw_rectangle_lines},
+    text::draw_text,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::{body::Body, world::World};
+use crate::{
+    animator::Animation,
+    asset_loading::{deserialize, AssetManageError, AssetManageResult},
+    body::Body,
+    events::GameEvent,
+    levels::{Level, LineOfSight},
+    lights::LightEmitter,
+    loot::{self, Loot, LootDrop},
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    projectiles::{Projectile, WallBehavior},
+    sprites::Sprite,
+    world::World,
+    TILE_SIZE,
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EnemyType {
     CopperOrb,
     DeceptiveFlower,
@@ -10,20 +34,1211 @@ pub enum EnemyType {
     SeaGoblin,
 }
 
+impl EnemyType {
+    pub const ALL: [EnemyType; 4] = [
+        EnemyType::CopperOrb,
+        EnemyType::DeceptiveFlower,
+        EnemyType::PurpleBlob,
+        EnemyType::SeaGoblin,
+    ];
+
+    /// Damage and speed of the shots this type fires while chasing, if it
+    /// has a ranged attack.
+    fn projectile(&self) -> Option<(u32, f32)> {
+        match self {
+            EnemyType::SeaGoblin => Some((1, 110.0)),
+            _ => None,
+        }
+    }
+
+    /// Base name of the sprite sheet under `assets/art/sprites/enemies`.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            EnemyType::CopperOrb => "copper_orb",
+            EnemyType::DeceptiveFlower => "deceptive_flower",
+            EnemyType::PurpleBlob => "purple_blob",
+            EnemyType::SeaGoblin => "sea_goblin",
+        }
+    }
+}
+
+/// Where an enemy type is allowed to stand.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TerrainMask {
+    Land,
+    Water,
+    Both,
+    /// Rooted to its anchor: never walks and isn't knocked back. Anything
+    /// that does shift it, like its own lunge, springs back.
+    Stationary,
+}
+
+impl TerrainMask {
+    fn allows(&self, level: &Level, point: Vec2) -> bool {
+        match self {
+            TerrainMask::Land => !level.is_water_at(point.x, point.y),
+            TerrainMask::Water => level.is_water_at(point.x, point.y),
+            TerrainMask::Both | TerrainMask::Stationary => true,
+        }
+    }
+}
+
+/// Per-type tuning read from `assets/config/enemies.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct EnemyStats {
+    pub hp: u32,
+    /// Walking speed while wandering, patrolling, or returning. Rooted enemies
+    /// have zero.
+    pub move_speed: f32,
+    pub chase_speed: f32,
+    /// How close the player has to get before the enemy gives chase.
+    pub aggro_radius: f32,
+    /// Hearts taken from the player on touch.
+    pub damage: u32,
+    /// Strength of the shove given to the player on touch.
+    pub knockback: f32,
+    /// From 0 to 1, how much of the player's knockback and stun the enemy
+    /// shrugs off.
+    pub stagger_resistance: f32,
+    /// Bosses are never knocked back or stunned, whatever their resistance.
+    pub boss: bool,
+    /// Rolled once when the enemy dies.
+    pub drops: Vec<LootDrop>,
+    /// Steer away from nearby enemies of the same type instead of stacking
+    /// on top of them.
+    pub flocking: bool,
+    pub terrain: TerrainMask,
+    /// Shots bounce off walls instead of breaking on them.
+    pub bouncing_shots: bool,
+    /// Glow given off while the enemy is visible.
+    pub light: Option<LightEmitter>,
+}
+
+impl Default for EnemyStats {
+    fn default() -> Self {
+        Self {
+            hp: 3,
+            move_speed: 30.0,
+            chase_speed: 45.0,
+            aggro_radius: TILE_SIZE * 4.0,
+            damage: 1,
+            knockback: 180.0,
+            stagger_resistance: 0.0,
+            boss: false,
+            drops: Vec::new(),
+            flocking: false,
+            terrain: TerrainMask::Land,
+            bouncing_shots: false,
+            light: None,
+        }
+    }
+}
+
+pub struct EnemyStatsTable {
+    stats: HashMap<EnemyType, EnemyStats>,
+}
+
+impl EnemyStatsTable {
+    const PATH: &str = "assets/config/enemies.json";
+
+    /// Fails if the file is unreadable or leaves out any enemy type.
+    pub fn load() -> AssetManageResult<Self> {
+        return Self::read(Self::PATH);
+    }
+
+    fn read(path: &str) -> AssetManageResult<Self> {
+        let stats: HashMap<EnemyType, EnemyStats> = deserialize(path)?;
+        if let Some(missing) = EnemyType::ALL
+            .iter()
+            .find(|r#type| !stats.contains_key(r#type))
+        {
+            return Err(AssetManageError::Missing(format!(
+                "stats for {:?} in {}",
+                missing, path
+            )));
+        }
+
+        return Ok(Self { stats });
+    }
+
+    pub fn get(&self, r#type: EnemyType) -> &EnemyStats {
+        &self.stats[&r#type]
+    }
+}
+
+/// Loads each enemy type's sprite the first time it is needed, so every
+/// enemy of a type shares one texture.
+pub struct EnemySprites {
+    cache: HashMap<EnemyType, Option<Sprite>>,
+}
+
+impl EnemySprites {
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+        }
+    }
+
+    /// A sprite that can't be read is reported once and cached as `None`, so
+    /// the enemy falls back to its debug rectangle.
+    pub async fn get(&mut self, r#type: EnemyType) -> Option<Sprite> {
+        if let Entry::Vacant(entry) = self.cache.entry(r#type) {
+            let sprite = match Sprite::load_enemy(r#type.file_name()).await {
+                Ok(sprite) => Some(sprite),
+                Err(err) => {
+                    warn!("Could not load sprite for {:?}: {}", r#type, err);
+                    None
+                }
+            };
+            entry.insert(sprite);
+        }
+
+        return self.cache[&r#type].clone();
+    }
+}
+
+/// A fixed route of waypoints walked in order, pausing at each one.
+pub struct PatrolRoute {
+    waypoints: Vec<Vec2>,
+    ping_pong: bool,
+    next: usize,
+    forward: bool,
+    pause_left: f32,
+    /// Time spent walking toward `next`, used to skip waypoints that can't
+    /// be reached.
+    leg_time: f32,
+}
+
+impl PatrolRoute {
+    const PAUSE_SECONDS: f32 = 0.75;
+    const LEG_TIMEOUT_SECONDS: f32 = 4.0;
+
+    pub fn new(waypoints: Vec<Vec2>, ping_pong: bool) -> Self {
+        Self {
+            waypoints,
+            ping_pong,
+            next: 0,
+            forward: true,
+            pause_left: 0.0,
+            leg_time: 0.0,
+        }
+    }
+
+    fn advance(&mut self) {
+        self.leg_time = 0.0;
+        let last = self.waypoints.len() - 1;
+        if last == 0 {
+            return;
+        }
+
+        match self.ping_pong {
+            true => {
+                if (self.forward && self.next == last) || (!self.forward && self.next == 0) {
+                    self.forward = !self.forward;
+                }
+                self.next = match self.forward {
+                    true => self.next + 1,
+                    false => self.next - 1,
+                };
+            }
+            false => self.next = (self.next + 1) % self.waypoints.len(),
+        }
+    }
+
+    fn step(&mut self, center: Vec2, speed: f32, dt: f32) -> Vec2 {
+        if self.pause_left > 0.0 {
+            self.pause_left -= dt;
+            return Vec2::ZERO;
+        }
+
+        let target = self.waypoints[self.next];
+        if center.distance(target) <= Enemy::ARRIVE_DISTANCE {
+            self.pause_left = Self::PAUSE_SECONDS;
+            self.advance();
+            return Vec2::ZERO;
+        }
+
+        self.leg_time += dt;
+        if self.leg_time >= Self::LEG_TIMEOUT_SECONDS {
+            self.advance();
+            return Vec2::ZERO;
+        }
+
+        return (target - center).normalize_or_zero() * speed;
+    }
+}
+
+#[derive(Debug)]
+enum EnemyState {
+    Idle {
+        time_left: f32,
+    },
+    Wander {
+        direction: Vec2,
+        time_left: f32,
+    },
+    Patrol,
+    /// `lost_time` counts how long the player has been out of sight or
+    /// outside the de-aggro radius; `last_seen` is where they were before
+    /// that.
+    Chase {
+        lost_time: f32,
+        last_seen: Vec2,
+    },
+    Return,
+    /// Knocked off balance by a hit; the enemy does nothing until it
+    /// recovers, then chases whoever hit it.
+    Stunned {
+        time_left: f32,
+    },
+    /// An ambusher passing for scenery, harmless until the player steps
+    /// too close.
+    Disguised,
+    /// Playing the reveal animation before the ambush starts.
+    Revealing,
+    /// Revealed and snapping at the player every `LUNGE_SECONDS`.
+    /// `away_time` counts how long the player has been outside
+    /// `DISGUISE_RADIUS`.
+    Ambush {
+        lunge_time: f32,
+        away_time: f32,
+    },
+}
+
+impl EnemyState {
+    fn label(&self) -> &'static str {
+        match self {
+            EnemyState::Idle { .. } => "Idle",
+            EnemyState::Wander { .. } => "Wander",
+            EnemyState::Patrol => "Patrol",
+            EnemyState::Chase { .. } => "Chase",
+            EnemyState::Return => "Return",
+            EnemyState::Stunned { .. } => "Stunned",
+            EnemyState::Disguised => "Disguised",
+            EnemyState::Revealing => "Revealing",
+            EnemyState::Ambush { .. } => "Ambush",
+        }
+    }
+}
+
 pub struct Enemy {
     pub body: Body,
     r#type: EnemyType,
+    stats: EnemyStats,
+    hp: u32,
+    /// Damage taken since the last update, reported to the event bus there.
+    damage_taken: u32,
+    /// The last player swing that landed, so one swing only hits once.
+    last_swing: Option<u32>,
+    /// Where the enemy spawned. Wandering never strays further than
+    /// `WANDER_RADIUS` from here.
+    anchor: Vec2,
+    state: EnemyState,
+    patrol: Option<PatrolRoute>,
+    fire_cooldown: f32,
+    /// Push away from same-type neighbours, set each frame for flocking
+    /// enemies.
+    separation: Vec2,
+    /// Time left showing the "!" that pops up on noticing the player.
+    alert_time: f32,
+    /// Cached line of sight to the player and where the player was when it
+    /// was cast.
+    sight: LineOfSight,
+    sight_target: Vec2,
+    sight_timer: f32,
+    rng: RandGenerator,
 }
 
 impl Enemy {
-    pub fn new(r#type: EnemyType, x: f32, y: f32) -> Self {
-        return Enemy {
-            body: Body::new(x, y, 16.0, 16.0, None),
+    pub const SIZE: f32 = 16.0;
+    const WANDER_RADIUS: f32 = TILE_SIZE * 3.0;
+    /// The de-aggro radius is the aggro radius times this.
+    const DEAGGRO_SCALE: f32 = 1.75;
+    /// Losing the player for less than this never drops aggro.
+    const SIGHT_GRACE_SECONDS: f32 = 1.5;
+    /// Past the grace period, the longest the enemy keeps heading for where
+    /// it last saw the player before giving up anyway.
+    const GIVE_UP_SECONDS: f32 = 4.0;
+    const ALERT_SECONDS: f32 = 0.5;
+    /// Flocking enemies closer than this push each other apart.
+    pub const SEPARATION_RADIUS: f32 = Self::SIZE * 1.5;
+    /// Push strength; the push falls off with the inverse of distance.
+    const SEPARATION_STRENGTH: f32 = 400.0;
+    /// The push never gets stronger than this fraction of the chase speed,
+    /// so it can't stop an enemy from chasing.
+    const SEPARATION_CAP: f32 = 0.75;
+    /// How fast a stationary enemy is pulled back to its anchor, as a
+    /// fraction of the gap per second.
+    const ROOT_SPRING: f32 = 10.0;
+    /// An ambusher reveals itself when the player's hitbox comes this close.
+    const AMBUSH_RADIUS: f32 = TILE_SIZE;
+    /// Past this distance for `DISGUISE_SECONDS`, an ambusher hides again.
+    const DISGUISE_RADIUS: f32 = TILE_SIZE * 3.0;
+    const DISGUISE_SECONDS: f32 = 2.0;
+    const LUNGE_SECONDS: f32 = 1.5;
+    const LUNGE_IMPULSE: f32 = 220.0;
+    const ARRIVE_DISTANCE: f32 = 2.0;
+    const SIGHT_SECONDS: f32 = 0.2;
+    const STUN_SECONDS: f32 = 0.3;
+    const FLASH_SECONDS: f32 = 0.05;
+
+    /// `seed` drives the enemy's wandering, so the same seed always wanders
+    /// the same way. Enemies with a patrol route walk it instead.
+    pub fn new(
+        r#type: EnemyType,
+        stats: EnemyStats,
+        x: f32,
+        y: f32,
+        sprite: Option<Sprite>,
+        seed: u64,
+        patrol: Option<PatrolRoute>,
+    ) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+
+        let mut enemy = Enemy {
+            body: Body::new(x, y, Self::SIZE, Self::SIZE, sprite),
             r#type,
+            hp: stats.hp,
+            last_swing: None,
+            stats,
+            anchor: vec2(x, y),
+            state: EnemyState::Return,
+            patrol,
+            fire_cooldown: 0.0,
+            separation: Vec2::ZERO,
+            alert_time: 0.0,
+            damage_taken: 0,
+            sight: LineOfSight::Blocked,
+            sight_target: vec2(x, y),
+            sight_timer: 0.0,
+            rng,
+        };
+        enemy.fire_cooldown = enemy.fire_interval();
+        enemy.state = enemy.idle();
+        return enemy;
+    }
+
+    /// The resting state: disguised for ambushers, walking the patrol if
+    /// there is one, otherwise standing still before the next wander.
+    fn idle(&self) -> EnemyState {
+        if self.is_ambusher() {
+            return EnemyState::Disguised;
+        }
+
+        match self.patrol {
+            Some(_) => EnemyState::Patrol,
+            None => EnemyState::Idle {
+                time_left: self.rng.gen_range(1.0, 2.0),
+            },
+        }
+    }
+
+    fn fire_interval(&self) -> f32 {
+        self.rng.gen_range(2.0, 3.0)
+    }
+
+    /// Re-casts the ray to the player every `SIGHT_SECONDS`, keeping the
+    /// last result in between.
+    fn update_sight(&mut self, level: &Level, player: &Player, dt: f32) {
+        self.sight_timer -= dt;
+        if self.sight_timer > 0.0 {
+            return;
+        }
+
+        self.sight_timer = Self::SIGHT_SECONDS;
+        self.sight_target = player.body.hitbox.center();
+        self.sight = level.line_of_sight(self.body.hitbox.center(), self.sight_target);
+    }
+
+    /// Counts down while chasing a visible player and fires a shot at where
+    /// they stand each time the cooldown runs out.
+    fn try_fire(&mut self, player: &Player, dt: f32) -> Option<Projectile> {
+        let (damage, speed) = self.r#type.projectile()?;
+        if !matches!(self.state, EnemyState::Chase { .. }) || player.is_dead() {
+            return None;
+        }
+
+        if self.sight != LineOfSight::Clear {
+            return None;
+        }
+
+        let origin = self.body.hitbox.center();
+        let target = player.body.hitbox.center();
+
+        self.fire_cooldown -= dt;
+        if self.fire_cooldown > 0.0 {
+            return None;
+        }
+        self.fire_cooldown = self.fire_interval();
+
+        let velocity = (target - origin).normalize_or_zero() * speed;
+        let wall = match self.stats.bouncing_shots {
+            true => WallBehavior::Bounce,
+            false => WallBehavior::Despawn,
+        };
+        return Some(Projectile::new(
+            origin,
+            velocity,
+            damage,
+            self.stats.knockback,
+            wall,
+        ));
+    }
+
+    /// Of the eight compass directions, the ones a body of `size` at
+    /// `center` could take a full tile's step in without touching anything
+    /// solid or leaving `terrain`.
+    fn open_directions(level: &Level, center: Vec2, size: Vec2, terrain: TerrainMask) -> Vec<Vec2> {
+        return (0..8)
+            .map(|idx| Vec2::from_angle(idx as f32 * std::f32::consts::FRAC_PI_4))
+            .filter(|direction| {
+                let ahead = center + *direction * TILE_SIZE;
+                let probe = Body::new(ahead.x, ahead.y, size.x, size.y, None);
+                !probe.overlaps_solid(level) && terrain.allows(level, ahead)
+            })
+            .collect();
+    }
+
+    /// Walks off in a random open direction, or keeps idling if boxed in.
+    fn wander(&self, level: &Level) -> EnemyState {
+        let open = Self::open_directions(
+            level,
+            self.body.hitbox.center(),
+            self.body.hitbox.size(),
+            self.stats.terrain,
+        );
+        if open.is_empty() {
+            return self.idle();
+        }
+
+        return EnemyState::Wander {
+            direction: open[self.rng.gen_range(0, open.len())],
+            time_left: self.rng.gen_range(0.5, 1.5),
         };
     }
 
-    pub fn render(&self, world: &World) {
+    /// The DeceptiveFlower lies in wait instead of wandering and chasing.
+    fn is_ambusher(&self) -> bool {
+        self.r#type == EnemyType::DeceptiveFlower
+    }
+
+    /// The ambusher's side of the state machine. It never walks; lunges are
+    /// impulses toward the player.
+    ///
+    /// - Disguised switches to Revealing once the player's hitbox is within
+    ///   `AMBUSH_RADIUS`.
+    /// - Revealing switches to Ambush when the reveal animation finishes.
+    /// - Ambush lunges every `LUNGE_SECONDS` and goes back to Disguised once
+    ///   the player has stayed beyond `DISGUISE_RADIUS` for
+    ///   `DISGUISE_SECONDS`.
+    fn think_ambush(&mut self, player: &Player, dt: f32) -> Vec2 {
+        let center = self.body.hitbox.center();
+        let target = player.body.hitbox.center();
+        let hitbox = player.body.hitbox;
+        let nearest = center.clamp(hitbox.point(), hitbox.point() + hitbox.size());
+
+        match &mut self.state {
+            EnemyState::Disguised => {
+                if !player.is_dead() && center.distance(nearest) <= Self::AMBUSH_RADIUS {
+                    self.state = EnemyState::Revealing;
+                }
+            }
+            EnemyState::Revealing => {
+                if self.body.animation_finished() {
+                    self.state = EnemyState::Ambush {
+                        lunge_time: 0.0,
+                        away_time: 0.0,
+                    };
+                }
+            }
+            EnemyState::Ambush {
+                lunge_time,
+                away_time,
+            } => {
+                match player.is_dead() || center.distance(target) > Self::DISGUISE_RADIUS {
+                    true => *away_time += dt,
+                    false => *away_time = 0.0,
+                }
+                if *away_time >= Self::DISGUISE_SECONDS {
+                    self.state = EnemyState::Disguised;
+                    return Vec2::ZERO;
+                }
+
+                *lunge_time -= dt;
+                if *lunge_time <= 0.0 && *away_time == 0.0 {
+                    *lunge_time = Self::LUNGE_SECONDS;
+                    let toward = (target - center).normalize_or_zero();
+                    self.body.apply_impulse(toward * Self::LUNGE_IMPULSE);
+                }
+            }
+            _ => self.state = self.idle(),
+        }
+
+        return Vec2::ZERO;
+    }
+
+    /// Runs the state machine for one frame and returns the velocity to move
+    /// at.
+    ///
+    /// - Idle/Wander/Patrol/Return switch to Chase when the player is inside
+    ///   the aggro radius and not hidden behind a wall. Low cover doesn't
+    ///   hide them.
+    /// - Idle and Wander alternate on timers. Wandering only sets off in
+    ///   directions that aren't walled off, and heads back toward the anchor
+    ///   whenever a step would leave the wander radius.
+    /// - Patrol walks the route until something aggros the enemy.
+    /// - Chase follows the player while they're in sight and inside the
+    ///   de-aggro radius, and heads for where they were last seen otherwise.
+    ///   It gives up into Return once they've been lost for
+    ///   `SIGHT_GRACE_SECONDS` and the last-seen spot turned up nothing, or
+    ///   after `GIVE_UP_SECONDS` regardless.
+    /// - Return goes Idle on reaching the anchor.
+    fn think(&mut self, level: &Level, player: &Player, dt: f32) -> Vec2 {
+        if self.is_ambusher() {
+            return self.think_ambush(player, dt);
+        }
+
+        let center = self.body.hitbox.center();
+        let target = player.body.hitbox.center();
+        let player_distance = match player.is_dead() {
+            true => f32::INFINITY,
+            false => center.distance(target),
+        };
+        let aggro = self.stats.aggro_radius;
+
+        if player_distance <= aggro
+            && self.sight != LineOfSight::Blocked
+            && !matches!(
+                self.state,
+                EnemyState::Chase { .. } | EnemyState::Stunned { .. }
+            )
+        {
+            self.state = EnemyState::Chase {
+                lost_time: 0.0,
+                last_seen: target,
+            };
+            self.alert_time = Self::ALERT_SECONDS;
+        }
+
+        match &mut self.state {
+            EnemyState::Idle { time_left } => {
+                *time_left -= dt;
+                if *time_left <= 0.0 {
+                    self.state = self.wander(level);
+                }
+                return Vec2::ZERO;
+            }
+            EnemyState::Wander {
+                direction,
+                time_left,
+            } => {
+                let speed = self.stats.move_speed;
+                *time_left -= dt;
+                if center.distance(self.anchor) + speed * dt > Self::WANDER_RADIUS {
+                    *direction = (self.anchor - center).normalize_or_zero();
+                }
+                let delta = *direction * speed;
+
+                if *time_left <= 0.0 {
+                    self.state = self.idle();
+                }
+                return delta;
+            }
+            EnemyState::Patrol => {
+                let speed = self.stats.move_speed;
+                return match &mut self.patrol {
+                    Some(patrol) => patrol.step(center, speed, dt),
+                    None => Vec2::ZERO,
+                };
+            }
+            EnemyState::Chase {
+                lost_time,
+                last_seen,
+            } => {
+                let in_sight = player_distance <= aggro * Self::DEAGGRO_SCALE
+                    && self.sight != LineOfSight::Blocked;
+                match in_sight {
+                    true => {
+                        *lost_time = 0.0;
+                        *last_seen = target;
+                    }
+                    false => *lost_time += dt,
+                }
+
+                let arrived = center.distance(*last_seen) <= Self::ARRIVE_DISTANCE;
+                if *lost_time >= Self::GIVE_UP_SECONDS
+                    || (*lost_time >= Self::SIGHT_GRACE_SECONDS && arrived)
+                {
+                    self.state = EnemyState::Return;
+                    return Vec2::ZERO;
+                }
+
+                if player_distance.is_infinite() || arrived {
+                    return Vec2::ZERO;
+                }
+                return (*last_seen - center).normalize_or_zero() * self.stats.chase_speed;
+            }
+            EnemyState::Stunned { time_left } => {
+                *time_left -= dt;
+                if *time_left <= 0.0 {
+                    self.state = EnemyState::Chase {
+                        lost_time: 0.0,
+                        last_seen: target,
+                    };
+                }
+                return Vec2::ZERO;
+            }
+            EnemyState::Return => {
+                if center.distance(self.anchor) <= Self::ARRIVE_DISTANCE {
+                    self.state = self.idle();
+                    return Vec2::ZERO;
+                }
+                return (self.anchor - center).normalize_or_zero() * self.stats.move_speed;
+            }
+            EnemyState::Disguised | EnemyState::Revealing | EnemyState::Ambush { .. } => {
+                self.state = self.idle();
+                return Vec2::ZERO;
+            }
+        }
+    }
+
+    /// The DeceptiveFlower passes for a plain flower until its ambush
+    /// springs. Every other type is always revealed.
+    pub fn is_revealed(&self) -> bool {
+        match self.is_ambusher() {
+            true => matches!(self.state, EnemyState::Ambush { .. }),
+            false => true,
+        }
+    }
+
+    /// Damage and knockback dealt to a player touching this enemy right now,
+    /// if any.
+    pub fn contact_damage(&self) -> Option<(u32, f32)> {
+        match self.is_revealed() {
+            true => Some((self.stats.damage, self.stats.knockback)),
+            false => None,
+        }
+    }
+
+    /// Hurts the enemy and knocks it away from `source` with an impulse of
+    /// `knockback`, stunning it briefly. Stagger resistance shortens both the
+    /// shove and the stun.
+    pub fn take_damage(&mut self, amount: u32, source: Vec2, knockback: f32) {
+        self.hp = self.hp.saturating_sub(amount);
+        self.damage_taken += amount;
+        self.body.flash(Self::FLASH_SECONDS);
+        if matches!(self.state, EnemyState::Disguised) {
+            self.state = EnemyState::Revealing;
+        }
+        if self.stats.boss {
+            return;
+        }
+
+        if self.stats.terrain == TerrainMask::Stationary {
+            return;
+        }
+
+        let stagger = 1.0 - self.stats.stagger_resistance.clamp(0.0, 1.0);
+        let away = (self.body.hitbox.center() - source).normalize_or_zero();
+        self.body.apply_impulse(away * knockback * stagger);
+
+        let stun = Self::STUN_SECONDS * stagger;
+        if stun > 0.0 {
+            self.state = EnemyState::Stunned { time_left: stun };
+        }
+    }
+
+    /// Records `swing` as having hit this enemy. Returns false if it already
+    /// had.
+    pub fn mark_swing(&mut self, swing: u32) -> bool {
+        return self.last_swing.replace(swing) != Some(swing);
+    }
+
+    pub fn r#type(&self) -> EnemyType {
+        self.r#type
+    }
+
+    pub fn is_flocking(&self) -> bool {
+        self.stats.flocking
+    }
+
+    /// Sets the push for the next update from the offsets (this enemy's
+    /// center minus theirs) to each same-type neighbour.
+    pub fn set_separation(&mut self, offsets: &[Vec2]) {
+        let push: Vec2 = offsets
+            .iter()
+            .filter(|offset| offset.length() < Self::SEPARATION_RADIUS)
+            .map(|offset| offset.normalize_or_zero() * Self::SEPARATION_STRENGTH / offset.length())
+            .sum();
+        self.separation = push.clamp_length_max(self.stats.chase_speed * Self::SEPARATION_CAP);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hp == 0
+    }
+
+    /// Rolls the drop table, returning each drop and where.
+    pub fn roll_drops(&self, level: &Level) -> Vec<(Loot, Vec2)> {
+        return loot::roll(
+            &self.stats.drops,
+            &self.rng,
+            level,
+            self.body.hitbox.center(),
+        );
+    }
+}
+
+impl GameObject for Enemy {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Enemy
+    }
+
+    /// A dead enemy drops its loot and reports its death on the way out. A
+    /// living one may fire a shot.
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        if self.damage_taken > 0 {
+            ctx.events.push(GameEvent::EnemyDamaged {
+                amount: std::mem::take(&mut self.damage_taken),
+                position: vec2(self.body.hitbox.center().x, self.body.hitbox.y),
+                critical: false,
+            });
+        }
+
+        if self.is_dead() {
+            for (loot, Vec2 { x, y }) in self.roll_drops(ctx.level) {
+                ctx.spawns.push(loot.spawn(x, y, ctx.item_sheet));
+            }
+            ctx.events.push(GameEvent::EnemyDied {
+                kind: self.r#type,
+                position: self.body.hitbox.center(),
+            });
+            if self.stats.boss {
+                ctx.events.push(GameEvent::ScreenFlash {
+                    color: WHITE,
+                    alpha: 0.8,
+                    seconds: 0.6,
+                });
+            }
+            return ObjectStatus::Dead;
+        }
+
+        let (level, player, dt) = (ctx.level, ctx.player, ctx.dt);
+        self.update_sight(level, player, dt);
+        self.alert_time = (self.alert_time - dt).max(0.0);
+        let mut delta = self.think(level, player, dt);
+        if !matches!(self.state, EnemyState::Stunned { .. }) {
+            delta += self.separation;
+        }
+        if self.stats.terrain == TerrainMask::Stationary {
+            delta = (self.anchor - self.body.hitbox.center()) * Self::ROOT_SPRING;
+        }
+        self.body.set_animation(match self.state {
+            EnemyState::Stunned { .. } => Animation::Hit,
+            EnemyState::Disguised => Animation::Disguise,
+            EnemyState::Revealing => Animation::Reveal,
+            _ => Animation::Walk,
+        });
+        let before = self.body.hitbox;
+        let result = self.body.r#move(delta, level, dt);
+        let mut blocked = result.blocked_x || result.blocked_y;
+
+        // Undo any step off the enemy's terrain, so a chase stops at the
+        // shoreline and knockback can't strand it on the wrong side.
+        if !self.stats.terrain.allows(level, self.body.hitbox.center()) {
+            self.body.hitbox = before;
+            self.body.clear_impulse();
+            blocked = true;
+        }
+
+        // Cut a walk short on bumping into something rather than sliding
+        // along it for the rest of the segment.
+        if matches!(self.state, EnemyState::Wander { .. }) && blocked {
+            self.state = self.idle();
+        }
+
+        if let Some(shot) = self.try_fire(player, dt) {
+            ctx.spawns.push(Box::new(shot));
+        }
+        return ObjectStatus::Alive;
+    }
+
+    /// Runs down the state's timers and the alert. Any state change they
+    /// lead to waits for the first update after waking. A far-off player
+    /// counts as out of sight, so a chase or ambush winds down meanwhile.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        self.alert_time = (self.alert_time - dt).max(0.0);
+        match &mut self.state {
+            EnemyState::Idle { time_left }
+            | EnemyState::Wander { time_left, .. }
+            | EnemyState::Stunned { time_left } => *time_left -= dt,
+            EnemyState::Chase { lost_time, .. } => *lost_time += dt,
+            EnemyState::Ambush { away_time, .. } => *away_time += dt,
+            _ => {}
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    /// Lets the player's swing land, then hurts the player on touch.
+    fn touch_player(&mut self, player: &mut Player) {
+        if let Some(attack) = player.attack() {
+            if attack.hitbox.overlaps(&self.body.hitbox) && self.mark_swing(attack.swing) {
+                self.take_damage(attack.damage, player.body.hitbox.center(), attack.knockback);
+            }
+        }
+
+        if let Some((damage, knockback)) = self.contact_damage() {
+            if !self.is_dead() && self.body.hitbox.overlaps(&player.body.hitbox) {
+                player.take_damage(damage, self.body.hitbox.center(), knockback);
+            }
+        }
+    }
+
+    fn render(&self, world: &World) {
         self.body.render(world);
     }
+
+    /// Disguised ambushers don't give themselves away by glowing.
+    fn light(&self) -> Option<&LightEmitter> {
+        match self.state {
+            EnemyState::Disguised => None,
+            _ => self.stats.light.as_ref(),
+        }
+    }
+
+    /// Draws "!" above the enemy just after it notices the player and "?"
+    /// while it heads home having lost them.
+    fn render_indicator(&self, world: &World) {
+        let text = match self.state {
+            _ if self.alert_time > 0.0 => "!",
+            EnemyState::Return => "?",
+            _ => return,
+        };
+
+        let x = self.body.hitbox.center().x - world.x - 2.0;
+        let y = self.body.hitbox.top() - world.y - 4.0;
+        draw_text(text, x, y, 12.0, YELLOW);
+    }
+
+    /// Hitbox outline, AI state, and the last line-of-sight ray, for tuning.
+    /// Disguised ambushers stay hidden here too.
+    fn render_debug(&self, world: &World) {
+        if matches!(self.state, EnemyState::Disguised) {
+            return;
+        }
+
+        let x = self.body.screen_x(world);
+        let y = self.body.screen_y(world);
+
+        let from = self.body.hitbox.center();
+        let ray_color = match self.sight {
+            LineOfSight::Clear => GREEN,
+            LineOfSight::Partial => YELLOW,
+            LineOfSight::Blocked => RED,
+        };
+        draw_line(
+            from.x - world.x,
+            from.y - world.y,
+            self.sight_target.x - world.x,
+            self.sight_target.y - world.y,
+            1.0,
+            ray_color,
+        );
+
+        draw_rectangle_lines(x, y, self.body.hitbox.w, self.body.hitbox.h, 1.0, RED);
+        draw_text(self.state.label(), x, y - 2.0, 8.0, WHITE);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        events::EventBus,
+        input::Input,
+        levels::tests::level,
+        object::{
+            tests::{block_on, object_assets, only_object, update},
+            LevelObjects,
+        },
+        player::tests::player_at,
+        player::PlayerConfig,
+        spawner::Spawner,
+        sprites::{tests::sprite, SpriteFrameSpan},
+        world::World,
+    };
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    /// A cache where every type's sprite is already known to be missing, so
+    /// enemies spawned in tests never load one.
+    pub fn no_sprites() -> EnemySprites {
+        return EnemySprites {
+            cache: EnemyType::ALL
+                .iter()
+                .map(|r#type| (*r#type, None))
+                .collect(),
+        };
+    }
+
+    fn enemy(terrain: TerrainMask, x: f32, y: f32, seed: u64) -> Enemy {
+        let stats = EnemyStats {
+            terrain,
+            ..EnemyStats::default()
+        };
+        return Enemy::new(EnemyType::PurpleBlob, stats, x, y, None, seed, None);
+    }
+
+    fn open_field() -> Level {
+        return level(&["............"; 12]);
+    }
+
+    /// Holds the player and `enemy` overlapping for `frames`, and counts the
+    /// hits the player takes.
+    fn hits_while_overlapping(enemy: &mut Enemy, player: &mut Player, frames: usize) -> u32 {
+        let level = open_field();
+        let start = player.body.hitbox.center();
+        let mut hits = 0;
+        for _ in 0..frames {
+            let hp = player.hp;
+            enemy.touch_player(player);
+            hits += hp - player.hp;
+
+            player.move_player(&level, &Input::default(), FRAME);
+            player.body.set_center(start);
+            player.body.clear_impulse();
+        }
+        return hits;
+    }
+
+    #[test]
+    fn stats_table_reads_every_type() {
+        let table = EnemyStatsTable::read("tests/fixtures/enemies.json").unwrap();
+        let goblin = table.get(EnemyType::SeaGoblin);
+        assert_eq!((goblin.hp, goblin.damage), (5, 2));
+        assert_eq!(goblin.terrain, TerrainMask::Water);
+        assert_eq!(goblin.drops.len(), 1);
+
+        // Left out fields keep their defaults.
+        let blob = table.get(EnemyType::PurpleBlob);
+        assert_eq!(blob.hp, 2);
+        assert!(blob.flocking);
+        assert_eq!(blob.chase_speed, EnemyStats::default().chase_speed);
+        assert!(blob.light.is_none());
+    }
+
+    #[test]
+    fn stats_ignore_fields_they_dont_know() {
+        let stats: EnemyStats =
+            serde_json::from_str(r#"{ "hp": 7, "mood": "grumpy", "spawn": { "weight": 2 } }"#)
+                .unwrap();
+        assert_eq!(stats.hp, 7);
+        assert_eq!(stats.damage, EnemyStats::default().damage);
+    }
+
+    #[test]
+    fn stats_tables_missing_a_type_say_which() {
+        let path = "tests/fixtures/enemies_missing_type.json";
+        let err = EnemyStatsTable::read(path).err().unwrap();
+        assert!(matches!(err, AssetManageError::Missing(_)));
+        assert_eq!(
+            err.to_string(),
+            format!("missing stats for SeaGoblin in {path}")
+        );
+    }
+
+    #[test]
+    fn contact_damage_fires_once_per_invincibility_window() {
+        let mut enemy = enemy(TerrainMask::Land, 96.0, 96.0, 0);
+        let mut player = player_at(vec2(100.0, 96.0));
+        let window = PlayerConfig::default().invincible_seconds;
+        let frames = (window / FRAME).floor() as usize;
+
+        assert_eq!(hits_while_overlapping(&mut enemy, &mut player, frames), 1);
+        assert_eq!(hits_while_overlapping(&mut enemy, &mut player, 2), 1);
+    }
+
+    #[test]
+    fn disguised_flower_deals_no_contact_damage() {
+        let stats = EnemyStats::default();
+        let mut flower = Enemy::new(EnemyType::DeceptiveFlower, stats, 96.0, 96.0, None, 0, None);
+        let mut player = player_at(vec2(100.0, 96.0));
+        flower.state = EnemyState::Disguised;
+        assert_eq!(hits_while_overlapping(&mut flower, &mut player, 1), 0);
+
+        flower.state = EnemyState::Ambush {
+            lunge_time: 1.0,
+            away_time: 0.0,
+        };
+        assert_eq!(hits_while_overlapping(&mut flower, &mut player, 1), 1);
+    }
+
+    #[test]
+    fn wander_only_picks_open_directions() {
+        // Walls above, to the left and below; only the right is open.
+        let level = level(&[".....", "..#..", ".#...", "..#..", "....."]);
+        let center = vec2(40.0, 40.0);
+        let size = Vec2::splat(Enemy::SIZE);
+        let open = Enemy::open_directions(&level, center, size, TerrainMask::Land);
+        assert_eq!(open.len(), 1);
+        assert!(open[0].abs_diff_eq(Vec2::X, 1e-6), "{open:?}");
+
+        for seed in 0..10 {
+            let enemy = enemy(TerrainMask::Land, center.x, center.y, seed);
+            match enemy.wander(&level) {
+                EnemyState::Wander { direction, .. } => {
+                    assert!(direction.abs_diff_eq(Vec2::X, 1e-6), "{direction:?}")
+                }
+                state => panic!("seed {seed} went {state:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn boxed_in_enemies_idle_instead_of_wandering() {
+        let level = level(&["###", "#.#", "###"]);
+        let enemy = enemy(TerrainMask::Land, 24.0, 24.0, 0);
+        let open = Enemy::open_directions(
+            &level,
+            enemy.body.hitbox.center(),
+            enemy.body.hitbox.size(),
+            TerrainMask::Land,
+        );
+        assert!(open.is_empty(), "{open:?}");
+        assert!(matches!(enemy.wander(&level), EnemyState::Idle { .. }));
+    }
+
+    #[test]
+    fn water_enemies_stop_at_the_shore() {
+        // Water on the left half, land on the right.
+        let level = level(&["~~~~~~......"; 12]);
+        let start = vec2(40.0, 96.0);
+        let mut player = player_at(vec2(100.0, 96.0));
+        let mut enemy = enemy(TerrainMask::Water, start.x, start.y, 0);
+
+        // Walk the player inland, dragging the chase out of the water.
+        for frame in 0..60 * 3 {
+            let x = 100.0 + frame as f32 * 0.5;
+            player.body.set_center(vec2(x, 96.0));
+            update(&mut enemy, &level, &player, FRAME);
+
+            let center = enemy.body.hitbox.center();
+            assert!(
+                level.is_water_at(center.x, center.y),
+                "left the water at {center} on frame {frame}"
+            );
+        }
+        assert!(matches!(enemy.state, EnemyState::Chase { .. }));
+        assert!(enemy.body.hitbox.center().x > start.x + TILE_SIZE);
+    }
+
+    #[test]
+    fn wander_never_leaves_the_radius() {
+        let level = open_field();
+        let player = player_at(vec2(1000.0, 1000.0));
+
+        for seed in 0..20 {
+            let mut enemy = enemy(TerrainMask::Land, 96.0, 96.0, seed);
+            let mut wandered = false;
+            for _ in 0..60 * 30 {
+                let updated = update(&mut enemy, &level, &player, FRAME);
+                assert!(matches!(updated.status, ObjectStatus::Alive));
+                assert!(updated.spawns.is_empty() && updated.events.is_empty());
+                wandered |= matches!(enemy.state, EnemyState::Wander { .. });
+                let distance = enemy.body.hitbox.center().distance(enemy.anchor);
+                assert!(
+                    distance <= Enemy::WANDER_RADIUS,
+                    "seed {seed} strayed {distance}"
+                );
+            }
+            assert!(wandered, "seed {seed} never wandered");
+        }
+    }
+
+    /// Runs `frames` updates of `objects` with the view at `world`.
+    fn run(objects: &mut LevelObjects, level: &Level, world: &World, frames: usize) {
+        let mut player = player_at(vec2(1000.0, 1000.0));
+        let mut events = EventBus::new();
+        for _ in 0..frames {
+            block_on(objects.update(level, &mut player, world, &mut events, FRAME));
+        }
+    }
+
+    #[test]
+    fn sleeping_enemies_tick_timers_but_neither_think_nor_restart_animations() {
+        let level = open_field();
+        let reveal = SpriteFrameSpan {
+            start_frame: 0,
+            number_of_frames: 1,
+            duration_seconds: 1.0,
+        };
+        let sprite = Sprite {
+            reveal: Some(reveal),
+            ..sprite()
+        };
+        let stats = EnemyStats::default();
+        let mut flower = Enemy::new(
+            EnemyType::DeceptiveFlower,
+            stats,
+            96.0,
+            96.0,
+            Some(sprite),
+            0,
+            None,
+        );
+        flower.state = EnemyState::Revealing;
+        flower.body.set_animation(Animation::Reveal);
+        flower.alert_time = 5.0;
+
+        let mut objects = LevelObjects::new(object_assets());
+        objects.set_sleep_screens(0.25);
+        objects.spawn(Box::new(flower));
+        let mut near = World::new();
+        near.center_on(vec2(96.0, 96.0));
+        // Its hitbox is 150px right of this view: past a quarter screen, but
+        // within the default half screen.
+        let mut far = World::new();
+        far.x = 96.0 - Enemy::SIZE / 2.0 - 150.0 - far.w;
+
+        run(&mut objects, &level, &near, 30);
+        run(&mut objects, &level, &far, 60);
+        let flower = only_object::<Enemy>(&mut objects);
+        assert!(matches!(flower.state, EnemyState::Revealing));
+        assert!(
+            (flower.alert_time - 3.5).abs() < 0.01,
+            "{}",
+            flower.alert_time
+        );
+
+        // The reveal picks up half way through rather than starting over.
+        run(&mut objects, &level, &near, 36);
+        let flower = only_object::<Enemy>(&mut objects);
+        assert!(matches!(flower.state, EnemyState::Ambush { .. }));
+    }
+
+    #[test]
+    fn sleeping_spawners_spawn_nothing_until_they_wake() {
+        let level = open_field();
+        let mut objects = LevelObjects::new(object_assets());
+        objects.set_sleep_screens(0.25);
+        let spawner = Spawner::new(96.0, 96.0, EnemyType::PurpleBlob, 3, 0.5, 3);
+        objects.spawn(Box::new(spawner));
+        let mut near = World::new();
+        near.center_on(vec2(96.0, 96.0));
+        let mut far = World::new();
+        far.x = 96.0 - TILE_SIZE / 2.0 - 150.0 - far.w;
+
+        run(&mut objects, &level, &far, 120);
+        assert_eq!(objects.len(), 1);
+
+        // Its timer ran down while it slept, so it spawns straight away.
+        run(&mut objects, &level, &near, 1);
+        assert_eq!(objects.len(), 2);
+    }
 }
@@ -0,0 +1,108 @@
+use macroquad::{
+    color::{Color, GRAY, RED, WHITE},
+    shapes::{draw_rectangle, draw_rectangle_lines},
+};
+
+use crate::{
+    asset_loading::AssetManageError,
+    dialogue::Dialogue,
+    input::Input,
+    pixel_font::{draw_pixel_text, measure_pixel_text, pixel_line_height},
+    portal::WarpTarget,
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+/// What to try again once the player picks Retry.
+#[derive(Debug, Clone)]
+pub enum Retry {
+    /// Warp to the level that failed to load.
+    Warp(WarpTarget),
+    /// Respawn, which failed to load the checkpoint's level.
+    Respawn,
+    /// Go back to playing, for failures that left nothing half done.
+    Resume,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorChoice {
+    Retry,
+    Quit,
+}
+
+impl ErrorChoice {
+    const ALL: [ErrorChoice; 2] = [ErrorChoice::Retry, ErrorChoice::Quit];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ErrorChoice::Retry => "Retry",
+            ErrorChoice::Quit => "Quit",
+        }
+    }
+}
+
+/// Says what went wrong during play, over the frozen game, until the
+/// player retries or quits.
+pub struct ErrorScreen {
+    lines: Vec<String>,
+    retry: Retry,
+    selected: usize,
+}
+
+impl ErrorScreen {
+    const W: f32 = 300.0;
+    const H: f32 = 140.0;
+    const PADDING: f32 = 12.0;
+
+    pub fn new(err: &AssetManageError, retry: Retry) -> Self {
+        Self {
+            lines: Dialogue::wrap(&format!("{err}"), Self::W - Self::PADDING * 2.0),
+            retry,
+            selected: 0,
+        }
+    }
+
+    pub fn retry(&self) -> &Retry {
+        &self.retry
+    }
+
+    /// Moves the selection and returns the choice once it is confirmed.
+    pub fn update(&mut self, input: &Input) -> Option<ErrorChoice> {
+        let count = ErrorChoice::ALL.len();
+        if input.menu_up {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.menu_down {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        return match input.confirm {
+            true => Some(ErrorChoice::ALL[self.selected]),
+            false => None,
+        };
+    }
+
+    pub fn render(&self) {
+        let x = (VIRTUAL_W - Self::W) / 2.0;
+        let y = (VIRTUAL_H - Self::H) / 2.0;
+        draw_rectangle(x, y, Self::W, Self::H, Color::from_rgba(30, 10, 10, 230));
+        draw_rectangle_lines(x, y, Self::W, Self::H, 1.0, RED);
+
+        draw_centered("Something went wrong", y + 16.0, RED);
+        for (idx, line) in self.lines.iter().enumerate() {
+            let line_y = y + 32.0 + idx as f32 * pixel_line_height();
+            draw_pixel_text(line, x + Self::PADDING, line_y, WHITE);
+        }
+
+        for (idx, choice) in ErrorChoice::ALL.iter().enumerate() {
+            let line_y = y + Self::H - 26.0 + idx as f32 * 12.0;
+            match idx == self.selected {
+                true => draw_centered(&format!("> {} <", choice.label()), line_y, WHITE),
+                false => draw_centered(choice.label(), line_y, GRAY),
+            }
+        }
+    }
+}
+
+fn draw_centered(text: &str, y: f32, color: Color) {
+    draw_pixel_text(text, (VIRTUAL_W - measure_pixel_text(text)) / 2.0, y, color);
+}
@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+
+use macroquad::{
+    color::{Color, WHITE},
+    math::Vec2,
+    text::draw_text,
+};
+
+use crate::{enemies::EnemyType, weather::WeatherKind};
+
+/// Something that happened during a frame that other systems may want to
+/// react to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A link on the bus went active or inactive.
+    LinkToggled {
+        link_id: String,
+        active: bool,
+    },
+    EnemyDied {
+        kind: EnemyType,
+        position: Vec2,
+    },
+    /// An enemy lost `amount` hit points, the killing blow included.
+    /// `position` is the top middle of its hitbox.
+    EnemyDamaged {
+        amount: u32,
+        position: Vec2,
+        critical: bool,
+    },
+    /// The player lost this many hearts in one hit.
+    PlayerDamaged(u32),
+    /// The player started a swing.
+    PlayerAttacked,
+    ChestOpened,
+    /// The weather should ease into this.
+    WeatherChanged(WeatherKind),
+    /// Flash the screen `color`, starting at `alpha` and fading out over
+    /// `seconds`.
+    ScreenFlash {
+        color: Color,
+        alpha: f32,
+        seconds: f32,
+    },
+}
+
+/// Events raised during a frame, queued in order until the game loop drains
+/// them. Anything raised after the drain waits for the next one.
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self { queue: Vec::new() }
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    /// Takes every event raised since the last call.
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.queue)
+    }
+}
+
+/// The last few events, shown with the debug overlay.
+pub struct EventLog {
+    lines: VecDeque<String>,
+}
+
+impl EventLog {
+    const LEN: usize = 6;
+
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: &GameEvent) {
+        if self.lines.len() == Self::LEN {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(format!("{event:?}"));
+    }
+
+    pub fn render(&self) {
+        for (idx, line) in self.lines.iter().enumerate() {
+            draw_text(line, 2.0, 28.0 + idx as f32 * 8.0, 8.0, WHITE);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use macroquad::{color::Color, shapes::draw_rectangle};
+
+use crate::{events::GameEvent, VIRTUAL_H, VIRTUAL_W};
+
+/// Counts down a short effect, like a flash or a shake, and says how far
+/// through it is.
+#[derive(Debug, Clone, Copy)]
+pub struct FlashTimer {
+    seconds: f32,
+    left: f32,
+}
+
+impl FlashTimer {
+    pub fn new() -> Self {
+        Self {
+            seconds: 0.0,
+            left: 0.0,
+        }
+    }
+
+    /// Starts over, running for `seconds`.
+    pub fn start(&mut self, seconds: f32) {
+        self.seconds = seconds;
+        self.left = seconds;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.left = (self.left - dt).max(0.0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.left > 0.0
+    }
+
+    /// Seconds since it started.
+    pub fn elapsed(&self) -> f32 {
+        self.seconds - self.left
+    }
+
+    /// 1 as it starts, easing out to 0 as it ends.
+    pub fn strength(&self) -> f32 {
+        if self.seconds <= 0.0 {
+            return 0.0;
+        }
+
+        let left = self.left / self.seconds;
+        return left * left;
+    }
+}
+
+/// A flash of color over the whole world for big moments, like a heavy hit.
+pub struct ScreenFlash {
+    /// Color, peak alpha and timer of each flash still fading.
+    flashes: Vec<(Color, f32, FlashTimer)>,
+}
+
+impl ScreenFlash {
+    /// Alpha the flashes add up to at most, so stacked ones never hide the
+    /// world entirely.
+    const MAX_ALPHA: f32 = 0.7;
+
+    pub fn new() -> Self {
+        Self {
+            flashes: Vec::new(),
+        }
+    }
+
+    /// Starts a flash of `color` at `alpha`, fading out over `seconds`.
+    pub fn flash(&mut self, color: Color, alpha: f32, seconds: f32) {
+        let mut timer = FlashTimer::new();
+        timer.start(seconds);
+        self.flashes.push((color, alpha, timer));
+    }
+
+    /// Starts the flash a gameplay event asks for, if any.
+    pub fn react(&mut self, event: &GameEvent) {
+        if let GameEvent::ScreenFlash {
+            color,
+            alpha,
+            seconds,
+        } = event
+        {
+            self.flash(*color, *alpha, *seconds);
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for (_, _, timer) in self.flashes.iter_mut() {
+            timer.update(dt);
+        }
+        self.flashes.retain(|(_, _, timer)| timer.is_active());
+    }
+
+    /// Draws every flash as one rectangle, colored by how strongly each is
+    /// showing.
+    pub fn render(&self) {
+        let (mut r, mut g, mut b, mut total) = (0.0, 0.0, 0.0, 0.0);
+        for (color, alpha, timer) in &self.flashes {
+            let alpha = alpha * timer.strength();
+            r += color.r * alpha;
+            g += color.g * alpha;
+            b += color.b * alpha;
+            total += alpha;
+        }
+        if total <= 0.0 {
+            return;
+        }
+
+        let color = Color::new(r / total, g / total, b / total, total.min(Self::MAX_ALPHA));
+        draw_rectangle(0.0, 0.0, VIRTUAL_W, VIRTUAL_H, color);
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+use macroquad::{
+    color::{Color, WHITE, YELLOW},
+    math::Vec2,
+};
+
+use crate::{
+    events::GameEvent,
+    pixel_font::{draw_pixel_text_scaled, measure_pixel_text},
+    world::World,
+};
+
+/// One number drifting up off whatever was hit.
+struct FloatingText {
+    label: String,
+    position: Vec2,
+    age: f32,
+    critical: bool,
+}
+
+impl FloatingText {
+    fn is_alive(&self) -> bool {
+        self.age < FloatingTexts::LIFETIME
+    }
+}
+
+/// Damage numbers, kept in a pool whose slots and labels are reused so big
+/// fights don't allocate.
+pub struct FloatingTexts {
+    texts: Vec<FloatingText>,
+}
+
+impl FloatingTexts {
+    /// Past this many live numbers, new ones are dropped.
+    const CAPACITY: usize = 64;
+    const LIFETIME: f32 = 0.6;
+    /// Pixels a number rises over its lifetime.
+    const RISE: f32 = 8.0;
+    const CRITICAL_SCALE: f32 = 2.0;
+
+    pub fn new() -> Self {
+        Self {
+            texts: Vec::with_capacity(Self::CAPACITY),
+        }
+    }
+
+    /// Shows the damage an event reports, unless damage numbers are
+    /// switched off.
+    pub fn react(&mut self, event: &GameEvent, enabled: bool) {
+        if let GameEvent::EnemyDamaged {
+            amount,
+            position,
+            critical,
+        } = event
+        {
+            if enabled {
+                self.spawn(*amount, *position, *critical);
+            }
+        }
+    }
+
+    fn spawn(&mut self, amount: u32, position: Vec2, critical: bool) {
+        let idx = match self.texts.iter().position(|text| !text.is_alive()) {
+            Some(idx) => idx,
+            None if self.texts.len() < Self::CAPACITY => {
+                self.texts.push(FloatingText {
+                    label: String::new(),
+                    position,
+                    age: 0.0,
+                    critical,
+                });
+                self.texts.len() - 1
+            }
+            None => return,
+        };
+
+        let text = &mut self.texts[idx];
+        text.label.clear();
+        let _ = write!(text.label, "{amount}");
+        text.position = position;
+        text.age = 0.0;
+        text.critical = critical;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for text in self.texts.iter_mut().filter(|text| text.is_alive()) {
+            text.age += dt;
+        }
+    }
+
+    /// Draws the live numbers. Call in the above pass.
+    pub fn render(&self, world: &World) {
+        for text in self.texts.iter().filter(|text| text.is_alive()) {
+            let progress = text.age / Self::LIFETIME;
+            let (scale, color) = match text.critical {
+                true => (Self::CRITICAL_SCALE, YELLOW),
+                false => (1.0, WHITE),
+            };
+
+            let x = text.position.x - world.x - measure_pixel_text(&text.label) * scale / 2.0;
+            let y = text.position.y - world.y - progress * Self::RISE;
+            let color = Color {
+                a: 1.0 - progress,
+                ..color
+            };
+            draw_pixel_text_scaled(&text.label, x, y, scale, color);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use macroquad::{
+    color::Color,
+    shapes::{draw_circle, draw_line},
+};
+
+use crate::{tilesets::SurfaceKind, world::World};
+
+struct Footprint {
+    x: f32,
+    y: f32,
+    age: f32,
+    surface: SurfaceKind,
+}
+
+/// Short-lived ground decals left behind by walking bodies.
+pub struct Footsteps {
+    prints: Vec<Footprint>,
+}
+
+impl Footsteps {
+    const LIFETIME: f32 = 0.5;
+
+    pub fn new() -> Self {
+        Self { prints: Vec::new() }
+    }
+
+    pub fn spawn(&mut self, x: f32, y: f32, surface: SurfaceKind) {
+        self.prints.push(Footprint {
+            x,
+            y,
+            age: 0.0,
+            surface,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for print in self.prints.iter_mut() {
+            print.age += dt;
+        }
+        self.prints.retain(|print| print.age < Self::LIFETIME);
+    }
+
+    pub fn render(&self, world: &World) {
+        for print in &self.prints {
+            let prog = print.age / Self::LIFETIME;
+            let alpha = 1.0 - prog;
+            let x = print.x - world.x;
+            let y = print.y - world.y;
+
+            match print.surface {
+                SurfaceKind::Sand => {
+                    let color = Color::new(0.85, 0.75, 0.55, alpha * 0.8);
+                    let spread = 1.0 + prog * 3.0;
+                    draw_circle(x - spread, y, 1.0 + prog, color);
+                    draw_circle(x + spread, y - 0.5, 1.0 + prog, color);
+                }
+                SurfaceKind::Grass => {
+                    let color = Color::new(0.35, 0.65, 0.25, alpha);
+                    let rise = prog * 3.0;
+                    draw_line(x - 2.0, y, x - 3.0, y - 2.0 - rise, 0.7, color);
+                    draw_line(x + 2.0, y, x + 3.0, y - 2.0 - rise, 0.7, color);
+                }
+                SurfaceKind::Stone => {
+                    let color = Color::new(0.6, 0.6, 0.6, alpha * 0.6);
+                    draw_circle(x, y, 0.5 + prog * 1.5, color);
+                }
+            }
+        }
+    }
+}
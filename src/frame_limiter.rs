@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use macroquad::time::get_time;
+
+use crate::settings::PresentMode;
+
+/// Holds frames to the rate the present mode asks for.
+pub struct FrameLimiter {
+    /// Whether the window was made with vsync, which can't be changed while
+    /// it's open.
+    vsync: bool,
+    /// When the last frame was let through.
+    last_frame: f64,
+}
+
+impl FrameLimiter {
+    /// Rate held when vsync is picked but the window was made without it,
+    /// until the next launch.
+    const VSYNC_STAND_IN_FPS: u32 = 60;
+    /// Leftover time under this is spun out instead of slept, as sleeps
+    /// can overshoot by a millisecond or more.
+    const SPIN_SECONDS: f64 = 0.002;
+
+    pub fn new(vsync: bool) -> Self {
+        Self {
+            vsync,
+            last_frame: get_time(),
+        }
+    }
+
+    /// Whether the window was made with vsync.
+    pub fn has_vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Waits out the rest of the frame if `mode` caps it. Call just before
+    /// `next_frame`.
+    pub fn wait(&mut self, mode: PresentMode) {
+        let fps = match mode {
+            PresentMode::Capped(fps) => fps,
+            PresentMode::Vsync if !self.vsync => Self::VSYNC_STAND_IN_FPS,
+            PresentMode::Vsync | PresentMode::Uncapped => {
+                self.last_frame = get_time();
+                return;
+            }
+        };
+
+        let period = 1.0 / fps.max(1) as f64;
+        let target = self.last_frame + period;
+        loop {
+            let left = target - get_time();
+            if left <= 0.0 {
+                break;
+            }
+
+            match left > Self::SPIN_SECONDS {
+                true => std::thread::sleep(Duration::from_secs_f64(left - Self::SPIN_SECONDS)),
+                false => std::hint::spin_loop(),
+            }
+        }
+
+        // Keep to the schedule, unless a slow frame fell a whole frame
+        // behind, which would otherwise rush the next few to catch up.
+        let now = get_time();
+        self.last_frame = match now - target > period {
+            true => now,
+            false => target,
+        };
+    }
+}
@@ -0,0 +1,65 @@
+use macroquad::{
+    camera::{set_camera, Camera2D},
+    color::WHITE,
+    math::{vec2, Rect},
+    texture::{draw_texture_ex, render_target, DrawTextureParams, RenderTarget},
+};
+
+use crate::{VIRTUAL_H, VIRTUAL_W};
+
+/// The targets each frame is drawn through, `scale` target pixels to a
+/// virtual one. The world is drawn to one, then post-processed into the
+/// other with the UI drawn over it.
+pub struct FrameTargets {
+    scale: u32,
+    pub world: RenderTarget,
+    pub screen: RenderTarget,
+}
+
+impl FrameTargets {
+    pub fn new(scale: u32) -> Self {
+        Self {
+            scale,
+            world: Self::target(scale),
+            screen: Self::target(scale),
+        }
+    }
+
+    fn target(scale: u32) -> RenderTarget {
+        return render_target(VIRTUAL_W as u32 * scale, VIRTUAL_H as u32 * scale);
+    }
+
+    /// Makes the targets again if `scale` changed.
+    pub fn set_scale(&mut self, scale: u32) {
+        if scale != self.scale {
+            *self = Self::new(scale);
+        }
+    }
+}
+
+/// Points the camera at `target`, with the virtual screen filling it.
+pub fn set_virtual_camera(target: &RenderTarget) {
+    set_camera(&Camera2D {
+        zoom: vec2(2.0 / VIRTUAL_W, 2.0 / VIRTUAL_H),
+        target: vec2(VIRTUAL_W / 2.0, VIRTUAL_H / 2.0),
+        render_target: Some(target.clone()),
+        ..Default::default()
+    });
+}
+
+/// Draws the whole of a virtual-screen `target` to `dest`, whatever the
+/// render scale it was made at.
+pub fn draw_virtual_target(target: &RenderTarget, dest: Rect) {
+    let size = target.texture.size();
+    draw_texture_ex(
+        &target.texture,
+        dest.x,
+        dest.y,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(dest.size()),
+            source: Some(Rect::new(0.0, 0.0, size.x, size.y)),
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,67 @@
+use macroquad::{
+    color::{Color, GRAY, RED, WHITE},
+    text::{draw_text, measure_text},
+};
+
+use crate::{input::Input, VIRTUAL_H, VIRTUAL_W};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameOverChoice {
+    Retry,
+    Quit,
+}
+
+impl GameOverChoice {
+    const ALL: [GameOverChoice; 2] = [GameOverChoice::Retry, GameOverChoice::Quit];
+
+    fn label(&self) -> &'static str {
+        match self {
+            GameOverChoice::Retry => "Retry",
+            GameOverChoice::Quit => "Quit",
+        }
+    }
+}
+
+/// The menu shown once the death fade has gone fully black.
+pub struct GameOverScreen {
+    selected: usize,
+}
+
+impl GameOverScreen {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    /// Moves the selection and returns the choice once it is confirmed.
+    pub fn update(&mut self, input: &Input) -> Option<GameOverChoice> {
+        let count = GameOverChoice::ALL.len();
+        if input.menu_up {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.menu_down {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        return match input.confirm {
+            true => Some(GameOverChoice::ALL[self.selected]),
+            false => None,
+        };
+    }
+
+    pub fn render(&self) {
+        draw_centered("Game Over", VIRTUAL_H / 2.0 - 24.0, 32.0, RED);
+
+        for (idx, choice) in GameOverChoice::ALL.iter().enumerate() {
+            let y = VIRTUAL_H / 2.0 + 12.0 + idx as f32 * 18.0;
+            match idx == self.selected {
+                true => draw_centered(&format!("> {} <", choice.label()), y, 16.0, WHITE),
+                false => draw_centered(choice.label(), y, 16.0, GRAY),
+            }
+        }
+    }
+}
+
+fn draw_centered(text: &str, y: f32, size: f32, color: Color) {
+    let width = measure_text(text, None, size as u16, 1.0).width;
+    draw_text(text, (VIRTUAL_W - width) / 2.0, y, size, color);
+}
@@ -0,0 +1,90 @@
+use macroquad::{
+    color::{DARKGRAY, GRAY},
+    shapes::draw_rectangle,
+};
+
+use crate::{
+    body::Body,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+    TILE_SIZE,
+};
+
+/// Bars filling its cell that sink into the ground while its link is
+/// active. The level decides whether the cell is solid; this only animates.
+pub struct Gate {
+    pub body: Body,
+    link_id: String,
+    open: bool,
+    /// From 0 when shut to 1 when fully sunk.
+    progress: f32,
+}
+
+impl Gate {
+    const MOVE_SECONDS: f32 = 0.2;
+    const BARS: usize = 4;
+
+    pub fn new(x: f32, y: f32, link_id: String) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE, TILE_SIZE, None),
+            link_id,
+            open: false,
+            progress: 0.0,
+        }
+    }
+
+    pub fn link_id(&self) -> &str {
+        &self.link_id
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+}
+
+impl GameObject for Gate {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Gate
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        return self.sleep(ctx.dt);
+    }
+
+    /// Bars keep sliding while asleep, so a gate is settled by the time it
+    /// comes into view.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        let step = dt / Self::MOVE_SECONDS;
+        self.progress = match self.open {
+            true => (self.progress + step).min(1.0),
+            false => (self.progress - step).max(0.0),
+        };
+
+        return ObjectStatus::Alive;
+    }
+
+    fn render(&self, world: &World) {
+        let x = self.body.screen_x(world);
+        let bottom = self.body.screen_y(world) + TILE_SIZE;
+        let height = TILE_SIZE * (1.0 - self.progress);
+        if height <= 0.0 {
+            return;
+        }
+
+        let gap = TILE_SIZE / Self::BARS as f32;
+        for bar in 0..Self::BARS {
+            draw_rectangle(
+                x + bar as f32 * gap + gap / 2.0 - 1.0,
+                bottom - height,
+                2.0,
+                height,
+                GRAY,
+            );
+        }
+        draw_rectangle(x, bottom - height, TILE_SIZE, 2.0, DARKGRAY);
+    }
+}
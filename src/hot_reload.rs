@@ -0,0 +1,126 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+use macroquad::{color::YELLOW, logging::info};
+
+use crate::{
+    asset_loading::meta_path, asset_root::AssetRoot, assets::Assets, levels::Level,
+    pixel_font::draw_pixel_text, player::Player, sprites::Sprite, tilesets::TilesetAsset,
+    VIRTUAL_H,
+};
+
+/// Reloads the level's tilesets and the player's sprite when their files
+/// change, so art can be worked on with the game running. Levels aren't
+/// watched, so unsaved editor work is never overwritten. Native builds only.
+pub struct HotReload {
+    /// Seconds until the files are next looked at.
+    poll_time: f32,
+    /// When each watched file was last seen modified, or `None` if it was
+    /// missing.
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+    /// What went wrong with the last reload, and seconds left showing it.
+    warning: Option<(String, f32)>,
+}
+
+impl HotReload {
+    const POLL_SECONDS: f32 = 1.0;
+    const WARNING_SECONDS: f32 = 4.0;
+
+    pub fn new() -> Self {
+        Self {
+            poll_time: Self::POLL_SECONDS,
+            modified: HashMap::new(),
+            warning: None,
+        }
+    }
+
+    /// Once a second, reloads whatever changed. A reload that fails keeps
+    /// the old asset and shows a warning.
+    pub async fn update(
+        &mut self,
+        level: &mut Level,
+        assets: &mut Assets,
+        player: &mut Player,
+        dt: f32,
+    ) {
+        if let Some((_, time)) = &mut self.warning {
+            *time -= dt;
+            if *time <= 0.0 {
+                self.warning = None;
+            }
+        }
+
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+
+        self.poll_time -= dt;
+        if self.poll_time > 0.0 {
+            return;
+        }
+        self.poll_time = Self::POLL_SECONDS;
+
+        let names: Vec<String> = level.tilesets().keys().cloned().collect();
+        for name in names {
+            if !self.changed_with_meta(&TilesetAsset::path(&name)) {
+                continue;
+            }
+
+            match TilesetAsset::load(&name).await {
+                Ok(tileset) => {
+                    level.set_tileset(&name, Rc::new(tileset));
+                    assets.keep_tilesets(level);
+                    info!("Reloaded tileset {}", name);
+                }
+                Err(err) => self.warn(format!("Tileset {name} not reloaded: {err}")),
+            }
+        }
+
+        if self.changed_with_meta(&Sprite::player_path()) {
+            match Sprite::load_player().await {
+                Ok(sprite) => {
+                    player.body.set_sprite(sprite);
+                    info!("Reloaded the player sprite");
+                }
+                Err(err) => self.warn(format!("Player sprite not reloaded: {err}")),
+            }
+        }
+    }
+
+    fn warn(&mut self, message: String) {
+        self.warning = Some((message, Self::WARNING_SECONDS));
+    }
+
+    /// Whether the texture at `path` or its meta changed since last looked
+    /// at. Both are always checked, so neither change is missed.
+    fn changed_with_meta(&mut self, path: &str) -> bool {
+        let tex = self.changed(Path::new(path));
+        let meta = self.changed(Path::new(&meta_path(path)));
+        return tex || meta;
+    }
+
+    /// Whether `path` was modified, or turned up, since last looked at. The
+    /// first look only notes the time.
+    fn changed(&mut self, path: &Path) -> bool {
+        let resolved = AssetRoot::resolve(path);
+        let modified = std::fs::metadata(resolved)
+            .and_then(|meta| meta.modified())
+            .ok();
+
+        return match self.modified.insert(path.to_owned(), modified) {
+            Some(previous) => previous != modified,
+            None => false,
+        };
+    }
+
+    /// Shows the last reload failure along the bottom of the screen.
+    pub fn render(&self) {
+        if let Some((message, _)) = &self.warning {
+            draw_pixel_text(message, 4.0, VIRTUAL_H - 4.0, YELLOW);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use macroquad::{
+    color::{Color, DARKGRAY, GREEN, WHITE},
+    math::Rect,
+    shapes::draw_rectangle,
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{load_tex_with_meta, AssetManageResult},
+    events::GameEvent,
+    flash::FlashTimer,
+    pixel_font::draw_pixel_text,
+    player::Player,
+    VIRTUAL_W,
+};
+
+#[derive(Serialize, Deserialize)]
+struct HudSheetSerializable {
+    heart_full: [f32; 4],
+    heart_half: [f32; 4],
+    heart_empty: [f32; 4],
+    coin: [f32; 4],
+    hp_per_heart: u32,
+    margin: f32,
+    heart_gap: f32,
+}
+
+/// The HUD's icons and how they're laid out.
+struct HudSheet {
+    tex: Texture2D,
+    heart_full: Rect,
+    heart_half: Rect,
+    heart_empty: Rect,
+    coin: Rect,
+    /// Hit points each heart holds. Hearts with some but not all of them
+    /// left show as half.
+    hp_per_heart: u32,
+    /// Gap between the icons and the screen edges.
+    margin: f32,
+    heart_gap: f32,
+}
+
+impl HudSheet {
+    const PATH: &str = "assets/art/sprites/hud.png";
+
+    async fn load() -> AssetManageResult<Self> {
+        let (serializable, tex): (HudSheetSerializable, _) = load_tex_with_meta(Self::PATH).await?;
+
+        let rect = |[x, y, w, h]: [f32; 4]| Rect::new(x, y, w, h);
+        return Ok(Self {
+            tex,
+            heart_full: rect(serializable.heart_full),
+            heart_half: rect(serializable.heart_half),
+            heart_empty: rect(serializable.heart_empty),
+            coin: rect(serializable.coin),
+            hp_per_heart: serializable.hp_per_heart.max(1),
+            margin: serializable.margin,
+            heart_gap: serializable.heart_gap,
+        });
+    }
+
+    fn draw(&self, source: Rect, x: f32, y: f32, color: Color) {
+        draw_texture_ex(
+            &self.tex,
+            x,
+            y,
+            color,
+            DrawTextureParams {
+                source: Some(source),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// The player's hearts, stamina meter and coin count, drawn in
+/// virtual-screen space.
+pub struct Hud {
+    sheet: HudSheet,
+    /// The hearts shaking after a hit.
+    hurt: FlashTimer,
+    /// The coin count on show, which ticks up to the player's.
+    shown_coins: f32,
+}
+
+impl Hud {
+    const HURT_SECONDS: f32 = 0.4;
+    /// How far the hearts shake each way, in pixels.
+    const SHAKE: f32 = 1.5;
+    /// Coins counted on per second.
+    const COIN_TICK_RATE: f32 = 20.0;
+
+    pub async fn load(player: &Player) -> AssetManageResult<Self> {
+        return Ok(Self {
+            sheet: HudSheet::load().await?,
+            hurt: FlashTimer::new(),
+            shown_coins: player.coins as f32,
+        });
+    }
+
+    /// Shakes the hearts when the player is hit.
+    pub fn react(&mut self, event: &GameEvent) {
+        if let GameEvent::PlayerDamaged(_) = event {
+            self.hurt.start(Self::HURT_SECONDS);
+        }
+    }
+
+    pub fn update(&mut self, player: &Player, dt: f32) {
+        self.hurt.update(dt);
+
+        // Counting down isn't worth showing, as after a load.
+        let coins = player.coins as f32;
+        self.shown_coins = match self.shown_coins < coins {
+            true => (self.shown_coins + Self::COIN_TICK_RATE * dt).min(coins),
+            false => coins,
+        };
+    }
+
+    pub fn render(&self, player: &Player) {
+        let sheet = &self.sheet;
+        let hearts = player.max_hp.div_ceil(sheet.hp_per_heart);
+        let step = sheet.heart_full.w + sheet.heart_gap;
+
+        let (shake, color) = match self.hurt.is_active() {
+            true => {
+                let flash = (self.hurt.elapsed() * 20.0) as i32 % 2 == 0;
+                let color = match flash {
+                    true => Color { a: 0.4, ..WHITE },
+                    false => WHITE,
+                };
+                ((self.hurt.elapsed() * 60.0).sin() * Self::SHAKE, color)
+            }
+            false => (0.0, WHITE),
+        };
+
+        for idx in 0..hearts {
+            let left = player.hp.saturating_sub(idx * sheet.hp_per_heart);
+            let source = match left {
+                0 => sheet.heart_empty,
+                left if left < sheet.hp_per_heart => sheet.heart_half,
+                _ => sheet.heart_full,
+            };
+            let x = sheet.margin + idx as f32 * step + shake;
+            sheet.draw(source, x, sheet.margin, color);
+        }
+
+        if !player.stamina.is_full() {
+            let w = hearts as f32 * step - sheet.heart_gap;
+            let y = sheet.margin + sheet.heart_full.h + 2.0;
+            draw_rectangle(sheet.margin, y, w, 2.0, DARKGRAY);
+            draw_rectangle(sheet.margin, y, w * player.stamina.fraction(), 2.0, GREEN);
+        }
+
+        let coin_x = VIRTUAL_W - sheet.margin - 24.0;
+        sheet.draw(sheet.coin, coin_x, sheet.margin, WHITE);
+        draw_pixel_text(
+            &(self.shown_coins as u32).to_string(),
+            coin_x + sheet.coin.w + 2.0,
+            sheet.margin + sheet.coin.h - 1.0,
+            WHITE,
+        );
+    }
+}
@@ -1,22 +1,49 @@
 use macroquad::input::{
-    is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed,
-    mouse_position_local, mouse_wheel, KeyCode, MouseButton,
+    is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, mouse_wheel,
+    KeyCode, MouseButton,
 };
 
+use crate::viewport::Viewport;
+
+#[derive(Default)]
 pub struct Input {
     pub vertical: f32,
     pub horizontal: f32,
+    pub sprint: bool,
+    pub dash: bool,
+    pub attack: bool,
+    pub jump: bool,
+    pub interact: bool,
+    pub interact_held: bool,
+    pub load_game: bool,
+    /// Sprays a burst of test particles at the player.
+    pub test_particles: bool,
+    /// Switches tiles between the atlas and their own textures.
+    pub toggle_atlas: bool,
+    pub toggle_atlas_view: bool,
     pub toggle_editor: bool,
+    pub toggle_inventory: bool,
+    pub toggle_map: bool,
+    /// Switches between whole-number and stretched window scaling.
+    pub toggle_scaling: bool,
+    pub toggle_debug_overlay: bool,
+    pub toggle_settings: bool,
     pub scroll: f32,
+    /// The mouse in virtual-screen pixels.
     pub mouse_x: f32,
     pub mouse_y: f32,
     pub click: bool,
     pub mouse_down: bool,
     pub enter: bool,
+    pub menu_up: bool,
+    pub menu_down: bool,
+    pub menu_left: bool,
+    pub menu_right: bool,
+    pub confirm: bool,
 }
 
 impl Input {
-    pub fn get() -> Input {
+    pub fn get(viewport: &Viewport) -> Input {
         let vertical = match (
             is_key_down(KeyCode::Up) || is_key_down(KeyCode::W),
             is_key_down(KeyCode::Down) || is_key_down(KeyCode::S),
@@ -36,8 +63,9 @@ impl Input {
         };
 
         let toggle_editor = is_key_pressed(KeyCode::P);
+        let toggle_inventory = is_key_pressed(KeyCode::Tab);
         let scroll = mouse_wheel().1;
-        let mpos = mouse_position_local();
+        let mpos = viewport.mouse();
 
         let click = is_mouse_button_pressed(MouseButton::Left);
         let mouse_down = is_mouse_button_down(MouseButton::Left);
@@ -45,13 +73,33 @@ impl Input {
         Input {
             vertical,
             horizontal,
+            sprint: is_key_down(KeyCode::LeftShift),
+            dash: is_key_pressed(KeyCode::K),
+            attack: is_key_pressed(KeyCode::J),
+            jump: is_key_pressed(KeyCode::Space),
+            interact: is_key_pressed(KeyCode::E),
+            interact_held: is_key_down(KeyCode::E),
+            load_game: is_key_pressed(KeyCode::F9),
+            test_particles: is_key_pressed(KeyCode::F8),
+            toggle_atlas: is_key_pressed(KeyCode::F6),
+            toggle_atlas_view: is_key_pressed(KeyCode::F7),
             toggle_editor,
+            toggle_inventory,
+            toggle_map: is_key_pressed(KeyCode::M),
+            toggle_scaling: is_key_pressed(KeyCode::F10),
+            toggle_debug_overlay: is_key_pressed(KeyCode::F3),
+            toggle_settings: is_key_pressed(KeyCode::Escape),
             scroll,
             mouse_x: mpos.x,
             mouse_y: mpos.y,
             click,
             mouse_down,
             enter: is_key_down(KeyCode::Enter),
+            menu_up: is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W),
+            menu_down: is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S),
+            menu_left: is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A),
+            menu_right: is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D),
+            confirm: is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space),
         }
     }
 }
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use macroquad::{
+    color::{Color, WHITE},
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    text::draw_text,
+};
+
+use crate::{
+    items::{ItemId, ItemSheet},
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+pub struct Inventory {
+    pub open: bool,
+    items: BTreeMap<ItemId, u32>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            items: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, item: ItemId, quantity: u32) {
+        *self.items.entry(item).or_insert(0) += quantity;
+    }
+
+    /// Removes `quantity` of `item`, returning false (and leaving the
+    /// inventory untouched) if there are not enough to remove.
+    pub fn remove(&mut self, item: ItemId, quantity: u32) -> bool {
+        match self.items.get_mut(&item) {
+            Some(count) if *count >= quantity => {
+                *count -= quantity;
+                if *count == 0 {
+                    self.items.remove(&item);
+                }
+                return true;
+            }
+            _ => return false,
+        }
+    }
+
+    pub fn has(&self, item: ItemId, quantity: u32) -> bool {
+        return self.count(item) >= quantity;
+    }
+
+    pub fn items(&self) -> &BTreeMap<ItemId, u32> {
+        &self.items
+    }
+
+    pub fn set_items(&mut self, items: BTreeMap<ItemId, u32>) {
+        self.items = items;
+    }
+
+    pub fn count(&self, item: ItemId) -> u32 {
+        return self.items.get(&item).copied().unwrap_or(0);
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn render_screen(&self, sheet: &ItemSheet) {
+        let margin = 32.0;
+        let w = VIRTUAL_W - margin * 2.0;
+        let h = VIRTUAL_H - margin * 2.0;
+
+        draw_rectangle(margin, margin, w, h, Color::from_rgba(20, 10, 30, 220));
+        draw_rectangle_lines(margin, margin, w, h, 1.0, WHITE);
+        draw_text("Inventory", margin + 8.0, margin + 14.0, 16.0, WHITE);
+
+        if self.items.is_empty() {
+            draw_text("Empty", margin + 8.0, margin + 34.0, 16.0, WHITE);
+            return;
+        }
+
+        let row_h = sheet.icon_h + 4.0;
+        for (idx, (item, count)) in self.items.iter().enumerate() {
+            let x = margin + 8.0;
+            let y = margin + 22.0 + idx as f32 * row_h;
+
+            sheet.draw_icon(*item, x, y, sheet.icon_w);
+            draw_text(
+                &format!("{} x{}", item.name(), count),
+                x + sheet.icon_w + 6.0,
+                y + sheet.icon_h - 4.0,
+                16.0,
+                WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use macroquad::{
+    color::WHITE,
+    math::{vec2, Rect},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{load_tex_with_meta, AssetManageResult},
+    body::Body,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ItemId {
+    Shell,
+    Driftwood,
+    Rope,
+    BrassKey,
+}
+
+impl ItemId {
+    pub const ALL: [ItemId; 4] = [
+        ItemId::Shell,
+        ItemId::Driftwood,
+        ItemId::Rope,
+        ItemId::BrassKey,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ItemId::Shell => "Shell",
+            ItemId::Driftwood => "Driftwood",
+            ItemId::Rope => "Rope",
+            ItemId::BrassKey => "Brass Key",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ItemSheetSerializable {
+    icon_w: f32,
+    icon_h: f32,
+    icons: HashMap<ItemId, (f32, f32)>,
+}
+
+#[derive(Clone)]
+pub struct ItemSheet {
+    pub tex: Texture2D,
+    pub icon_w: f32,
+    pub icon_h: f32,
+    icons: HashMap<ItemId, (f32, f32)>,
+}
+
+impl ItemSheet {
+    const PATH: &str = "assets/art/sprites/items.png";
+
+    pub async fn load() -> AssetManageResult<Self> {
+        let (serializable, tex): (ItemSheetSerializable, _) =
+            load_tex_with_meta(Self::PATH).await?;
+
+        return Ok(Self {
+            tex,
+            icon_w: serializable.icon_w,
+            icon_h: serializable.icon_h,
+            icons: serializable.icons,
+        });
+    }
+
+    pub fn source(&self, item: ItemId) -> Rect {
+        let (x, y) = self.icons.get(&item).copied().unwrap_or((0.0, 0.0));
+        return Rect::new(x, y, self.icon_w, self.icon_h);
+    }
+
+    pub fn draw_icon(&self, item: ItemId, x: f32, y: f32, size: f32) {
+        draw_texture_ex(
+            &self.tex,
+            x,
+            y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(size, size)),
+                source: Some(self.source(item)),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+pub struct ItemPickup {
+    pub body: Body,
+    pub item: ItemId,
+    sheet: ItemSheet,
+    /// Seconds until a dropped pickup disappears. Placed pickups stay.
+    time_left: Option<f32>,
+}
+
+impl ItemPickup {
+    pub const SIZE: f32 = 10.0;
+    const DROP_SECONDS: f32 = 30.0;
+    const BLINK_SECONDS: f32 = 5.0;
+    const BLINKS_PER_SECOND: f32 = 4.0;
+
+    pub fn new(item: ItemId, x: f32, y: f32, sheet: &ItemSheet) -> Self {
+        Self {
+            body: Body::new(x, y, Self::SIZE, Self::SIZE, None),
+            item,
+            sheet: sheet.clone(),
+            time_left: None,
+        }
+    }
+
+    /// A pickup dropped by an enemy, which only lasts `DROP_SECONDS`.
+    pub fn dropped(item: ItemId, x: f32, y: f32, sheet: &ItemSheet) -> Self {
+        let mut pickup = Self::new(item, x, y, sheet);
+        pickup.time_left = Some(Self::DROP_SECONDS);
+        return pickup;
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.time_left.is_some_and(|time_left| time_left <= 0.0)
+    }
+}
+
+impl GameObject for ItemPickup {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Item
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        if let Some(ref mut time_left) = self.time_left {
+            *time_left -= ctx.dt;
+        }
+
+        return match self.is_expired() {
+            true => ObjectStatus::Dead,
+            false => ObjectStatus::Alive,
+        };
+    }
+
+    /// Blinks through the last `BLINK_SECONDS` before expiring.
+    fn render(&self, world: &World) {
+        if let Some(time_left) = self.time_left {
+            let blink = (time_left * Self::BLINKS_PER_SECOND * 2.0) as i32;
+            if time_left < Self::BLINK_SECONDS && blink % 2 == 0 {
+                return;
+            }
+        }
+
+        let center = self.body.hitbox.center();
+        self.sheet.draw_icon(
+            self.item,
+            center.x - self.sheet.icon_w / 2.0 - world.x,
+            self.body.hitbox.bottom() - self.sheet.icon_h - world.y,
+            self.sheet.icon_w,
+        );
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::asset_loading::tests::stand_in_tex;
+
+    /// A sheet with no icons, for anything that spawns pickups in tests.
+    pub fn item_sheet() -> ItemSheet {
+        return ItemSheet {
+            tex: stand_in_tex(),
+            icon_w: 16.0,
+            icon_h: 16.0,
+            icons: HashMap::new(),
+        };
+    }
+}
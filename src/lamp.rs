@@ -0,0 +1,53 @@
+use macroquad::{
+    color::DARKGRAY,
+    shapes::{draw_circle, draw_rectangle},
+};
+
+use crate::{
+    body::Body,
+    lights::LightEmitter,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+    TILE_SIZE,
+};
+
+/// A torch or lantern on a post, placed for its light.
+pub struct Lamp {
+    pub body: Body,
+    light: LightEmitter,
+}
+
+impl Lamp {
+    pub fn new(x: f32, y: f32, light: LightEmitter) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE * 0.25, TILE_SIZE * 0.25, None),
+            light,
+        }
+    }
+}
+
+impl GameObject for Lamp {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Lamp
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn light(&self) -> Option<&LightEmitter> {
+        Some(&self.light)
+    }
+
+    fn render(&self, world: &World) {
+        let x = self.body.hitbox.center().x - world.x;
+        let y = self.body.hitbox.bottom() - world.y;
+
+        draw_rectangle(x - 1.0, y - 10.0, 2.0, 10.0, DARKGRAY);
+        draw_circle(x, y - 11.0, 2.5, self.light.color());
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use macroquad::texture::{render_target, FilterMode, RenderTarget};
+
+use crate::TILE_SIZE;
+
+struct CachedChunk {
+    target: RenderTarget,
+    /// Drawn again every frame while any of its tiles animate.
+    animated: bool,
+    /// A tile in it changed since it was drawn.
+    dirty: bool,
+}
+
+/// A tile layer drawn ahead of time into square chunks, so a frame only
+/// copies the chunks in view instead of drawing every tile. Chunks are kept
+/// small and only near the view, so big levels don't need a huge texture.
+pub struct LayerCache {
+    chunks: HashMap<(usize, usize), CachedChunk>,
+}
+
+impl LayerCache {
+    /// Cells along each side of a chunk.
+    pub const CHUNK_CELLS: usize = 32;
+    pub const CHUNK_SIZE: f32 = Self::CHUNK_CELLS as f32 * TILE_SIZE;
+    /// Chunks kept before those out of view are let go.
+    const MAX_CHUNKS: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Marks every chunk to be drawn again.
+    pub fn clear(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.dirty = true;
+        }
+    }
+
+    /// Marks the chunks touching the cell at `row`, `col` or any of its
+    /// neighbours to be drawn again, as auto-tiling can change those too.
+    pub fn invalidate_around(&mut self, row: usize, col: usize) {
+        for row in row.saturating_sub(1)..=row + 1 {
+            for col in col.saturating_sub(1)..=col + 1 {
+                let key = (row / Self::CHUNK_CELLS, col / Self::CHUNK_CELLS);
+                if let Some(chunk) = self.chunks.get_mut(&key) {
+                    chunk.dirty = true;
+                }
+            }
+        }
+    }
+
+    /// Marks the chunk at `key` to be drawn again.
+    pub fn invalidate(&mut self, key: (usize, usize)) {
+        if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk.dirty = true;
+        }
+    }
+
+    /// The target for the chunk at `(chunk_row, chunk_col)`, and whether it
+    /// needs drawing before it's used.
+    pub fn target(&mut self, key: (usize, usize)) -> (RenderTarget, bool) {
+        let chunk = self.chunks.entry(key).or_insert_with(|| {
+            let size = Self::CHUNK_SIZE as u32;
+            let target = render_target(size, size);
+            target.texture.set_filter(FilterMode::Nearest);
+            CachedChunk {
+                target,
+                animated: false,
+                dirty: true,
+            }
+        });
+
+        return (chunk.target.clone(), chunk.dirty || chunk.animated);
+    }
+
+    /// Records that the chunk was just drawn, and whether it animates.
+    pub fn drawn(&mut self, key: (usize, usize), animated: bool) {
+        if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk.dirty = false;
+            chunk.animated = animated;
+        }
+    }
+
+    /// Lets go of the chunks outside `visible` once there are too many.
+    pub fn evict(&mut self, visible: &[(usize, usize)]) {
+        if self.chunks.len() > Self::MAX_CHUNKS {
+            self.chunks.retain(|key, _| visible.contains(key));
+        }
+    }
+}
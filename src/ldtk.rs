@@ -0,0 +1,536 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{
+        asset_exists, check_saving, deserialize, serialize, AssetManageError, AssetManageResult,
+        Versioned,
+    },
+    level_file::LevelFormat,
+    levels::{LevelSerializable, TilePointer, TileVec},
+    object::{ObjectListing, ObjectType},
+    tilesets::{known_tile_tables, tile_at_pos, TileAsset, TileLayer},
+    weather::WeatherKind,
+    TILE_SIZE,
+};
+
+/// An LDtk project, with only what the importer reads.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkProject {
+    #[serde(default)]
+    levels: Vec<LdtkLevel>,
+    /// Holds the levels instead in projects with more than one world.
+    #[serde(default)]
+    worlds: Vec<LdtkWorld>,
+}
+
+#[derive(Deserialize)]
+struct LdtkWorld {
+    #[serde(default)]
+    levels: Vec<LdtkLevel>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkLevel {
+    identifier: String,
+    iid: String,
+    world_x: i64,
+    world_y: i64,
+    px_wid: usize,
+    px_hei: usize,
+    #[serde(default, rename = "__neighbours")]
+    neighbours: Vec<LdtkNeighbour>,
+    /// Missing when the level is saved in its own file.
+    #[serde(default)]
+    layer_instances: Option<Vec<LdtkLayer>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkNeighbour {
+    level_iid: String,
+    dir: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkLayer {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    r#type: String,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(default, rename = "__tilesetRelPath")]
+    tileset_rel_path: Option<String>,
+    #[serde(default)]
+    grid_tiles: Vec<LdtkTile>,
+    /// Tiles placed by the layer's rules, for IntGrid and auto layers.
+    #[serde(default)]
+    auto_layer_tiles: Vec<LdtkTile>,
+    #[serde(default)]
+    entity_instances: Vec<LdtkEntity>,
+}
+
+#[derive(Deserialize)]
+struct LdtkTile {
+    /// Where the tile sits in the level, in pixels.
+    px: [i64; 2],
+    /// Where the tile is drawn from in the tileset, in pixels.
+    src: [i64; 2],
+    /// Set when the tile is flipped.
+    #[serde(default)]
+    f: u8,
+}
+
+#[derive(Deserialize)]
+struct LdtkEntity {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__grid")]
+    grid: [i64; 2],
+}
+
+/// How LDtk layers and entities become the game's. Those it doesn't list
+/// are matched by name, so a layer called Overlay lands on the overlay
+/// layer and an entity called Checkpoint becomes a checkpoint.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct LdtkMapping {
+    pub layers: HashMap<String, TileLayer>,
+    pub entities: HashMap<String, ObjectType>,
+}
+
+impl LdtkMapping {
+    const PATH: &str = "assets/config/ldtk.json";
+
+    /// The mapping in the config, or none if there's no config.
+    pub fn load() -> AssetManageResult<Self> {
+        return match asset_exists(Self::PATH) {
+            true => deserialize(Self::PATH),
+            false => Ok(Self::default()),
+        };
+    }
+
+    fn layer(&self, identifier: &str) -> Option<TileLayer> {
+        if let Some(layer) = self.layers.get(identifier) {
+            return Some(layer.clone());
+        }
+
+        return match identifier.to_lowercase().as_str() {
+            "background" => Some(TileLayer::Background),
+            "object" => Some(TileLayer::Object),
+            "overlay" => Some(TileLayer::Overlay),
+            _ => None,
+        };
+    }
+
+    fn entity(&self, identifier: &str) -> Option<ObjectType> {
+        return match self.entities.get(identifier) {
+            Some(r#type) => Some(r#type.clone()),
+            None => ObjectType::parse(identifier),
+        };
+    }
+}
+
+/// Where an imported level sits in the LDtk world, for the world map.
+#[derive(Serialize, Debug)]
+pub struct WorldLevel {
+    pub level: String,
+    /// Top left corner in world pixels.
+    pub x: i64,
+    pub y: i64,
+    /// Levels touching this one, with the side LDtk says they're on.
+    pub neighbours: Vec<(String, String)>,
+}
+
+/// An LDtk project brought over as levels.
+pub struct LdtkImport {
+    pub levels: Vec<(String, LevelSerializable)>,
+    pub world: Vec<WorldLevel>,
+    /// What couldn't be brought over, with how many times.
+    pub skipped: Vec<String>,
+}
+
+/// Reads the LDtk project `json`, from `file`, as levels drawn with the
+/// tilesets in `known`. Tile, IntGrid and auto layers land on the layer
+/// `mapping` gives them, and entities become the objects it gives them.
+/// Anything that can't be brought over is left out and listed in
+/// `skipped`.
+pub fn import_ldtk(
+    json: &[u8],
+    file: &str,
+    known: &HashMap<String, Vec<TileAsset>>,
+    mapping: &LdtkMapping,
+) -> AssetManageResult<LdtkImport> {
+    let project: LdtkProject = serde_json::from_slice(json)?;
+    let ldtk_levels: Vec<&LdtkLevel> = project
+        .levels
+        .iter()
+        .chain(project.worlds.iter().flat_map(|world| &world.levels))
+        .collect();
+    if ldtk_levels.is_empty() {
+        return Err(AssetManageError::Missing(format!("levels in {file}")));
+    }
+
+    let names: HashMap<&str, &str> = ldtk_levels
+        .iter()
+        .map(|level| (level.iid.as_str(), level.identifier.as_str()))
+        .collect();
+
+    let mut importer = LdtkImporter {
+        known,
+        mapping,
+        skipped: BTreeMap::new(),
+    };
+    let mut levels = Vec::new();
+    let mut world = Vec::new();
+    for ldtk_level in ldtk_levels {
+        let Some(level) = importer.level(ldtk_level) else {
+            continue;
+        };
+        levels.push((ldtk_level.identifier.clone(), level));
+
+        world.push(WorldLevel {
+            level: ldtk_level.identifier.clone(),
+            x: ldtk_level.world_x,
+            y: ldtk_level.world_y,
+            neighbours: ldtk_level
+                .neighbours
+                .iter()
+                .filter_map(|neighbour| {
+                    let level = names.get(neighbour.level_iid.as_str())?;
+                    Some((neighbour.dir.clone(), level.to_string()))
+                })
+                .collect(),
+        });
+    }
+
+    return Ok(LdtkImport {
+        levels,
+        world,
+        skipped: importer
+            .skipped
+            .into_iter()
+            .map(|(what, count)| match count {
+                1 => what,
+                count => format!("{what} (x{count})"),
+            })
+            .collect(),
+    });
+}
+
+/// Imports the LDtk project at `path` and writes each of its levels that
+/// the game doesn't have yet, along with where they all sit in
+/// `<project>.world.json`. Returns what was written and what was left out.
+pub fn import_ldtk_project(path: &str) -> AssetManageResult<Vec<String>> {
+    check_saving()?;
+    let import = import_ldtk(
+        &std::fs::read(path)?,
+        path,
+        &known_tile_tables()?,
+        &LdtkMapping::load()?,
+    )?;
+
+    let mut report = Vec::new();
+    for (name, level) in &import.levels {
+        let existing = LevelFormat::find(name);
+        if asset_exists(&existing) {
+            report.push(format!("kept the existing {existing}"));
+            continue;
+        }
+
+        let level_path = LevelFormat::Json.path(name);
+        LevelFormat::Json.write(level, &level_path)?;
+        report.push(format!("wrote {level_path}"));
+    }
+
+    let project = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("ldtk");
+    let world_path = format!("{}/{}.world.json", LevelFormat::DIR, project);
+    serialize(&import.world, &world_path)?;
+    report.push(format!("wrote {world_path}"));
+
+    report.extend(
+        import
+            .skipped
+            .into_iter()
+            .map(|what| format!("skipped {what}")),
+    );
+    return Ok(report);
+}
+
+struct LdtkImporter<'a> {
+    known: &'a HashMap<String, Vec<TileAsset>>,
+    mapping: &'a LdtkMapping,
+    skipped: BTreeMap<String, usize>,
+}
+
+impl LdtkImporter<'_> {
+    fn skip(&mut self, what: String) {
+        *self.skipped.entry(what).or_default() += 1;
+    }
+
+    fn level(&mut self, ldtk: &LdtkLevel) -> Option<LevelSerializable> {
+        let Some(layers) = &ldtk.layer_instances else {
+            self.skip(format!(
+                "level {:?}, saved in its own file",
+                ldtk.identifier
+            ));
+            return None;
+        };
+
+        let rows = ldtk.px_hei / TILE_SIZE as usize;
+        let cols = ldtk.px_wid / TILE_SIZE as usize;
+        let mut level = LevelSerializable {
+            version: LevelSerializable::VERSION,
+            background_layer: empty_layer(rows, cols),
+            object_layer: empty_layer(rows, cols),
+            overlay_layer: empty_layer(rows, cols),
+            rows,
+            cols,
+            objects: Vec::new(),
+            indoors: false,
+            darkness: 0.0,
+            weather: WeatherKind::default(),
+        };
+
+        // LDtk lists the top layer first, so go from the bottom up to let
+        // higher layers cover lower ones that share a game layer.
+        for layer in layers.iter().rev() {
+            if layer.grid_size != TILE_SIZE as u32 {
+                self.skip(format!(
+                    "layer {:?} on a {}px grid",
+                    layer.identifier, layer.grid_size
+                ));
+                continue;
+            }
+
+            match layer.r#type.as_str() {
+                "Entities" => self.entities(layer, &mut level),
+                "Tiles" | "IntGrid" | "AutoLayer" => self.tiles(layer, &mut level),
+                other => self.skip(format!("{other} layer {:?}", layer.identifier)),
+            }
+        }
+
+        return Some(level);
+    }
+
+    fn tiles(&mut self, layer: &LdtkLayer, level: &mut LevelSerializable) {
+        let tiles = match layer.r#type.as_str() {
+            "Tiles" => &layer.grid_tiles,
+            _ => &layer.auto_layer_tiles,
+        };
+        if tiles.is_empty() {
+            if layer.r#type == "IntGrid" {
+                self.skip(format!(
+                    "IntGrid layer {:?} with no tiles",
+                    layer.identifier
+                ));
+            }
+            return;
+        }
+
+        let Some(target) = self.mapping.layer(&layer.identifier) else {
+            self.skip(format!(
+                "layer {:?}, not mapped to background, object or overlay",
+                layer.identifier
+            ));
+            return;
+        };
+
+        let name = layer
+            .tileset_rel_path
+            .as_deref()
+            .and_then(|path| Path::new(path).file_stem())
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let Some(table) = self.known.get(name) else {
+            self.skip(format!(
+                "layer {:?}, its tileset isn't one of the game's",
+                layer.identifier
+            ));
+            return;
+        };
+
+        let cells = match target {
+            TileLayer::Background => &mut level.background_layer,
+            TileLayer::Object => &mut level.object_layer,
+            TileLayer::Overlay => &mut level.overlay_layer,
+        };
+        for tile in tiles {
+            if tile.f != 0 {
+                self.skip("flipped tiles".to_owned());
+                continue;
+            }
+
+            let Some(idx) = tile_at_pos(table, tile.src[0] as f32, tile.src[1] as f32) else {
+                self.skip(format!("tiles of {name} not set up in the game"));
+                continue;
+            };
+
+            let row = tile.px[1].div_euclid(TILE_SIZE as i64);
+            let col = tile.px[0].div_euclid(TILE_SIZE as i64);
+            match cells
+                .get_mut(row.max(0) as usize)
+                .and_then(|cells| cells.get_mut(col.max(0) as usize))
+            {
+                Some(cell) if row >= 0 && col >= 0 => {
+                    *cell = Some(TilePointer(name.to_owned(), idx));
+                }
+                _ => self.skip("tiles outside the level".to_owned()),
+            }
+        }
+    }
+
+    fn entities(&mut self, layer: &LdtkLayer, level: &mut LevelSerializable) {
+        for entity in &layer.entity_instances {
+            let Some(r#type) = self.mapping.entity(&entity.identifier) else {
+                self.skip(format!("entity {:?}, no object type", entity.identifier));
+                continue;
+            };
+
+            let [col, row] = entity.grid;
+            if col < 0 || row < 0 || col as usize >= level.cols || row as usize >= level.rows {
+                self.skip(format!("entity {:?} outside the level", entity.identifier));
+                continue;
+            }
+
+            level
+                .objects
+                .push(ObjectListing::new(0, row as usize, col as usize, r#type));
+        }
+    }
+}
+
+fn empty_layer(rows: usize, cols: usize) -> TileVec {
+    return vec![vec![None; cols]; rows];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(x: f32, y: f32) -> TileAsset {
+        return TileAsset {
+            x,
+            y,
+            auto_rule: None,
+            layer: TileLayer::Background,
+            group: None,
+            collision_matrix: None,
+            surface: None,
+            water: false,
+            animation: None,
+            y_sort: false,
+            sort_offset: 0.0,
+        };
+    }
+
+    /// Imports the fixture `name` with the game knowing the first two tiles
+    /// of the beach sheet, and the fixture mapping.
+    fn import(name: &str) -> AssetManageResult<LdtkImport> {
+        let path = format!("tests/fixtures/{name}");
+        let known = HashMap::from([("beach".to_owned(), vec![tile(0.0, 0.0), tile(16.0, 0.0)])]);
+        let mapping: LdtkMapping = deserialize("tests/fixtures/ldtk_mapping.json").unwrap();
+        return import_ldtk(&std::fs::read(&path).unwrap(), &path, &known, &mapping);
+    }
+
+    fn beach(idx: usize) -> Option<TilePointer> {
+        return Some(TilePointer("beach".to_owned(), idx));
+    }
+
+    #[test]
+    fn imports_every_level_with_its_layers() {
+        let import = import("ldtk_project.ldtk").unwrap();
+        let names: Vec<&str> = import
+            .levels
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["Shore", "Cove"]);
+
+        let shore = &import.levels[0].1;
+        assert_eq!((shore.rows, shore.cols), (2, 3));
+        // Background covers Sand, which is listed below it.
+        assert_eq!(
+            shore.background_layer,
+            [vec![beach(1), beach(0), None], vec![None, None, beach(0)]]
+        );
+        assert_eq!(shore.object_layer, [vec![None; 3], vec![None; 3]]);
+        assert_eq!(
+            shore.overlay_layer,
+            [vec![None, beach(0), None], vec![None; 3]]
+        );
+
+        let cove = &import.levels[1].1;
+        assert_eq!(cove.background_layer, [vec![None]]);
+    }
+
+    #[test]
+    fn imports_entities_through_the_mapping() {
+        let import = import("ldtk_project.ldtk").unwrap();
+        let objects: Vec<(usize, usize, String)> = import.levels[0]
+            .1
+            .objects
+            .iter()
+            .map(|listing| (listing.row(), listing.col(), listing.r#type().label()))
+            .collect();
+        assert_eq!(
+            objects,
+            [
+                (0, 0, "Checkpoint".to_owned()),
+                (1, 2, "Enemy:SeaGoblin".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_where_levels_sit() {
+        let world = import("ldtk_project.ldtk").unwrap().world;
+        let shore = &world[0];
+        assert_eq!((shore.level.as_str(), shore.x, shore.y), ("Shore", 0, 0));
+        // Neighbours that weren't in the project are left off.
+        assert_eq!(shore.neighbours, [("e".to_owned(), "Cove".to_owned())]);
+        let cove = &world[1];
+        assert_eq!((cove.level.as_str(), cove.x, cove.y), ("Cove", 48, 0));
+        assert_eq!(cove.neighbours, [("w".to_owned(), "Shore".to_owned())]);
+    }
+
+    #[test]
+    fn lists_what_it_skipped() {
+        let skipped = import("ldtk_project.ldtk").unwrap().skipped;
+        for expected in [
+            "level \"Cave\", saved in its own file",
+            "layer \"Detail\" on a 8px grid",
+            "IntGrid layer \"Walls\" with no tiles",
+            "flipped tiles",
+            "tiles of beach not set up in the game",
+            "tiles outside the level",
+            "entity \"Dragon\", no object type",
+            "entity \"Checkpoint\" outside the level",
+            "layer \"Background\", its tileset isn't one of the game's",
+        ] {
+            assert!(skipped.iter().any(|what| what == expected), "{skipped:?}");
+        }
+        assert_eq!(skipped.len(), 9);
+    }
+
+    #[test]
+    fn projects_without_levels_are_an_error() {
+        let err = import("ldtk_empty.ldtk").err().unwrap();
+        assert!(matches!(err, AssetManageError::Missing(_)));
+        assert_eq!(
+            err.to_string(),
+            "missing levels in tests/fixtures/ldtk_empty.ldtk"
+        );
+    }
+}
@@ -0,0 +1,605 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+use macroquad::logging::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    asset_loading::{
+        deserialize, deserialize_versioned, serialize, AssetManageError, AssetManageResult,
+        Versioned,
+    },
+    asset_root::AssetRoot,
+    layer_cache::LayerCache,
+    levels::{LevelSerializable, TilePointer, TileVec},
+    object::ObjectListing,
+    tilesets::TileLayer,
+    weather::WeatherKind,
+};
+
+/// A chunk's `(chunk_row, chunk_col)`.
+pub type ChunkKey = (usize, usize);
+
+/// A square of cells from each tile layer of a streamed level. Always
+/// `CELLS` along each side, with cells past the level's edge left empty.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelChunk {
+    background: TileVec,
+    object: TileVec,
+    overlay: TileVec,
+}
+
+impl LevelChunk {
+    /// Cells along each side, the same as the background cache's chunks so
+    /// one arriving only redraws its own.
+    pub const CELLS: usize = LayerCache::CHUNK_CELLS;
+
+    fn empty() -> Self {
+        let layer = || vec![vec![None; Self::CELLS]; Self::CELLS];
+        Self {
+            background: layer(),
+            object: layer(),
+            overlay: layer(),
+        }
+    }
+
+    fn layer(&self, layer: &TileLayer) -> &TileVec {
+        match layer {
+            TileLayer::Background => &self.background,
+            TileLayer::Object => &self.object,
+            TileLayer::Overlay => &self.overlay,
+        }
+    }
+
+    fn layer_mut(&mut self, layer: &TileLayer) -> &mut TileVec {
+        match layer {
+            TileLayer::Background => &mut self.background,
+            TileLayer::Object => &mut self.object,
+            TileLayer::Overlay => &mut self.overlay,
+        }
+    }
+
+    fn pointers(&self) -> impl Iterator<Item = &TilePointer> {
+        return [&self.background, &self.object, &self.overlay]
+            .into_iter()
+            .flat_map(|layer| layer.iter().flatten().flatten());
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.pointers().next().is_none();
+    }
+}
+
+/// Everything in a streamed level but its tiles, kept as `index.json` in
+/// the folder with its chunks.
+#[derive(Serialize, Deserialize)]
+pub struct ChunkIndex {
+    version: u32,
+    rows: usize,
+    cols: usize,
+    objects: Vec<ObjectListing>,
+    indoors: bool,
+    darkness: f32,
+    weather: WeatherKind,
+    /// Every tileset the chunks draw from, so they load before the chunks.
+    tilesets: Vec<String>,
+    /// The chunks with tiles in them. The rest are empty and have no file.
+    chunks: Vec<ChunkKey>,
+}
+
+impl Versioned for ChunkIndex {
+    const VERSION: u32 = 1;
+
+    fn migrate(from: u32, _doc: &mut Value) -> AssetManageResult<()> {
+        return Err(AssetManageError::Missing(format!(
+            "chunked level migration from version {from}"
+        )));
+    }
+}
+
+impl ChunkIndex {
+    /// The level without its tiles, which stream in later.
+    pub fn into_serializable(self) -> LevelSerializable {
+        LevelSerializable {
+            version: LevelSerializable::VERSION,
+            background_layer: Vec::new(),
+            object_layer: Vec::new(),
+            overlay_layer: Vec::new(),
+            rows: self.rows,
+            cols: self.cols,
+            objects: self.objects,
+            indoors: self.indoors,
+            darkness: self.darkness,
+            weather: self.weather,
+        }
+    }
+}
+
+/// The folder a chunked level with its index at `index` keeps its chunks in.
+fn chunk_dir(index: &str) -> &str {
+    return Path::new(index)
+        .parent()
+        .and_then(|dir| dir.to_str())
+        .unwrap_or(index);
+}
+
+fn chunk_path(dir: &str, (chunk_row, chunk_col): ChunkKey) -> String {
+    return format!("{dir}/{chunk_row}_{chunk_col}.json");
+}
+
+/// Reads the chunked level with its index at `path` whole, for converting
+/// and checking it.
+pub fn read_chunked(path: &str) -> AssetManageResult<LevelSerializable> {
+    let index: ChunkIndex = deserialize_versioned(path)?;
+    let dir = chunk_dir(path);
+    let (rows, cols) = (index.rows, index.cols);
+    let mut layers = [(); 3].map(|_| vec![vec![None; cols]; rows]);
+    for &key in &index.chunks {
+        let chunk: LevelChunk = deserialize(chunk_path(dir, key))?;
+        for (layer, from) in
+            layers
+                .iter_mut()
+                .zip([&chunk.background, &chunk.object, &chunk.overlay])
+        {
+            copy_chunk(from, key, layer);
+        }
+    }
+
+    let [background_layer, object_layer, overlay_layer] = layers;
+    return Ok(LevelSerializable {
+        background_layer,
+        object_layer,
+        overlay_layer,
+        ..index.into_serializable()
+    });
+}
+
+/// Copies the cells of `chunk`, at `key`, that fall inside `layer`.
+fn copy_chunk(chunk: &TileVec, (chunk_row, chunk_col): ChunkKey, layer: &mut TileVec) {
+    for (row, cells) in chunk.iter().enumerate() {
+        let Some(to) = layer.get_mut(chunk_row * LevelChunk::CELLS + row) else {
+            break;
+        };
+        for (col, cell) in cells.iter().enumerate() {
+            if let Some(to) = to.get_mut(chunk_col * LevelChunk::CELLS + col) {
+                *to = cell.clone();
+            }
+        }
+    }
+}
+
+/// Splits `level` into chunks and writes them, and its index at `path`,
+/// into a fresh folder.
+pub fn write_chunked(level: &LevelSerializable, path: &str) -> AssetManageResult<()> {
+    let dir = chunk_dir(path);
+    let resolved = AssetRoot::resolve(dir);
+    if resolved.exists() {
+        std::fs::remove_dir_all(&resolved)?;
+    }
+    std::fs::create_dir_all(&resolved)?;
+
+    let mut written = Vec::new();
+    let mut tilesets = HashSet::new();
+    for chunk_row in 0..level.rows.div_ceil(LevelChunk::CELLS) {
+        for chunk_col in 0..level.cols.div_ceil(LevelChunk::CELLS) {
+            let key = (chunk_row, chunk_col);
+            let mut chunk = LevelChunk::empty();
+            for (from, layer) in [
+                (&level.background_layer, TileLayer::Background),
+                (&level.object_layer, TileLayer::Object),
+                (&level.overlay_layer, TileLayer::Overlay),
+            ] {
+                let to = chunk.layer_mut(&layer);
+                for (row, cells) in to.iter_mut().enumerate() {
+                    let Some(from) = from.get(chunk_row * LevelChunk::CELLS + row) else {
+                        break;
+                    };
+                    for (col, cell) in cells.iter_mut().enumerate() {
+                        if let Some(from) = from.get(chunk_col * LevelChunk::CELLS + col) {
+                            *cell = from.clone();
+                        }
+                    }
+                }
+            }
+
+            if !chunk.is_empty() {
+                tilesets.extend(chunk.pointers().map(|ptr| ptr.0.clone()));
+                serialize(&chunk, chunk_path(dir, key))?;
+                written.push(key);
+            }
+        }
+    }
+
+    return write_index(level, tilesets, written, path);
+}
+
+fn write_index(
+    level: &LevelSerializable,
+    tilesets: HashSet<String>,
+    chunks: Vec<ChunkKey>,
+    path: &str,
+) -> AssetManageResult<()> {
+    let mut tilesets: Vec<String> = tilesets.into_iter().collect();
+    tilesets.sort();
+    let index = ChunkIndex {
+        version: ChunkIndex::VERSION,
+        rows: level.rows,
+        cols: level.cols,
+        objects: level.objects.clone(),
+        indoors: level.indoors,
+        darkness: level.darkness,
+        weather: level.weather,
+        tilesets,
+        chunks,
+    };
+    return serialize(&index, path);
+}
+
+/// What the loader thread sends back: the chunk, or why it couldn't be
+/// read. Asset errors can't cross threads, so it's only the message.
+type Loaded = (ChunkKey, Result<LevelChunk, String>);
+
+/// A thread reading and parsing the chunk files sent to it. It stops once
+/// the level drops it.
+struct ChunkLoader {
+    requests: Sender<(ChunkKey, PathBuf)>,
+    loaded: Receiver<Loaded>,
+}
+
+impl ChunkLoader {
+    fn spawn() -> Self {
+        let (requests, to_read) = channel::<(ChunkKey, PathBuf)>();
+        let (send, loaded) = channel();
+        std::thread::spawn(move || {
+            for (key, path) in to_read {
+                let chunk = read_chunk_file(&path).map_err(|err| err.to_string());
+                if send.send((key, chunk)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { requests, loaded }
+    }
+}
+
+/// The tile layers of a streamed level, of which only the chunks in and
+/// around the view are held. Chunks in view are read at once and those
+/// around it on a loader thread, so walking into them doesn't wait on the
+/// disk.
+pub struct ChunkedLayers {
+    index: String,
+    /// The chunks with a file. Those without are empty.
+    on_disk: HashSet<ChunkKey>,
+    tilesets: HashSet<String>,
+    loaded: HashMap<ChunkKey, LevelChunk>,
+    /// Chunks asked of the loader that haven't come back.
+    requested: HashSet<ChunkKey>,
+    /// Chunks the editor changed since they were saved, kept until then.
+    edited: HashSet<ChunkKey>,
+    /// `None` where assets can't be read off the main thread, like web and
+    /// embedded builds, which read every chunk at once instead.
+    loader: Option<ChunkLoader>,
+    /// Chunks read on the main thread although there was a loader, each one
+    /// a wait in the middle of a frame.
+    blocking_reads: usize,
+}
+
+impl ChunkedLayers {
+    /// Chunks past the view on each side loaded ahead.
+    const AHEAD: usize = 1;
+    /// Chunks past the view on each side kept after they were left, so
+    /// walking back and forth over an edge doesn't read them again.
+    pub const KEEP: usize = 2;
+
+    /// Opens the chunks of the level with its index, `index`, at `path`.
+    /// None are loaded until `stream`.
+    pub fn open(path: &str, index: &ChunkIndex) -> Self {
+        let loader = match cfg!(any(feature = "embedded-assets", target_arch = "wasm32")) {
+            true => None,
+            false => Some(ChunkLoader::spawn()),
+        };
+        Self {
+            index: path.to_owned(),
+            on_disk: index.chunks.iter().copied().collect(),
+            tilesets: index.tilesets.iter().cloned().collect(),
+            loaded: HashMap::new(),
+            requested: HashSet::new(),
+            edited: HashSet::new(),
+            loader,
+            blocking_reads: 0,
+        }
+    }
+
+    /// How many chunks are held in memory.
+    pub fn loaded_count(&self) -> usize {
+        return self.loaded.len();
+    }
+
+    /// How many chunks were asked of the loader and haven't come back.
+    pub fn pending_count(&self) -> usize {
+        return self.requested.len();
+    }
+
+    /// How many chunks were read on the main thread when the loader could
+    /// have read them.
+    pub fn blocking_reads(&self) -> usize {
+        return self.blocking_reads;
+    }
+
+    /// Names of the tilesets the chunks are drawn from.
+    pub fn tileset_names(&self) -> HashSet<String> {
+        let mut names = self.tilesets.clone();
+        for key in &self.edited {
+            names.extend(self.loaded[key].pointers().map(|ptr| ptr.0.clone()));
+        }
+        return names;
+    }
+
+    /// The tile in `layer` at `row`, `col`, or `None` if the chunk isn't
+    /// loaded.
+    pub fn cell(&self, layer: &TileLayer, row: usize, col: usize) -> Option<&TilePointer> {
+        let key = (row / LevelChunk::CELLS, col / LevelChunk::CELLS);
+        let chunk = self.loaded.get(&key)?;
+        return chunk.layer(layer)[row % LevelChunk::CELLS][col % LevelChunk::CELLS].as_ref();
+    }
+
+    /// The cell in `layer` at `row`, `col` for the editor to change, or
+    /// `None` if its chunk is on disk but not loaded yet.
+    pub fn cell_mut(
+        &mut self,
+        layer: &TileLayer,
+        row: usize,
+        col: usize,
+    ) -> Option<&mut Option<TilePointer>> {
+        let key = (row / LevelChunk::CELLS, col / LevelChunk::CELLS);
+        if !self.loaded.contains_key(&key) {
+            if self.on_disk.contains(&key) {
+                return None;
+            }
+            self.loaded.insert(key, LevelChunk::empty());
+        }
+
+        self.edited.insert(key);
+        let chunk = self.loaded.get_mut(&key).expect("Chunk should be loaded");
+        return Some(&mut chunk.layer_mut(layer)[row % LevelChunk::CELLS][col % LevelChunk::CELLS]);
+    }
+
+    /// Loads the chunks covering the cells `rows` by `cols` now, asks for
+    /// those around them, and lets go of those far away. Returns the chunks
+    /// that arrived or left.
+    pub fn stream(&mut self, rows: Range<usize>, cols: Range<usize>) -> Vec<ChunkKey> {
+        let chunks = |range: &Range<usize>, pad: usize| {
+            let first = (range.start / LevelChunk::CELLS).saturating_sub(pad);
+            let last = range.end.div_ceil(LevelChunk::CELLS) + pad;
+            first..last
+        };
+        let around = |pad: usize| {
+            let (rows, cols) = (chunks(&rows, pad), chunks(&cols, pad));
+            return move |&(row, col): &ChunkKey| rows.contains(&row) && cols.contains(&col);
+        };
+        let (in_view, ahead, kept) = (around(0), around(Self::AHEAD), around(Self::KEEP));
+
+        let mut changed = Vec::new();
+        while let Some((key, chunk)) = self.receive() {
+            if self.requested.remove(&key) && !self.loaded.contains_key(&key) {
+                self.loaded.insert(key, chunk);
+                changed.push(key);
+            }
+        }
+
+        let missing: Vec<ChunkKey> = self
+            .on_disk
+            .iter()
+            .filter(|key| ahead(key) && !self.loaded.contains_key(key))
+            .copied()
+            .collect();
+        for key in missing {
+            match (&self.loader, in_view(&key)) {
+                (Some(loader), false) => {
+                    if self.requested.insert(key) {
+                        let path = AssetRoot::resolve(chunk_path(chunk_dir(&self.index), key));
+                        let _ = loader.requests.send((key, path));
+                    }
+                }
+                // Needed this frame, or there's no loader to wait on.
+                _ => {
+                    self.requested.remove(&key);
+                    if self.loader.is_some() {
+                        self.blocking_reads += 1;
+                    }
+                    self.loaded.insert(key, self.read_now(key));
+                    changed.push(key);
+                }
+            }
+        }
+
+        let edited = &self.edited;
+        self.loaded.retain(|key, _| {
+            let keep = kept(key) || edited.contains(key);
+            if !keep {
+                changed.push(*key);
+            }
+            return keep;
+        });
+        self.requested.retain(|key| kept(key));
+        return changed;
+    }
+
+    /// A chunk the loader finished, if there is one yet.
+    fn receive(&self) -> Option<(ChunkKey, LevelChunk)> {
+        let (key, chunk) = self.loader.as_ref()?.loaded.try_recv().ok()?;
+        return Some((key, self.or_empty(key, chunk)));
+    }
+
+    fn read_now(&self, key: ChunkKey) -> LevelChunk {
+        let chunk = deserialize(chunk_path(chunk_dir(&self.index), key));
+        return self.or_empty(key, chunk.map_err(|err| err.to_string()));
+    }
+
+    /// The chunk, or an empty one if it couldn't be read, so it isn't read
+    /// again every frame.
+    fn or_empty(&self, key: ChunkKey, chunk: Result<LevelChunk, String>) -> LevelChunk {
+        return chunk.unwrap_or_else(|err| {
+            warn!("Could not read chunk {:?} of {}: {}", key, self.index, err);
+            LevelChunk::empty()
+        });
+    }
+
+    /// Writes the edited chunks back, and `level`'s index without its
+    /// tiles. Chunks left empty lose their file.
+    pub fn save(&mut self, level: &LevelSerializable) -> AssetManageResult<()> {
+        let dir = chunk_dir(&self.index).to_owned();
+        std::fs::create_dir_all(AssetRoot::resolve(&dir))?;
+        for key in self.edited.clone() {
+            let chunk = &self.loaded[&key];
+            let path = chunk_path(&dir, key);
+            match chunk.is_empty() {
+                true => {
+                    if self.on_disk.remove(&key) {
+                        std::fs::remove_file(AssetRoot::resolve(path))?;
+                    }
+                }
+                false => {
+                    serialize(chunk, path)?;
+                    self.on_disk.insert(key);
+                }
+            }
+            self.tilesets
+                .extend(chunk.pointers().map(|ptr| ptr.0.clone()));
+        }
+        self.edited.clear();
+
+        let mut chunks: Vec<ChunkKey> = self.on_disk.iter().copied().collect();
+        chunks.sort();
+        return write_index(level, self.tilesets.clone(), chunks, &self.index);
+    }
+}
+
+fn read_chunk_file(path: &Path) -> AssetManageResult<LevelChunk> {
+    return Ok(serde_json::from_slice(&std::fs::read(path)?)?);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::asset_loading::tests::scratch_dir;
+
+    const SIDE: usize = 6;
+
+    /// A level `SIDE` chunks each way with a tile in every cell of the
+    /// first `filled` rows of chunks, written chunked under the test `name`.
+    fn chunked_level(name: &str, filled: usize) -> ChunkedLayers {
+        let cells = SIDE * LevelChunk::CELLS;
+        let layer = |pointer: Option<TilePointer>| {
+            let mut layer = vec![vec![None; cells]; cells];
+            for row in layer.iter_mut().take(filled * LevelChunk::CELLS) {
+                row.fill(pointer.clone());
+            }
+            layer
+        };
+        let level = LevelSerializable {
+            version: LevelSerializable::VERSION,
+            background_layer: layer(Some(TilePointer("test".to_owned(), 0))),
+            object_layer: layer(None),
+            overlay_layer: layer(None),
+            rows: cells,
+            cols: cells,
+            objects: Vec::new(),
+            indoors: false,
+            darkness: 0.0,
+            weather: WeatherKind::Clear,
+        };
+
+        let path = scratch_dir(name).join("index.json");
+        let path = path.to_string_lossy();
+        write_chunked(&level, &path).unwrap();
+        let index: ChunkIndex = deserialize_versioned(&*path).unwrap();
+        return ChunkedLayers::open(&path, &index);
+    }
+
+    /// Streams the view over chunk `key` until the loader has sent back
+    /// everything asked of it. Returns the chunks that arrived or left.
+    fn settle(layers: &mut ChunkedLayers, (row, col): ChunkKey) -> HashSet<ChunkKey> {
+        let rows = row * LevelChunk::CELLS..(row + 1) * LevelChunk::CELLS;
+        let cols = col * LevelChunk::CELLS..(col + 1) * LevelChunk::CELLS;
+        let mut changed = HashSet::new();
+        for _ in 0..1000 {
+            changed.extend(layers.stream(rows.clone(), cols.clone()));
+            if layers.requested.is_empty() {
+                return changed;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("The loader never sent back {:?}", layers.requested);
+    }
+
+    fn loaded(layers: &ChunkedLayers) -> Vec<ChunkKey> {
+        let mut keys: Vec<ChunkKey> = layers.loaded.keys().copied().collect();
+        keys.sort();
+        return keys;
+    }
+
+    #[test]
+    fn chunks_load_and_leave_with_the_view() {
+        let mut layers = chunked_level("chunks_follow_the_view", SIDE);
+        let cell = LevelChunk::CELLS;
+        assert!(layers.cell(&TileLayer::Background, 0, 0).is_none());
+
+        // The chunk in view is read at once, the ones around it after, or
+        // at once too in builds without a loader thread.
+        let mut first = layers.stream(0..cell, 0..cell);
+        first.sort();
+        match layers.loader {
+            Some(_) => assert_eq!(first, [(0, 0)]),
+            None => assert_eq!(first, [(0, 0), (0, 1), (1, 0), (1, 1)]),
+        }
+        assert!(layers.cell(&TileLayer::Background, 0, 0).is_some());
+        settle(&mut layers, (0, 0));
+        assert_eq!(loaded(&layers), [(0, 0), (0, 1), (1, 0), (1, 1)]);
+
+        // A step over keeps what's left behind.
+        settle(&mut layers, (1, 1));
+        assert_eq!(loaded(&layers).len(), 9);
+        assert!(layers.cell(&TileLayer::Background, 0, 0).is_some());
+
+        // Far away, only the chunks around the view are held.
+        let changed = settle(&mut layers, (SIDE - 1, SIDE - 1));
+        let far = [(4, 4), (4, 5), (5, 4), (5, 5)];
+        assert_eq!(loaded(&layers), far);
+        assert!(changed.contains(&(0, 0)));
+        assert!(far.iter().all(|key| changed.contains(key)));
+        assert!(layers.cell(&TileLayer::Background, 0, 0).is_none());
+        let last = SIDE * cell - 1;
+        assert!(layers.cell(&TileLayer::Background, last, last).is_some());
+    }
+
+    #[test]
+    fn only_chunks_without_a_file_can_be_edited_before_loading() {
+        let mut layers = chunked_level("chunks_edit_before_loading", 1);
+
+        // The first row of chunks has tiles, so has files to read first.
+        assert!(layers.cell_mut(&TileLayer::Object, 0, 0).is_none());
+        assert!(layers.loaded.is_empty());
+
+        // The rest are empty and start fresh.
+        let row = LevelChunk::CELLS;
+        let cell = layers.cell_mut(&TileLayer::Object, row, 0).unwrap();
+        assert!(cell.is_none());
+        *cell = Some(TilePointer("test".to_owned(), 1));
+        assert_eq!(loaded(&layers), [(1, 0)]);
+
+        // Edits are held until saved, however far the view goes.
+        settle(&mut layers, (SIDE - 1, SIDE - 1));
+        assert_eq!(
+            layers.cell(&TileLayer::Object, row, 0),
+            Some(&TilePointer("test".to_owned(), 1))
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{
+        asset_exists, deserialize_versioned, read_asset, serialize, AssetManageError,
+        AssetManageResult, Versioned,
+    },
+    asset_root::AssetRoot,
+    level_chunks::{read_chunked, write_chunked},
+    levels::{LevelSerializable, TilePointer, TileVec},
+    object::ObjectListing,
+    weather::WeatherKind,
+};
+
+/// How a level is written to disk, told apart by the file's extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LevelFormat {
+    /// Pretty-printed, for reading and merging by hand.
+    Json,
+    /// Compact cells, for big levels that load fast.
+    Binary,
+    /// JSON, gzipped, for big levels that stay small on disk.
+    GzippedJson,
+    /// A folder of JSON chunks and an index, for huge levels streamed in
+    /// around the camera.
+    Chunked,
+}
+
+impl LevelFormat {
+    pub const DIR: &str = "assets/levels";
+    /// The file in a chunked level's folder holding all but its tiles.
+    pub const INDEX: &str = "index.json";
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LevelFormat::Json | LevelFormat::Chunked => "json",
+            LevelFormat::Binary => "bin",
+            LevelFormat::GzippedJson => "json.gz",
+        }
+    }
+
+    /// The format of the level file at `path`.
+    pub fn of(path: &str) -> Self {
+        if path.ends_with(&format!("/{}", Self::INDEX)) {
+            return LevelFormat::Chunked;
+        }
+        if path.ends_with(".json.gz") {
+            return LevelFormat::GzippedJson;
+        }
+        return match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("bin") => LevelFormat::Binary,
+            _ => LevelFormat::Json,
+        };
+    }
+
+    /// The name of the level kept at `path`, if it's a level file or a
+    /// chunked level's index.
+    pub fn name_of(path: &Path) -> Option<&str> {
+        let file = path.file_name()?.to_str()?;
+        if file == Self::INDEX {
+            return path.parent()?.file_name()?.to_str();
+        }
+        return [
+            LevelFormat::GzippedJson,
+            LevelFormat::Json,
+            LevelFormat::Binary,
+        ]
+        .iter()
+        .find_map(|format| file.strip_suffix(&format!(".{}", format.extension())));
+    }
+
+    pub fn other(&self) -> Self {
+        match self {
+            LevelFormat::Json | LevelFormat::GzippedJson => LevelFormat::Binary,
+            LevelFormat::Binary => LevelFormat::Chunked,
+            LevelFormat::Chunked => LevelFormat::Json,
+        }
+    }
+
+    /// The format to save a level read in this one as, gzipping JSON if
+    /// `gzip` and unzipping it otherwise.
+    pub fn saved_as(&self, gzip: bool) -> Self {
+        match self {
+            LevelFormat::Json | LevelFormat::GzippedJson if gzip => LevelFormat::GzippedJson,
+            LevelFormat::Json | LevelFormat::GzippedJson => LevelFormat::Json,
+            format => *format,
+        }
+    }
+
+    /// Where `level` is kept in this format.
+    pub fn path(&self, level: &str) -> String {
+        return match self {
+            LevelFormat::Chunked => format!("{}/{}/{}", Self::DIR, level, Self::INDEX),
+            _ => format!("{}/{}.{}", Self::DIR, level, self.extension()),
+        };
+    }
+
+    /// Where `level` is kept, preferring the binary file, then the chunked
+    /// one, then the gzipped one, if there are more than one.
+    pub fn find(level: &str) -> String {
+        let found = [
+            LevelFormat::Binary,
+            LevelFormat::Chunked,
+            LevelFormat::GzippedJson,
+        ]
+        .iter()
+        .map(|format| format.path(level))
+        .find(|path| asset_exists(path));
+        return found.unwrap_or_else(|| LevelFormat::Json.path(level));
+    }
+
+    pub fn read(&self, path: &str) -> AssetManageResult<LevelSerializable> {
+        return match self {
+            LevelFormat::Json | LevelFormat::GzippedJson => deserialize_versioned(path),
+            LevelFormat::Binary => decode(&read_asset(path)?, path),
+            LevelFormat::Chunked => read_chunked(path),
+        };
+    }
+
+    /// Deletes the level kept at `path` in this format.
+    pub fn remove(&self, path: &str) -> std::io::Result<()> {
+        let resolved = AssetRoot::resolve(path);
+        return match (self, resolved.parent()) {
+            (LevelFormat::Chunked, Some(dir)) => std::fs::remove_dir_all(dir),
+            _ => std::fs::remove_file(resolved),
+        };
+    }
+
+    pub fn write(&self, level: &LevelSerializable, path: &str) -> AssetManageResult<()> {
+        match self {
+            LevelFormat::Json | LevelFormat::GzippedJson => serialize(level, path)?,
+            LevelFormat::Binary => std::fs::write(AssetRoot::resolve(path), encode(level, path)?)?,
+            LevelFormat::Chunked => write_chunked(level, path)?,
+        }
+        return Ok(());
+    }
+}
+
+/// Everything in a binary level past the tile layers, kept as JSON since
+/// it's small and its shape changes often.
+#[derive(Serialize, Deserialize)]
+struct BinaryExtras {
+    objects: Vec<ObjectListing>,
+    indoors: bool,
+    darkness: f32,
+    weather: WeatherKind,
+}
+
+const MAGIC: &[u8; 4] = b"IPIL";
+const VERSION: u8 = 1;
+/// Tileset index of an empty cell.
+const EMPTY: u16 = u16::MAX;
+/// A tileset index and a tile index.
+const CELL_BYTES: usize = 4;
+
+/// Lays a level out as a header, the table of tileset names, every cell of
+/// the three layers as a tileset index and a tile index, and then the
+/// extras. Numbers are little-endian.
+fn encode(level: &LevelSerializable, path: &str) -> AssetManageResult<Vec<u8>> {
+    let layers = [
+        &level.background_layer,
+        &level.object_layer,
+        &level.overlay_layer,
+    ];
+
+    let mut names: Vec<&str> = Vec::new();
+    for ptr in layers
+        .iter()
+        .flat_map(|layer| layer.iter().flatten().flatten())
+    {
+        if !names.contains(&ptr.0.as_str()) {
+            names.push(&ptr.0);
+        }
+    }
+    if names.len() >= EMPTY as usize {
+        return Err(AssetManageError::corrupt(
+            path,
+            "it uses too many tilesets to store",
+        ));
+    }
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&(level.rows as u32).to_le_bytes());
+    bytes.extend_from_slice(&(level.cols as u32).to_le_bytes());
+
+    bytes.extend_from_slice(&(names.len() as u16).to_le_bytes());
+    for name in &names {
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    for layer in layers {
+        for row in 0..level.rows {
+            for col in 0..level.cols {
+                let cell = layer.get(row).and_then(|cells| cells.get(col));
+                let (tileset, tile) = match cell.and_then(|cell| cell.as_ref()) {
+                    Some(ptr) => {
+                        let tileset = names.iter().position(|name| *name == ptr.0);
+                        let tile = u16::try_from(ptr.1).map_err(|_| {
+                            AssetManageError::corrupt(path, "a tile index is too big to store")
+                        })?;
+                        (tileset.unwrap_or_default() as u16, tile)
+                    }
+                    None => (EMPTY, 0),
+                };
+                bytes.extend_from_slice(&tileset.to_le_bytes());
+                bytes.extend_from_slice(&tile.to_le_bytes());
+            }
+        }
+    }
+
+    let extras = serde_json::to_vec(&BinaryExtras {
+        objects: level.objects.clone(),
+        indoors: level.indoors,
+        darkness: level.darkness,
+        weather: level.weather,
+    })?;
+    bytes.extend_from_slice(&(extras.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&extras);
+
+    return Ok(bytes);
+}
+
+fn decode(bytes: &[u8], path: &str) -> AssetManageResult<LevelSerializable> {
+    let mut reader = ByteReader { bytes, path };
+
+    if reader.take(MAGIC.len(), "the header")? != MAGIC {
+        return Err(AssetManageError::corrupt(path, "it isn't a binary level"));
+    }
+    let version = reader.take(1, "the header")?[0];
+    if version != VERSION {
+        return Err(AssetManageError::corrupt(
+            path,
+            &format!("unknown version {version}"),
+        ));
+    }
+
+    let rows = reader.u32("the header")? as usize;
+    let cols = reader.u32("the header")? as usize;
+
+    let count = reader.u16("the tileset table")? as usize;
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = reader.u16("the tileset table")? as usize;
+        let name = std::str::from_utf8(reader.take(len, "the tileset table")?)
+            .map_err(|_| AssetManageError::corrupt(path, "a tileset name isn't valid text"))?;
+        names.push(name.to_owned());
+    }
+
+    // Checked before reserving room, so a bad size can't ask for too much.
+    let fits = rows
+        .checked_mul(cols)
+        .is_some_and(|cells| cells.saturating_mul(CELL_BYTES * 3) <= reader.bytes.len());
+    if !fits {
+        return Err(AssetManageError::corrupt(
+            path,
+            &format!("it's too short for {rows} by {cols} cells"),
+        ));
+    }
+
+    let mut layers: [TileVec; 3] = Default::default();
+    for (layer, what) in layers.iter_mut().zip([
+        "the background layer",
+        "the object layer",
+        "the overlay layer",
+    ]) {
+        layer.reserve(rows);
+        for _ in 0..rows {
+            let mut row = Vec::with_capacity(cols);
+            for _ in 0..cols {
+                let tileset = reader.u16(what)?;
+                let tile = reader.u16(what)?;
+                row.push(match tileset {
+                    EMPTY => None,
+                    idx => {
+                        let name = names.get(idx as usize).ok_or_else(|| {
+                            let reason = format!("{what} uses tileset {idx} of only {count}");
+                            AssetManageError::corrupt(path, &reason)
+                        })?;
+                        Some(TilePointer(name.clone(), tile as usize))
+                    }
+                });
+            }
+            layer.push(row);
+        }
+    }
+
+    let len = reader.u32("the extras")? as usize;
+    let extras: BinaryExtras =
+        serde_json::from_slice(reader.take(len, "the extras")?).map_err(|err| {
+            AssetManageError::corrupt(path, &format!("the extras can't be read: {err}"))
+        })?;
+    if !reader.bytes.is_empty() {
+        return Err(AssetManageError::corrupt(
+            path,
+            "it has data past the end of the level",
+        ));
+    }
+
+    let [background_layer, object_layer, overlay_layer] = layers;
+    return Ok(LevelSerializable {
+        version: LevelSerializable::VERSION,
+        background_layer,
+        object_layer,
+        overlay_layer,
+        rows,
+        cols,
+        objects: extras.objects,
+        indoors: extras.indoors,
+        darkness: extras.darkness,
+        weather: extras.weather,
+    });
+}
+
+/// Reads a binary level from the front, saying what it was reading if the
+/// file runs out.
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    path: &'a str,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize, what: &str) -> AssetManageResult<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(AssetManageError::corrupt(
+                self.path,
+                &format!("it ends partway through {what}"),
+            ));
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        return Ok(taken);
+    }
+
+    fn u16(&mut self, what: &str) -> AssetManageResult<u16> {
+        let bytes = self.take(2, what)?;
+        return Ok(u16::from_le_bytes([bytes[0], bytes[1]]));
+    }
+
+    fn u32(&mut self, what: &str) -> AssetManageResult<u32> {
+        let bytes = self.take(4, what)?;
+        return Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+    }
+}
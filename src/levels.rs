@@ -1,28 +1,58 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     iter,
     ops::Range,
-    usize,
+    rc::Rc,
 };
 
 use macroquad::{
-    color::{Color, BLACK, DARKPURPLE, GRAY as GREY, RED, WHITE},
-    math::{clamp, vec2, Rect},
-    shapes::{draw_line, draw_rectangle},
+    camera::{pop_camera_state, push_camera_state, set_camera, Camera2D},
+    color::{
+        Color, BLACK, DARKPURPLE, GRAY as GREY, GREEN, MAGENTA, ORANGE, RED, SKYBLUE, WHITE, YELLOW,
+    },
+    logging::info,
+    math::{clamp, vec2, Rect, Vec2},
+    shapes::{draw_circle_lines, draw_line, draw_rectangle, draw_rectangle_lines},
     text::draw_text,
-    texture::{draw_texture_ex, DrawTextureParams},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+    time::get_time,
     ui::root_ui,
+    window::clear_background,
 };
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
-    asset_loading::{deserialize, serialize, AssetManageResult},
+    aseprite,
+    asset_loading::{
+        asset_problems, check_saving, deserialize_versioned, fill_field, serialize,
+        AssetManageError, AssetManageResult, Versioned,
+    },
+    assets::Assets,
+    atlas::TileAtlas,
+    body::Body,
+    dialogue::DialoguePage,
+    enemies::EnemyType,
     input::Input,
-    object::{LevelObjects, ObjectListing},
-    tilesets::{TileAsset, TileAutoRule, TileLayer, TilesetAsset, TilesetAssetSerializable},
-    utils::{alert, prompt, splitter},
+    items::ItemId,
+    layer_cache::LayerCache,
+    ldtk,
+    level_chunks::{ChunkIndex, ChunkedLayers},
+    level_file::LevelFormat,
+    manifest::AssetManifest,
+    object::{LevelObjects, ObjectListing, ObjectType},
+    tile_batch::TileBatch,
+    tiled,
+    tilesets::{
+        known_tile_tables, SurfaceKind, TileAnimation, TileAsset, TileAutoRule, TileLayer,
+        TilesetAsset,
+    },
+    utils::{alert, confirm, prompt, splitter},
+    validate::validate_assets,
+    weather::WeatherKind,
     world::World,
+    world_state::ObjectState,
     TILE_COLLISION_SECTIONS, TILE_SIZE, VIRTUAL_H, VIRTUAL_W,
 };
 
@@ -30,17 +60,77 @@ use super::tilesets::CollisionMatrix;
 
 pub type TileVec = Vec<Vec<Option<TilePointer>>>;
 
+/// An object-layer tile drawn in the y-sorted pass, borrowed from the level
+/// for one frame.
+pub struct SortedTile<'a> {
+    tex: &'a Texture2D,
+    source: Rect,
+    x: f32,
+    y: f32,
+    sort_key: i32,
+}
+
+impl SortedTile<'_> {
+    pub fn y_sort_key(&self) -> i32 {
+        self.sort_key
+    }
+
+    pub fn render(&self, world: &World) {
+        draw_texture_ex(
+            self.tex,
+            self.x - world.x,
+            self.y - world.y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(self.source),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TilePointer(pub String, pub usize);
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TilePointer(String, pub usize);
+pub struct LevelSerializable {
+    pub version: u32,
+    pub background_layer: TileVec,
+    pub object_layer: TileVec,
+    pub overlay_layer: TileVec,
+    pub rows: usize,
+    pub cols: usize,
+    pub objects: Vec<ObjectListing>,
+    /// Shut off from the sky, so the time of day doesn't show.
+    pub indoors: bool,
+    /// How much light the level lacks away from any light, from 0 for none
+    /// to 1 for pitch black.
+    pub darkness: f32,
+    pub weather: WeatherKind,
+}
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LevelSerializable {
-    background_layer: TileVec,
-    object_layer: TileVec,
-    overlay_layer: TileVec,
-    rows: usize,
-    cols: usize,
-    objects: Vec<ObjectListing>,
+impl Versioned for LevelSerializable {
+    const VERSION: u32 = 1;
+
+    fn migrate(from: u32, doc: &mut Value) -> AssetManageResult<()> {
+        match from {
+            // Before versions, levels could be missing everything added
+            // after the tile layers, going back to before objects could be
+            // placed.
+            0 => {
+                fill_field(doc, "objects", Vec::<ObjectListing>::new())?;
+                fill_field(doc, "indoors", false)?;
+                fill_field(doc, "darkness", 0.0)?;
+                fill_field(doc, "weather", WeatherKind::default())?;
+            }
+            _ => {
+                return Err(AssetManageError::Missing(format!(
+                    "level migration from version {from}"
+                )))
+            }
+        }
+        return Ok(());
+    }
 }
 
 pub struct LevelEditorSettings {
@@ -51,11 +141,29 @@ pub struct LevelEditorSettings {
     pub show_background: bool,
     pub show_object: bool,
     pub show_overlay: bool,
+    pub show_hitboxes: bool,
     editing_tile: bool,
+    object_mode: bool,
+    selected_object_type: Option<usize>,
+    /// Listing whose patrol route clicks are currently adding waypoints to.
+    patrol_target: Option<usize>,
+    /// Listing shown in the inspector.
+    inspecting: Option<usize>,
+    /// The next click moves the inspected listing to that cell.
+    moving: bool,
+    /// Cell where the spawn region or camera zone being dragged out started.
+    region_start: Option<(usize, usize)>,
+    /// Save JSON levels gzipped, from the settings.
+    compress_levels: bool,
+    /// The level has been edited since it was loaded or last saved.
+    dirty: bool,
 }
 
 impl LevelEditorSettings {
-    pub fn new() -> Self {
+    /// Width of the panel down the left of the screen.
+    pub const PANEL_WIDTH: f32 = VIRTUAL_W / 3.0;
+
+    pub fn new(compress_levels: bool) -> Self {
         Self {
             open: false,
             selected_tileset: None,
@@ -64,7 +172,16 @@ impl LevelEditorSettings {
             show_background: true,
             show_object: true,
             show_overlay: true,
+            show_hitboxes: false,
             editing_tile: false,
+            object_mode: false,
+            selected_object_type: None,
+            patrol_target: None,
+            inspecting: None,
+            moving: false,
+            region_start: None,
+            compress_levels,
+            dirty: false,
         }
     }
 
@@ -73,6 +190,20 @@ impl LevelEditorSettings {
         self.selected_tile = None;
         self.selected_tileset = None;
     }
+
+    /// Whether the mouse is over the open panel rather than the level.
+    pub fn has_mouse(&self, input: &Input) -> bool {
+        return self.open && input.mouse_x <= Self::PANEL_WIDTH;
+    }
+}
+
+/// What lies between two points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineOfSight {
+    Clear,
+    /// Only low sections (bushes, fences) are in the way.
+    Partial,
+    Blocked,
 }
 
 pub struct TileHitInfo {
@@ -80,6 +211,7 @@ pub struct TileHitInfo {
     col: f32,
 }
 
+#[allow(clippy::wrong_self_convention)]
 impl TileHitInfo {
     const SMALL: f32 = 0.0001;
 
@@ -100,37 +232,100 @@ impl TileHitInfo {
     }
 }
 
-macro_rules! get_tile_mut {
-    ($level:expr, $layer_id:expr, $row:expr, $col:expr) => {
-        match $layer_id {
-            TileLayer::Background => &mut $level.background_layer,
-            TileLayer::Object => &mut $level.object_layer,
-            TileLayer::Overlay => &mut $level.overlay_layer,
-        }
-        .get_mut($row)
-        .expect("Row should exist")
-        .get_mut($col)
-        .expect("Tile should exist")
-    };
-}
-
 pub struct Level {
     rows: usize,
     cols: usize,
+    name: String,
     path: String,
     background_layer: TileVec,
     object_layer: TileVec,
     overlay_layer: TileVec,
-    tilesets: HashMap<String, TilesetAsset>,
+    /// Shared with other levels through `Assets`. The editor changes them
+    /// copy-on-write.
+    tilesets: HashMap<String, Rc<TilesetAsset>>,
     objects: Vec<ObjectListing>,
-    spawned_objects: HashSet<usize>,
+    indoors: bool,
+    darkness: f32,
+    weather: WeatherKind,
+    /// Ids of the listings whose objects are alive or gone for good.
+    spawned_objects: HashSet<u64>,
+    /// Ids of the chest and door listings opened, plate listings latched,
+    /// and lever listings pulled an odd number of times, this session.
+    opened_objects: HashSet<u64>,
+    /// Ids of the listings whose objects were killed or collected since the
+    /// level was loaded or last reset.
+    cleared_objects: HashSet<u64>,
+    /// Ids of the breakable listings broken this session. Unlike cleared
+    /// listings, dying doesn't bring them back.
+    broken_objects: HashSet<u64>,
+    /// Link ids whose gates are currently open.
+    open_links: HashSet<String>,
+    /// Cells filled by solid objects, as `(row, col)`. Opened doors, broken
+    /// props and open gates leave theirs.
+    solid_cells: HashSet<(usize, usize)>,
+    /// One bit per cell, row by row, set once the cell has been in view.
+    explored: Vec<u64>,
+    background_cache: LayerCache,
+    /// Shared with other levels through `Assets`.
+    atlas: Rc<TileAtlas>,
+    /// Off to draw each tileset from its own texture, for comparing.
+    draw_from_atlas: bool,
+    /// The tiles of a chunked level, streamed in around the camera. Its
+    /// layers above stay empty.
+    chunks: Option<ChunkedLayers>,
 }
 
 impl Level {
-    pub async fn load<'a>(level: &str) -> AssetManageResult<Level> {
-        let path = format!("assets/levels/{}.json", level);
-        let serializable: LevelSerializable = deserialize(&path)?;
+    /// How many rings of cells `free_body` searches for room.
+    const FREE_SEARCH_CELLS: isize = 3;
+    /// Most cells `overlay_cluster` gathers, so a level-wide canopy can't
+    /// stall a frame.
+    const OVERLAY_CLUSTER_CELLS: usize = 256;
+
+    /// Loads `level`, sharing any tilesets already in `assets` instead of
+    /// reading them from disk again.
+    pub async fn load(level: &str, assets: &mut Assets) -> AssetManageResult<Level> {
+        let start = get_time();
+        let mut new = Self::read(level, assets)?;
+        let cached = new.load_tilesets(assets).await?;
+
+        info!(
+            "Loaded level {} in {:.1}ms, {} of {} tilesets already loaded",
+            level,
+            (get_time() - start) * 1000.0,
+            cached,
+            new.tilesets.len()
+        );
+        return Ok(new);
+    }
+
+    /// Reads `level`, from its binary file if it has one, without its
+    /// tilesets, which `load_tilesets` fills in. A level preloaded into
+    /// `assets` isn't read again. Chunked levels only read their index, and
+    /// stream their tiles in with `stream_chunks`.
+    pub fn read(level: &str, assets: &Assets) -> AssetManageResult<Level> {
+        let path = LevelFormat::find(level);
+        let format = LevelFormat::of(&path);
+        let (serializable, chunks) = match assets.level_file(level) {
+            Some(preloaded) => (preloaded.clone(), None),
+            None if format == LevelFormat::Chunked => {
+                let index: ChunkIndex = deserialize_versioned(&path)?;
+                let chunks = ChunkedLayers::open(&path, &index);
+                (index.into_serializable(), Some(chunks))
+            }
+            None => (format.read(&path)?, None),
+        };
+        return Ok(Self::from_serializable(level, path, serializable, chunks));
+    }
 
+    /// Sets up the level `name`, kept at `path`, from its file's contents,
+    /// with no tilesets yet.
+    fn from_serializable(
+        name: &str,
+        path: String,
+        serializable: LevelSerializable,
+        chunks: Option<ChunkedLayers>,
+    ) -> Level {
         let mut new = Level {
             background_layer: serializable.background_layer,
             object_layer: serializable.object_layer,
@@ -139,29 +334,445 @@ impl Level {
             rows: serializable.rows,
             cols: serializable.cols,
             objects: serializable.objects,
+            indoors: serializable.indoors,
+            darkness: serializable.darkness,
+            weather: serializable.weather,
             spawned_objects: HashSet::new(),
+            opened_objects: HashSet::new(),
+            cleared_objects: HashSet::new(),
+            broken_objects: HashSet::new(),
+            open_links: HashSet::new(),
+            solid_cells: HashSet::new(),
+            explored: Vec::new(),
+            background_cache: LayerCache::new(),
+            atlas: Rc::new(TileAtlas::empty()),
+            draw_from_atlas: true,
+            chunks,
+            name: name.to_owned(),
             path,
         };
 
-        let mut textures = HashSet::new();
-        for row in (&new.background_layer)
-            .into_iter()
-            .chain(&new.object_layer)
-            .chain(&new.overlay_layer)
-        {
-            for ptr in row {
-                if let Some(ptr) = ptr {
-                    textures.insert(ptr.0.clone());
+        new.assign_listing_ids();
+        new.refresh_solid_cells();
+        return new;
+    }
+
+    /// Names of the tilesets the level's tiles are drawn from.
+    pub fn tileset_names(&self) -> HashSet<String> {
+        if let Some(chunks) = &self.chunks {
+            return chunks.tileset_names();
+        }
+        return self
+            .background_layer
+            .iter()
+            .chain(&self.object_layer)
+            .chain(&self.overlay_layer)
+            .flat_map(|row| row.iter().flatten())
+            .map(|ptr| ptr.0.clone())
+            .collect();
+    }
+
+    /// Takes every tileset the level uses from `assets`, loading those it
+    /// doesn't have yet. Returns how many it already had.
+    pub async fn load_tilesets(&mut self, assets: &mut Assets) -> AssetManageResult<usize> {
+        let mut cached = 0;
+        for name in self.tileset_names() {
+            let (tileset, was_cached) = assets.tileset(&name).await?;
+            if was_cached {
+                cached += 1;
+            }
+            self.tilesets.insert(name, tileset);
+        }
+        self.atlas = assets.atlas();
+        self.background_cache.clear();
+        return Ok(cached);
+    }
+
+    /// Gives every listing without an id, or sharing one with an earlier
+    /// listing, a fresh one.
+    fn assign_listing_ids(&mut self) {
+        let mut seen = HashSet::new();
+        for idx in 0..self.objects.len() {
+            let id = self.objects[idx].id();
+            if id == 0 || !seen.insert(id) {
+                let fresh = self.next_listing_id();
+                self.objects[idx].set_id(fresh);
+                seen.insert(fresh);
+            }
+        }
+    }
+
+    fn refresh_solid_cells(&mut self) {
+        self.solid_cells = self
+            .objects
+            .iter()
+            .filter(|listing| match listing.r#type() {
+                ObjectType::Door { .. } => !self.opened_objects.contains(&listing.id()),
+                ObjectType::Breakable(_) => !self.broken_objects.contains(&listing.id()),
+                ObjectType::Gate { link_id } => !self.open_links.contains(link_id),
+                r#type => r#type.is_solid(),
+            })
+            .map(|listing| (listing.row(), listing.col()))
+            .collect();
+    }
+
+    fn next_listing_id(&self) -> u64 {
+        return self
+            .objects
+            .iter()
+            .map(|listing| listing.id())
+            .max()
+            .unwrap_or(0)
+            + 1;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The level's `(rows, cols)`.
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// Center of the level's spawn-point marker, or the middle of the first
+    /// screen when no marker was placed.
+    pub fn spawn_point(&self) -> Vec2 {
+        return self
+            .objects
+            .iter()
+            .find(|listing| matches!(listing.r#type(), ObjectType::SpawnPoint))
+            .map(|listing| listing.center())
+            .unwrap_or(vec2(VIRTUAL_W / 2.0, VIRTUAL_H / 2.0));
+    }
+
+    /// The smallest camera zone containing `point`, if any.
+    pub fn camera_zone(&self, point: Vec2) -> Option<Rect> {
+        return self
+            .objects
+            .iter()
+            .filter(|listing| matches!(listing.r#type(), ObjectType::CameraZone { .. }))
+            .filter_map(|listing| listing.area())
+            .filter(|area| area.contains(point))
+            .min_by(|a, b| (a.w * a.h).total_cmp(&(b.w * b.h)));
+    }
+
+    /// The light everywhere no light reaches, given the `daylight` outside.
+    pub fn ambient(&self, daylight: Color) -> Color {
+        let base = match self.indoors {
+            true => WHITE,
+            false => daylight,
+        };
+        let level = 1.0 - self.darkness.clamp(0.0, 1.0);
+        return Color::new(base.r * level, base.g * level, base.b * level, 1.0);
+    }
+
+    /// The weather the level starts with.
+    pub fn weather(&self) -> WeatherKind {
+        self.weather
+    }
+
+    pub fn tilesets(&self) -> &HashMap<String, Rc<TilesetAsset>> {
+        &self.tilesets
+    }
+
+    /// Swaps in a reloaded copy of the tileset `name`.
+    pub fn set_tileset(&mut self, name: &str, tileset: Rc<TilesetAsset>) {
+        self.tilesets.insert(name.to_owned(), tileset);
+        self.background_cache.clear();
+    }
+
+    pub fn atlas(&self) -> &TileAtlas {
+        &self.atlas
+    }
+
+    /// Switches between drawing tiles from the atlas and from each
+    /// tileset's own texture. Returns whether the atlas is now used.
+    pub fn toggle_atlas(&mut self) -> bool {
+        self.draw_from_atlas = !self.draw_from_atlas;
+        self.background_cache.clear();
+        return self.draw_from_atlas;
+    }
+
+    /// The texture to draw the tiles of `tileset`, called `name`, from, and
+    /// where its image starts on it.
+    fn tile_texture<'a>(&'a self, name: &str, tileset: &'a TilesetAsset) -> (&'a Texture2D, Vec2) {
+        return match self.draw_from_atlas {
+            true => self.atlas.locate(name, tileset),
+            false => (&tileset.tex, Vec2::ZERO),
+        };
+    }
+
+    /// Center of the anchor marker called `name`.
+    pub fn anchor(&self, name: &str) -> Option<Vec2> {
+        return self
+            .objects
+            .iter()
+            .find(|listing| {
+                matches!(listing.r#type(), ObjectType::Anchor { name: other } if other == name)
+            })
+            .map(|listing| listing.center());
+    }
+
+    /// Forgets which listings have been spawned so the next
+    /// `spawn_objects` call rebuilds the level's objects from scratch.
+    /// Broken props stay broken.
+    pub fn reset_spawns(&mut self) {
+        self.spawned_objects = self.broken_objects.clone();
+        self.cleared_objects = self.broken_objects.clone();
+    }
+
+    /// Records listings whose objects are gone for good, for saving.
+    pub fn clear_listings(&mut self, listing_ids: &[u64]) {
+        self.cleared_objects.extend(listing_ids);
+        self.break_listings(listing_ids);
+    }
+
+    /// Remembers which of `listing_ids` are breakables, and frees their
+    /// cells.
+    fn break_listings(&mut self, listing_ids: &[u64]) {
+        let broken: Vec<u64> = self
+            .objects
+            .iter()
+            .filter(|listing| {
+                matches!(listing.r#type(), ObjectType::Breakable(_))
+                    && listing_ids.contains(&listing.id())
+            })
+            .map(|listing| listing.id())
+            .collect();
+
+        if !broken.is_empty() {
+            self.broken_objects.extend(broken);
+            self.refresh_solid_cells();
+        }
+    }
+
+    /// Every listing that was opened or cleared, for keeping the level as it
+    /// is across a level swap or in a save.
+    pub fn object_states(&self) -> BTreeMap<u64, ObjectState> {
+        let opened = self
+            .opened_objects
+            .iter()
+            .map(|&id| (id, ObjectState::Opened));
+        let cleared = self
+            .cleared_objects
+            .iter()
+            .map(|&id| (id, ObjectState::Cleared));
+        return opened.chain(cleared).collect();
+    }
+
+    /// Puts back the states from `object_states` onto the freshly loaded
+    /// level. Cleared listings never spawn again.
+    pub fn restore_object_states(&mut self, states: &BTreeMap<u64, ObjectState>) {
+        let with_state = |wanted: ObjectState| -> HashSet<u64> {
+            return states
+                .iter()
+                .filter(|(_, &state)| state == wanted)
+                .map(|(&id, _)| id)
+                .collect();
+        };
+
+        self.opened_objects = with_state(ObjectState::Opened);
+        self.cleared_objects = with_state(ObjectState::Cleared);
+        self.spawned_objects = self.cleared_objects.clone();
+        let cleared: Vec<u64> = self.cleared_objects.iter().copied().collect();
+        self.broken_objects.clear();
+        self.break_listings(&cleared);
+        self.refresh_solid_cells();
+    }
+
+    /// Marks the cells inside `view` as seen, for the map's fog.
+    pub fn explore(&mut self, view: &Rect) {
+        let words = (self.rows * self.cols).div_ceil(64);
+        if self.explored.len() != words {
+            self.explored = vec![0; words];
+        }
+
+        let first_row = (view.y / TILE_SIZE).floor().max(0.0) as usize;
+        let first_col = (view.x / TILE_SIZE).floor().max(0.0) as usize;
+        let last_row = ((view.bottom() / TILE_SIZE).ceil().max(0.0) as usize).min(self.rows);
+        let last_col = ((view.right() / TILE_SIZE).ceil().max(0.0) as usize).min(self.cols);
+        for row in first_row..last_row {
+            for col in first_col..last_col {
+                let idx = row * self.cols + col;
+                self.explored[idx / 64] |= 1 << (idx % 64);
+            }
+        }
+    }
+
+    pub fn is_explored(&self, row: usize, col: usize) -> bool {
+        let idx = row * self.cols + col;
+        return self
+            .explored
+            .get(idx / 64)
+            .is_some_and(|word| word & (1 << (idx % 64)) != 0);
+    }
+
+    pub fn explored(&self) -> &[u64] {
+        &self.explored
+    }
+
+    /// Puts back cells seen on an earlier visit.
+    pub fn set_explored(&mut self, explored: Vec<u64>) {
+        self.explored = explored;
+    }
+
+    /// Cells of the level's portals, as `(row, col)`.
+    pub fn portal_cells(&self) -> Vec<(usize, usize)> {
+        return self
+            .objects
+            .iter()
+            .filter(|listing| matches!(listing.r#type(), ObjectType::Portal { .. }))
+            .map(|listing| (listing.row(), listing.col()))
+            .collect();
+    }
+
+    /// Draws every cell of every layer from the top left, `cell` pixels to
+    /// a tile, for the map screen.
+    pub fn render_map(&self, cell: f32) {
+        for layer in [TileLayer::Background, TileLayer::Object, TileLayer::Overlay] {
+            for row in 0..self.rows {
+                for col in 0..self.cols {
+                    let Some(pointer) = self.cell(&layer, row, col) else {
+                        continue;
+                    };
+                    let tileset = &self.tilesets[&pointer.0];
+                    let (tex, offset) = self.tile_texture(&pointer.0, tileset);
+
+                    draw_texture_ex(
+                        tex,
+                        col as f32 * cell,
+                        row as f32 * cell,
+                        WHITE,
+                        DrawTextureParams {
+                            source: Some(tileset.tile(pointer.1).source(0.0).offset(offset)),
+                            dest_size: Some(vec2(cell, cell)),
+                            ..Default::default()
+                        },
+                    );
                 }
             }
         }
+    }
+
+    /// How many chunks of a chunked level are held in memory.
+    pub fn chunks_loaded(&self) -> usize {
+        return self
+            .chunks
+            .as_ref()
+            .map_or(0, |chunks| chunks.loaded_count());
+    }
+
+    /// How many chunks of a chunked level are on their way from the loader,
+    /// and how many had to be read while the game waited.
+    pub fn chunk_reads(&self) -> (usize, usize) {
+        return self.chunks.as_ref().map_or((0, 0), |chunks| {
+            (chunks.pending_count(), chunks.blocking_reads())
+        });
+    }
+
+    /// How many listings are spawned or used up, out of how many there are.
+    pub fn spawn_counts(&self) -> (usize, usize) {
+        return (self.spawned_objects.len(), self.objects.len());
+    }
 
-        for tex in textures {
-            let tiles = TilesetAsset::load(&tex).await?;
-            new.tilesets.insert(tex, tiles);
+    /// Lets listings whose objects were despawned (rather than collected or
+    /// killed) spawn again when they come back into view.
+    pub fn release_spawns(&mut self, listing_ids: &[u64]) {
+        for id in listing_ids {
+            self.spawned_objects.remove(id);
         }
+    }
 
-        return Ok(new);
+    /// Keeps the chest, door or latching plate placed by `listing_id` open
+    /// when it spawns again, and lets bodies through an opened door's cell.
+    pub fn mark_opened(&mut self, listing_id: u64) {
+        self.opened_objects.insert(listing_id);
+        self.refresh_solid_cells();
+    }
+
+    /// Flips the lever placed by `listing_id`, switching its link and how it
+    /// looks when it spawns again.
+    pub fn flip_lever(&mut self, listing_id: u64) {
+        if !self.opened_objects.remove(&listing_id) {
+            self.opened_objects.insert(listing_id);
+        }
+    }
+
+    /// Link ids switched on by levers. Each pulled lever flips its link, so
+    /// any lever on an id can switch it either way.
+    pub fn switched_links(&self) -> HashSet<String> {
+        let mut switched = HashSet::new();
+        for listing in self.objects.iter() {
+            if let ObjectType::Lever { link_id } = listing.r#type() {
+                if self.opened_objects.contains(&listing.id()) && !switched.remove(link_id) {
+                    switched.insert(link_id.clone());
+                }
+            }
+        }
+
+        return switched;
+    }
+
+    /// Opens the gates on `links` and shuts the rest. Returns each link
+    /// that changed and whether it is now open.
+    pub fn set_open_links(&mut self, links: HashSet<String>) -> Vec<(String, bool)> {
+        if links == self.open_links {
+            return Vec::new();
+        }
+
+        let opened = links
+            .difference(&self.open_links)
+            .map(|link_id| (link_id.clone(), true));
+        let shut = self
+            .open_links
+            .difference(&links)
+            .map(|link_id| (link_id.clone(), false));
+        let changed = opened.chain(shut).collect();
+
+        self.open_links = links;
+        self.refresh_solid_cells();
+        return changed;
+    }
+
+    /// Moves `body` to the center of the nearest cell it fits in if it is
+    /// stuck inside something solid. Left where it is if nothing within
+    /// `FREE_SEARCH_CELLS` is free.
+    pub fn free_body(&self, body: &mut Body) {
+        if !body.overlaps_solid(self) {
+            return;
+        }
+
+        let center = body.hitbox.center();
+        let row = (center.y / TILE_SIZE).floor() as isize;
+        let col = (center.x / TILE_SIZE).floor() as isize;
+
+        for ring in 1..=Self::FREE_SEARCH_CELLS {
+            let spot = (-ring..=ring)
+                .flat_map(|d_row| (-ring..=ring).map(move |d_col| (row + d_row, col + d_col)))
+                .filter(|&(other_row, other_col)| {
+                    (other_row - row).abs() == ring || (other_col - col).abs() == ring
+                })
+                .filter(|&(row, col)| {
+                    row >= 0 && col >= 0 && (row as usize) < self.rows && (col as usize) < self.cols
+                })
+                .map(|(row, col)| {
+                    vec2(
+                        col as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+                        row as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+                    )
+                })
+                .filter(|spot| {
+                    let probe = Body::new(spot.x, spot.y, body.hitbox.w, body.hitbox.h, None);
+                    !probe.overlaps_solid(self)
+                })
+                .min_by(|a, b| a.distance(center).total_cmp(&b.distance(center)));
+
+            if let Some(spot) = spot {
+                body.set_center(spot);
+                return;
+            }
+        }
     }
 
     fn get_showing_range(&self, world: &World) -> (Range<usize>, Range<usize>) {
@@ -179,39 +790,85 @@ impl Level {
         return (row_range, col_range);
     }
 
-    pub fn spawn_objects(&mut self, world: &World, level_objects: &mut LevelObjects) {
+    /// A listing stays marked as spawned until `release_spawns` frees it, so
+    /// a live object is never duplicated and objects that leave the world
+    /// for good (picked up items, killed enemies) don't come back this level
+    /// session.
+    pub async fn spawn_objects(&mut self, world: &World, level_objects: &mut LevelObjects) {
         let (row_range, col_range) = self.get_showing_range(world);
-        for (object_id, object) in self.objects.iter().enumerate() {
-            if object.is_in_range(&row_range, &col_range) {
-                if !self.spawned_objects.contains(&object_id) {
-                    self.spawned_objects.insert(object_id);
-                    level_objects.add_listing(object);
-                }
+        for listing in self.objects.iter() {
+            if listing.is_in_range(&row_range, &col_range)
+                && self.spawned_objects.insert(listing.id())
+            {
+                let opened = self.opened_objects.contains(&listing.id());
+                level_objects.add_listing(listing, opened).await;
             }
         }
     }
 
-    fn render_layer(&self, layer: &TileVec, world: &World, is_background: bool) {
+    /// Draws the cells of `layer` in view, one draw per tileset. Animated
+    /// tiles show their frame for `time` on the tile animation clock. Tiles
+    /// flagged to y-sort are left for `y_sorted_tiles` when `skip_y_sorted`
+    /// is set.
+    fn render_layer(
+        &self,
+        layer: &TileLayer,
+        world: &World,
+        time: f32,
+        is_background: bool,
+        skip_y_sorted: bool,
+        alphas: Option<&HashMap<(usize, usize), f32>>,
+    ) {
+        let mut batch = TileBatch::new();
+        self.visit_layer(
+            layer,
+            world,
+            time,
+            is_background,
+            skip_y_sorted,
+            alphas,
+            |tex, x, y, source, color| batch.push(tex, x, y, source, color),
+        );
+        batch.draw();
+    }
+
+    /// Hands `draw` the texture, screen position, source and tint of each
+    /// tile of `layer` in view, filling in missing background cells.
+    #[allow(clippy::too_many_arguments)]
+    fn visit_layer(
+        &self,
+        layer: &TileLayer,
+        world: &World,
+        time: f32,
+        is_background: bool,
+        skip_y_sorted: bool,
+        alphas: Option<&HashMap<(usize, usize), f32>>,
+        mut draw: impl FnMut(&Texture2D, f32, f32, Rect, Color),
+    ) {
         let (row_range, col_range) = self.get_showing_range(world);
 
         for row in row_range {
             for col in col_range.clone() {
                 let x = col as f32 * TILE_SIZE - world.x;
                 let y = row as f32 * TILE_SIZE - world.y;
-                if let Some(tile) = &layer[row as usize][col as usize] {
-                    let tileset = &self.tilesets[&tile.0];
-                    let tile = &tileset.tiles[tile.1];
+                if let Some(pointer) = self.cell(layer, row, col) {
+                    let tileset = &self.tilesets[&pointer.0];
+                    let tile = tileset.tile(pointer.1);
+                    if skip_y_sorted && tile.y_sort {
+                        continue;
+                    }
 
-                    draw_texture_ex(
-                        &tileset.tex,
+                    let alpha = alphas
+                        .and_then(|alphas| alphas.get(&(row, col)))
+                        .copied()
+                        .unwrap_or(1.0);
+                    let (tex, offset) = self.tile_texture(&pointer.0, tileset);
+                    draw(
+                        tex,
                         x,
                         y,
-                        WHITE,
-                        DrawTextureParams {
-                            dest_size: None,
-                            source: Some(Rect::new(tile.x, tile.y, TILE_SIZE, TILE_SIZE)),
-                            ..Default::default()
-                        },
+                        tile.source(time).offset(offset),
+                        Color { a: alpha, ..WHITE },
                     );
                 } else if is_background {
                     draw_rectangle(
@@ -226,16 +883,164 @@ impl Level {
         }
     }
 
-    pub fn render_background(&self, world: &World) {
-        self.render_layer(&self.background_layer, world, true);
+    /// Copies the background chunks in view from the cache, drawing any
+    /// that are new, changed or animated first.
+    pub fn render_background(&mut self, world: &World, time: f32) {
+        let view = world.view(0.0, 0.0);
+        let size = LayerCache::CHUNK_SIZE;
+        let chunk_rows = self.rows.div_ceil(LayerCache::CHUNK_CELLS);
+        let chunk_cols = self.cols.div_ceil(LayerCache::CHUNK_CELLS);
+        let first_row = (view.y / size).floor().max(0.0) as usize;
+        let first_col = (view.x / size).floor().max(0.0) as usize;
+        let last_row = ((view.bottom() / size).ceil().max(0.0) as usize).min(chunk_rows);
+        let last_col = ((view.right() / size).ceil().max(0.0) as usize).min(chunk_cols);
+
+        let mut visible = Vec::new();
+        for chunk_row in first_row..last_row {
+            for chunk_col in first_col..last_col {
+                let key = (chunk_row, chunk_col);
+                visible.push(key);
+                let (x, y) = (chunk_col as f32 * size, chunk_row as f32 * size);
+
+                let (target, needs_draw) = self.background_cache.target(key);
+                if needs_draw {
+                    push_camera_state();
+                    set_camera(&Camera2D {
+                        zoom: vec2(2.0 / size, 2.0 / size),
+                        target: vec2(size / 2.0, size / 2.0),
+                        render_target: Some(target.clone()),
+                        ..Default::default()
+                    });
+                    clear_background(Color::new(0.0, 0.0, 0.0, 0.0));
+                    let chunk = World {
+                        x,
+                        y,
+                        w: size,
+                        h: size,
+                    };
+                    self.render_layer(&TileLayer::Background, &chunk, time, true, false, None);
+                    pop_camera_state();
+
+                    let animated = self.chunk_animated(&TileLayer::Background, key);
+                    self.background_cache.drawn(key, animated);
+                }
+
+                draw_texture_ex(
+                    &target.texture,
+                    x - world.x,
+                    y - world.y,
+                    WHITE,
+                    DrawTextureParams {
+                        dest_size: Some(vec2(size, size)),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+
+        self.background_cache.evict(&visible);
+    }
+
+    /// Whether any tile of `layer` in the cache chunk `key` animates.
+    fn chunk_animated(&self, layer: &TileLayer, (chunk_row, chunk_col): (usize, usize)) -> bool {
+        let cells = LayerCache::CHUNK_CELLS;
+        let rows = chunk_row * cells..((chunk_row + 1) * cells).min(self.rows);
+        let cols = chunk_col * cells..((chunk_col + 1) * cells).min(self.cols);
+        return rows.into_iter().any(|row| {
+            cols.clone()
+                .filter_map(|col| self.cell(layer, row, col))
+                .any(|pointer| self.get_tile(pointer).animation.is_some())
+        });
+    }
+
+    pub fn render_object_layer(&self, world: &World, time: f32) {
+        self.render_layer(&TileLayer::Object, world, time, false, true, None);
+    }
+
+    /// The object-layer tiles in view flagged to y-sort, showing their frame
+    /// for `time` on the tile animation clock.
+    pub fn y_sorted_tiles(&self, world: &World, time: f32) -> Vec<SortedTile<'_>> {
+        let (row_range, col_range) = self.get_showing_range(world);
+        let mut tiles = Vec::new();
+        for row in row_range {
+            for col in col_range.clone() {
+                let Some(pointer) = self.cell(&TileLayer::Object, row, col) else {
+                    continue;
+                };
+                let tileset = &self.tilesets[&pointer.0];
+                let tile = tileset.tile(pointer.1);
+                if !tile.y_sort {
+                    continue;
+                }
+
+                let (x, y) = (col as f32 * TILE_SIZE, row as f32 * TILE_SIZE);
+                let (tex, offset) = self.tile_texture(&pointer.0, tileset);
+                tiles.push(SortedTile {
+                    tex,
+                    source: tile.source(time).offset(offset),
+                    x,
+                    y,
+                    sort_key: ((y + TILE_SIZE + tile.sort_offset) * 100.0) as i32,
+                });
+            }
+        }
+
+        return tiles;
     }
 
-    pub fn render_object_layer(&self, world: &World) {
-        self.render_layer(&self.object_layer, world, false);
+    /// Draws the overlay layer, with the cells in `alphas` see-through by
+    /// that much.
+    pub fn render_overlay(&self, world: &World, time: f32, alphas: &HashMap<(usize, usize), f32>) {
+        self.render_layer(&TileLayer::Overlay, world, time, false, false, Some(alphas));
     }
 
-    pub fn render_overlay(&self, world: &World) {
-        self.render_layer(&self.overlay_layer, world, false);
+    /// The overlay cells over `hitbox`, and the cells joined to them from
+    /// the same tileset, as `(row, col)`.
+    pub fn overlay_cluster(&self, hitbox: &Rect) -> HashSet<(usize, usize)> {
+        let first_row = (hitbox.y / TILE_SIZE).floor().max(0.0) as usize;
+        let first_col = (hitbox.x / TILE_SIZE).floor().max(0.0) as usize;
+        let last_row = ((hitbox.bottom() / TILE_SIZE).ceil().max(0.0) as usize).min(self.rows);
+        let last_col = ((hitbox.right() / TILE_SIZE).ceil().max(0.0) as usize).min(self.cols);
+
+        let mut cluster = HashSet::new();
+        let mut open = Vec::new();
+        for row in first_row..last_row {
+            for col in first_col..last_col {
+                let covered = self.cell(&TileLayer::Overlay, row, col).is_some();
+                if covered && cluster.insert((row, col)) {
+                    open.push((row, col));
+                }
+            }
+        }
+
+        while let Some((row, col)) = open.pop() {
+            if cluster.len() >= Self::OVERLAY_CLUSTER_CELLS {
+                break;
+            }
+
+            let Some(pointer) = self.cell(&TileLayer::Overlay, row, col) else {
+                continue;
+            };
+            let neighbours = [
+                (row.wrapping_sub(1), col),
+                (row + 1, col),
+                (row, col.wrapping_sub(1)),
+                (row, col + 1),
+            ];
+            for (next_row, next_col) in neighbours {
+                if next_row >= self.rows || next_col >= self.cols {
+                    continue;
+                }
+                let joined = self
+                    .cell(&TileLayer::Overlay, next_row, next_col)
+                    .is_some_and(|next| next.0 == pointer.0);
+                if joined && cluster.insert((next_row, next_col)) {
+                    open.push((next_row, next_col));
+                }
+            }
+        }
+
+        return cluster;
     }
 
     pub fn get_layer(&self, layer: &TileLayer) -> &TileVec {
@@ -246,34 +1051,145 @@ impl Level {
         }
     }
 
-    pub fn check_for_collision(&self, x: f32, y: f32) -> Option<TileHitInfo> {
-        let row = (y / TILE_SIZE).floor();
-        let col = (x / TILE_SIZE).floor();
+    /// The tile in `layer` at `row`, `col`, if there is one. Chunked levels
+    /// have no tiles in chunks that aren't loaded.
+    pub fn cell(&self, layer: &TileLayer, row: usize, col: usize) -> Option<&TilePointer> {
+        return match &self.chunks {
+            Some(chunks) if row < self.rows && col < self.cols => chunks.cell(layer, row, col),
+            Some(_) => None,
+            None => self.get_layer(layer).get(row)?.get(col)?.as_ref(),
+        };
+    }
 
-        let tile_ptr = match self.object_layer.get(row as usize) {
-            Some(row) => match row.get(col as usize) {
-                Some(tile) => match tile {
-                    Some(tile) => tile,
-                    None => return None,
-                },
-                None => return None,
-            },
-            None => return None,
+    /// The cell in `layer` at `row`, `col` for the editor to change. `None`
+    /// outside the level, or in a chunk that isn't loaded yet.
+    fn cell_mut(
+        &mut self,
+        layer: &TileLayer,
+        row: usize,
+        col: usize,
+    ) -> Option<&mut Option<TilePointer>> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        let whole = match layer {
+            TileLayer::Background => &mut self.background_layer,
+            TileLayer::Object => &mut self.object_layer,
+            TileLayer::Overlay => &mut self.overlay_layer,
+        };
+        return match &mut self.chunks {
+            Some(chunks) => chunks.cell_mut(layer, row, col),
+            None => whole.get_mut(row)?.get_mut(col),
+        };
+    }
+
+    fn set_cell(&mut self, layer: &TileLayer, row: usize, col: usize, tile: Option<TilePointer>) {
+        if let Some(cell) = self.cell_mut(layer, row, col) {
+            *cell = tile;
+        }
+    }
+
+    /// Loads the chunks of a chunked level in and around the view, and lets
+    /// go of those far from it. Chunks in view are read before this
+    /// returns, so the first frame after a warp has its walls.
+    pub fn stream_chunks(&mut self, world: &World) {
+        let (rows, cols) = self.get_showing_range(world);
+        let Some(chunks) = &mut self.chunks else {
+            return;
+        };
+        for key in chunks.stream(rows, cols) {
+            self.background_cache.invalidate(key);
+        }
+    }
+
+    fn background_tile_at(&self, x: f32, y: f32) -> Option<&TileAsset> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        let row = (y / TILE_SIZE).floor() as usize;
+        let col = (x / TILE_SIZE).floor() as usize;
+
+        return self
+            .cell(&TileLayer::Background, row, col)
+            .map(|tile_ptr| self.get_tile(tile_ptr));
+    }
+
+    pub fn surface_at(&self, x: f32, y: f32) -> Option<SurfaceKind> {
+        return self.background_tile_at(x, y).and_then(|tile| tile.surface);
+    }
+
+    pub fn is_water_at(&self, x: f32, y: f32) -> bool {
+        return self.background_tile_at(x, y).is_some_and(|tile| tile.water);
+    }
+
+    /// Walks the segment from `from` to `to` in collision-section steps and
+    /// returns the first point that lands in a solid section, if any.
+    /// `ignore_low` lets the ray pass over low sections.
+    pub fn raycast(&self, from: Vec2, to: Vec2, ignore_low: bool) -> Option<Vec2> {
+        let step = TILE_SIZE / TILE_COLLISION_SECTIONS / 2.0;
+        let distance = from.distance(to);
+        let steps = (distance / step).ceil() as usize;
+
+        for idx in 0..=steps {
+            let point = from.lerp(to, (idx as f32 / steps.max(1) as f32).min(1.0));
+            if self
+                .check_for_collision(point.x, point.y, ignore_low)
+                .is_some()
+            {
+                return Some(point);
+            }
+        }
+
+        return None;
+    }
+
+    /// Whether `to` can be seen from `from`, treating low sections as cover
+    /// rather than walls.
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> LineOfSight {
+        if self.raycast(from, to, false).is_none() {
+            return LineOfSight::Clear;
+        }
+
+        return match self.raycast(from, to, true).is_none() {
+            true => LineOfSight::Partial,
+            false => LineOfSight::Blocked,
         };
+    }
 
-        let tile = &self.tilesets[&tile_ptr.0].tiles[tile_ptr.1];
+    /// Finds the solid section under a point. `ignore_low` skips sections
+    /// low enough to hop over.
+    pub fn check_for_collision(&self, x: f32, y: f32, ignore_low: bool) -> Option<TileHitInfo> {
+        let row = (y / TILE_SIZE).floor();
+        let col = (x / TILE_SIZE).floor();
 
         let portion_size = TILE_SIZE / TILE_COLLISION_SECTIONS;
         let portion_row = ((y - (row * TILE_SIZE)) / portion_size).floor();
         let portion_col = ((x - (col * TILE_SIZE)) / portion_size).floor();
 
+        if row >= 0.0 && col >= 0.0 && self.solid_cells.contains(&(row as usize, col as usize)) {
+            return Some(TileHitInfo {
+                row: row + portion_row * (1.0 / TILE_COLLISION_SECTIONS),
+                col: col + portion_col * (1.0 / TILE_COLLISION_SECTIONS),
+            });
+        }
+
+        let tile_ptr = self.cell(&TileLayer::Object, row as usize, col as usize)?;
+
+        let tile = self.tilesets[&tile_ptr.0].tile(tile_ptr.1);
+
         match &tile.collision_matrix {
             Some(collision_matrix) => {
-                match collision_matrix.matrix[portion_row as usize][portion_col as usize] {
+                let (portion_row, portion_col) = (portion_row as usize, portion_col as usize);
+                if ignore_low && collision_matrix.is_low(portion_row, portion_col) {
+                    return None;
+                }
+
+                match collision_matrix.matrix[portion_row][portion_col] {
                     true => {
                         return Some(TileHitInfo {
-                            row: row + portion_row * (1.0 / TILE_COLLISION_SECTIONS),
-                            col: col + portion_col * (1.0 / TILE_COLLISION_SECTIONS),
+                            row: row + portion_row as f32 * (1.0 / TILE_COLLISION_SECTIONS),
+                            col: col + portion_col as f32 * (1.0 / TILE_COLLISION_SECTIONS),
                         })
                     }
                     false => None,
@@ -297,8 +1213,18 @@ impl Level {
         if let Some(tileset_id) = &editor.selected_tileset {
             if root_ui().button(None, "Save Tileset Data") {
                 if let Some(tileset_id) = &editor.selected_tileset {
-                    let serializable = self.tileset_to_serializable(&tileset_id);
-                    let msg = match serialize(&serializable, &self.tilesets[tileset_id].meta_path) {
+                    let tileset = &self.tilesets[tileset_id];
+                    let serializable = tileset.to_serializable();
+                    // Saving a placeholder would write an empty meta over
+                    // the real one once it turns up.
+                    let saved = match tileset.placeholder {
+                        true => Err(AssetManageError::Missing(format!(
+                            "the image or meta of {tileset_id}, so there's nothing to save"
+                        ))),
+                        false => check_saving()
+                            .and_then(|_| serialize(&serializable, &tileset.meta_path)),
+                    };
+                    let msg = match saved {
                         Ok(_) => "Meta Saved",
                         Err(err) => &format!("{err}"),
                     };
@@ -308,10 +1234,13 @@ impl Level {
             }
 
             if root_ui().button(None, "Cut Tiles") {
-                self.tilesets
-                    .get_mut(tileset_id)
-                    .expect("Tileset should exist")
-                    .cut()
+                Rc::make_mut(
+                    self.tilesets
+                        .get_mut(tileset_id)
+                        .expect("Tileset should exist"),
+                )
+                .cut();
+                self.background_cache.clear();
             }
 
             let tileset = self.tilesets.get(tileset_id).expect("Tileset should exist");
@@ -343,12 +1272,12 @@ impl Level {
                 WHITE,
                 DrawTextureParams {
                     dest_size,
-                    source: Some(editor.zoom.clone()),
+                    source: Some(editor.zoom),
                     ..Default::default()
                 },
             );
 
-            if input.mouse_x < -1.0 / 3.0 {
+            if input.mouse_x < editor_width {
                 let tiles_per_sec = 10.0;
                 editor.zoom.x += input.horizontal * dt * TILE_SIZE * tiles_per_sec;
                 editor.zoom.x = clamp(editor.zoom.x, 0.0, tileset.tex.width() - editor.zoom.w);
@@ -356,11 +1285,10 @@ impl Level {
                 editor.zoom.y += input.vertical * dt * TILE_SIZE * tiles_per_sec;
                 editor.zoom.y = clamp(editor.zoom.y, 0.0, tileset.tex.height() - editor.zoom.h);
 
-                let rm = if input.mouse_x < -1.0 / 3.0
-                    && input.mouse_y > editor_width / VIRTUAL_H * 2.0 - 1.0
-                {
-                    let x = (1.0 + input.mouse_x) / (2.0 / 3.0);
-                    Some((x, input.mouse_y))
+                let rm = if input.mouse_y > editor_y {
+                    let x = input.mouse_x / editor_width;
+                    let y = (input.mouse_y - editor_y) / editor_width;
+                    Some((x, y))
                 } else {
                     None
                 };
@@ -415,15 +1343,133 @@ impl Level {
         draw_line(0.0, editor_y, editor_width, editor_y, 1.0, WHITE);
     }
 
+    /// Saves the level the way the editor is set to, saying how it went.
+    fn save_edits(&mut self, editor: &mut LevelEditorSettings) -> String {
+        let format = LevelFormat::of(&self.path);
+        let saved_as = format.saved_as(editor.compress_levels);
+        let path = saved_as.path(&self.name);
+        let msg = match self.save(saved_as, &path) {
+            // Saving gzipped, or no longer, leaves the old file behind.
+            Ok(_) if path != self.path => match format.remove(&self.path) {
+                Ok(_) => {
+                    self.path = path;
+                    "Level Saved".to_owned()
+                }
+                Err(err) => {
+                    format!("Saved {path}, but couldn't remove the old file: {err}")
+                }
+            },
+            Ok(_) => "Level Saved".to_owned(),
+            Err(err) => return format!("{err}"),
+        };
+        editor.dirty = false;
+        return msg;
+    }
+
+    /// Offers to save unsaved edits before the level is left or the game
+    /// quits. Either way the editor starts clean afterwards.
+    pub async fn offer_save(&mut self, editor: &mut LevelEditorSettings) {
+        if !editor.dirty {
+            return;
+        }
+
+        let question = format!("{} has unsaved changes. Save them?", self.name);
+        if confirm(&question).await {
+            let msg = self.save_edits(editor);
+            alert(&msg).await;
+        }
+        editor.dirty = false;
+    }
+
     async fn editor_panel(&mut self, editor: &mut LevelEditorSettings) -> AssetManageResult<()> {
-        if root_ui().button(None, "Save Level") {
-            let serializable = self.to_serializable();
-            let msg = match serialize(&serializable, &self.path) {
-                Ok(_) => "Level Saved",
-                Err(err) => &format!("{err}"),
+        let format = LevelFormat::of(&self.path);
+        let saved_as = format.saved_as(editor.compress_levels);
+        if root_ui().button(None, format!("Save Level ({:?})", saved_as)) {
+            let msg = self.save_edits(editor);
+
+            alert(&msg).await;
+        }
+
+        let other = format.other();
+        if root_ui().button(None, format!("Convert to {:?}", other)) {
+            let path = other.path(&self.name);
+            let msg = match self.save(other, &path) {
+                Ok(_) => match format.remove(&self.path) {
+                    Ok(_) => {
+                        self.path = path;
+                        editor.dirty = false;
+                        "Level Converted".to_owned()
+                    }
+                    Err(err) => format!("Saved {path}, but couldn't remove the old file: {err}"),
+                },
+                Err(err) => format!("{err}"),
+            };
+
+            alert(&msg).await;
+        }
+
+        if root_ui().button(None, "Write Asset Manifest") {
+            let written = AssetManifest::scan().and_then(|manifest| {
+                manifest.write()?;
+                Ok(manifest)
+            });
+            let msg = match written {
+                Ok(manifest) => format!(
+                    "Wrote {} with {} levels, {} tilesets and {} sprites",
+                    AssetManifest::PATH,
+                    manifest.levels.len(),
+                    manifest.tilesets.len(),
+                    manifest.sprites.len()
+                ),
+                Err(err) => format!("{err}"),
+            };
+            alert(&msg).await;
+        }
+
+        if root_ui().button(None, "Validate") {
+            let placeholders = asset_problems();
+            let mut problems = validate_assets();
+            if !placeholders.is_empty() {
+                problems.push(format!("Placeholders in use: {}", placeholders.join("; ")));
+            }
+            let msg = match problems.is_empty() {
+                true => "No asset problems".to_owned(),
+                false => problems.join("; "),
             };
+            alert(&msg).await;
+        }
+
+        if root_ui().button(None, "Import Tiled...") {
+            if let Some(path) = prompt("Tiled Map (.tmj)").await {
+                let imported = self.import_tiled(path.trim()).await;
+                editor.dirty |= imported.is_ok();
+                let msg = match imported {
+                    Ok(skipped) if skipped.is_empty() => "Map Imported".to_owned(),
+                    Ok(skipped) => format!("Map imported, skipping: {}", skipped.join("; ")),
+                    Err(err) => format!("{err}"),
+                };
+                alert(&msg).await;
+            }
+        }
+
+        if root_ui().button(None, "Import Aseprite...") {
+            if let Some(path) = prompt("Aseprite Sheet (.json)").await {
+                let msg = match aseprite::import_aseprite_file(path.trim()) {
+                    Ok(meta) => format!("Wrote {meta}"),
+                    Err(err) => format!("{err}"),
+                };
+                alert(&msg).await;
+            }
+        }
 
-            alert(msg).await;
+        if root_ui().button(None, "Import LDtk...") {
+            if let Some(path) = prompt("LDtk Project (.ldtk)").await {
+                let msg = match ldtk::import_ldtk_project(path.trim()) {
+                    Ok(report) => format!("Project imported: {}", report.join("; ")),
+                    Err(err) => format!("{err}"),
+                };
+                alert(&msg).await;
+            }
         }
         splitter();
 
@@ -436,6 +1482,13 @@ impl Level {
                         (Ok(rows), Ok(cols)) => {
                             self.rows = rows;
                             self.cols = cols;
+                            editor.dirty = true;
+                            self.background_cache.clear();
+                            // Chunks are always full size, and cells past
+                            // the edge are ignored.
+                            if self.chunks.is_some() {
+                                return Ok(());
+                            }
 
                             for row in self.background_layer.iter_mut() {
                                 row.resize_with(cols, || None);
@@ -468,13 +1521,45 @@ impl Level {
                 }
             }
         }
+
+        if root_ui().button(None, format!("Indoors: {}", self.indoors)) {
+            self.indoors = !self.indoors;
+            editor.dirty = true;
+        }
+
+        if root_ui().button(None, format!("Darkness: {}", self.darkness)) {
+            if let Some(answer) = prompt("Darkness (0 lit to 1 pitch black)").await {
+                match answer.trim().parse::<f32>() {
+                    Ok(darkness) => {
+                        self.darkness = darkness.clamp(0.0, 1.0);
+                        editor.dirty = true;
+                    }
+                    Err(_) => alert("Invalid darkness.").await,
+                }
+            }
+        }
+
+        if root_ui().button(None, format!("Weather: {:?}", self.weather)) {
+            let next = WeatherKind::ALL
+                .iter()
+                .position(|kind| *kind == self.weather)
+                .unwrap()
+                + 1;
+            self.weather = WeatherKind::ALL[next % WeatherKind::ALL.len()];
+            editor.dirty = true;
+        }
         splitter();
 
         if root_ui().button(None, "Add tileset") {
             if let Some(tileset_name) = prompt("Tileset Name").await {
                 match TilesetAsset::load(&tileset_name).await {
                     Ok(tileset) => {
-                        self.tilesets.insert(tileset_name, tileset);
+                        // A new tileset has no tiles yet, so open it to be
+                        // cut.
+                        if tileset.tiles.is_empty() && !tileset.placeholder {
+                            editor.selected_tileset = Some(tileset_name.clone());
+                        }
+                        self.tilesets.insert(tileset_name, Rc::new(tileset));
                     }
                     Err(err) => alert(&format!("{err}")).await,
                 }
@@ -505,6 +1590,18 @@ impl Level {
             editor.show_overlay = !editor.show_overlay
         }
 
+        if root_ui().button(
+            None,
+            format!("Toggle Hitboxes {}", on_off(editor.show_hitboxes)),
+        ) {
+            editor.show_hitboxes = !editor.show_hitboxes
+        }
+
+        if root_ui().button(None, "Object Mode") {
+            editor.object_mode = true;
+            editor.selected_tile = None;
+        }
+
         splitter();
 
         root_ui().label(None, "Loaded Tilesets");
@@ -536,7 +1633,7 @@ impl Level {
     }
 
     fn get_tile(&self, tile_ptr: &TilePointer) -> &TileAsset {
-        &self.tilesets[&tile_ptr.0].tiles[tile_ptr.1]
+        self.tilesets[&tile_ptr.0].tile(tile_ptr.1)
     }
 
     fn get_auto_tile_for_index(
@@ -546,8 +1643,6 @@ impl Level {
         layer: &TileLayer,
         group: Option<u8>,
     ) -> TileAutoRule {
-        let layer = self.get_layer(layer);
-
         let i_row = row as i32;
         let i_col = col as i32;
 
@@ -563,24 +1658,13 @@ impl Level {
         ];
 
         let present = present.map(|(row, col)| {
-            match layer.get(if row >= 0 {
-                row as usize
-            } else {
+            if row < 0 || col < 0 {
                 return false;
-            }) {
-                Some(row) => match row.get(if col >= 0 {
-                    col as usize
-                } else {
-                    return false;
-                }) {
-                    Some(tile) => match tile {
-                        Some(tile) => self.get_tile(tile).group == group,
-                        None => false,
-                    },
-                    None => false,
-                },
-                None => false,
             }
+            return match self.cell(layer, row as usize, col as usize) {
+                Some(tile) => self.get_tile(tile).group == group,
+                None => false,
+            };
         });
 
         return TileAutoRule::from_array(present);
@@ -631,8 +1715,7 @@ impl Level {
                 let row = set.0 as usize;
                 let col = set.1 as usize;
 
-                let layer = self.get_layer(layer_id);
-                if let Some(tile_ptr) = &layer[row][col] {
+                if let Some(tile_ptr) = self.cell(layer_id, row, col) {
                     let tile_ptr = self.find_best_tile_for_index(
                         row,
                         col,
@@ -640,8 +1723,8 @@ impl Level {
                         &tile_ptr.0,
                     );
 
-                    if let Some(_) = tile_ptr {
-                        *get_tile_mut!(self, layer_id, row, col) = tile_ptr;
+                    if tile_ptr.is_some() {
+                        self.set_cell(layer_id, row, col, tile_ptr);
                     }
                 }
             }
@@ -655,6 +1738,7 @@ impl Level {
         editor: &LevelEditorSettings,
         auto_tile: bool,
     ) {
+        self.background_cache.invalidate_around(row, col);
         if let (Some(tileset_id), Some(tile_id)) = (&editor.selected_tileset, editor.selected_tile)
         {
             if auto_tile {
@@ -662,37 +1746,41 @@ impl Level {
                     (&editor.selected_tileset, editor.selected_tile)
                 {
                     let tile = &self.tilesets[tileset_id].tiles[tile_id];
-                    let layer = &tile.layer;
+                    let layer = tile.layer.clone();
                     let tile_ptr = match self.find_best_tile_for_index(row, col, tile, tileset_id) {
                         Some(tile_ptr) => Some(tile_ptr),
                         None => Some(TilePointer(tileset_id.clone(), tile_id)),
                     };
 
-                    *get_tile_mut!(self, layer, row, col) = tile_ptr;
-                    self.set_surrounding_tiles(row, col, &layer.clone());
+                    self.set_cell(&layer, row, col, tile_ptr);
+                    self.set_surrounding_tiles(row, col, &layer);
                 }
             } else {
-                let layer = &self.tilesets[tileset_id].tiles[tile_id].layer;
-                *get_tile_mut!(self, layer, row, col) =
-                    Some(TilePointer(tileset_id.clone(), tile_id));
+                let layer = self.tilesets[tileset_id].tiles[tile_id].layer.clone();
+                self.set_cell(
+                    &layer,
+                    row,
+                    col,
+                    Some(TilePointer(tileset_id.clone(), tile_id)),
+                );
             }
         } else {
             if editor.show_background {
-                *get_tile_mut!(self, TileLayer::Background, row, col) = None;
+                self.set_cell(&TileLayer::Background, row, col, None);
                 if auto_tile {
                     self.set_surrounding_tiles(row, col, &TileLayer::Background);
                 }
             }
 
             if editor.show_object {
-                *get_tile_mut!(self, TileLayer::Object, row, col) = None;
+                self.set_cell(&TileLayer::Object, row, col, None);
                 if auto_tile {
                     self.set_surrounding_tiles(row, col, &TileLayer::Object);
                 }
             }
 
             if editor.show_overlay {
-                *get_tile_mut!(self, TileLayer::Overlay, row, col) = None;
+                self.set_cell(&TileLayer::Overlay, row, col, None);
                 if auto_tile {
                     self.set_surrounding_tiles(row, col, &TileLayer::Overlay);
                 }
@@ -707,14 +1795,11 @@ impl Level {
         input: &Input,
         world: &World,
     ) {
-        if input.mouse_x < -1.0 / 3.0 {
+        if input.mouse_x < editor_width {
             return;
         }
 
-        let mouse = (
-            (input.mouse_x + 1.0) / 2.0 * VIRTUAL_W,
-            (input.mouse_y + 1.0) / 2.0 * VIRTUAL_H,
-        );
+        let mouse = (input.mouse_x, input.mouse_y);
 
         let col = ((mouse.0 + world.x) / TILE_SIZE).floor();
         let row = ((mouse.1 + world.y) / TILE_SIZE).floor();
@@ -759,7 +1844,16 @@ impl Level {
         }
 
         if input.mouse_down {
-            self.place_tile(row as usize, col as usize, &editor, !input.enter);
+            let (row, col) = (row as usize, col as usize);
+            let layers = [TileLayer::Background, TileLayer::Object, TileLayer::Overlay];
+            let before = layers
+                .each_ref()
+                .map(|layer| self.cell(layer, row, col).cloned());
+            self.place_tile(row, col, editor, !input.enter);
+            let after = layers
+                .each_ref()
+                .map(|layer| self.cell(layer, row, col).cloned());
+            editor.dirty |= before != after;
         }
     }
 
@@ -780,10 +1874,7 @@ impl Level {
                     let x = tile_x + col_idx as f32 * space;
                     let y = tile_y + row_idx as f32 * space;
 
-                    let mpos = (
-                        (input.mouse_x + 1.0) / 2.0 * VIRTUAL_W,
-                        (input.mouse_y + 1.0) / 2.0 * VIRTUAL_H,
-                    );
+                    let mpos = (input.mouse_x, input.mouse_y);
 
                     let hovering =
                         mpos.0 > x && mpos.0 < x + space && mpos.1 < y + space && mpos.1 > y;
@@ -828,10 +1919,7 @@ impl Level {
                 let tx = x + offset - 4.0;
                 let ty = y + offset + 4.0;
 
-                let mpos = (
-                    ((input.mouse_x + 1.0) / 2.0) * VIRTUAL_W,
-                    ((input.mouse_y + 1.0) / 2.0) * VIRTUAL_H,
-                );
+                let mpos = (input.mouse_x, input.mouse_y);
 
                 let hovering = mpos.0 >= x
                     && mpos.0 <= x + tile_size
@@ -895,7 +1983,7 @@ impl Level {
                     }
                     "X" => {
                         tile.layer = TileLayer::Object;
-                        if let None = tile.collision_matrix {
+                        if tile.collision_matrix.is_none() {
                             tile.collision_matrix = Some(CollisionMatrix::new());
                         }
                     }
@@ -909,6 +1997,82 @@ impl Level {
         }
     }
 
+    async fn edit_tile_surface(tile: &mut TileAsset) {
+        root_ui().label(None, &format!("Surface: {:?}", tile.surface));
+
+        if root_ui().button(None, format!("Water: {}", tile.water)) {
+            tile.water = !tile.water;
+        }
+
+        if root_ui().button(None, "Set Surface") {
+            if let Some(surface) = prompt("Surface [S:sand/ G:grass/ R:stone/ N:none]").await {
+                match surface.as_str() {
+                    "S" => tile.surface = Some(SurfaceKind::Sand),
+                    "G" => tile.surface = Some(SurfaceKind::Grass),
+                    "R" => tile.surface = Some(SurfaceKind::Stone),
+                    "N" => tile.surface = None,
+                    _ => alert("Invalid surface code.").await,
+                }
+            }
+        }
+    }
+
+    /// Toggles whether the tile y-sorts with bodies and prompts for where
+    /// its sort line sits.
+    async fn edit_tile_y_sort(tile: &mut TileAsset) {
+        if root_ui().button(None, format!("Y Sort: {}", tile.y_sort)) {
+            tile.y_sort = !tile.y_sort;
+        }
+
+        if tile.y_sort && root_ui().button(None, format!("Sort Offset: {}", tile.sort_offset)) {
+            if let Some(answer) = prompt("Pixels below the tile's bottom edge to sort at").await {
+                match answer.trim().parse::<f32>() {
+                    Ok(offset) => tile.sort_offset = offset,
+                    Err(_) => alert("Invalid offset.").await,
+                }
+            }
+        }
+    }
+
+    /// Prompts for how many frames the tile's animation has and then how
+    /// fast it plays. One frame or fewer turns the animation off.
+    async fn edit_tile_animation(tile: &mut TileAsset) {
+        match tile.animation {
+            Some(animation) => root_ui().label(
+                None,
+                &format!(
+                    "Animation: {} frames at {}fps",
+                    animation.frames, animation.fps
+                ),
+            ),
+            None => root_ui().label(None, "Animation: None"),
+        }
+
+        if !root_ui().button(None, "Set Animation") {
+            return;
+        }
+
+        let Some(frames) = prompt("Frames, running right from this tile (1 for none)").await else {
+            return;
+        };
+        let frames = match frames.trim().parse::<usize>() {
+            Ok(frames) => frames,
+            Err(_) => return alert("Invalid frame count.").await,
+        };
+        if frames <= 1 {
+            tile.animation = None;
+            return;
+        }
+
+        let Some(fps) = prompt("Frames per second").await else {
+            return;
+        };
+        match fps.trim().parse::<f32>() {
+            Ok(fps) if fps > 0.0 => tile.animation = Some(TileAnimation { frames, fps }),
+            _ => alert("Invalid frames per second.").await,
+        }
+    }
+
     async fn edit_tile(
         &mut self,
         input: &Input,
@@ -916,6 +2080,9 @@ impl Level {
         editor_width: f32,
         editor_y: f32,
     ) {
+        // Any change to the tile shows wherever it's placed.
+        self.background_cache.clear();
+
         if let (Some(tileset_id), Some(tile_id)) = (&editor.selected_tileset, editor.selected_tile)
         {
             root_ui().label(None, &format!("{tileset_id}:{tile_id}"));
@@ -927,10 +2094,11 @@ impl Level {
             }
             splitter();
 
-            let tileset = self
-                .tilesets
-                .get_mut(tileset_id)
-                .expect("Tileset will exist");
+            let tileset = Rc::make_mut(
+                self.tilesets
+                    .get_mut(tileset_id)
+                    .expect("Tileset will exist"),
+            );
 
             let tile = tileset.tiles.get_mut(tile_id).expect("Tileset will exist");
 
@@ -948,6 +2116,9 @@ impl Level {
             splitter();
 
             Self::edit_tile_layer(tile).await;
+            Self::edit_tile_surface(tile).await;
+            Self::edit_tile_animation(tile).await;
+            Self::edit_tile_y_sort(tile).await;
 
             let x = editor_width / 3.0;
             let y = editor_y + editor_width / 3.0;
@@ -970,6 +2141,776 @@ impl Level {
         }
     }
 
+    fn remove_object_listing(&mut self, idx: usize) {
+        let listing = self.objects.remove(idx);
+        self.spawned_objects.remove(&listing.id());
+    }
+
+    /// Prompts for each spawner field in turn. Blank answers keep the
+    /// current value.
+    async fn edit_spawner(r#type: &mut ObjectType) {
+        let ObjectType::Spawner {
+            enemy,
+            count,
+            interval,
+            max_alive,
+        } = r#type
+        else {
+            return;
+        };
+
+        if let Some(answer) = prompt("Enemy [O:orb/ F:flower/ B:blob/ G:goblin]").await {
+            match answer.trim().to_uppercase().as_str() {
+                "" => {}
+                "O" => *enemy = EnemyType::CopperOrb,
+                "F" => *enemy = EnemyType::DeceptiveFlower,
+                "B" => *enemy = EnemyType::PurpleBlob,
+                "G" => *enemy = EnemyType::SeaGoblin,
+                _ => alert("Invalid enemy").await,
+            }
+        }
+
+        if let Some(answer) = prompt(&format!("Count ({count})")).await {
+            if !answer.trim().is_empty() {
+                match answer.trim().parse() {
+                    Ok(value) => *count = value,
+                    Err(_) => alert("Invalid count").await,
+                }
+            }
+        }
+
+        if let Some(answer) = prompt(&format!("Interval seconds ({interval})")).await {
+            if !answer.trim().is_empty() {
+                match answer.trim().parse() {
+                    Ok(value) => *interval = value,
+                    Err(_) => alert("Invalid interval").await,
+                }
+            }
+        }
+
+        if let Some(answer) = prompt(&format!("Max alive ({max_alive})")).await {
+            if !answer.trim().is_empty() {
+                match answer.trim().parse() {
+                    Ok(value) => *max_alive = value,
+                    Err(_) => alert("Invalid max alive").await,
+                }
+            }
+        }
+    }
+
+    /// Prompts for a chest's contents as `Item:count` pairs, e.g.
+    /// `Shell:3 Rope:1`. A blank answer keeps the current contents.
+    async fn edit_chest(r#type: &mut ObjectType) {
+        let ObjectType::Chest { contents } = r#type else {
+            return;
+        };
+
+        let Some(answer) = prompt("Contents (Item:count ...)").await else {
+            return;
+        };
+        if answer.trim().is_empty() {
+            return;
+        }
+
+        let mut parsed = Vec::new();
+        for pair in answer.split_whitespace() {
+            let (name, count) = pair.split_once(':').unwrap_or((pair, "1"));
+            let item = ItemId::ALL
+                .iter()
+                .find(|item| format!("{item:?}").eq_ignore_ascii_case(name));
+            match (item, count.parse()) {
+                (Some(item), Ok(count)) => parsed.push((*item, count)),
+                _ => {
+                    alert(&format!("Invalid contents: {pair}")).await;
+                    return;
+                }
+            }
+        }
+        *contents = parsed;
+    }
+
+    /// Prompts for the item a door needs, then whether opening uses it up.
+    /// A blank item answer keeps the current key; `none` removes it.
+    async fn edit_door(r#type: &mut ObjectType) {
+        let ObjectType::Door { key, consume_key } = r#type else {
+            return;
+        };
+
+        if let Some(answer) = prompt("Key item (none for unlocked)").await {
+            let answer = answer.trim();
+            let item = ItemId::ALL
+                .iter()
+                .find(|item| format!("{item:?}").eq_ignore_ascii_case(answer));
+            match (answer, item) {
+                ("", _) => {}
+                (answer, _) if answer.eq_ignore_ascii_case("none") => *key = None,
+                (_, Some(item)) => *key = Some(*item),
+                (_, None) => alert("Invalid key").await,
+            }
+        }
+
+        if key.is_none() {
+            *consume_key = false;
+            return;
+        }
+
+        if let Some(answer) = prompt("Use up key [Y/N]").await {
+            match answer.trim().to_uppercase().as_str() {
+                "" => {}
+                "Y" => *consume_key = true,
+                "N" => *consume_key = false,
+                _ => alert("Invalid answer").await,
+            }
+        }
+    }
+
+    /// Prompts for the NPC's sprite, then its pages one at a time until a
+    /// blank page, each with an optional speaker and portrait. A blank first
+    /// page, or cancelling, keeps the current pages.
+    async fn edit_npc(r#type: &mut ObjectType) {
+        let ObjectType::Npc { sprite, pages } = r#type else {
+            return;
+        };
+
+        let Some(answer) = prompt(&format!("Sprite ({sprite})")).await else {
+            return;
+        };
+        if !answer.trim().is_empty() {
+            *sprite = answer.trim().to_owned();
+        }
+
+        let mut edited = Vec::new();
+        loop {
+            let number = edited.len() + 1;
+            let Some(text) = prompt(&format!("Page {number} text (blank to finish)")).await else {
+                return;
+            };
+            if text.trim().is_empty() {
+                break;
+            }
+
+            let speaker = prompt(&format!("Page {number} speaker (blank for none)"))
+                .await
+                .map(|speaker| speaker.trim().to_owned())
+                .filter(|speaker| !speaker.is_empty());
+            let portrait = prompt(&format!("Page {number} portrait (blank for none)"))
+                .await
+                .map(|portrait| portrait.trim().to_owned())
+                .filter(|portrait| !portrait.is_empty());
+            edited.push(DialoguePage {
+                speaker,
+                portrait,
+                text: text.trim().to_owned(),
+            });
+        }
+        if !edited.is_empty() {
+            *pages = edited;
+        }
+    }
+
+    /// Prompts for where a portal leads. Blank answers keep the current
+    /// value.
+    async fn edit_portal(r#type: &mut ObjectType) {
+        let ObjectType::Portal {
+            target_level,
+            target_anchor,
+        } = r#type
+        else {
+            return;
+        };
+
+        if let Some(answer) = prompt(&format!("Target level ({target_level})")).await {
+            if !answer.trim().is_empty() {
+                *target_level = answer.trim().to_owned();
+            }
+        }
+
+        if let Some(answer) = prompt(&format!("Target anchor ({target_anchor})")).await {
+            if !answer.trim().is_empty() {
+                *target_anchor = answer.trim().to_owned();
+            }
+        }
+    }
+
+    /// Prompts for an anchor's name. Blank answers keep the current name.
+    async fn edit_anchor(r#type: &mut ObjectType) {
+        let ObjectType::Anchor { name } = r#type else {
+            return;
+        };
+
+        if let Some(answer) = prompt(&format!("Anchor name ({name})")).await {
+            if !answer.trim().is_empty() {
+                *name = answer.trim().to_owned();
+            }
+        }
+    }
+
+    /// Prompts for a plate's, lever's or gate's link id. Cancelling or
+    /// answering blank keeps the current one.
+    async fn edit_link(r#type: &mut ObjectType) {
+        let (ObjectType::PressurePlate { link_id, .. }
+        | ObjectType::Lever { link_id }
+        | ObjectType::Gate { link_id }) = r#type
+        else {
+            return;
+        };
+
+        if let Some(answer) = prompt(&format!("Link id ({link_id})")).await {
+            if !answer.trim().is_empty() {
+                *link_id = answer.trim().to_owned();
+            }
+        }
+    }
+
+    /// Prompts for a lamp's radius and then its color as `r,g,b`.
+    /// Cancelling or an answer that doesn't parse keeps the current value.
+    async fn edit_lamp(r#type: &mut ObjectType) {
+        let ObjectType::Lamp(light) = r#type else {
+            return;
+        };
+
+        if let Some(answer) = prompt(&format!("Light radius ({})", light.radius)).await {
+            if let Ok(radius) = answer.trim().parse::<f32>() {
+                light.radius = radius.max(0.0);
+            }
+        }
+
+        let [r, g, b] = light.color;
+        if let Some(answer) = prompt(&format!("Light color as r,g,b ({r},{g},{b})")).await {
+            let parts: Vec<Option<u8>> = answer
+                .split(',')
+                .map(|part| part.trim().parse().ok())
+                .collect();
+            if let [Some(r), Some(g), Some(b)] = parts[..] {
+                light.color = [r, g, b];
+            }
+        }
+    }
+
+    /// Prompts for a spike trap's period and then its offset, both in
+    /// seconds. Cancelling or an answer that doesn't parse keeps the current
+    /// value.
+    async fn edit_spikes(r#type: &mut ObjectType) {
+        let ObjectType::SpikeTrap { period, offset } = r#type else {
+            return;
+        };
+
+        if let Some(answer) = prompt(&format!("Period in seconds ({period})")).await {
+            if let Ok(value) = answer.trim().parse::<f32>() {
+                *period = value.max(0.1);
+            }
+        }
+
+        if let Some(answer) = prompt(&format!("Offset in seconds ({offset})")).await {
+            if let Ok(value) = answer.trim().parse::<f32>() {
+                *offset = value;
+            }
+        }
+    }
+
+    /// Prompts for a spawn region's population and then its respawn delay
+    /// in seconds. Cancelling or an answer that doesn't parse keeps the
+    /// current value.
+    async fn edit_region(r#type: &mut ObjectType) {
+        let ObjectType::SpawnRegion {
+            population,
+            respawn_delay,
+            ..
+        } = r#type
+        else {
+            return;
+        };
+
+        if let Some(answer) = prompt(&format!("Population ({population})")).await {
+            if let Ok(value) = answer.trim().parse() {
+                *population = value;
+            }
+        }
+
+        if let Some(answer) = prompt(&format!("Respawn delay in seconds ({respawn_delay})")).await {
+            if let Ok(value) = answer.trim().parse::<f32>() {
+                *respawn_delay = value.max(0.0);
+            }
+        }
+    }
+
+    /// Prompts for a sign's text. Cancelling keeps the current text.
+    async fn edit_sign(r#type: &mut ObjectType) {
+        let ObjectType::Sign { text, .. } = r#type else {
+            return;
+        };
+
+        if let Some(answer) = prompt("Sign text").await {
+            *text = answer.trim().to_owned();
+        }
+    }
+
+    /// Details of the listing at `idx` and buttons to change it.
+    async fn inspector(&mut self, editor: &mut LevelEditorSettings, idx: usize) {
+        let listing = &mut self.objects[idx];
+        root_ui().label(None, &listing.r#type().label());
+        root_ui().label(None, &format!("Cell: {}, {}", listing.row(), listing.col()));
+
+        let waypoints = listing.patrol().map_or(Vec::new(), |patrol| {
+            patrol
+                .iter()
+                .map(|(row, col)| format!("{row},{col}"))
+                .collect()
+        });
+        if !waypoints.is_empty() {
+            root_ui().label(None, &format!("Patrol: {}", waypoints.join(" ")));
+        }
+
+        if let ObjectType::Enemy(enemy) = listing.type_mut() {
+            if root_ui().button(None, "Change Type") {
+                let next = EnemyType::ALL
+                    .iter()
+                    .position(|other| other == enemy)
+                    .unwrap()
+                    + 1;
+                *enemy = EnemyType::ALL[next % EnemyType::ALL.len()];
+                editor.dirty = true;
+            }
+
+            if root_ui().button(None, "Edit Patrol") {
+                editor.patrol_target = Some(idx);
+            }
+        }
+
+        if let ObjectType::SpawnRegion { enemy, .. } = listing.type_mut() {
+            if root_ui().button(None, "Change Type") {
+                let next = EnemyType::ALL
+                    .iter()
+                    .position(|other| other == enemy)
+                    .unwrap()
+                    + 1;
+                *enemy = EnemyType::ALL[next % EnemyType::ALL.len()];
+                editor.dirty = true;
+            }
+
+            if root_ui().button(None, "Edit Region") {
+                Self::edit_region(listing.type_mut()).await;
+                editor.dirty = true;
+            }
+        }
+
+        if matches!(listing.r#type(), ObjectType::Chest { .. })
+            && root_ui().button(None, "Edit Contents")
+        {
+            Self::edit_chest(listing.type_mut()).await;
+            editor.dirty = true;
+        }
+
+        if matches!(listing.r#type(), ObjectType::Door { .. }) && root_ui().button(None, "Edit Key")
+        {
+            Self::edit_door(listing.type_mut()).await;
+            editor.dirty = true;
+        }
+
+        if matches!(listing.r#type(), ObjectType::Npc { .. })
+            && root_ui().button(None, "Edit Dialogue")
+        {
+            Self::edit_npc(listing.type_mut()).await;
+            editor.dirty = true;
+        }
+
+        if matches!(listing.r#type(), ObjectType::Portal { .. })
+            && root_ui().button(None, "Edit Target")
+        {
+            Self::edit_portal(listing.type_mut()).await;
+            editor.dirty = true;
+        }
+
+        if matches!(listing.r#type(), ObjectType::Anchor { .. })
+            && root_ui().button(None, "Edit Name")
+        {
+            Self::edit_anchor(listing.type_mut()).await;
+            editor.dirty = true;
+        }
+
+        if let ObjectType::PressurePlate { latch, .. } = listing.type_mut() {
+            let label = match latch {
+                true => "Latch: Yes",
+                false => "Latch: No",
+            };
+            if root_ui().button(None, label) {
+                *latch = !*latch;
+                editor.dirty = true;
+            }
+        }
+
+        if matches!(
+            listing.r#type(),
+            ObjectType::PressurePlate { .. } | ObjectType::Lever { .. } | ObjectType::Gate { .. }
+        ) && root_ui().button(None, "Edit Link")
+        {
+            Self::edit_link(listing.type_mut()).await;
+            editor.dirty = true;
+        }
+
+        if matches!(listing.r#type(), ObjectType::SpikeTrap { .. })
+            && root_ui().button(None, "Edit Timing")
+        {
+            Self::edit_spikes(listing.type_mut()).await;
+            editor.dirty = true;
+        }
+
+        if let ObjectType::Lamp(light) = listing.type_mut() {
+            let label = match light.flicker {
+                true => "Flicker: Yes",
+                false => "Flicker: No",
+            };
+            if root_ui().button(None, label) {
+                light.flicker = !light.flicker;
+                editor.dirty = true;
+            }
+
+            if root_ui().button(None, "Edit Light") {
+                Self::edit_lamp(listing.type_mut()).await;
+                editor.dirty = true;
+            }
+        }
+
+        if let ObjectType::Sign { solid, .. } = listing.type_mut() {
+            let label = match solid {
+                true => "Solid: Yes",
+                false => "Solid: No",
+            };
+            if root_ui().button(None, label) {
+                *solid = !*solid;
+                editor.dirty = true;
+            }
+
+            if root_ui().button(None, "Edit Text") {
+                Self::edit_sign(listing.type_mut()).await;
+                editor.dirty = true;
+            }
+        }
+
+        let move_label = match editor.moving {
+            true => "Moving: click a cell",
+            false => "Move",
+        };
+        if root_ui().button(None, move_label) {
+            editor.moving = !editor.moving;
+        }
+
+        if root_ui().button(None, "Delete") {
+            self.remove_object_listing(idx);
+            editor.dirty = true;
+            editor.inspecting = None;
+            editor.moving = false;
+            editor.patrol_target = match editor.patrol_target {
+                Some(target) if target == idx => None,
+                Some(target) if target > idx => Some(target - 1),
+                target => target,
+            };
+            return;
+        }
+
+        if root_ui().button(None, "Deselect") {
+            editor.inspecting = None;
+            editor.moving = false;
+        }
+    }
+
+    async fn object_panel(&mut self, editor: &mut LevelEditorSettings) {
+        if root_ui().button(None, "Tile Mode") {
+            editor.object_mode = false;
+        }
+        splitter();
+
+        let types = ObjectType::all();
+        let selected = match editor.selected_object_type {
+            Some(idx) => types[idx].label(),
+            None => "Erase".to_owned(),
+        };
+        root_ui().label(None, &format!("Placing: {selected}"));
+
+        if root_ui().button(None, "Next Type") {
+            editor.selected_object_type = match editor.selected_object_type {
+                Some(idx) if idx + 1 < types.len() => Some(idx + 1),
+                Some(_) => None,
+                None => Some(0),
+            };
+        }
+
+        if root_ui().button(None, "Erase") {
+            editor.selected_object_type = None;
+        }
+        splitter();
+
+        root_ui().label(None, &format!("Listings: {}", self.objects.len()));
+        splitter();
+
+        if let Some(idx) = editor.inspecting {
+            self.inspector(editor, idx).await;
+            splitter();
+        }
+
+        if root_ui().button(None, "Edit Last Spawner") {
+            let last_spawner = self
+                .objects
+                .iter_mut()
+                .rev()
+                .find(|listing| matches!(listing.r#type(), ObjectType::Spawner { .. }));
+            match last_spawner {
+                Some(listing) => {
+                    Self::edit_spawner(listing.type_mut()).await;
+                    editor.dirty = true;
+                }
+                None => alert("No spawner placed").await,
+            }
+        }
+
+        let Some(idx) = editor.patrol_target else {
+            if root_ui().button(None, "Patrol Last Enemy") {
+                editor.patrol_target = self
+                    .objects
+                    .iter()
+                    .rposition(|listing| matches!(listing.r#type(), ObjectType::Enemy(_)));
+            }
+            return;
+        };
+
+        let listing = &mut self.objects[idx];
+        let waypoints = listing.patrol().map_or(0, |patrol| patrol.len());
+        root_ui().label(None, &format!("Patrol: {waypoints} waypoints"));
+
+        let mode = match listing.ping_pong() {
+            true => "Ping-Pong",
+            false => "Loop",
+        };
+        if root_ui().button(None, format!("Mode: {mode}")) {
+            listing.toggle_ping_pong();
+            editor.dirty = true;
+        }
+
+        if root_ui().button(None, "Clear Patrol") {
+            listing.clear_patrol();
+            editor.dirty = true;
+        }
+
+        if root_ui().button(None, "Done Patrol") {
+            editor.patrol_target = None;
+        }
+    }
+
+    /// Lines from each pressure plate and lever to the gates sharing its
+    /// link id.
+    fn render_links(&self, world: &World) {
+        let offset = vec2(world.x, world.y);
+        for source in self.objects.iter() {
+            let (ObjectType::PressurePlate { link_id, .. } | ObjectType::Lever { link_id }) =
+                source.r#type()
+            else {
+                continue;
+            };
+
+            let from = source.center() - offset;
+            for gate in self.objects.iter() {
+                if matches!(gate.r#type(), ObjectType::Gate { link_id: other } if other == link_id)
+                {
+                    let to = gate.center() - offset;
+                    draw_line(from.x, from.y, to.x, to.y, 1.0, SKYBLUE);
+                }
+            }
+        }
+    }
+
+    /// Shows the area being dragged from `start` to `end`, both
+    /// `(row, col)`, and places it once the mouse is let go.
+    fn drag_region(
+        &mut self,
+        editor: &mut LevelEditorSettings,
+        start: (usize, usize),
+        end: (usize, usize),
+        input: &Input,
+        world: &World,
+    ) {
+        let (top, left) = (start.0.min(end.0), start.1.min(end.1));
+        let rows = start.0.max(end.0) - top + 1;
+        let cols = start.1.max(end.1) - left + 1;
+        draw_rectangle_lines(
+            left as f32 * TILE_SIZE - world.x,
+            top as f32 * TILE_SIZE - world.y,
+            cols as f32 * TILE_SIZE,
+            rows as f32 * TILE_SIZE,
+            2.0,
+            MAGENTA,
+        );
+
+        if input.mouse_down {
+            return;
+        }
+
+        editor.region_start = None;
+        let Some(mut r#type) = editor
+            .selected_object_type
+            .map(|idx| ObjectType::all().swap_remove(idx))
+        else {
+            return;
+        };
+        r#type.set_area(rows, cols);
+
+        let id = self.next_listing_id();
+        self.objects.push(ObjectListing::new(id, top, left, r#type));
+        editor.dirty = true;
+    }
+
+    fn object_placer(&mut self, editor: &mut LevelEditorSettings, input: &Input, world: &World) {
+        let (row_range, col_range) = self.get_showing_range(world);
+        for (idx, listing) in self.objects.iter().enumerate() {
+            if listing.is_in_range(&row_range, &col_range) {
+                let x = listing.col() as f32 * TILE_SIZE - world.x;
+                let y = listing.row() as f32 * TILE_SIZE - world.y;
+                match editor.inspecting == Some(idx) {
+                    true => draw_rectangle_lines(x, y, TILE_SIZE, TILE_SIZE, 2.0, SKYBLUE),
+                    false => draw_rectangle_lines(x, y, TILE_SIZE, TILE_SIZE, 1.0, YELLOW),
+                }
+                draw_text(
+                    &listing.r#type().label()[..1],
+                    x + 2.0,
+                    y + 8.0,
+                    8.0,
+                    YELLOW,
+                );
+            }
+
+            if let Some(area) = listing.area() {
+                let color = match listing.r#type() {
+                    ObjectType::CameraZone { .. } => GREEN,
+                    _ => MAGENTA,
+                };
+                let (x, y) = (area.x - world.x, area.y - world.y);
+                draw_rectangle(x, y, area.w, area.h, Color { a: 0.15, ..color });
+                draw_rectangle_lines(x, y, area.w, area.h, 1.0, color);
+            }
+
+            let inspecting = editor.inspecting == Some(idx);
+            if let Some(light) = listing.r#type().light().filter(|_| inspecting) {
+                let center = listing.center();
+                draw_circle_lines(
+                    center.x - world.x,
+                    center.y - world.y,
+                    light.radius,
+                    1.0,
+                    light.color(),
+                );
+            }
+
+            if let Some(points) = listing.patrol_points() {
+                let offset = vec2(world.x, world.y);
+                let mut previous = listing.center() - offset;
+                for point in points {
+                    let point = point - offset;
+                    draw_line(previous.x, previous.y, point.x, point.y, 1.0, ORANGE);
+                    draw_rectangle(point.x - 1.5, point.y - 1.5, 3.0, 3.0, ORANGE);
+                    previous = point;
+                }
+            }
+        }
+
+        self.render_links(world);
+
+        if input.mouse_x < LevelEditorSettings::PANEL_WIDTH {
+            return;
+        }
+
+        let mouse = (input.mouse_x, input.mouse_y);
+
+        let col = ((mouse.0 + world.x) / TILE_SIZE).floor();
+        let row = ((mouse.1 + world.y) / TILE_SIZE).floor();
+        let x = col * TILE_SIZE - world.x;
+        let y = row * TILE_SIZE - world.y;
+
+        if col < 0.0 || col >= self.cols as f32 || row < 0.0 || row >= self.rows as f32 {
+            draw_rectangle(x, y, TILE_SIZE, TILE_SIZE, RED);
+            return;
+        }
+        draw_rectangle(
+            x,
+            y,
+            TILE_SIZE,
+            TILE_SIZE,
+            Color::from_rgba(255, 255, 0, 100),
+        );
+
+        let (row, col) = (row as usize, col as usize);
+        if let Some(start) = editor.region_start {
+            self.drag_region(editor, start, (row, col), input, world);
+            return;
+        }
+
+        if !input.click {
+            return;
+        }
+
+        if let Some(idx) = editor.patrol_target {
+            self.objects[idx].add_waypoint(row, col);
+            editor.dirty = true;
+            return;
+        }
+
+        if let (true, Some(idx)) = (editor.moving, editor.inspecting) {
+            self.objects[idx].set_cell(row, col);
+            editor.moving = false;
+            editor.dirty = true;
+            return;
+        }
+
+        // Clicking a listing inspects it rather than stacking another on it.
+        if editor.selected_object_type.is_some() && self.select_listing(editor, row, col) {
+            return;
+        }
+
+        match editor.selected_object_type {
+            Some(idx) => {
+                let r#type = ObjectType::all().swap_remove(idx);
+                if r#type.is_area() {
+                    editor.region_start = Some((row, col));
+                    return;
+                }
+
+                let id = self.next_listing_id();
+                self.objects.push(ObjectListing::new(id, row, col, r#type));
+                editor.dirty = true;
+            }
+            None => {
+                while let Some(idx) = self
+                    .objects
+                    .iter()
+                    .position(|listing| listing.row() == row && listing.col() == col)
+                {
+                    self.remove_object_listing(idx);
+                    editor.inspecting = None;
+                    editor.dirty = true;
+                }
+            }
+        }
+    }
+
+    /// Inspects a listing on the clicked cell, if there is one. Clicking the
+    /// same cell again moves on to the next listing there.
+    fn select_listing(&self, editor: &mut LevelEditorSettings, row: usize, col: usize) -> bool {
+        let on_cell: Vec<usize> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, listing)| listing.row() == row && listing.col() == col)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let current = editor
+            .inspecting
+            .and_then(|inspecting| on_cell.iter().position(|&idx| idx == inspecting));
+        editor.inspecting = match current {
+            Some(pos) => Some(on_cell[(pos + 1) % on_cell.len()]),
+            None => on_cell.first().copied(),
+        };
+        return !on_cell.is_empty();
+    }
+
     pub async fn level_editor(
         &mut self,
         editor: &mut LevelEditorSettings,
@@ -977,10 +2918,20 @@ impl Level {
         dt: f32,
         world: &World,
     ) -> AssetManageResult<()> {
-        let editor_width = VIRTUAL_W / 3.0;
+        let editor_width = LevelEditorSettings::PANEL_WIDTH;
         let editor_y = VIRTUAL_H - editor_width;
 
         self.draw_panel(editor_width, editor_y);
+        if editor.dirty {
+            root_ui().label(None, "Unsaved changes");
+        }
+
+        if editor.object_mode {
+            self.object_panel(editor).await;
+            self.object_placer(editor, input, world);
+            self.refresh_solid_cells();
+            return Ok(());
+        }
 
         if editor.editing_tile {
             self.edit_tile(input, editor, editor_width, editor_y).await;
@@ -995,21 +2946,577 @@ impl Level {
         return Ok(());
     }
 
-    fn to_serializable(&self) -> LevelSerializable {
+    /// Swaps the level's tiles and objects for those of the Tiled map at
+    /// `path`, keeping its other settings. Returns what the map had that
+    /// couldn't be brought over.
+    async fn import_tiled(&mut self, path: &str) -> AssetManageResult<Vec<String>> {
+        if path.ends_with(".tmx") {
+            return Err(AssetManageError::Unsupported {
+                file: path.to_owned(),
+                features: vec!["XML maps, export it as JSON".to_owned()],
+            });
+        }
+        let import = tiled::import_tiled(&std::fs::read(path)?, path, &known_tile_tables()?)?;
+        let level = import.level;
+
+        // Loaded before anything changes, so a failure leaves the level as
+        // it was.
+        for row in level
+            .background_layer
+            .iter()
+            .chain(&level.object_layer)
+            .chain(&level.overlay_layer)
+        {
+            for ptr in row.iter().flatten() {
+                if !self.tilesets.contains_key(&ptr.0) {
+                    let tileset = TilesetAsset::load(&ptr.0).await?;
+                    self.tilesets.insert(ptr.0.clone(), Rc::new(tileset));
+                }
+            }
+        }
+
+        self.rows = level.rows;
+        self.cols = level.cols;
+        self.background_layer = level.background_layer;
+        self.object_layer = level.object_layer;
+        self.overlay_layer = level.overlay_layer;
+        // Held whole now, and split into chunks again when saved.
+        self.chunks = None;
+        self.objects = level.objects;
+        self.assign_listing_ids();
+        self.refresh_solid_cells();
+        self.reset_spawns();
+        self.background_cache.clear();
+        return Ok(import.skipped);
+    }
+
+    /// Writes the level to `path` as `format`. Chunked levels write their
+    /// edited chunks back first, and saved as anything else read the rest
+    /// from disk and stop streaming.
+    fn save(&mut self, format: LevelFormat, path: &str) -> AssetManageResult<()> {
+        check_saving()?;
+        let serializable = self.to_serializable();
+        let Some(chunks) = &mut self.chunks else {
+            return format.write(&serializable, path);
+        };
+
+        chunks.save(&serializable)?;
+        if format == LevelFormat::Chunked {
+            return Ok(());
+        }
+        let whole = LevelFormat::Chunked.read(&self.path)?;
+        format.write(&whole, path)?;
+        self.background_layer = whole.background_layer;
+        self.object_layer = whole.object_layer;
+        self.overlay_layer = whole.overlay_layer;
+        self.chunks = None;
+        return Ok(());
+    }
+
+    /// The level as it's saved. Chunked levels leave their tiles out.
+    pub fn to_serializable(&self) -> LevelSerializable {
         LevelSerializable {
+            version: LevelSerializable::VERSION,
             background_layer: self.background_layer.clone(),
             object_layer: self.object_layer.clone(),
             overlay_layer: self.overlay_layer.clone(),
             rows: self.rows,
             cols: self.cols,
             objects: self.objects.clone(),
+            indoors: self.indoors,
+            darkness: self.darkness,
+            weather: self.weather,
         }
     }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use macroquad::{texture::Image, window::next_frame};
+
+    use super::*;
+    use crate::{
+        asset_loading::tests::{scratch_dir, stand_in_tex},
+        asset_root::tests::use_root,
+        level_chunks::{ChunkedLayers, LevelChunk},
+        tile_batch::take_draw_count,
+    };
+
+    const TILESET: &str = "test";
+    const LAND: usize = 0;
+    const WATER: usize = 1;
+    const WALL: usize = 2;
+
+    fn tile(layer: TileLayer, water: bool, collision_matrix: Option<CollisionMatrix>) -> TileAsset {
+        return TileAsset {
+            x: 0.0,
+            y: 0.0,
+            auto_rule: None,
+            layer,
+            group: None,
+            collision_matrix,
+            surface: None,
+            water,
+            animation: None,
+            y_sort: false,
+            sort_offset: 0.0,
+        };
+    }
+
+    /// Land, water, and a wall solid all over.
+    fn tileset() -> TilesetAsset {
+        return TilesetAsset {
+            tex: stand_in_tex(),
+            tiles: vec![
+                tile(TileLayer::Background, false, None),
+                tile(TileLayer::Background, true, None),
+                tile(TileLayer::Object, false, Some(CollisionMatrix::new())),
+            ],
+            meta_path: Default::default(),
+            placeholder: false,
+        };
+    }
+
+    /// A level drawn a row of cells to a line: `.` for land, `~` for water
+    /// and `#` for a wall standing on land.
+    pub fn level(map: &[&str]) -> Level {
+        let layer = |pick: fn(char) -> Option<usize>| -> TileVec {
+            return map
+                .iter()
+                .map(|row| {
+                    row.chars()
+                        .map(|cell| pick(cell).map(|idx| TilePointer(TILESET.to_owned(), idx)))
+                        .collect()
+                })
+                .collect();
+        };
+        let background = layer(|cell| match cell {
+            '~' => Some(WATER),
+            _ => Some(LAND),
+        });
+        let object = layer(|cell| match cell {
+            '#' => Some(WALL),
+            _ => None,
+        });
+        let empty = layer(|_| None);
+
+        let serializable = LevelSerializable {
+            version: LevelSerializable::VERSION,
+            background_layer: background,
+            object_layer: object,
+            overlay_layer: empty,
+            rows: map.len(),
+            cols: map.first().map_or(0, |row| row.len()),
+            objects: Vec::new(),
+            indoors: false,
+            darkness: 0.0,
+            weather: WeatherKind::default(),
+        };
+        let mut level = Level::from_serializable(TILESET, String::new(), serializable, None);
+        level
+            .tilesets
+            .insert(TILESET.to_owned(), Rc::new(tileset()));
+        return level;
+    }
+
+    /// A one-row level with a cell from each of `tilesets`, holding a
+    /// stand-in copy of each.
+    pub fn level_using(tilesets: &[&str]) -> Level {
+        let mut level = level(&[".".repeat(tilesets.len()).as_str()]);
+        level.background_layer = vec![tilesets
+            .iter()
+            .map(|name| Some(TilePointer(name.to_string(), LAND)))
+            .collect()];
+        level.tilesets = tilesets
+            .iter()
+            .map(|name| (name.to_string(), Rc::new(tileset())))
+            .collect();
+        return level;
+    }
+
+    /// `level(map)`, called `name`.
+    pub fn named_level(name: &str, map: &[&str]) -> Level {
+        let mut level = level(map);
+        level.name = name.to_owned();
+        return level;
+    }
+
+    #[test]
+    fn builds_levels_from_maps() {
+        let level = level(&[".~", "#."]);
+        assert_eq!(level.size(), (2, 2));
+        assert!(!level.is_water_at(8.0, 8.0));
+        assert!(level.is_water_at(24.0, 8.0));
+        assert!(level.check_for_collision(8.0, 24.0, false).is_some());
+        assert!(level.check_for_collision(24.0, 24.0, false).is_none());
+    }
+
+    #[test]
+    fn level_types_construct() {
+        let settings = LevelEditorSettings::new(true);
+        assert!(!settings.open && !settings.dirty && settings.compress_levels);
 
-    fn tileset_to_serializable(&self, tileset_id: &String) -> TilesetAssetSerializable {
-        TilesetAssetSerializable {
-            tiles: self.tilesets[tileset_id].tiles.clone(),
-            meta_path: self.tilesets[tileset_id].meta_path.clone(),
+        let serializable = level(&[".#"]).to_serializable();
+        assert_eq!(serializable.version, LevelSerializable::VERSION);
+        assert_eq!(
+            serializable.object_layer[0][1],
+            Some(TilePointer(TILESET.to_owned(), WALL))
+        );
+
+        let mut level = level(&[".#", ".."]);
+        let wall = level.tilesets[TILESET].tiles[WALL].clone();
+        Rc::make_mut(level.tilesets.get_mut(TILESET).unwrap()).tiles[WALL] = TileAsset {
+            y_sort: true,
+            ..wall
+        };
+        let sorted = level.y_sorted_tiles(&World::new(), 0.0);
+        assert_eq!(sorted.len(), 1);
+        assert_eq!(sorted[0].y_sort_key(), 1600);
+
+        let hit = level.check_for_collision(20.0, 4.0, false).unwrap();
+        assert_eq!(hit.from_left(), 16.0 - TileHitInfo::SMALL);
+        assert_eq!(
+            level.line_of_sight(vec2(4.0, 4.0), vec2(4.0, 28.0)),
+            LineOfSight::Clear
+        );
+    }
+
+    /// Places a listing of `r#type` at `row` and `col`, returning its id.
+    pub fn place(level: &mut Level, row: usize, col: usize, r#type: ObjectType) -> u64 {
+        let id = level.next_listing_id();
+        level.objects.push(ObjectListing::new(id, row, col, r#type));
+        level.refresh_solid_cells();
+        return id;
+    }
+
+    /// Writes `level` to `path` the way saving does, but without the check
+    /// that refuses to save in builds with the assets built in, so tests of
+    /// the formats run in those too.
+    fn write(level: &Level, format: LevelFormat, path: &str) {
+        format.write(&level.to_serializable(), path).unwrap();
+    }
+
+    /// Where the test `name` keeps `file`.
+    fn scratch_file(name: &str, file: &str) -> String {
+        return scratch_dir(name).join(file).to_string_lossy().into_owned();
+    }
+
+    #[test]
+    fn streaming_a_huge_level_holds_few_chunks_and_never_stalls() {
+        const CELLS: usize = 1000;
+        let root = scratch_dir("huge_chunked_level");
+        use_root(&root);
+        let row = ".".repeat(CELLS);
+        let huge = level(&vec![row.as_str(); CELLS]);
+        write(
+            &huge,
+            LevelFormat::Chunked,
+            &LevelFormat::Chunked.path("huge"),
+        );
+
+        let mut huge = Level::read("huge", &Assets::new()).unwrap();
+        // The view is narrower than a chunk, so spans two each way at most,
+        // and is held with `KEEP` chunks past it on every side.
+        assert!(VIRTUAL_W < (LevelChunk::CELLS as f32 * TILE_SIZE));
+        let bound = (2 + 2 * ChunkedLayers::KEEP).pow(2);
+
+        // The chunks in view on arrival are read at once.
+        let mut world = World::new();
+        huge.stream_chunks(&world);
+        let (_, arrival_reads) = huge.chunk_reads();
+
+        // Corner to corner, at a running pace, with the loader given the
+        // time frames would take between steps.
+        let far = CELLS as f32 * TILE_SIZE - VIRTUAL_W;
+        while world.x < far {
+            world.x += 4.0;
+            world.y += 4.0 * VIRTUAL_H / VIRTUAL_W;
+            huge.stream_chunks(&world);
+            for _ in 0..1000 {
+                if huge.chunk_reads().0 == 0 {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(1));
+                huge.stream_chunks(&world);
+            }
+
+            let (pending, reads) = huge.chunk_reads();
+            assert_eq!(pending, 0, "the loader never caught up");
+            assert_eq!(reads, arrival_reads, "waited on a chunk at {world:?}");
+            assert!(huge.chunks_loaded() <= bound);
         }
+        assert!(huge.chunks_loaded() > 0);
+    }
+
+    /// A level using every layer and some objects, as saved.
+    fn busy_level() -> Level {
+        let mut busy = level(&["..~~", ".#f.", "~~.."]);
+        busy.overlay_layer[0][1] = Some(TilePointer(TILESET.to_owned(), LAND));
+        place(&mut busy, 1, 3, ObjectType::Enemy(EnemyType::PurpleBlob));
+        place(
+            &mut busy,
+            2,
+            3,
+            ObjectType::Sign {
+                text: "Hi".to_owned(),
+                solid: true,
+            },
+        );
+        busy.indoors = true;
+        busy.darkness = 0.25;
+        return busy;
+    }
+
+    #[test]
+    fn listings_survive_saving_and_loading() {
+        let root = scratch_dir("saved_listings");
+        use_root(&root);
+        std::fs::create_dir_all(root.join("levels")).unwrap();
+        let mut saved = busy_level();
+        saved.objects[0].add_waypoint(0, 0);
+        saved.objects[0].add_waypoint(2, 2);
+        saved.objects[0].toggle_ping_pong();
+        write(
+            &saved,
+            LevelFormat::Json,
+            &LevelFormat::Json.path("listings"),
+        );
+
+        let read = Level::read("listings", &Assets::new()).unwrap();
+        let listings = |level: &Level| serde_json::to_value(&level.objects).unwrap();
+        assert_eq!(read.objects.len(), 2);
+        assert_eq!(listings(&read), listings(&saved));
+    }
+
+    #[test]
+    fn levels_from_before_listings_load_without_any() {
+        let root = scratch_dir("level_before_listings");
+        use_root(&root);
+        std::fs::create_dir_all(root.join("levels")).unwrap();
+        std::fs::copy("tests/fixtures/level_v0.json", root.join("levels/old.json")).unwrap();
+
+        let old = Level::read("old", &Assets::new()).unwrap();
+        assert!(old.objects.is_empty());
+        assert_eq!(old.spawn_counts(), (0, 0));
+    }
+
+    #[test]
+    fn binary_and_json_hold_the_same_level() {
+        let saved = busy_level();
+        let json = scratch_file("binary_and_json", "level.json");
+        let binary = scratch_file("binary_and_json", "level.bin");
+        write(&saved, LevelFormat::Json, &json);
+        write(&saved, LevelFormat::Binary, &binary);
+
+        let from_json = LevelFormat::Json.read(&json).unwrap();
+        let from_binary = LevelFormat::Binary.read(&binary).unwrap();
+        let from_json = Level::from_serializable(TILESET, json, from_json, None);
+        let from_binary = Level::from_serializable(TILESET, binary, from_binary, None);
+
+        let contents = |level: &Level| serde_json::to_value(level.to_serializable()).unwrap();
+        assert_eq!(contents(&from_binary), contents(&from_json));
+        assert_eq!(contents(&from_binary), contents(&saved));
+        assert_eq!(from_binary.solid_cells, from_json.solid_cells);
+    }
+
+    #[test]
+    fn gzipped_levels_read_back_the_same() {
+        let saved = busy_level();
+        let path = scratch_file("gzipped_level", "level.json.gz");
+        write(&saved, LevelFormat::GzippedJson, &path);
+
+        assert_eq!(LevelFormat::of(&path), LevelFormat::GzippedJson);
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes[..2], [0x1f, 0x8b]);
+        let read = LevelFormat::GzippedJson.read(&path).unwrap();
+        let read = Level::from_serializable(TILESET, path, read, None);
+
+        let contents = |level: &Level| serde_json::to_value(level.to_serializable()).unwrap();
+        assert_eq!(contents(&read), contents(&saved));
+    }
+
+    /// The reason `bytes`, read as a binary level, is corrupt.
+    fn corrupt_reason(bytes: &[u8]) -> String {
+        let path = scratch_file(&format!("corrupt_binary_{}", bytes.len()), "level.bin");
+        std::fs::write(&path, bytes).unwrap();
+        return match LevelFormat::Binary.read(&path) {
+            Err(AssetManageError::Corrupt { file, reason }) => {
+                assert_eq!(file, path);
+                reason
+            }
+            Err(err) => panic!("expected a corrupt file, got {err}"),
+            Ok(_) => panic!("read {} bytes of nonsense as a level", bytes.len()),
+        };
+    }
+
+    #[test]
+    fn corrupt_binary_levels_say_what_is_wrong() {
+        let path = scratch_file("corrupt_binary", "level.bin");
+        write(&busy_level(), LevelFormat::Binary, &path);
+        let bytes = std::fs::read(&path).unwrap();
+
+        assert_eq!(corrupt_reason(b"PNG?junk"), "it isn't a binary level");
+
+        let mut version = bytes.clone();
+        version[4] = 99;
+        assert_eq!(corrupt_reason(&version), "unknown version 99");
+
+        let mut huge = bytes.clone();
+        huge[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(corrupt_reason(&huge).contains("too short for"));
+
+        // The first cell's tileset index, past the one-name table.
+        let first_cell = 4 + 1 + 8 + 2 + 2 + TILESET.len();
+        let mut tileset = bytes.clone();
+        tileset[first_cell..first_cell + 2].copy_from_slice(&7u16.to_le_bytes());
+        assert!(corrupt_reason(&tileset).contains("uses tileset 7 of only 1"));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            corrupt_reason(&trailing),
+            "it has data past the end of the level"
+        );
+
+        // Cut short anywhere, it fails rather than panicking.
+        for len in 0..bytes.len() {
+            let reason = corrupt_reason(&bytes[..len]);
+            assert!(
+                reason.contains("partway") || reason.contains("too short"),
+                "cut at {len}: {reason}"
+            );
+        }
+    }
+
+    #[test]
+    fn unversioned_levels_gain_what_was_added_since() {
+        let level: LevelSerializable =
+            deserialize_versioned("tests/fixtures/level_v0.json").unwrap();
+        assert_eq!(level.version, LevelSerializable::VERSION);
+        assert_eq!((level.rows, level.cols), (1, 2));
+        assert_eq!(
+            level.object_layer[0][1],
+            Some(TilePointer("beach".to_owned(), 3))
+        );
+        assert!(level.objects.is_empty());
+        assert!(!level.indoors);
+        assert_eq!(level.darkness, 0.0);
+        assert_eq!(level.weather, WeatherKind::Clear);
+    }
+
+    #[test]
+    fn levels_from_a_newer_game_are_refused() {
+        let path = "tests/fixtures/level_too_new.json";
+        match deserialize_versioned::<LevelSerializable, _>(path) {
+            Err(AssetManageError::TooNew {
+                file,
+                version,
+                supported,
+            }) => {
+                assert_eq!(file, path);
+                assert_eq!((version, supported), (99, LevelSerializable::VERSION));
+            }
+            Err(err) => panic!("expected the level to be too new, got {err}"),
+            Ok(_) => panic!("read a level from a newer game"),
+        }
+    }
+
+    /// Frames each way of drawing is timed over by the tile benchmark.
+    const BENCHMARK_FRAMES: u32 = 300;
+
+    /// Draws `level`'s background layer for `BENCHMARK_FRAMES` frames with
+    /// `draw`, and returns the average milliseconds a frame took, GPU work
+    /// included, and the batch meshes drawn each frame.
+    async fn time_frames(
+        level: &Level,
+        world: &World,
+        draw: impl Fn(&Level, &World),
+    ) -> (f64, usize) {
+        // Lets the first frame's uploads settle before timing.
+        draw(level, world);
+        next_frame().await;
+        take_draw_count();
+
+        let start = std::time::Instant::now();
+        for _ in 0..BENCHMARK_FRAMES {
+            clear_background(BLACK);
+            draw(level, world);
+            next_frame().await;
+        }
+        let ms = start.elapsed().as_secs_f64() * 1000.0 / BENCHMARK_FRAMES as f64;
+        return (ms, take_draw_count() / BENCHMARK_FRAMES as usize);
+    }
+
+    /// Compares drawing a full screen of tiles one `draw_texture_ex` at a
+    /// time against drawing it batched. Needs a display, so run it by hand:
+    /// `cargo test --release tile_drawing_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "opens a window"]
+    fn tile_drawing_benchmark() {
+        let config = macroquad::conf::Conf {
+            miniquad_conf: macroquad::miniquad::conf::Conf {
+                window_title: "Tile benchmark".to_owned(),
+                window_width: VIRTUAL_W as i32,
+                window_height: VIRTUAL_H as i32,
+                platform: macroquad::miniquad::conf::Platform {
+                    // Frames as fast as they draw, not at the display's rate.
+                    swap_interval: Some(0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        macroquad::Window::from_config(config, async {
+            let cols = (VIRTUAL_W / TILE_SIZE) as usize;
+            let rows = (VIRTUAL_H / TILE_SIZE) as usize;
+            let row = ".".repeat(cols);
+            let mut level = level(&vec![row.as_str(); rows]);
+            let image = Image::gen_image_color(TILE_SIZE as u16 * 3, TILE_SIZE as u16, WHITE);
+            let tileset = TilesetAsset {
+                tex: Texture2D::from_image(&image),
+                ..tileset()
+            };
+            level.tilesets.insert(TILESET.to_owned(), Rc::new(tileset));
+            let world = World::new();
+            let layer = &TileLayer::Background;
+
+            let (per_tile, per_tile_meshes) = time_frames(&level, &world, |level, world| {
+                level.visit_layer(
+                    layer,
+                    world,
+                    0.0,
+                    true,
+                    false,
+                    None,
+                    |tex, x, y, source, color| {
+                        draw_texture_ex(
+                            tex,
+                            x,
+                            y,
+                            color,
+                            DrawTextureParams {
+                                source: Some(source),
+                                ..Default::default()
+                            },
+                        );
+                    },
+                );
+            })
+            .await;
+            let (batched, batched_meshes) = time_frames(&level, &world, |level, world| {
+                level.render_layer(layer, world, 0.0, true, false, None);
+            })
+            .await;
+
+            println!("{cols}x{rows} tiles, averaged over {BENCHMARK_FRAMES} frames:");
+            println!(
+                "  per tile: {per_tile:.3}ms a frame, {per_tile_meshes} batch meshes and {} draw_texture_ex calls",
+                cols * rows
+            );
+            println!("  batched:  {batched:.3}ms a frame, {batched_meshes} batch meshes");
+        });
     }
 }
@@ -0,0 +1,68 @@
+use macroquad::{
+    color::{DARKGRAY, GRAY, RED},
+    math::vec2,
+    shapes::{draw_circle, draw_line, draw_rectangle},
+};
+
+use crate::{
+    body::Body,
+    object::{in_reach, GameObject, Interaction, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    world::World,
+    TILE_SIZE,
+};
+
+/// A switch pulled with interact. The level tracks which links its levers
+/// have flipped; the lever itself only shows which way it was last pulled.
+pub struct Lever {
+    pub body: Body,
+    on: bool,
+}
+
+impl Lever {
+    const STICK_LENGTH: f32 = 7.0;
+
+    pub fn new(x: f32, y: f32, on: bool) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE * 0.75, TILE_SIZE * 0.5, None),
+            on,
+        }
+    }
+}
+
+impl GameObject for Lever {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Lever
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn can_interact(&self, player: &Player) -> bool {
+        return in_reach(player, &self.body.hitbox);
+    }
+
+    fn interact(&mut self, _player: &mut Player) -> Interaction {
+        self.on = !self.on;
+        return Interaction::Toggled;
+    }
+
+    fn render(&self, world: &World) {
+        let x = self.body.hitbox.center().x - world.x;
+        let y = self.body.hitbox.bottom() - world.y;
+
+        let lean = match self.on {
+            true => 1.0,
+            false => -1.0,
+        };
+        let tip = vec2(x, y - 3.0) + vec2(lean * 0.6, -0.8).normalize() * Self::STICK_LENGTH;
+        draw_line(x, y - 3.0, tip.x, tip.y, 1.5, GRAY);
+        draw_circle(tip.x, tip.y, 1.5, RED);
+        draw_rectangle(x - 5.0, y - 4.0, 10.0, 4.0, DARKGRAY);
+    }
+}
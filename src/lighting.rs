@@ -0,0 +1,111 @@
+use macroquad::{
+    camera::{pop_camera_state, push_camera_state, set_camera, Camera2D},
+    color::{Color, WHITE},
+    math::vec2,
+    texture::{
+        draw_texture_ex, render_target, DrawTextureParams, FilterMode, Image, RenderTarget,
+        Texture2D,
+    },
+    window::clear_background,
+};
+
+use crate::{
+    lights::Light,
+    shaders::{draw_additive, draw_multiplied},
+    world::World,
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+/// Darkens the world to an ambient color and lets lights back through it.
+/// Lights are drawn as soft radial glows into a small light map, which is
+/// then multiplied over the world.
+pub struct Lighting {
+    light_map: RenderTarget,
+    glow: Texture2D,
+}
+
+impl Lighting {
+    /// Light map size as a fraction of the virtual screen. Lower is cheaper
+    /// and blurrier.
+    const RESOLUTION: f32 = 0.5;
+    const GLOW_SIZE: u16 = 64;
+
+    pub fn new() -> Self {
+        let light_map = render_target(
+            (VIRTUAL_W * Self::RESOLUTION) as u32,
+            (VIRTUAL_H * Self::RESOLUTION) as u32,
+        );
+        light_map.texture.set_filter(FilterMode::Linear);
+
+        Self {
+            light_map,
+            glow: Self::glow_texture(),
+        }
+    }
+
+    /// White fading out from the middle, with an eased falloff so the edge
+    /// of a light doesn't show.
+    fn glow_texture() -> Texture2D {
+        let size = Self::GLOW_SIZE;
+        let mut image = Image::gen_image_color(size, size, Color::new(1.0, 1.0, 1.0, 0.0));
+        let half = size as f32 / 2.0;
+        for y in 0..size as u32 {
+            for x in 0..size as u32 {
+                let offset = vec2(x as f32 + 0.5 - half, y as f32 + 0.5 - half);
+                let falloff = (1.0 - offset.length() / half).max(0.0);
+                image.set_pixel(x, y, Color::new(1.0, 1.0, 1.0, falloff * falloff));
+            }
+        }
+
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Linear);
+        return texture;
+    }
+
+    /// Multiplies the light map over everything drawn so far. `ambient` is
+    /// the light everywhere no light reaches; pure white skips the pass.
+    pub fn render(&self, lights: &[Light], ambient: Color, world: &World) {
+        if ambient == WHITE {
+            return;
+        }
+
+        push_camera_state();
+        set_camera(&Camera2D {
+            zoom: vec2(2.0 / VIRTUAL_W, 2.0 / VIRTUAL_H),
+            target: vec2(VIRTUAL_W / 2.0, VIRTUAL_H / 2.0),
+            render_target: Some(self.light_map.clone()),
+            ..Default::default()
+        });
+        clear_background(ambient);
+
+        draw_additive(|| {
+            for light in lights {
+                let size = light.radius * 2.0;
+                draw_texture_ex(
+                    &self.glow,
+                    light.position.x - world.x - light.radius,
+                    light.position.y - world.y - light.radius,
+                    light.color,
+                    DrawTextureParams {
+                        dest_size: Some(vec2(size, size)),
+                        ..Default::default()
+                    },
+                );
+            }
+        });
+        pop_camera_state();
+
+        draw_multiplied(|| {
+            draw_texture_ex(
+                &self.light_map.texture,
+                0.0,
+                0.0,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(VIRTUAL_W, VIRTUAL_H)),
+                    ..Default::default()
+                },
+            );
+        });
+    }
+}
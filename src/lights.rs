@@ -0,0 +1,104 @@
+use macroquad::{
+    color::Color,
+    math::{Rect, Vec2},
+    shapes::draw_circle_lines,
+    time::get_time,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::world::World;
+
+/// Makes an object give off light, centered on its body.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LightEmitter {
+    pub radius: f32,
+    pub color: [u8; 3],
+    /// Waver in size and brightness, like a flame.
+    #[serde(default)]
+    pub flicker: bool,
+}
+
+impl LightEmitter {
+    /// How much of the radius and brightness a flicker can take away.
+    const FLICKER_DEPTH: f32 = 0.15;
+    /// Noise steps per second; higher flickers faster.
+    const FLICKER_RATE: f32 = 6.0;
+
+    pub fn color(&self) -> Color {
+        let [r, g, b] = self.color;
+        return Color::from_rgba(r, g, b, 255);
+    }
+
+    /// The light this gives off at `position` right now. Flickering lights
+    /// seeded from their position, so neighbours don't waver in step.
+    pub fn light_at(&self, position: Vec2) -> Light {
+        let strength = match self.flicker {
+            true => {
+                let seed = (position.x as i32 as u32).wrapping_mul(31) ^ position.y as i32 as u32;
+                let t = get_time() as f32 * Self::FLICKER_RATE;
+                1.0 - smooth_noise(t, seed) * Self::FLICKER_DEPTH
+            }
+            false => 1.0,
+        };
+
+        return Light {
+            position,
+            radius: self.radius * strength,
+            color: Color {
+                a: strength,
+                ..self.color()
+            },
+        };
+    }
+}
+
+/// One light for this frame. The color's alpha is its brightness.
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub position: Vec2,
+    pub radius: f32,
+    pub color: Color,
+}
+
+impl Light {
+    /// Whether any of the light reaches `view`.
+    pub fn reaches(&self, view: &Rect) -> bool {
+        let padded = Rect::new(
+            view.x - self.radius,
+            view.y - self.radius,
+            view.w + self.radius * 2.0,
+            view.h + self.radius * 2.0,
+        );
+        return padded.contains(self.position);
+    }
+
+    /// Outline of the lit area, for the debug overlay.
+    pub fn render_debug(&self, world: &World) {
+        draw_circle_lines(
+            self.position.x - world.x,
+            self.position.y - world.y,
+            self.radius,
+            1.0,
+            self.color,
+        );
+    }
+}
+
+/// Pseudo-random value in 0..1 for step `n`.
+fn hash(n: i32, seed: u32) -> f32 {
+    let mut x = (n as u32).wrapping_mul(0x9E37_79B1) ^ seed.wrapping_mul(0x85EB_CA77);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2C1B_3C6D);
+    x ^= x >> 12;
+    return (x & 0xFFFF) as f32 / 65535.0;
+}
+
+/// Value noise in 0..1 that eases between a random value at each whole `t`.
+pub fn smooth_noise(t: f32, seed: u32) -> f32 {
+    let step = t.floor();
+    let fraction = t - step;
+    let eased = fraction * fraction * (3.0 - 2.0 * fraction);
+    let from = hash(step as i32, seed);
+    let to = hash(step as i32 + 1, seed);
+    return from + (to - from) * eased;
+}
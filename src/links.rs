@@ -0,0 +1,52 @@
+use std::collections::{HashMap, HashSet};
+
+/// Shared on/off state for linked objects, keyed by link id. Plates and
+/// levers write to it; gates read from it.
+pub struct LinkBus {
+    /// Seconds each id held by a plate has left before it lets go.
+    held: HashMap<String, f32>,
+    /// Ids switched on by levers.
+    switched: HashSet<String>,
+}
+
+impl LinkBus {
+    /// How long a link stays active after its plate is let go.
+    const HOLD_SECONDS: f32 = 0.3;
+
+    pub fn new() -> Self {
+        Self {
+            held: HashMap::new(),
+            switched: HashSet::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.held.clear();
+        self.switched.clear();
+    }
+
+    /// Counts down the holds left by plates that are no longer pressed.
+    pub fn tick(&mut self, dt: f32) {
+        self.held.retain(|_, time| {
+            *time -= dt;
+            return *time > 0.0;
+        });
+    }
+
+    /// Keeps `link_id` active for another `HOLD_SECONDS`.
+    pub fn hold(&mut self, link_id: &str) {
+        self.held.insert(link_id.to_owned(), Self::HOLD_SECONDS);
+    }
+
+    pub fn set_switched(&mut self, switched: HashSet<String>) {
+        self.switched = switched;
+    }
+
+    pub fn is_active(&self, link_id: &str) -> bool {
+        self.held.contains_key(link_id) || self.switched.contains(link_id)
+    }
+
+    pub fn active(&self) -> HashSet<String> {
+        return self.held.keys().chain(&self.switched).cloned().collect();
+    }
+}
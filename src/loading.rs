@@ -0,0 +1,138 @@
+use macroquad::{
+    camera::set_default_camera,
+    color::{Color, BLACK, GRAY, RED, WHITE},
+    miniquad::date,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    texture::{render_target, FilterMode, RenderTarget},
+    window::{clear_background, next_frame},
+};
+
+use crate::{
+    asset_loading::AssetManageError,
+    dialogue::Dialogue,
+    frame_targets::{draw_virtual_target, set_virtual_camera},
+    input::Input,
+    pixel_font::{draw_pixel_text, measure_pixel_text, pixel_line_height},
+    viewport::Viewport,
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+/// Shown while the game starts, so the window says how far loading has got
+/// instead of sitting frozen. Loads are split into units, each reported as
+/// it finishes. A load that fails is shown here until the player quits.
+pub struct LoadingScreen {
+    loaded: usize,
+    total: usize,
+    /// The unit that finished last.
+    label: String,
+    tip: &'static str,
+    viewport: Viewport,
+    target: RenderTarget,
+}
+
+impl LoadingScreen {
+    const TIPS: [&str; 5] = [
+        "Hold Shift to sprint, but watch your stamina.",
+        "Press K to dash through danger.",
+        "Press M to see how much of the island you've explored.",
+        "Touch a checkpoint to come back there after a fall.",
+        "Press Escape to change how the game looks.",
+    ];
+    const BAR_W: f32 = 200.0;
+    const BAR_H: f32 = 8.0;
+    const ERROR_W: f32 = 300.0;
+
+    pub fn new() -> Self {
+        let target = render_target(VIRTUAL_W as u32, VIRTUAL_H as u32);
+        target.texture.set_filter(FilterMode::Nearest);
+
+        Self {
+            loaded: 0,
+            total: 0,
+            label: String::new(),
+            tip: Self::TIPS[date::now() as usize % Self::TIPS.len()],
+            viewport: Viewport::new(),
+            target,
+        }
+    }
+
+    /// Adds `units` more to what has to load.
+    pub fn expect(&mut self, units: usize) {
+        self.total += units;
+    }
+
+    /// Counts one unit, named `label`, as loaded and shows the progress for
+    /// a frame.
+    pub async fn loaded(&mut self, label: &str) {
+        self.loaded += 1;
+        self.label.clear();
+        self.label.push_str(label);
+        self.show().await;
+    }
+
+    /// Shows the progress so far for a frame.
+    pub async fn show(&mut self) {
+        self.present(|screen| screen.render()).await;
+    }
+
+    /// Shows why loading failed until the player confirms, to quit.
+    pub async fn fail(&mut self, err: &AssetManageError) {
+        let lines = Dialogue::wrap(&format!("{err}"), Self::ERROR_W);
+        loop {
+            if Input::get(&self.viewport).confirm {
+                return;
+            }
+
+            self.present(|_| {
+                draw_centered("The game couldn't load", VIRTUAL_H / 2.0 - 32.0, RED);
+                for (idx, line) in lines.iter().enumerate() {
+                    let y = VIRTUAL_H / 2.0 - 12.0 + idx as f32 * pixel_line_height();
+                    draw_centered(line, y, WHITE);
+                }
+                draw_centered("Press Enter to quit", VIRTUAL_H - 24.0, GRAY);
+            })
+            .await;
+        }
+    }
+
+    fn render(&self) {
+        let x = (VIRTUAL_W - Self::BAR_W) / 2.0;
+        let y = VIRTUAL_H / 2.0;
+        let filled = match self.total {
+            0 => 0.0,
+            total => self.loaded.min(total) as f32 / total as f32,
+        };
+
+        draw_centered("Loading", y - 16.0, WHITE);
+        draw_rectangle(x, y, Self::BAR_W * filled, Self::BAR_H, WHITE);
+        draw_rectangle_lines(x, y, Self::BAR_W, Self::BAR_H, 1.0, WHITE);
+
+        let progress = match self.label.is_empty() {
+            true => format!("{} of {}", self.loaded, self.total),
+            false => format!("{} of {}: {}", self.loaded, self.total, self.label),
+        };
+        draw_centered(&progress, y + Self::BAR_H + 14.0, GRAY);
+        draw_centered(
+            self.tip,
+            VIRTUAL_H - 24.0,
+            Color::from_rgba(200, 200, 150, 255),
+        );
+    }
+
+    /// Draws one frame of the virtual screen with `draw` and shows it.
+    async fn present(&mut self, draw: impl FnOnce(&Self)) {
+        self.viewport.update();
+        set_virtual_camera(&self.target);
+        clear_background(BLACK);
+        draw(self);
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_virtual_target(&self.target, self.viewport.dest());
+        next_frame().await;
+    }
+}
+
+fn draw_centered(text: &str, y: f32, color: Color) {
+    draw_pixel_text(text, (VIRTUAL_W - measure_pixel_text(text)) / 2.0, y, color);
+}
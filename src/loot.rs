@@ -0,0 +1,83 @@
+use macroquad::{math::Vec2, rand::RandGenerator};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::Body,
+    items::{ItemId, ItemPickup, ItemSheet},
+    levels::Level,
+    object::GameObject,
+    pickups::{Pickup, PickupKind},
+};
+
+/// Something that can be dropped: an inventory item, or a coin or heart.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(untagged)]
+pub enum Loot {
+    Item(ItemId),
+    Pickup(PickupKind),
+}
+
+impl Loot {
+    /// The object that lies in the level until the player picks it up.
+    pub fn spawn(self, x: f32, y: f32, item_sheet: &ItemSheet) -> Box<dyn GameObject> {
+        match self {
+            Loot::Item(item) => Box::new(ItemPickup::dropped(item, x, y, item_sheet)),
+            Loot::Pickup(kind) => Box::new(Pickup::new(kind, x, y)),
+        }
+    }
+}
+
+/// One entry in a drop table: with probability `chance`, drop between `min`
+/// and `max` (inclusive) of `item`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LootDrop {
+    pub item: Loot,
+    pub chance: f32,
+    pub min: u8,
+    pub max: u8,
+}
+
+/// How far from where it was rolled loot may scatter.
+const DROP_SCATTER: f32 = 10.0;
+const DROP_ATTEMPTS: u32 = 8;
+
+/// Rolls `drops`, returning each drop and where. Drops scatter around
+/// `center` but never land inside a wall.
+pub fn roll(
+    drops: &[LootDrop],
+    rng: &RandGenerator,
+    level: &Level,
+    center: Vec2,
+) -> Vec<(Loot, Vec2)> {
+    let mut rolled = Vec::new();
+
+    for drop in drops {
+        if rng.gen_range(0.0, 1.0) >= drop.chance {
+            continue;
+        }
+
+        let count = rng.gen_range(drop.min as u32, drop.max.max(drop.min) as u32 + 1);
+        for _ in 0..count {
+            rolled.push((drop.item, scatter(rng, level, center)));
+        }
+    }
+
+    return rolled;
+}
+
+/// A random free spot within `DROP_SCATTER` of `center`, or `center` itself
+/// if none turns up.
+fn scatter(rng: &RandGenerator, level: &Level, center: Vec2) -> Vec2 {
+    for _ in 0..DROP_ATTEMPTS {
+        let angle = rng.gen_range(0.0, std::f32::consts::TAU);
+        let distance = rng.gen_range(0.0, DROP_SCATTER);
+        let spot = center + Vec2::from_angle(angle) * distance;
+
+        let probe = Body::new(spot.x, spot.y, ItemPickup::SIZE, ItemPickup::SIZE, None);
+        if !probe.overlaps_solid(level) {
+            return spot;
+        }
+    }
+
+    return center;
+}
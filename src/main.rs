@@ -1,176 +1,1071 @@
+// Functions end in an explicit `return` throughout, as a matter of style.
+#![allow(clippy::needless_return)]
+
 mod animator;
+mod aseprite;
 mod asset_loading;
+mod asset_root;
+mod assets;
+mod atlas;
+mod audio;
 mod body;
+mod breakable;
+mod camera;
+mod camera_shake;
+mod checkpoint;
+mod chest;
+mod data_dir;
+mod day_clock;
+mod debug_overlay;
+mod dialogue;
+mod door;
 mod enemies;
+mod error_screen;
+mod events;
+mod flash;
+mod floating_text;
+mod footsteps;
+mod frame_limiter;
+mod frame_targets;
+mod game_over;
+mod gate;
+mod hot_reload;
+mod hud;
 mod input;
+mod inventory;
+mod items;
+mod lamp;
+mod layer_cache;
+mod ldtk;
+mod level_chunks;
+mod level_file;
 mod levels;
+mod lever;
+mod lighting;
+mod lights;
+mod links;
+mod loading;
+mod loot;
+mod manifest;
+mod map;
+mod npc;
 mod object;
+mod overlay_fade;
+mod particles;
+mod pickups;
+mod pixel_font;
+mod plate;
 mod player;
+mod player_state;
+mod popup;
+mod portal;
+mod projectiles;
+mod save;
+mod settings;
+mod shaders;
+mod sign;
+mod spatial_grid;
+mod spawn_region;
+mod spawner;
+mod spikes;
 mod sprites;
+mod stamina;
+mod tile_batch;
+mod tiled;
 mod tilesets;
+mod transition;
 mod utils;
+mod validate;
+mod viewport;
+mod weather;
 mod world;
+mod world_state;
 
+use asset_loading::{asset_problems, AssetManageResult};
+use asset_root::AssetRoot;
+use assets::Assets;
+use audio::{AudioAssets, Sfx};
+use camera::CameraFollow;
+use camera_shake::CameraShake;
+use checkpoint::{RespawnPoint, Respawner};
+use day_clock::DayClock;
+use debug_overlay::DebugOverlay;
+use dialogue::Dialogue;
+use error_screen::{ErrorChoice, ErrorScreen, Retry};
+use events::{EventBus, EventLog, GameEvent};
+use flash::ScreenFlash;
+use floating_text::FloatingTexts;
+use footsteps::Footsteps;
+use frame_limiter::FrameLimiter;
+use frame_targets::{draw_virtual_target, set_virtual_camera, FrameTargets};
+use game_over::{GameOverChoice, GameOverScreen};
+use hot_reload::HotReload;
+use hud::Hud;
 use input::Input;
 use levels::LevelEditorSettings;
+use lighting::Lighting;
+use loading::LoadingScreen;
 use macroquad::{
-    camera::{set_camera, set_default_camera, Camera2D},
-    color::{BLACK, WHITE},
-    math::{vec2, Rect},
+    camera::set_default_camera,
+    color::BLACK,
+    input::{is_quit_requested, prevent_quit},
+    logging::{error, info, warn},
+    math::{vec2, Rect, Vec2},
     miniquad::conf::Platform,
-    texture::{draw_texture_ex, render_target, DrawTextureParams, RenderTarget},
-    time::get_frame_time,
-    window::{clear_background, next_frame, screen_height, screen_width, Conf},
+    time::{get_frame_time, get_time},
+    window::{clear_background, next_frame, Conf},
 };
+use manifest::AssetManifest;
+use map::{LevelMap, MapScreen};
+use overlay_fade::OverlayFade;
+use particles::{spark_burst, ParticleLayer, ParticleSystem};
+use pixel_font::load_pixel_font;
 use player::Player;
+use portal::WarpTarget;
+use save::{SaveData, SaveMenu};
+use settings::{CameraSmoothing, PresentMode, Settings, SettingsMenu};
+use shaders::draw_post_processed;
+use tile_batch::take_draw_count;
+use transition::{Transition, TransitionStyle};
+use viewport::Viewport;
+use weather::Weather;
 use world::World;
+use world_state::WorldState;
 
-use crate::{levels::Level, object::LevelObjects};
+use crate::{
+    levels::Level,
+    object::{Interaction, LevelObjects, ObjectAssets},
+};
 
-const TILE_SIZE: f32 = 16 as f32;
-const TILE_COLLISION_SECTIONS: f32 = 3 as f32;
-const VIRTUAL_W: f32 = TILE_SIZE * 24 as f32;
-const VIRTUAL_H: f32 = TILE_SIZE * 16 as f32;
-const SUB_PIX_LEVEL: f32 = 3 as f32;
+const TILE_SIZE: f32 = 16.0;
+const TILE_COLLISION_SECTIONS: f32 = 3.0;
+const VIRTUAL_W: f32 = TILE_SIZE * 24.0;
+const VIRTUAL_H: f32 = TILE_SIZE * 16.0;
 
 fn window_config() -> Conf {
     let window_scale = 3;
+    // Vsync can only be set as the window is made.
+    let vsync = Settings::load().present_mode == PresentMode::Vsync;
 
     Conf {
         window_title: "Island Past Infinity".to_owned(),
         window_width: VIRTUAL_W as i32 * window_scale,
         window_height: VIRTUAL_H as i32 * window_scale,
-        window_resizable: false,
+        window_resizable: true,
         fullscreen: false,
         platform: Platform {
-            swap_interval: Some(0),
+            swap_interval: match vsync {
+                true => Some(1),
+                false => Some(0),
+            },
             ..Default::default()
         },
         ..Default::default()
     }
 }
 
-fn get_render_target(vw: u32, vh: u32) -> RenderTarget {
-    return render_target(vw, vh);
+enum GameState {
+    Playing,
+    /// Covering the screen on the way to another level. Nothing in the old
+    /// level updates meanwhile.
+    Warping(WarpTarget),
+    /// Asking whether to save after the player used a save point.
+    Saving(SaveMenu),
+    /// Looking at the map. Nothing updates meanwhile.
+    Map(MapScreen),
+    GameOver(GameOverScreen),
+    /// Changing the settings. Nothing updates meanwhile.
+    Settings(SettingsMenu),
+    /// Showing what failed, until the player retries or quits. Nothing
+    /// updates meanwhile.
+    Error(ErrorScreen),
+}
+
+/// Everything that lives across frames of the main loop.
+struct Game {
+    state: GameState,
+    editor: LevelEditorSettings,
+    world: World,
+    player: Player,
+    level: Level,
+    level_objects: LevelObjects,
+    footsteps: Footsteps,
+    respawner: Respawner,
+    /// The conversation on screen, if any. Play is paused while it's open.
+    dialogue: Option<Dialogue>,
+    /// Object states of the levels left behind this session.
+    world_state: WorldState,
+    /// Tilesets and the like shared between levels.
+    assets: Assets,
+    /// Raised during the frame's logic and drained at the end of it.
+    events: EventBus,
+    event_log: EventLog,
+    camera: CameraFollow,
+    camera_shake: CameraShake,
+    screen_flash: ScreenFlash,
+    transition: Transition,
+    /// Seconds of play so far, driving animated tiles. It only runs during
+    /// play, so tiles hold still behind menus.
+    tile_time: f32,
+    day_clock: DayClock,
+    lighting: Lighting,
+    particles: ParticleSystem,
+    floating_texts: FloatingTexts,
+    weather: Weather,
+    hud: Hud,
+    audio: AudioAssets,
+    map: LevelMap,
+    overlay_fade: OverlayFade,
+    viewport: Viewport,
+    debug_overlay: DebugOverlay,
+    hot_reload: HotReload,
+    settings: Settings,
+    frame_limiter: FrameLimiter,
 }
 
-fn run_logic(
-    editor: &mut LevelEditorSettings,
-    world: &mut World,
-    player: &mut Player,
-    level: &mut Level,
-    level_objects: &mut LevelObjects,
-) -> (World, Input, f32) {
+async fn run_logic(game: &mut Game) -> (World, Input, f32) {
+    let Game {
+        state,
+        editor,
+        world,
+        player,
+        level,
+        level_objects,
+        footsteps,
+        dialogue,
+        events,
+        event_log,
+        camera,
+        camera_shake,
+        screen_flash,
+        transition,
+        tile_time,
+        day_clock,
+        particles,
+        weather,
+        hud,
+        audio,
+        viewport,
+        map,
+        overlay_fade,
+        frame_limiter,
+        floating_texts,
+        settings,
+        assets,
+        hot_reload,
+        ..
+    } = game;
+
     let dt = get_frame_time();
-    let input = Input::get();
+    *tile_time += dt;
+    hot_reload.update(level, assets, player, dt).await;
+    level.stream_chunks(world);
+    day_clock.update(dt);
+    let input = Input::get(viewport);
 
     if input.toggle_editor {
         editor.toggle();
     }
 
-    if !editor.open || input.mouse_x > -0.33 {
+    if input.toggle_inventory {
+        player.inventory.toggle();
+    }
+
+    let talking = dialogue.is_some();
+    if input.toggle_map && !editor.open && !talking {
+        *state = GameState::Map(map.open(level, player));
+    }
+
+    if input.toggle_settings && !editor.open && !talking {
+        *state = GameState::Settings(SettingsMenu::new(frame_limiter.has_vsync()));
+    }
+
+    let editor_has_mouse = editor.has_mouse(&input);
+
+    if !player.inventory.open && !editor_has_mouse && !talking {
+        let swing = player.attack().map(|attack| attack.swing);
         player.move_player(level, &input, dt);
+        if player
+            .attack()
+            .is_some_and(|attack| Some(attack.swing) != swing)
+        {
+            events.push(GameEvent::PlayerAttacked);
+        }
     }
 
-    world.x += (player.body.hitbox.center().x - VIRTUAL_W / 2.0 - world.x) * 2.0 * dt;
-    world.y += (player.body.hitbox.center().y - VIRTUAL_H / 2.0 - world.y) * 2.0 * dt;
+    if !editor_has_mouse && !talking {
+        level_objects.update(level, player, world, events, dt).await;
+    }
 
-    level.spawn_objects(&world, level_objects);
+    if player.body.take_step() {
+        let feet = vec2(player.body.hitbox.center().x, player.body.hitbox.bottom());
+        if let Some(surface) = level.surface_at(feet.x, feet.y) {
+            footsteps.spawn(feet.x, feet.y, surface);
+        }
+    }
+    footsteps.update(dt);
 
-    return (world.rounded(), input, dt);
+    if input.test_particles {
+        particles.burst(
+            &spark_burst(ParticleLayer::Above),
+            player.body.hitbox.center(),
+        );
+    }
+
+    if let Some(active) = dialogue {
+        if !active.update(&input, dt) {
+            *dialogue = None;
+        }
+    } else if input.interact && !player.inventory.open {
+        match level_objects.interact(player) {
+            Some((Interaction::Opened, Some(listing_id))) => level.mark_opened(listing_id),
+            Some((Interaction::Looted, listing_id)) => {
+                if let Some(listing_id) = listing_id {
+                    level.mark_opened(listing_id);
+                }
+                events.push(GameEvent::ChestOpened);
+            }
+            Some((Interaction::Toggled, Some(listing_id))) => level.flip_lever(listing_id),
+            Some((Interaction::Talk(pages), _)) => *dialogue = Dialogue::talk(pages).await,
+            Some((Interaction::Save, _)) => *state = GameState::Saving(SaveMenu::new()),
+            _ => {}
+        }
+    }
+
+    level_objects.collect_items(player);
+    level_objects.touch_checkpoints(player, level.name());
+
+    if let Some(target) = level_objects.touch_portals(player) {
+        transition.start_out(TransitionStyle::Wipe, Transition::LEVEL_OUT_SECONDS);
+        *state = GameState::Warping(target);
+    }
+
+    let center = player.body.hitbox.center();
+    let target = camera.target(level.camera_zone(center), center, world, dt);
+    world.x += (target.x - world.x) * 2.0 * dt;
+    world.y += (target.y - world.y) * 2.0 * dt;
+    level.explore(&world.view(0.0, 0.0));
+    overlay_fade.update(level, &player.body, dt);
+
+    level.clear_listings(&level_objects.take_cleared());
+    for listing_id in level_objects.take_opened() {
+        level.mark_opened(listing_id);
+    }
+    let toggled = level.set_open_links(level_objects.open_links());
+    if toggled.iter().any(|(_, open)| !open) {
+        level.free_body(&mut player.body);
+        level_objects.free_bodies(level);
+    }
+    for (link_id, active) in toggled {
+        events.push(GameEvent::LinkToggled { link_id, active });
+    }
+    level.release_spawns(&level_objects.despawn_distant(world));
+    level.spawn_objects(world, level_objects).await;
+
+    for event in events.drain() {
+        match event {
+            GameEvent::EnemyDied { position, .. } => {
+                particles.burst(&spark_burst(ParticleLayer::World), position);
+            }
+            GameEvent::WeatherChanged(kind) => weather.set(kind),
+            _ => {}
+        }
+        camera_shake.react(&event);
+        screen_flash.react(&event);
+        floating_texts.react(&event, settings.damage_numbers);
+        hud.react(&event);
+        audio.react(&event);
+        event_log.record(&event);
+    }
+    camera_shake.update(dt);
+    screen_flash.update(dt);
+    hud.update(player, dt);
+    weather.update(particles, world, dt);
+    particles.update(dt);
+    floating_texts.update(dt);
+
+    return (world.rounded(settings.sub_pixels()), input, dt);
 }
 
-async fn render(
-    editor: &mut LevelEditorSettings,
-    world: &World,
-    player: &mut Player,
-    level_objects: &mut LevelObjects,
-    level: &mut Level,
-    input: &Input,
-    dt: f32,
-) {
+async fn respawn(game: &mut Game) -> AssetManageResult<()> {
+    let Game {
+        world,
+        player,
+        level,
+        level_objects,
+        world_state,
+        assets,
+        camera,
+        overlay_fade,
+        events,
+        editor,
+        ..
+    } = game;
+
+    let point = match &player.checkpoint {
+        Some(point) => point.clone(),
+        None => RespawnPoint {
+            level: level.name().to_owned(),
+            position: level.spawn_point(),
+        },
+    };
+
+    if point.level != level.name() {
+        level.offer_save(editor).await;
+        world_state.store(level);
+        assets.keep_tilesets(level);
+        *level = Level::load(&point.level, assets)
+            .await
+            .map_err(|err| err.context(format!("Loading checkpoint level {}", point.level)))?;
+        assets.evict_unused(level);
+        world_state.restore(level);
+    }
+    level.reset_spawns();
+
+    level_objects.reset();
+    player.respawn(point.position);
+    world.center_on(point.position);
+    camera.reset();
+    overlay_fade.reset();
+    events.push(GameEvent::WeatherChanged(level.weather()));
+
+    return Ok(());
+}
+
+/// Respawns the player, or shows why it failed.
+async fn try_respawn(game: &mut Game) {
+    match respawn(game).await {
+        Ok(()) => {
+            game.respawner.fade_in();
+            game.state = GameState::Playing;
+        }
+        Err(err) => {
+            error!("Could not respawn: {}", err);
+            game.state = GameState::Error(ErrorScreen::new(&err, Retry::Respawn));
+        }
+    }
+}
+
+/// Swaps in the target level and puts the player on the target anchor, or
+/// on the level's spawn point if the anchor is missing. A level that fails
+/// to load leaves the player where they were.
+async fn warp_to(game: &mut Game, target: &WarpTarget) -> AssetManageResult<()> {
+    let Game {
+        world,
+        player,
+        level,
+        level_objects,
+        world_state,
+        assets,
+        camera,
+        overlay_fade,
+        events,
+        editor,
+        ..
+    } = game;
+
+    level.offer_save(editor).await;
+    assets.keep_tilesets(level);
+    match Level::load(&target.level, assets).await {
+        Ok(loaded) => {
+            world_state.store(level);
+            *level = loaded;
+            world_state.restore(level);
+            assets.trim(level);
+        }
+        Err(err) => {
+            return Err(err.context(format!("Loading level {}", target.level)));
+        }
+    }
+
+    let position = level.anchor(&target.anchor).unwrap_or_else(|| {
+        warn!(
+            "Level {} has no anchor {}, using its spawn point",
+            target.level, target.anchor
+        );
+        level.spawn_point()
+    });
+
+    level_objects.reset();
+    player.body.set_center(position);
+    world.center_on(position);
+    camera.reset();
+    overlay_fade.reset();
+    events.push(GameEvent::WeatherChanged(level.weather()));
+    return Ok(());
+}
+
+/// Heals the player and writes the save file, saying how it went.
+fn save_game(game: &mut Game) {
+    game.player.hp = game.player.max_hp;
+    let message = match SaveData::capture(&game.player, &game.level, &game.world_state).write() {
+        Ok(()) => "Game saved.".to_owned(),
+        Err(err) => format!("The game couldn't be saved: {err}"),
+    };
+    game.dialogue = Dialogue::message(message);
+}
+
+/// Puts the game back the way it was saved. A missing or broken save, or a
+/// saved level that no longer loads, is reported in the dialogue box and
+/// leaves the game as it is.
+async fn load_game(game: &mut Game) {
+    let Game {
+        world,
+        player,
+        level,
+        level_objects,
+        dialogue,
+        world_state,
+        assets,
+        camera,
+        overlay_fade,
+        transition,
+        events,
+        editor,
+        ..
+    } = game;
+
+    let data = match SaveData::read() {
+        Ok(data) => data,
+        Err(err) => {
+            *dialogue = Dialogue::message(SaveData::describe_error(&err));
+            return;
+        }
+    };
+
+    level.offer_save(editor).await;
+    assets.keep_tilesets(level);
+    match Level::load(data.level(), assets).await {
+        Ok(loaded) => {
+            *level = loaded;
+            assets.evict_unused(level);
+        }
+        Err(err) => {
+            warn!("Could not load saved level {}: {}", data.level(), err);
+            *dialogue = Dialogue::message("The saved level couldn't be loaded.");
+            return;
+        }
+    }
+
+    data.restore(player, level, world_state);
+    level_objects.reset();
+    world.center_on(player.body.hitbox.center());
+    camera.reset();
+    overlay_fade.reset();
+    events.push(GameEvent::WeatherChanged(level.weather()));
+    transition.start_in(Transition::LEVEL_IN_SECONDS);
+    *dialogue = None;
+}
+
+/// The view to draw from this frame. The editor works in the steady view,
+/// so nothing it draws or picks under the mouse shakes.
+fn drawn_view(game: &Game, world: &World) -> World {
+    return match game.editor.open {
+        true => world.clone(),
+        false => game.camera_shake.apply(world, game.settings.sub_pixels()),
+    };
+}
+
+/// Draws the level and everything in it, the part of the frame the
+/// post-process applies to.
+fn render_world(game: &mut Game, world: &World) {
+    let world = &drawn_view(game, world);
+    let Game {
+        player,
+        level,
+        level_objects,
+        footsteps,
+        tile_time,
+        day_clock,
+        lighting,
+        particles,
+        floating_texts,
+        weather,
+        overlay_fade,
+        editor,
+        dialogue,
+        ..
+    } = game;
+
     if editor.show_background {
-        level.render_background(&world);
+        level.render_background(world, *tile_time);
     }
 
     if editor.show_object {
-        level.render_object_layer(&world);
+        level.render_object_layer(world, *tile_time);
     }
 
-    level_objects.render(&mut [&player.body], &world);
+    footsteps.render(world);
+    let tiles = match editor.show_object {
+        true => level.y_sorted_tiles(world, *tile_time),
+        false => Vec::new(),
+    };
+    // Shows what the interact key would use, while it can be used.
+    let targeted = match editor.open || dialogue.is_some() || player.inventory.open {
+        true => None,
+        false => level_objects.targeted(player),
+    };
+    level_objects.render(&[&player.body], &tiles, particles, targeted, world);
 
     if editor.show_overlay {
-        level.render_overlay(&world);
+        level.render_overlay(world, *tile_time, overlay_fade.alphas());
+    }
+
+    let ambient = level.ambient(day_clock.tint());
+    lighting.render(&level_objects.lights(player, world), ambient, world);
+    weather.render();
+
+    level_objects.render_above(world);
+    floating_texts.render(world);
+    particles.render_above(world);
+}
+
+/// Draws the HUD, menus and editor over the post-processed world so they
+/// stay crisp. Fails if the editor does, once the rest is drawn.
+async fn render_ui(
+    game: &mut Game,
+    world: &World,
+    input: &Input,
+    dt: f32,
+) -> AssetManageResult<()> {
+    let world = &drawn_view(game, world);
+    let Game {
+        state,
+        respawner,
+        editor,
+        player,
+        level,
+        level_objects,
+        dialogue,
+        event_log,
+        camera,
+        screen_flash,
+        transition,
+        day_clock,
+        particles,
+        hud,
+        settings,
+        hot_reload,
+        ..
+    } = game;
+
+    screen_flash.render();
+
+    if editor.show_hitboxes {
+        level_objects.render_debug(player, world);
+        camera.render_debug(world);
+        day_clock.render_debug();
+        particles.render_debug();
+        event_log.render();
+    }
+
+    if editor.open || editor.show_hitboxes {
+        level_objects.render_markers(world);
+    }
+
+    if !editor.open && dialogue.is_none() {
+        hud.render(player);
     }
 
-    if editor.open {
-        level
-            .level_editor(editor, &input, dt, &world)
+    if let Some(dialogue) = dialogue {
+        dialogue.render();
+    }
+
+    if player.inventory.open {
+        player.inventory.render_screen(level_objects.item_sheet());
+    }
+
+    // The editor's widgets draw over everything, so they sit out any
+    // transition rather than show through it.
+    let mut edited = Ok(());
+    if editor.open && !transition.is_active() && !matches!(state, GameState::Error(_)) {
+        edited = level
+            .level_editor(editor, input, dt, world)
             .await
-            .unwrap();
+            .map_err(|err| err.context("In the level editor".to_owned()));
+    }
+
+    hot_reload.render();
+    respawner.render();
+    match state {
+        GameState::Saving(menu) => menu.render(),
+        GameState::Map(screen) => screen.render(),
+        GameState::GameOver(screen) => screen.render(),
+        GameState::Settings(menu) => menu.render(settings),
+        GameState::Error(screen) => screen.render(),
+        GameState::Playing | GameState::Warping(_) => {}
     }
+
+    return edited;
 }
 
-#[macroquad::main(window_config)]
-async fn main() {
+/// What the debug overlay shows about the game beyond frame timing.
+fn debug_lines(game: &Game, input: &Input, tile_draws: usize) -> Vec<String> {
+    let (spawned, listed) = game.level.spawn_counts();
+    let (pending, blocking) = game.level.chunk_reads();
+    let position = game.player.body.hitbox.center();
+    let cursor_col = ((input.mouse_x + game.world.x) / TILE_SIZE).floor();
+    let cursor_row = ((input.mouse_y + game.world.y) / TILE_SIZE).floor();
+
+    let mut lines = vec![
+        format!("Present: {:?}", game.settings.present_mode),
+        format!("Objects: {}", game.level_objects.len()),
+        format!("Listings spawned: {spawned}/{listed}"),
+        format!(
+            "Chunks loaded: {} ({pending} coming, {blocking} waited on)",
+            game.level.chunks_loaded()
+        ),
+        format!("Player: {:.1}, {:.1}", position.x, position.y),
+        format!("State: {:?}", game.player.state),
+        format!("Camera: {:.1}, {:.1}", game.world.x, game.world.y),
+        format!("Cursor cell: {cursor_col}, {cursor_row}"),
+        format!(
+            "Tile draws: {tile_draws} ({} atlas pages)",
+            game.level.atlas().pages().len()
+        ),
+    ];
+    for problem in asset_problems() {
+        lines.push(format!("Placeholder {problem}"));
+    }
+    return lines;
+}
+
+/// Loads everything the game starts with, showing each step on the
+/// loading screen.
+async fn start_game(loading: &mut LoadingScreen) -> AssetManageResult<Game> {
+    // The player sprite, the level file, the HUD, the object sheets and the
+    // sounds, then everything in the manifest when preloading, then each of
+    // the level's tilesets once the level says which.
+    loading.expect(5);
+    loading.show().await;
+
+    let settings = Settings::load();
+    let manifest = match settings.preload_assets {
+        true => AssetManifest::load(),
+        false => None,
+    };
+
     let mut world = World::new();
-    let mut player = Player::new(&world).await.unwrap();
-    let mut level = Level::load("beach").await.unwrap();
-    let mut level_objects = LevelObjects::new();
+    let mut player = Player::new(&world).await?;
+    loading.loaded("Player").await;
 
-    let render_target = get_render_target(
-        (VIRTUAL_W * SUB_PIX_LEVEL) as u32,
-        (VIRTUAL_H * SUB_PIX_LEVEL) as u32,
-    );
+    let mut assets = Assets::new();
+    if let Some(manifest) = &manifest {
+        preload(manifest, &mut assets, loading).await;
+    }
 
-    let mut editor = LevelEditorSettings::new();
+    let mut level = Level::read("beach", &assets)?;
+    loading.loaded("Level").await;
 
-    loop {
-        let (world, input, dt) = run_logic(
-            &mut editor,
-            &mut world,
-            &mut player,
-            &mut level,
-            &mut level_objects,
+    let tilesets = level.tileset_names();
+    loading.expect(tilesets.len());
+    for name in tilesets {
+        assets.tileset(&name).await?;
+        loading.loaded(&name).await;
+    }
+    level.load_tilesets(&mut assets).await?;
+
+    let hud = Hud::load(&player).await?;
+    loading.loaded("HUD").await;
+    let mut object_assets = ObjectAssets::load().await?;
+    loading.loaded("Objects").await;
+    let audio = AudioAssets::load().await;
+    loading.loaded("Sounds").await;
+    if let Some(manifest) = &manifest {
+        loading.expect(manifest.sprites.len());
+        for sprite in &manifest.sprites {
+            object_assets.preload_sprite(sprite).await;
+            loading.loaded(sprite).await;
+        }
+    }
+
+    player.body.set_center(level.spawn_point());
+    world.center_on(level.spawn_point());
+    let weather = Weather::new(level.weather());
+    let mut level_objects = LevelObjects::new(object_assets);
+    level_objects.set_sleep_screens(settings.sleep_screens);
+
+    return Ok(Game {
+        state: GameState::Playing,
+        editor: LevelEditorSettings::new(settings.compress_levels),
+        world,
+        player,
+        level,
+        level_objects,
+        footsteps: Footsteps::new(),
+        respawner: Respawner::new(),
+        dialogue: None,
+        world_state: WorldState::new(),
+        assets,
+        events: EventBus::new(),
+        event_log: EventLog::new(),
+        camera: CameraFollow::new(),
+        camera_shake: CameraShake::new(),
+        screen_flash: ScreenFlash::new(),
+        transition: Transition::new(),
+        tile_time: 0.0,
+        day_clock: DayClock::new(),
+        lighting: Lighting::new(),
+        particles: ParticleSystem::new(),
+        floating_texts: FloatingTexts::new(),
+        weather,
+        hud,
+        audio,
+        map: LevelMap::new(),
+        overlay_fade: OverlayFade::new(),
+        viewport: Viewport::new(),
+        debug_overlay: DebugOverlay::new(),
+        hot_reload: HotReload::new(),
+        frame_limiter: FrameLimiter::new(settings.present_mode == PresentMode::Vsync),
+        settings,
+    });
+}
+
+/// Loads the manifest's levels and tilesets into `assets`. Any that fail
+/// are left to load when they're needed.
+async fn preload(manifest: &AssetManifest, assets: &mut Assets, loading: &mut LoadingScreen) {
+    loading.expect(manifest.levels.len() + manifest.tilesets.len());
+    for name in &manifest.levels {
+        if let Err(err) = assets.preload_level(name) {
+            warn!("Could not preload level {}: {}", name, err);
+        }
+        loading.loaded(name).await;
+    }
+    for name in &manifest.tilesets {
+        if let Err(err) = assets.preload_tileset(name).await {
+            warn!("Could not preload tileset {}: {}", name, err);
+        }
+        loading.loaded(name).await;
+    }
+}
+
+/// Validates the assets and exits with `--validate`, before any window is
+/// opened, or otherwise runs the game.
+fn main() {
+    if std::env::args().any(|arg| arg == "--validate") {
+        std::process::exit(validate::run_cli());
+    }
+    macroquad::Window::from_config(window_config(), run());
+}
+
+async fn run() {
+    if let Err(err) = AssetRoot::install() {
+        error!("Could not start the game: {}", err);
+        LoadingScreen::new().fail(&err).await;
+        return;
+    }
+
+    if let Err(err) = load_pixel_font().await {
+        warn!(
+            "Could not load the pixel font, using the built-in one: {}",
+            err
         );
+    }
 
-        set_camera(&Camera2D {
-            zoom: vec2(2.0 / VIRTUAL_W, 2.0 / VIRTUAL_H),
-            target: vec2(VIRTUAL_W / 2.0, VIRTUAL_H / 2.0),
-            render_target: Some(render_target.clone()),
-            ..Default::default()
-        });
+    let mut loading = LoadingScreen::new();
+    let mut game = match start_game(&mut loading).await {
+        Ok(game) => game,
+        Err(err) => {
+            error!("Could not start the game: {}", err);
+            loading.fail(&err).await;
+            return;
+        }
+    };
+    drop(loading);
+
+    let mut targets = FrameTargets::new(game.settings.render_scale());
+
+    // Closing the window waits for the editor to offer to save.
+    prevent_quit();
+    loop {
+        if is_quit_requested() {
+            game.level.offer_save(&mut game.editor).await;
+            break;
+        }
+        game.viewport.update();
+        let logic_start = get_time();
+        let (world, input, dt) = match &mut game.state {
+            GameState::Playing => {
+                let frame = run_logic(&mut game).await;
+                if frame.1.load_game {
+                    load_game(&mut game).await;
+                }
+                if game.respawner.update(game.player.is_dead(), frame.2) {
+                    game.state = GameState::GameOver(GameOverScreen::new());
+                }
+                frame
+            }
+            GameState::Warping(target) => {
+                if game.transition.is_covered() {
+                    let target = target.clone();
+                    game.transition.start_in(Transition::LEVEL_IN_SECONDS);
+                    game.state = match warp_to(&mut game, &target).await {
+                        Ok(()) => GameState::Playing,
+                        Err(err) => {
+                            error!("Could not warp: {}", err);
+                            GameState::Error(ErrorScreen::new(&err, Retry::Warp(target)))
+                        }
+                    };
+                }
+                (
+                    game.world.rounded(game.settings.sub_pixels()),
+                    Input::get(&game.viewport),
+                    get_frame_time(),
+                )
+            }
+            GameState::Map(screen) => {
+                let input = Input::get(&game.viewport);
+                let dt = get_frame_time();
+                if !screen.update(&input, dt) {
+                    game.state = GameState::Playing;
+                }
+                (game.world.rounded(game.settings.sub_pixels()), input, dt)
+            }
+            GameState::Saving(menu) => {
+                let input = Input::get(&game.viewport);
+                if let Some(save) = menu.update(&input) {
+                    game.audio.play_sfx(Sfx::UI_CONFIRM);
+                    if save {
+                        save_game(&mut game);
+                    }
+                    game.state = GameState::Playing;
+                }
+                (
+                    game.world.rounded(game.settings.sub_pixels()),
+                    input,
+                    get_frame_time(),
+                )
+            }
+            GameState::Settings(menu) => {
+                let input = Input::get(&game.viewport);
+                if !menu.update(&input, &mut game.settings) {
+                    if let Err(err) = game.settings.write() {
+                        warn!("Could not save the settings: {}", err);
+                    }
+                    game.state = GameState::Playing;
+                }
+                (
+                    game.world.rounded(game.settings.sub_pixels()),
+                    input,
+                    get_frame_time(),
+                )
+            }
+            GameState::GameOver(screen) => {
+                let input = Input::get(&game.viewport);
+                let choice = screen.update(&input);
+                if choice.is_some() {
+                    game.audio.play_sfx(Sfx::UI_CONFIRM);
+                }
+                match choice {
+                    Some(GameOverChoice::Retry) => try_respawn(&mut game).await,
+                    Some(GameOverChoice::Quit) => {
+                        game.level.offer_save(&mut game.editor).await;
+                        break;
+                    }
+                    None => {}
+                }
+                (
+                    game.world.rounded(game.settings.sub_pixels()),
+                    input,
+                    get_frame_time(),
+                )
+            }
+            GameState::Error(screen) => {
+                let input = Input::get(&game.viewport);
+                let choice = screen.update(&input);
+                if choice.is_some() {
+                    game.audio.play_sfx(Sfx::UI_CONFIRM);
+                }
+                match choice {
+                    Some(ErrorChoice::Retry) => match screen.retry().clone() {
+                        Retry::Warp(target) => {
+                            game.transition
+                                .start_out(TransitionStyle::Wipe, Transition::LEVEL_OUT_SECONDS);
+                            game.state = GameState::Warping(target);
+                        }
+                        Retry::Respawn => try_respawn(&mut game).await,
+                        Retry::Resume => game.state = GameState::Playing,
+                    },
+                    Some(ErrorChoice::Quit) => {
+                        game.level.offer_save(&mut game.editor).await;
+                        break;
+                    }
+                    None => {}
+                }
+                (
+                    game.world.rounded(game.settings.sub_pixels()),
+                    input,
+                    get_frame_time(),
+                )
+            }
+        };
+
+        let logic_time = get_time() - logic_start;
+
+        if input.toggle_scaling {
+            game.viewport.toggle_stretch();
+        }
+        if input.toggle_debug_overlay {
+            game.debug_overlay.toggle();
+        }
+        if input.toggle_atlas_view {
+            game.debug_overlay.toggle_atlas();
+        }
+        if input.toggle_atlas {
+            let on = game.level.toggle_atlas();
+            info!(
+                "Drawing tiles from {}",
+                match on {
+                    true => "the atlas",
+                    false => "their own textures",
+                }
+            );
+        }
+
+        let render_start = get_time();
+        targets.set_scale(game.settings.render_scale());
+        set_virtual_camera(&targets.world);
         clear_background(BLACK);
+        render_world(&mut game, &world);
+
+        // Smooth camera slides the whole world by what rounding the view
+        // took off, so tiles and bodies never part by a sub-pixel. The
+        // target's linear filtering blends the slide between its pixels.
+        let slide = match game.settings.camera {
+            CameraSmoothing::Snapped => Vec2::ZERO,
+            CameraSmoothing::Smooth => vec2(world.x - game.world.x, world.y - game.world.y),
+        };
 
-        render(
-            &mut editor,
-            &world,
-            &mut player,
-            &mut level_objects,
-            &mut level,
-            &input,
-            dt,
-        )
-        .await;
+        set_virtual_camera(&targets.screen);
+        clear_background(BLACK);
+        draw_post_processed(&game.settings.post_process, || {
+            draw_virtual_target(
+                &targets.world,
+                Rect::new(slide.x, slide.y, VIRTUAL_W, VIRTUAL_H),
+            );
+        });
+        if let Err(err) = render_ui(&mut game, &world, &input, dt).await {
+            error!("{}", err);
+            game.state = GameState::Error(ErrorScreen::new(&err, Retry::Resume));
+        }
+        game.debug_overlay
+            .record(logic_time, get_time() - render_start);
+        let tile_draws = take_draw_count();
 
         set_default_camera();
-        draw_texture_ex(
-            &render_target.texture,
-            0.0,
-            0.0,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(screen_width().round(), screen_height().round())),
-                source: Some(Rect::new(
-                    0.0,
-                    0.0,
-                    VIRTUAL_W * SUB_PIX_LEVEL,
-                    VIRTUAL_H * SUB_PIX_LEVEL,
-                )),
-                ..Default::default()
-            },
-        );
+        clear_background(BLACK);
+        draw_virtual_target(&targets.screen, game.viewport.dest());
+        game.transition.update(dt);
+        game.transition.render();
+        if game.debug_overlay.is_atlas_open() {
+            game.debug_overlay.render_atlas(game.level.atlas());
+        }
+        if game.debug_overlay.is_open() {
+            game.debug_overlay
+                .render(dt, &debug_lines(&game, &input, tile_draws));
+        }
 
+        game.frame_limiter.wait(game.settings.present_mode);
         next_frame().await;
     }
 }
@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use macroquad::logging::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{asset_exists, check_saving, deserialize, serialize, AssetManageResult},
+    asset_root::AssetRoot,
+    level_file::LevelFormat,
+    sprites::Sprite,
+    tilesets::TilesetAsset,
+};
+
+/// Every level, tileset and sprite sheet the game has, for loading them all
+/// at startup. Anything left out still loads when it's first needed.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AssetManifest {
+    /// Level names, as `Level::load` takes them.
+    pub levels: Vec<String>,
+    /// Tileset names, as `TilesetAsset::load` takes them.
+    pub tilesets: Vec<String>,
+    /// Sprite sheets, as paths under the sprites folder.
+    pub sprites: Vec<String>,
+}
+
+impl AssetManifest {
+    pub const PATH: &str = "assets/manifest.json";
+
+    /// The manifest, or `None` if there isn't one or it can't be read.
+    pub fn load() -> Option<Self> {
+        if !asset_exists(Self::PATH) {
+            warn!("No asset manifest, loading assets as they're needed");
+            return None;
+        }
+
+        return match deserialize(Self::PATH) {
+            Ok(manifest) => Some(manifest),
+            Err(err) => {
+                warn!("Could not read the asset manifest: {}", err);
+                None
+            }
+        };
+    }
+
+    /// Lists what's in the asset folders. Everything is sorted, so the
+    /// file only changes when the assets do.
+    pub fn scan() -> AssetManageResult<Self> {
+        let mut levels = Vec::new();
+        // Chunked levels are folders with an index in them.
+        let mut level_files = files_in(Path::new(LevelFormat::DIR), false)?;
+        for entry in std::fs::read_dir(AssetRoot::resolve(LevelFormat::DIR))? {
+            let index = entry?.path().join(LevelFormat::INDEX);
+            if index.is_file() {
+                level_files.push(index);
+            }
+        }
+        for path in level_files {
+            let Some(name) = LevelFormat::name_of(&path) else {
+                continue;
+            };
+            // LDtk world layouts sit beside the levels.
+            if !name.ends_with(".world") {
+                levels.push(name.to_owned());
+            }
+        }
+
+        let mut tilesets = Vec::new();
+        for path in files_in(Path::new(TilesetAsset::DIR), false)? {
+            if path.extension().is_some_and(|ext| ext == "png") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    tilesets.push(stem.to_owned());
+                }
+            }
+        }
+
+        let mut sprites = Vec::new();
+        let sprite_dir = AssetRoot::resolve(Sprite::DIR);
+        for path in files_in(Path::new(Sprite::DIR), true)? {
+            if path.extension().is_some_and(|ext| ext == "png") {
+                let relative = path.strip_prefix(&sprite_dir).unwrap_or(&path);
+                sprites.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+
+        for list in [&mut levels, &mut tilesets, &mut sprites] {
+            list.sort();
+            list.dedup();
+        }
+        return Ok(Self {
+            levels,
+            tilesets,
+            sprites,
+        });
+    }
+
+    pub fn write(&self) -> AssetManageResult<()> {
+        check_saving()?;
+        return serialize(self, Self::PATH);
+    }
+}
+
+/// The files in the asset folder `dir`, and in its subfolders if `deep`.
+fn files_in(dir: &Path, deep: bool) -> AssetManageResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut open = vec![AssetRoot::resolve(dir)];
+    while let Some(dir) = open.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            match path.is_dir() {
+                true if deep => open.push(path),
+                true => {}
+                false => files.push(path),
+            }
+        }
+    }
+    return Ok(files);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Preloading is only as good as the manifest, so a stale one fails
+    /// here rather than quietly loading assets late.
+    #[test]
+    fn shipped_manifest_lists_every_asset() {
+        let shipped = AssetManifest::load().expect("the manifest should ship with the assets");
+        let scanned = AssetManifest::scan().unwrap();
+        assert_eq!(shipped.levels, scanned.levels);
+        assert_eq!(shipped.tilesets, scanned.tilesets);
+        assert_eq!(shipped.sprites, scanned.sprites);
+    }
+}
@@ -0,0 +1,176 @@
+use macroquad::{
+    camera::{pop_camera_state, push_camera_state, set_camera, Camera2D},
+    color::{Color, BLACK, GREEN, ORANGE, SKYBLUE, WHITE},
+    math::{vec2, Vec2},
+    shapes::{draw_circle, draw_rectangle, draw_rectangle_lines},
+    text::{draw_text, measure_text},
+    texture::{draw_texture_ex, render_target, DrawTextureParams, FilterMode, RenderTarget},
+    window::clear_background,
+};
+
+use crate::{input::Input, levels::Level, player::Player, TILE_SIZE, VIRTUAL_H, VIRTUAL_W};
+
+/// The level drawn small, kept between openings of the map so a target is
+/// only made again when the level's size changes.
+pub struct LevelMap {
+    target: Option<RenderTarget>,
+}
+
+impl LevelMap {
+    /// Pixels to a tile on the map.
+    const CELL: f32 = 4.0;
+    const FOG: Color = Color::new(0.05, 0.05, 0.1, 1.0);
+
+    pub fn new() -> Self {
+        Self { target: None }
+    }
+
+    /// Draws `level` with its unexplored cells fogged over and opens the
+    /// map screen on it. Call outside the frame's render, as it draws to
+    /// its own target.
+    pub fn open(&mut self, level: &Level, player: &Player) -> MapScreen {
+        let (rows, cols) = level.size();
+        let (w, h) = (
+            cols as u32 * Self::CELL as u32,
+            rows as u32 * Self::CELL as u32,
+        );
+        let target = match &self.target {
+            Some(target) if target.texture.size() == vec2(w as f32, h as f32) => target.clone(),
+            _ => {
+                let target = render_target(w.max(1), h.max(1));
+                target.texture.set_filter(FilterMode::Nearest);
+                self.target = Some(target.clone());
+                target
+            }
+        };
+
+        push_camera_state();
+        set_camera(&Camera2D {
+            zoom: vec2(2.0 / w as f32, 2.0 / h as f32),
+            target: vec2(w as f32 / 2.0, h as f32 / 2.0),
+            render_target: Some(target.clone()),
+            ..Default::default()
+        });
+        clear_background(BLACK);
+        level.render_map(Self::CELL);
+        for row in 0..rows {
+            for col in 0..cols {
+                if !level.is_explored(row, col) {
+                    let (x, y) = (col as f32 * Self::CELL, row as f32 * Self::CELL);
+                    draw_rectangle(x, y, Self::CELL, Self::CELL, Self::FOG);
+                }
+            }
+        }
+        pop_camera_state();
+
+        let to_map = |position: Vec2| position / TILE_SIZE * Self::CELL;
+        let checkpoint = player
+            .checkpoint
+            .as_ref()
+            .filter(|point| point.level == level.name())
+            .map(|point| to_map(point.position));
+        let portals = level
+            .portal_cells()
+            .into_iter()
+            .filter(|&(row, col)| level.is_explored(row, col))
+            .map(|(row, col)| vec2(col as f32 + 0.5, row as f32 + 0.5) * Self::CELL)
+            .collect();
+
+        return MapScreen::new(
+            target,
+            level.name().to_owned(),
+            to_map(player.body.hitbox.center()),
+            portals,
+            checkpoint,
+        );
+    }
+}
+
+/// The full-screen map. Gameplay waits while it's open.
+pub struct MapScreen {
+    target: RenderTarget,
+    level: String,
+    /// Marker positions in map pixels.
+    player: Vec2,
+    portals: Vec<Vec2>,
+    checkpoint: Option<Vec2>,
+    /// Map pixel at the top left of the screen.
+    pan: Vec2,
+}
+
+impl MapScreen {
+    /// Map pixels panned per second.
+    const PAN_SPEED: f32 = 160.0;
+
+    fn new(
+        target: RenderTarget,
+        level: String,
+        player: Vec2,
+        portals: Vec<Vec2>,
+        checkpoint: Option<Vec2>,
+    ) -> Self {
+        let mut screen = Self {
+            target,
+            level,
+            player,
+            portals,
+            checkpoint,
+            pan: player - vec2(VIRTUAL_W, VIRTUAL_H) / 2.0,
+        };
+        screen.clamp_pan();
+        return screen;
+    }
+
+    /// Keeps the map on screen, centering it on any axis it's too small to
+    /// fill.
+    fn clamp_pan(&mut self) {
+        let size = self.target.texture.size();
+        let screen = vec2(VIRTUAL_W, VIRTUAL_H);
+        for axis in 0..2 {
+            self.pan[axis] = match size[axis] > screen[axis] {
+                true => self.pan[axis].clamp(0.0, size[axis] - screen[axis]),
+                false => (size[axis] - screen[axis]) / 2.0,
+            };
+        }
+    }
+
+    /// Pans with the movement keys. Returns false once the map is closed.
+    pub fn update(&mut self, input: &Input, dt: f32) -> bool {
+        self.pan += vec2(input.horizontal, input.vertical) * Self::PAN_SPEED * dt;
+        self.clamp_pan();
+        return !input.toggle_map;
+    }
+
+    pub fn render(&self) {
+        draw_rectangle(0.0, 0.0, VIRTUAL_W, VIRTUAL_H, BLACK);
+
+        let origin = -self.pan.round();
+        let size = self.target.texture.size();
+        draw_texture_ex(
+            &self.target.texture,
+            origin.x,
+            origin.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(size),
+                ..Default::default()
+            },
+        );
+        draw_rectangle_lines(origin.x, origin.y, size.x, size.y, 1.0, WHITE);
+
+        for portal in &self.portals {
+            let at = origin + *portal;
+            draw_circle(at.x, at.y, 2.5, SKYBLUE);
+        }
+        if let Some(checkpoint) = self.checkpoint {
+            let at = origin + checkpoint;
+            draw_circle(at.x, at.y, 2.5, ORANGE);
+        }
+        let at = origin + self.player;
+        draw_circle(at.x, at.y, 3.0, BLACK);
+        draw_circle(at.x, at.y, 2.0, GREEN);
+
+        let width = measure_text(&self.level, None, 16, 1.0).width;
+        draw_text(&self.level, (VIRTUAL_W - width) / 2.0, 14.0, 16.0, WHITE);
+    }
+}
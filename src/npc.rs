@@ -0,0 +1,88 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use macroquad::logging::warn;
+
+use crate::{
+    body::Body,
+    dialogue::DialoguePage,
+    object::{in_reach, GameObject, Interaction, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    sprites::Sprite,
+    world::World,
+};
+
+/// NPC sprites by file name, loaded the first time each is needed.
+pub struct NpcSprites {
+    cache: HashMap<String, Option<Sprite>>,
+}
+
+impl NpcSprites {
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+        }
+    }
+
+    /// A sprite that can't be read is reported once and cached as `None`, so
+    /// the NPC falls back to its debug rectangle.
+    pub async fn get(&mut self, name: &str) -> Option<Sprite> {
+        if let Entry::Vacant(entry) = self.cache.entry(name.to_owned()) {
+            let sprite = match Sprite::load_npc(name).await {
+                Ok(sprite) => Some(sprite),
+                Err(err) => {
+                    warn!("Could not load NPC sprite {}: {}", name, err);
+                    None
+                }
+            };
+            entry.insert(sprite);
+        }
+
+        return self.cache[name].clone();
+    }
+}
+
+/// Someone standing in the level with something to say.
+pub struct Npc {
+    pub body: Body,
+    pages: Vec<DialoguePage>,
+}
+
+impl Npc {
+    const SIZE: f32 = 12.0;
+
+    pub fn new(x: f32, y: f32, sprite: Option<Sprite>, pages: Vec<DialoguePage>) -> Self {
+        Self {
+            body: Body::new(x, y, Self::SIZE, Self::SIZE, sprite),
+            pages,
+        }
+    }
+}
+
+impl GameObject for Npc {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Npc
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn can_interact(&self, player: &Player) -> bool {
+        return !self.pages.is_empty() && in_reach(player, &self.body.hitbox);
+    }
+
+    /// Turns to face the player and starts talking.
+    fn interact(&mut self, player: &mut Player) -> Interaction {
+        self.body
+            .face(player.body.hitbox.center() - self.body.hitbox.center());
+        return Interaction::Talk(self.pages.clone());
+    }
+
+    fn render(&self, world: &World) {
+        self.body.render(world);
+    }
+}
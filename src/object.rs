@@ -1,31 +1,294 @@
+use macroquad::{
+    color::{Color, RED, WHITE},
+    math::{vec2, Rect, Vec2},
+    text::draw_text,
+    texture::Texture2D,
+    time::get_time,
+};
 use serde::{Deserialize, Serialize};
-use std::ops::Range;
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
 use crate::{
+    asset_loading::AssetManageResult,
     body::Body,
-    enemies::{Enemy, EnemyType},
+    breakable::{Breakable, BreakableDropsTable, BreakableKind},
+    checkpoint::Checkpoint,
+    chest::{Chest, ChestSheet},
+    dialogue::DialoguePage,
+    door::{Door, DoorSheet},
+    enemies::{Enemy, EnemySprites, EnemyStatsTable, EnemyType, PatrolRoute},
+    events::{EventBus, GameEvent},
+    gate::Gate,
+    items::{ItemId, ItemPickup, ItemSheet},
+    lamp::Lamp,
+    levels::{Level, SortedTile},
+    lever::Lever,
+    lights::{Light, LightEmitter},
+    links::LinkBus,
+    npc::{Npc, NpcSprites},
+    particles::ParticleSystem,
+    pickups::{Pickup, PickupKind},
+    plate::PressurePlate,
+    player::Player,
+    portal::{Portal, WarpTarget},
+    save::SavePoint,
+    shaders::draw_flashing,
+    sign::Sign,
+    spatial_grid::SpatialGrid,
+    spawn_region::SpawnRegion,
+    spawner::Spawner,
+    spikes::SpikeTrap,
     world::World,
-    TILE_SIZE,
+    TILE_SIZE, VIRTUAL_H,
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ObjectListing {
+    /// Stable across edits and saves, unlike the listing's index. Zero
+    /// means none was assigned yet.
+    #[serde(default)]
+    id: u64,
     row: usize,
     col: usize,
     r#type: ObjectType,
+    /// Waypoint cells an enemy walks between instead of wandering.
+    #[serde(default)]
+    patrol: Option<Vec<(usize, usize)>>,
+    /// Walk the patrol back and forth instead of looping to the start.
+    #[serde(default)]
+    ping_pong: bool,
+    /// Keep the spawned object alive even when the camera is far away.
+    #[serde(default)]
+    persistent: bool,
+}
+
+fn cell_center(row: usize, col: usize) -> Vec2 {
+    return vec2(
+        col as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        row as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+    );
 }
 
 impl ObjectListing {
+    pub fn new(id: u64, row: usize, col: usize, r#type: ObjectType) -> Self {
+        Self {
+            id,
+            row,
+            col,
+            r#type,
+            patrol: None,
+            ping_pong: false,
+            persistent: false,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    pub fn r#type(&self) -> &ObjectType {
+        &self.r#type
+    }
+
+    pub fn type_mut(&mut self) -> &mut ObjectType {
+        &mut self.r#type
+    }
+
+    pub fn set_cell(&mut self, row: usize, col: usize) {
+        self.row = row;
+        self.col = col;
+    }
+
     pub fn is_in_range(&self, row_range: &Range<usize>, col_range: &Range<usize>) -> bool {
         return row_range.contains(&self.row) && col_range.contains(&self.col);
     }
 
-    pub fn resolve(&self) -> Object {
-        let x = self.col as f32 * TILE_SIZE + TILE_SIZE / 2.0;
-        let y = self.row as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+    pub fn center(&self) -> Vec2 {
+        return cell_center(self.row, self.col);
+    }
+
+    pub fn patrol(&self) -> Option<&Vec<(usize, usize)>> {
+        self.patrol.as_ref()
+    }
+
+    pub fn ping_pong(&self) -> bool {
+        self.ping_pong
+    }
+
+    pub fn add_waypoint(&mut self, row: usize, col: usize) {
+        self.patrol.get_or_insert_with(Vec::new).push((row, col));
+    }
+
+    pub fn clear_patrol(&mut self) {
+        self.patrol = None;
+    }
+
+    pub fn toggle_ping_pong(&mut self) {
+        self.ping_pong = !self.ping_pong;
+    }
+
+    /// The world-space rectangle an area listing covers, from its cell down
+    /// and right.
+    pub fn area(&self) -> Option<Rect> {
+        let (rows, cols) = match self.r#type {
+            ObjectType::SpawnRegion { rows, cols, .. } | ObjectType::CameraZone { rows, cols } => {
+                (rows, cols)
+            }
+            _ => return None,
+        };
+        return Some(Rect::new(
+            self.col as f32 * TILE_SIZE,
+            self.row as f32 * TILE_SIZE,
+            cols.max(1) as f32 * TILE_SIZE,
+            rows.max(1) as f32 * TILE_SIZE,
+        ));
+    }
+
+    /// World-space centers of the patrol waypoints, if any are set.
+    pub fn patrol_points(&self) -> Option<Vec<Vec2>> {
+        return self
+            .patrol
+            .as_ref()
+            .filter(|cells| !cells.is_empty())
+            .map(|cells| {
+                cells
+                    .iter()
+                    .map(|&(row, col)| cell_center(row, col))
+                    .collect()
+            });
+    }
+
+    /// Builds the runtime object for this listing. Pure markers (like the
+    /// spawn point) resolve to nothing. `opened` is whether the listing's
+    /// chest or door was already opened this session.
+    pub async fn resolve(
+        &self,
+        assets: &mut ObjectAssets,
+        opened: bool,
+    ) -> Option<Box<dyn GameObject>> {
+        let Vec2 { x, y } = self.center();
 
         return match &self.r#type {
-            ObjectType::Enemy(enemy_type) => Object::Enemy(Enemy::new(enemy_type.clone(), x, y)),
+            ObjectType::Enemy(enemy_type) => {
+                let sprite = assets.enemy_sprites.get(*enemy_type).await;
+                let seed = (self.row as u64) << 32 | self.col as u64;
+                let patrol = self
+                    .patrol_points()
+                    .map(|points| PatrolRoute::new(points, self.ping_pong));
+                Some(Box::new(Enemy::new(
+                    *enemy_type,
+                    assets.enemy_stats.get(*enemy_type).clone(),
+                    x,
+                    y,
+                    sprite,
+                    seed,
+                    patrol,
+                )))
+            }
+            ObjectType::Item(item) => {
+                Some(Box::new(ItemPickup::new(*item, x, y, &assets.item_sheet)))
+            }
+            ObjectType::Pickup(kind) => Some(Box::new(Pickup::new(*kind, x, y))),
+            ObjectType::Breakable(kind) => {
+                let seed = (self.row as u64) << 32 | self.col as u64;
+                Some(Box::new(Breakable::new(
+                    *kind,
+                    x,
+                    y,
+                    assets.breakable_drops.get(*kind).to_vec(),
+                    seed,
+                )))
+            }
+            ObjectType::Checkpoint => Some(Box::new(Checkpoint::new(x, y))),
+            ObjectType::SavePoint => Some(Box::new(SavePoint::new(x, y))),
+            ObjectType::Lamp(light) => Some(Box::new(Lamp::new(x, y, light.clone()))),
+            ObjectType::SpikeTrap { period, offset } => {
+                Some(Box::new(SpikeTrap::new(x, y, *period, *offset)))
+            }
+            ObjectType::SpawnPoint | ObjectType::Anchor { .. } | ObjectType::CameraZone { .. } => {
+                None
+            }
+            ObjectType::Portal {
+                target_level,
+                target_anchor,
+            } => Some(Box::new(Portal::new(
+                x,
+                y,
+                WarpTarget {
+                    level: target_level.clone(),
+                    anchor: target_anchor.clone(),
+                },
+            ))),
+            ObjectType::Chest { contents } => Some(Box::new(Chest::new(
+                x,
+                y,
+                contents.clone(),
+                opened,
+                &assets.chest_sheet,
+            ))),
+            ObjectType::Door { key, consume_key } => Some(Box::new(Door::new(
+                x,
+                y,
+                *key,
+                *consume_key,
+                opened,
+                &assets.door_sheet,
+            ))),
+            ObjectType::PressurePlate { link_id, latch } => Some(Box::new(PressurePlate::new(
+                x,
+                y,
+                link_id.clone(),
+                *latch,
+                opened,
+            ))),
+            ObjectType::Gate { link_id } => Some(Box::new(Gate::new(x, y, link_id.clone()))),
+            ObjectType::Lever { .. } => Some(Box::new(Lever::new(x, y, opened))),
+            ObjectType::Npc { sprite, pages } => {
+                let sprite = assets.npc_sprites.get(sprite).await;
+                Some(Box::new(Npc::new(x, y, sprite, pages.clone())))
+            }
+            ObjectType::Sign { text, .. } => {
+                Some(Box::new(Sign::new(x, y, text.clone(), &assets.sign_tex)))
+            }
+            ObjectType::SpawnRegion {
+                enemy,
+                population,
+                respawn_delay,
+                ..
+            } => {
+                let seed = (self.row as u64) << 32 | self.col as u64;
+                Some(Box::new(SpawnRegion::new(
+                    self.area()?,
+                    *enemy,
+                    *population,
+                    *respawn_delay,
+                    seed,
+                )))
+            }
+            ObjectType::Spawner {
+                enemy,
+                count,
+                interval,
+                max_alive,
+            } => Some(Box::new(Spawner::new(
+                x, y, *enemy, *count, *interval, *max_alive,
+            ))),
         };
     }
 }
@@ -33,83 +296,1419 @@ impl ObjectListing {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ObjectType {
     Enemy(EnemyType),
+    Item(ItemId),
+    Pickup(PickupKind),
+    /// Solid until the player's attack breaks it.
+    Breakable(BreakableKind),
+    Checkpoint,
+    SavePoint,
+    SpawnPoint,
+    /// A post that only gives off light.
+    Lamp(LightEmitter),
+    /// Extends every `period` seconds, shifted `offset` seconds along the
+    /// shared cycle.
+    SpikeTrap {
+        period: f32,
+        #[serde(default)]
+        offset: f32,
+    },
+    Spawner {
+        enemy: EnemyType,
+        count: usize,
+        interval: f32,
+        max_alive: usize,
+    },
+    /// Keeps `population` enemies alive in the `rows` by `cols` cells from
+    /// the listing's cell down and right, respawning each one
+    /// `respawn_delay` seconds after it dies.
+    SpawnRegion {
+        enemy: EnemyType,
+        rows: usize,
+        cols: usize,
+        population: usize,
+        respawn_delay: f32,
+    },
+    Chest {
+        contents: Vec<(ItemId, u8)>,
+    },
+    Door {
+        key: Option<ItemId>,
+        /// Take the key from the player when the door opens.
+        #[serde(default)]
+        consume_key: bool,
+    },
+    /// Opens every gate sharing its `link_id` while something stands on it.
+    PressurePlate {
+        link_id: String,
+        /// Stay pressed for good after the first press.
+        #[serde(default)]
+        latch: bool,
+    },
+    /// Flips every gate sharing its `link_id` each time it is pulled.
+    /// Levers on the same id work together like a multi-way switch.
+    Lever {
+        link_id: String,
+    },
+    /// Solid unless a plate with the same `link_id` is pressed or a lever
+    /// has switched it on.
+    Gate {
+        link_id: String,
+    },
+    Npc {
+        /// File name under `assets/art/sprites/npcs`, without the extension.
+        sprite: String,
+        pages: Vec<DialoguePage>,
+    },
+    Sign {
+        text: String,
+        /// Block movement through the sign's cell.
+        #[serde(default)]
+        solid: bool,
+    },
+    /// Sends the player to the anchor called `target_anchor` in
+    /// `target_level`.
+    Portal {
+        target_level: String,
+        target_anchor: String,
+    },
+    /// A named spot portals from other levels can arrive at.
+    Anchor {
+        name: String,
+    },
+    /// Keeps the camera inside the `rows` by `cols` cells from the
+    /// listing's cell down and right while the player is in them. A zone one
+    /// screen in size locks the camera in place.
+    CameraZone {
+        rows: usize,
+        cols: usize,
+    },
 }
 
-pub enum Object {
-    Enemy(Enemy),
-}
+impl ObjectType {
+    /// Every placeable type, in the order the editor cycles through them.
+    pub fn all() -> Vec<ObjectType> {
+        let enemies = EnemyType::ALL.iter().copied().map(ObjectType::Enemy);
+        let items = ItemId::ALL.iter().copied().map(ObjectType::Item);
+        let pickups = PickupKind::ALL.iter().copied().map(ObjectType::Pickup);
+        let breakables = BreakableKind::ALL
+            .iter()
+            .copied()
+            .map(ObjectType::Breakable);
+        let markers = [
+            ObjectType::Checkpoint,
+            ObjectType::SavePoint,
+            ObjectType::SpawnPoint,
+            ObjectType::Lamp(LightEmitter {
+                radius: TILE_SIZE * 3.0,
+                color: [255, 200, 120],
+                flicker: true,
+            }),
+            ObjectType::SpikeTrap {
+                period: 2.0,
+                offset: 0.0,
+            },
+            ObjectType::Spawner {
+                enemy: EnemyType::PurpleBlob,
+                count: 5,
+                interval: 2.0,
+                max_alive: 2,
+            },
+            ObjectType::SpawnRegion {
+                enemy: EnemyType::PurpleBlob,
+                rows: 1,
+                cols: 1,
+                population: 3,
+                respawn_delay: 5.0,
+            },
+            ObjectType::Chest {
+                contents: vec![(ItemId::Shell, 3)],
+            },
+            ObjectType::Door {
+                key: None,
+                consume_key: false,
+            },
+            ObjectType::PressurePlate {
+                link_id: "a".to_owned(),
+                latch: false,
+            },
+            ObjectType::Lever {
+                link_id: "a".to_owned(),
+            },
+            ObjectType::Gate {
+                link_id: "a".to_owned(),
+            },
+            ObjectType::Npc {
+                sprite: "fisher".to_owned(),
+                pages: vec![DialoguePage {
+                    speaker: Some("Fisher".to_owned()),
+                    portrait: None,
+                    text: "The tide's turning. Best not wander too far out.".to_owned(),
+                }],
+            },
+            ObjectType::Sign {
+                text: "Beach".to_owned(),
+                solid: false,
+            },
+            ObjectType::Portal {
+                target_level: "beach".to_owned(),
+                target_anchor: "start".to_owned(),
+            },
+            ObjectType::Anchor {
+                name: "start".to_owned(),
+            },
+            ObjectType::CameraZone { rows: 1, cols: 1 },
+        ];
+        return enemies
+            .chain(items)
+            .chain(pickups)
+            .chain(breakables)
+            .chain(markers)
+            .collect();
+    }
+
+    /// Reads a type written as the level file writes it, or just the name
+    /// of one without fields, like `Checkpoint`.
+    pub fn parse(text: &str) -> Option<Self> {
+        return serde_json::from_str(text)
+            .or_else(|_| serde_json::from_value(serde_json::Value::String(text.to_owned())))
+            .ok();
+    }
 
-impl Object {
-    fn get_y_sort_key(&self) -> i32 {
+    pub fn label(&self) -> String {
         match self {
-            Object::Enemy(enemy) => enemy.body.get_y_sort_key(),
+            ObjectType::Enemy(enemy_type) => format!("Enemy:{:?}", enemy_type),
+            ObjectType::Item(item) => format!("Item:{:?}", item),
+            ObjectType::Pickup(kind) => format!("Pickup:{:?}", kind),
+            ObjectType::Breakable(kind) => format!("Breakable:{:?}", kind),
+            ObjectType::Checkpoint => "Checkpoint".to_owned(),
+            ObjectType::SavePoint => "SavePoint".to_owned(),
+            ObjectType::SpawnPoint => "SpawnPoint".to_owned(),
+            ObjectType::Lamp(light) => format!("Lamp:r{}", light.radius),
+            ObjectType::SpikeTrap { period, offset } => format!("Spikes:{period}s +{offset}s"),
+            ObjectType::Spawner {
+                enemy,
+                count,
+                interval,
+                max_alive,
+            } => format!("Spawner:{enemy:?} x{count} every {interval}s, max {max_alive}"),
+            ObjectType::SpawnRegion {
+                enemy,
+                rows,
+                cols,
+                population,
+                respawn_delay,
+            } => format!(
+                "Region:{enemy:?} {cols}x{rows}, {population} alive, every {respawn_delay}s"
+            ),
+            ObjectType::Chest { contents } => {
+                let contents: Vec<String> = contents
+                    .iter()
+                    .map(|(item, count)| format!("{item:?}:{count}"))
+                    .collect();
+                format!("Chest:{}", contents.join(" "))
+            }
+            ObjectType::Door { key: None, .. } => "Door".to_owned(),
+            ObjectType::Door {
+                key: Some(key),
+                consume_key,
+            } => match consume_key {
+                true => format!("Door:{key:?} (used up)"),
+                false => format!("Door:{key:?}"),
+            },
+            ObjectType::PressurePlate { link_id, latch } => match latch {
+                true => format!("Plate:{link_id} (latch)"),
+                false => format!("Plate:{link_id}"),
+            },
+            ObjectType::Lever { link_id } => format!("Lever:{link_id}"),
+            ObjectType::Gate { link_id } => format!("Gate:{link_id}"),
+            ObjectType::Npc { sprite, pages } => {
+                format!("Npc:{sprite} ({} pages)", pages.len())
+            }
+            ObjectType::Sign { text, solid } => match solid {
+                true => format!("Sign:{text} (solid)"),
+                false => format!("Sign:{text}"),
+            },
+            ObjectType::Portal {
+                target_level,
+                target_anchor,
+            } => format!("Portal:{target_level}/{target_anchor}"),
+            ObjectType::Anchor { name } => format!("Anchor:{name}"),
+            ObjectType::CameraZone { rows, cols } => format!("CameraZone:{cols}x{rows}"),
+        }
+    }
+
+    /// Whether the editor drags the type out over a rectangle of cells
+    /// instead of placing it in one.
+    pub fn is_area(&self) -> bool {
+        return matches!(
+            self,
+            ObjectType::SpawnRegion { .. } | ObjectType::CameraZone { .. }
+        );
+    }
+
+    /// Sets how many cells an area type covers. Other types are left as
+    /// they are.
+    pub fn set_area(&mut self, new_rows: usize, new_cols: usize) {
+        if let ObjectType::SpawnRegion { rows, cols, .. } | ObjectType::CameraZone { rows, cols } =
+            self
+        {
+            (*rows, *cols) = (new_rows, new_cols);
         }
     }
 
-    fn render(&self, world: &World) {
+    /// The light the placed object gives off, for showing in the editor.
+    pub fn light(&self) -> Option<LightEmitter> {
         match self {
-            Object::Enemy(enemy) => enemy.body.render(world),
+            ObjectType::Lamp(light) => Some(light.clone()),
+            ObjectType::SavePoint => Some(SavePoint::LIGHT),
+            _ => None,
+        }
+    }
+
+    /// Whether the object fills its cell like a solid tile. Doors only do
+    /// so until opened, breakables until broken, and gates while shut.
+    pub fn is_solid(&self) -> bool {
+        match self {
+            ObjectType::Chest { .. }
+            | ObjectType::Door { .. }
+            | ObjectType::Breakable(_)
+            | ObjectType::Gate { .. } => true,
+            ObjectType::Sign { solid, .. } => *solid,
+            _ => false,
+        }
+    }
+}
+
+/// What an object is, for the passes that only care about one kind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectKind {
+    Enemy,
+    Item,
+    Checkpoint,
+    Projectile,
+    Spawner,
+    Chest,
+    Door,
+    Npc,
+    Sign,
+    Pickup,
+    Portal,
+    SavePoint,
+    Breakable,
+    PressurePlate,
+    Gate,
+    Lever,
+    Lamp,
+    SpikeTrap,
+    SpawnRegion,
+}
+
+impl ObjectKind {
+    const ALL: [ObjectKind; 19] = [
+        ObjectKind::Enemy,
+        ObjectKind::Item,
+        ObjectKind::Checkpoint,
+        ObjectKind::Projectile,
+        ObjectKind::Spawner,
+        ObjectKind::Chest,
+        ObjectKind::Door,
+        ObjectKind::Npc,
+        ObjectKind::Sign,
+        ObjectKind::Pickup,
+        ObjectKind::Portal,
+        ObjectKind::SavePoint,
+        ObjectKind::Breakable,
+        ObjectKind::PressurePlate,
+        ObjectKind::Gate,
+        ObjectKind::Lever,
+        ObjectKind::Lamp,
+        ObjectKind::SpikeTrap,
+        ObjectKind::SpawnRegion,
+    ];
+}
+
+/// What interacting with an object did, for the level and the main loop to
+/// follow up on.
+pub enum Interaction {
+    Nothing,
+    /// Opened for good, so the level should remember it.
+    Opened,
+    /// Opened a chest, which the level remembers the same way.
+    Looted,
+    /// Pulled a lever, so the level should flip its links.
+    Toggled,
+    Talk(Vec<DialoguePage>),
+    /// Asks whether to save the game.
+    Save,
+}
+
+/// Whether an object should stay in the level after its update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectStatus {
+    Alive,
+    Dead,
+}
+
+/// Which pass an object is drawn in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderLayer {
+    /// Y-sorted in with the player and tall tiles, under the overlay.
+    Ground,
+    /// Over the overlay, in list order, for things up in the air.
+    Above,
+}
+
+/// What an object can see and do during its update.
+pub struct UpdateCtx<'a> {
+    pub level: &'a Level,
+    pub player: &'a Player,
+    pub item_sheet: &'a ItemSheet,
+    pub dt: f32,
+    /// Objects to add once every object has updated, so the list isn't
+    /// changed mid-iteration.
+    pub spawns: &'a mut Vec<Box<dyn GameObject>>,
+    pub events: &'a mut EventBus,
+}
+
+/// Anything that lives in the level. `ObjectListing` describes what to
+/// place; this is what it resolves into. Passes that need one concrete type
+/// check `kind` and downcast.
+pub trait GameObject: Any {
+    fn body(&self) -> &Body;
+
+    fn kind(&self) -> ObjectKind;
+
+    /// Anything the object spawns, like shots or loot, goes through
+    /// `ctx.spawns`.
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus;
+
+    fn render(&self, world: &World);
+
+    fn render_layer(&self) -> RenderLayer {
+        RenderLayer::Ground
+    }
+
+    /// Runs before `update` each frame, for objects that hurt or can be hurt
+    /// by the player.
+    fn touch_player(&mut self, _player: &mut Player) {}
+
+    /// Drawn while the editor or the hitbox overlay is open, for objects
+    /// that are invisible during play or need tuning aids.
+    fn render_marker(&self, _world: &World) {}
+
+    /// Drawn over everything else while hitboxes are toggled on.
+    fn render_debug(&self, _world: &World) {}
+
+    /// Drawn in the above pass, over every object, so it stays readable.
+    fn render_indicator(&self, _world: &World) {}
+
+    /// Whether pressing interact right now would do anything.
+    fn can_interact(&self, _player: &Player) -> bool {
+        false
+    }
+
+    fn interact(&mut self, _player: &mut Player) -> Interaction {
+        Interaction::Nothing
+    }
+
+    /// The light the object gives off, centered on its body.
+    fn light(&self) -> Option<&LightEmitter> {
+        None
+    }
+
+    /// Runs instead of `touch_player` and `update` while the object is far
+    /// from the view. Timers keep ticking, but nothing thinks or moves, so
+    /// the object picks up where it left off when it wakes.
+    fn sleep(&mut self, _dt: f32) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+}
+
+/// Whether the player is facing `hitbox` with no more than a tile between
+/// them.
+pub fn in_reach(player: &Player, hitbox: &Rect) -> bool {
+    let reach = player.body.hitbox.offset(player.body.facing() * TILE_SIZE);
+    return reach.overlaps(hitbox);
+}
+
+fn downcast<T: GameObject>(object: &dyn GameObject) -> Option<&T> {
+    return (object as &dyn Any).downcast_ref();
+}
+
+fn downcast_mut<T: GameObject>(object: &mut dyn GameObject) -> Option<&mut T> {
+    return (object as &mut dyn Any).downcast_mut();
+}
+
+/// One thing to draw in the y-sorted pass, as an index into the list it
+/// came from.
+#[derive(Clone, Copy)]
+enum Drawable {
+    Object(usize),
+    Body(usize),
+    Tile(usize),
+    Particle(usize),
+}
+
+/// A live object and the listing it was spawned from, if any.
+struct SpawnedObject {
+    id: u64,
+    object: Box<dyn GameObject>,
+    /// Id of the listing, not its index.
+    listing: Option<u64>,
+    /// Id of the spawner that produced this object.
+    spawned_by: Option<u64>,
+    persistent: bool,
+    status: ObjectStatus,
+}
+
+/// Sheets, sprites and tuning shared by every object in the level.
+pub struct ObjectAssets {
+    item_sheet: ItemSheet,
+    chest_sheet: ChestSheet,
+    door_sheet: DoorSheet,
+    enemy_sprites: EnemySprites,
+    enemy_stats: EnemyStatsTable,
+    breakable_drops: BreakableDropsTable,
+    npc_sprites: NpcSprites,
+    sign_tex: Texture2D,
+}
+
+impl ObjectAssets {
+    /// Enemy and NPC sprites are loaded lazily as they are first needed.
+    pub async fn load() -> AssetManageResult<Self> {
+        return Ok(Self {
+            item_sheet: ItemSheet::load().await?,
+            chest_sheet: ChestSheet::load().await?,
+            door_sheet: DoorSheet::load().await?,
+            enemy_sprites: EnemySprites::new(),
+            enemy_stats: EnemyStatsTable::load()?,
+            breakable_drops: BreakableDropsTable::load()?,
+            npc_sprites: NpcSprites::new(),
+            sign_tex: Sign::load_tex().await?,
+        });
+    }
+
+    /// Loads the enemy or NPC sheet `sprite`, a path under the sprites
+    /// folder, ahead of its first use. Other sheets load with the rest of
+    /// the object assets anyway.
+    pub async fn preload_sprite(&mut self, sprite: &str) {
+        let Some((folder, file)) = sprite.split_once('/') else {
+            return;
+        };
+        let name = file.trim_end_matches(".png");
+        match folder {
+            "enemies" => {
+                let r#type = EnemyType::ALL
+                    .into_iter()
+                    .find(|r#type| r#type.file_name() == name);
+                if let Some(r#type) = r#type {
+                    self.enemy_sprites.get(r#type).await;
+                }
+            }
+            "npcs" => {
+                self.npc_sprites.get(name).await;
+            }
+            _ => {}
         }
     }
 }
 
 pub struct LevelObjects {
-    lst: Vec<Object>,
+    lst: Vec<SpawnedObject>,
+    next_id: u64,
+    assets: ObjectAssets,
+    /// Listings whose objects died or were collected since the last
+    /// `take_cleared`.
+    cleared: Vec<u64>,
+    /// Listings of the plates latched since the last `take_opened`.
+    opened: Vec<u64>,
+    links: LinkBus,
+    /// The y-sorted pass, kept between frames so `render` doesn't allocate.
+    draw_order: Vec<(i32, Drawable)>,
+    /// Objects more than this many screens outside the world view only
+    /// sleep.
+    sleep_screens: f32,
 }
 
 impl LevelObjects {
-    pub fn new() -> Self {
-        Self { lst: Vec::new() }
+    const DESPAWN_SCREENS: f32 = 1.5;
+    /// The default for `sleep_screens`.
+    pub const SLEEP_SCREENS: f32 = 0.5;
+    /// The furthest `set_sleep_screens` goes. Below `DESPAWN_SCREENS`, so
+    /// objects sleep before they despawn.
+    const MAX_SLEEP_SCREENS: f32 = Self::DESPAWN_SCREENS - 0.5;
+    /// Hit points lost in one hit that flash the screen red.
+    const BIG_HIT: u32 = 2;
+    const OUTLINE_COLOR: Color = Color::new(1.0, 0.95, 0.6, 1.0);
+    /// Radians a second the targeted object's outline pulses at.
+    const OUTLINE_PULSE_RATE: f32 = 5.0;
+
+    pub fn new(assets: ObjectAssets) -> Self {
+        Self {
+            lst: Vec::new(),
+            next_id: 0,
+            assets,
+            cleared: Vec::new(),
+            opened: Vec::new(),
+            links: LinkBus::new(),
+            draw_order: Vec::new(),
+            sleep_screens: Self::SLEEP_SCREENS,
+        }
     }
 
-    pub fn add_listing(&mut self, listing: &ObjectListing) {
-        self.lst.push(listing.resolve());
+    /// Sets how many screens outside the world view objects keep running
+    /// before they only sleep, kept between 0 and `MAX_SLEEP_SCREENS`.
+    pub fn set_sleep_screens(&mut self, screens: f32) {
+        self.sleep_screens = screens.clamp(0.0, Self::MAX_SLEEP_SCREENS);
     }
 
-    pub fn render(&mut self, other_bodies: &mut [&Body], world: &World) {
-        other_bodies.sort_by_key(|body| body.get_y_sort_key());
-        self.lst.sort_by_key(|obj| obj.get_y_sort_key());
+    /// How many objects are live in the level.
+    pub fn len(&self) -> usize {
+        return self.lst.len();
+    }
 
-        let mut obj_idx = 0;
-        let calc_obj_y = |idx: usize| match self.lst.get(idx) {
-            Some(obj) => Some(obj.get_y_sort_key()),
-            None => None,
-        };
-        let mut obj_y = calc_obj_y(obj_idx);
+    /// Spawns the object for `listing`.
+    pub async fn add_listing(&mut self, listing: &ObjectListing, opened: bool) {
+        if let Some(object) = listing.resolve(&mut self.assets, opened).await {
+            self.push(object, Some(listing.id), None, listing.persistent);
+        }
+    }
 
-        let mut body_idx = 0;
-        let calc_bodies_y = |idx: usize| match other_bodies.get(idx) {
-            Some(first) => Some(first.get_y_sort_key()),
-            None => None,
-        };
-        let mut bodies_y = calc_bodies_y(body_idx);
-
-        loop {
-            match (obj_y, bodies_y) {
-                (None, None) => break,
-                (None, Some(_)) => {
-                    other_bodies[body_idx].render(world);
-                    body_idx += 1;
-                    bodies_y = calc_bodies_y(body_idx);
+    pub fn reset(&mut self) {
+        self.lst.clear();
+        self.cleared.clear();
+        self.opened.clear();
+        self.links.clear();
+    }
+
+    /// Ids of the listings whose objects are gone for good since the last
+    /// call.
+    pub fn take_cleared(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.cleared)
+    }
+
+    /// Ids of the listings opened for good since the last call, other than
+    /// through `interact`.
+    pub fn take_opened(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.opened)
+    }
+
+    /// Link ids whose gates should be open.
+    pub fn open_links(&self) -> HashSet<String> {
+        return self.links.active();
+    }
+
+    pub fn item_sheet(&self) -> &ItemSheet {
+        &self.assets.item_sheet
+    }
+
+    /// Removes non-persistent objects more than `DESPAWN_SCREENS` screens
+    /// outside the world view, returning the ids of the listings they came
+    /// from so those listings can spawn them again later.
+    pub fn despawn_distant(&mut self, world: &World) -> Vec<u64> {
+        let keep_area = world.view(
+            world.w * Self::DESPAWN_SCREENS,
+            world.h * Self::DESPAWN_SCREENS,
+        );
+
+        let mut released = Vec::new();
+        self.lst.retain(|spawned| {
+            if spawned.persistent || spawned.object.body().hitbox.overlaps(&keep_area) {
+                return true;
+            }
+            released.extend(spawned.listing);
+            return false;
+        });
+
+        return released;
+    }
+
+    /// Adds an object that wasn't placed in the level, like a projectile.
+    /// It never respawns once gone.
+    pub fn spawn(&mut self, object: Box<dyn GameObject>) {
+        self.push(object, None, None, false);
+    }
+
+    fn push(
+        &mut self,
+        object: Box<dyn GameObject>,
+        listing: Option<u64>,
+        spawned_by: Option<u64>,
+        persistent: bool,
+    ) {
+        self.lst.push(SpawnedObject {
+            id: self.next_id,
+            object,
+            listing,
+            spawned_by,
+            persistent,
+            status: ObjectStatus::Alive,
+        });
+        self.next_id += 1;
+    }
+
+    /// Lets the player's swing land on enemies and hurts the player for each
+    /// enemy or projectile touching them, then updates every object. The
+    /// player's invincibility frames keep a long overlap from landing more
+    /// than one hit. Objects more than `sleep_screens` outside `world`
+    /// only sleep, and sleeping spawners don't spawn. Objects that report
+    /// themselves dead are removed after the
+    /// loop; their listings stay marked as spawned so they don't come back.
+    pub async fn update(
+        &mut self,
+        level: &Level,
+        player: &mut Player,
+        world: &World,
+        events: &mut EventBus,
+        dt: f32,
+    ) {
+        self.separate_flocks();
+        self.update_links(level, player, dt);
+
+        let mut alive: HashMap<u64, usize> = HashMap::new();
+        for spawner_id in self.lst.iter().filter_map(|spawned| spawned.spawned_by) {
+            *alive.entry(spawner_id).or_default() += 1;
+        }
+
+        let awake_area = world.view(world.w * self.sleep_screens, world.h * self.sleep_screens);
+
+        let mut spawns = Vec::new();
+        let mut spawner_requests = Vec::new();
+        for spawned in self.lst.iter_mut() {
+            let obj = spawned.object.as_mut();
+            match obj.body().hitbox.overlaps(&awake_area) {
+                true => {
+                    let hp = player.hp;
+                    obj.touch_player(player);
+                    if player.hp < hp {
+                        let lost = hp - player.hp;
+                        events.push(GameEvent::PlayerDamaged(lost));
+                        if lost >= Self::BIG_HIT {
+                            events.push(GameEvent::ScreenFlash {
+                                color: RED,
+                                alpha: 0.5,
+                                seconds: 0.3,
+                            });
+                        }
+                    }
+
+                    let mut ctx = UpdateCtx {
+                        level,
+                        player,
+                        item_sheet: &self.assets.item_sheet,
+                        dt,
+                        spawns: &mut spawns,
+                        events,
+                    };
+                    spawned.status = obj.update(&mut ctx);
+
+                    let alive = alive.get(&spawned.id).copied().unwrap_or(0);
+                    if let Some(spawner) = downcast_mut::<Spawner>(obj) {
+                        if spawner.tick(alive, dt) {
+                            spawner_requests.push((spawned.id, spawner.spawn_request(level)));
+                        }
+                    } else if let Some(region) = downcast_mut::<SpawnRegion>(obj) {
+                        if region.tick(alive, dt) {
+                            let request = region.spawn_request(level, player.body.hitbox.center());
+                            spawner_requests.push((spawned.id, request));
+                        }
+                    }
                 }
-                (Some(_), None) => {
-                    self.lst[obj_idx].render(world);
-                    obj_idx += 1;
-                    obj_y = calc_obj_y(obj_idx);
+                false => spawned.status = obj.sleep(dt),
+            }
+        }
+
+        self.cleared.extend(
+            self.lst
+                .iter()
+                .filter(|spawned| spawned.status == ObjectStatus::Dead)
+                .filter_map(|spawned| spawned.listing),
+        );
+        self.lst
+            .retain(|spawned| spawned.status == ObjectStatus::Alive);
+
+        for object in spawns {
+            self.spawn(object);
+        }
+
+        for (spawner_id, request) in spawner_requests {
+            let Some((enemy_type, position, seed)) = request else {
+                continue;
+            };
+            let sprite = self.assets.enemy_sprites.get(enemy_type).await;
+            let stats = self.assets.enemy_stats.get(enemy_type).clone();
+            let enemy = Enemy::new(
+                enemy_type, stats, position.x, position.y, sprite, seed, None,
+            );
+            self.push(Box::new(enemy), None, Some(spawner_id), false);
+        }
+    }
+
+    /// Presses plates under the player or an enemy, puts the links of
+    /// pressed plates and the level's switched levers on the bus, and opens
+    /// or shuts each gate to match.
+    fn update_links(&mut self, level: &Level, player: &Player, dt: f32) {
+        let bodies: Vec<Rect> = std::iter::once(player.body.hitbox)
+            .chain(
+                self.lst
+                    .iter()
+                    .filter_map(|spawned| downcast::<Enemy>(spawned.object.as_ref()))
+                    .map(|enemy| enemy.body.hitbox),
+            )
+            .collect();
+
+        self.links.tick(dt);
+        self.links.set_switched(level.switched_links());
+
+        for spawned in self.lst.iter_mut() {
+            if let Some(plate) = downcast_mut::<PressurePlate>(spawned.object.as_mut()) {
+                if plate.press(&bodies) {
+                    self.opened.extend(spawned.listing);
+                }
+                if plate.is_down() {
+                    self.links.hold(plate.link_id());
+                }
+            }
+        }
+
+        for spawned in self.lst.iter_mut() {
+            if let Some(gate) = downcast_mut::<Gate>(spawned.object.as_mut()) {
+                let open = self.links.is_active(gate.link_id());
+                gate.set_open(open);
+            }
+        }
+    }
+
+    /// Moves every enemy stuck inside something solid, like a gate that shut
+    /// on it, out to the nearest free cell.
+    pub fn free_bodies(&mut self, level: &Level) {
+        for spawned in self.lst.iter_mut() {
+            if let Some(enemy) = downcast_mut::<Enemy>(spawned.object.as_mut()) {
+                level.free_body(&mut enemy.body);
+            }
+        }
+    }
+
+    /// Hands every flocking enemy the offsets to its same-type neighbours.
+    /// Enemies sitting exactly on top of each other get a made-up offset,
+    /// opposite for each of the pair, so they still split up.
+    fn separate_flocks(&mut self) {
+        let mut grid = SpatialGrid::new(Enemy::SEPARATION_RADIUS);
+        let mut flock = Vec::new();
+        for (idx, spawned) in self.lst.iter().enumerate() {
+            if let Some(enemy) = downcast::<Enemy>(spawned.object.as_ref()) {
+                if enemy.is_flocking() {
+                    let center = enemy.body.hitbox.center();
+                    grid.insert(idx, center);
+                    flock.push((idx, enemy.r#type(), center));
                 }
-                (Some(obj), Some(body)) => match obj > body {
-                    true => {
-                        other_bodies[body_idx].render(world);
-                        body_idx += 1;
-                        bodies_y = calc_bodies_y(body_idx);
+            }
+        }
+
+        let centers: HashMap<usize, (EnemyType, Vec2)> = flock
+            .iter()
+            .map(|&(idx, r#type, center)| (idx, (r#type, center)))
+            .collect();
+        for &(idx, r#type, center) in flock.iter() {
+            let offsets: Vec<Vec2> = grid
+                .near(center)
+                .filter(|&other| other != idx && centers[&other].0 == r#type)
+                .map(|other| {
+                    let offset = center - centers[&other].1;
+                    if offset != Vec2::ZERO {
+                        return offset;
                     }
-                    false => {
-                        self.lst[body_idx].render(world);
-                        obj_idx += 1;
-                        obj_y = calc_obj_y(obj_idx);
+
+                    let angle = idx.min(other) as f32 * 2.4 + idx.max(other) as f32;
+                    let side = match idx < other {
+                        true => 1.0,
+                        false => -1.0,
+                    };
+                    return Vec2::from_angle(angle) * side * 0.01;
+                })
+                .collect();
+
+            if let Some(enemy) = downcast_mut::<Enemy>(self.lst[idx].object.as_mut()) {
+                enemy.set_separation(&offsets);
+            }
+        }
+    }
+
+    /// Moves every item pickup overlapping the player into their inventory.
+    pub fn collect_items(&mut self, player: &mut Player) {
+        self.lst.retain(|spawned| {
+            let Some(item) = downcast::<ItemPickup>(spawned.object.as_ref()) else {
+                return true;
+            };
+            if !item.body.hitbox.overlaps(&player.body.hitbox) {
+                return true;
+            }
+
+            player.inventory.add(item.item, 1);
+            self.cleared.extend(spawned.listing);
+            return false;
+        });
+    }
+
+    /// Interacts with the first object the player can, returning what that
+    /// did along with the id of the listing the object was placed from.
+    pub fn interact(&mut self, player: &mut Player) -> Option<(Interaction, Option<u64>)> {
+        let spawned = self
+            .lst
+            .iter_mut()
+            .find(|spawned| spawned.object.can_interact(player))?;
+
+        return Some((spawned.object.interact(player), spawned.listing));
+    }
+
+    /// Id of the object `interact` would pick, for highlighting it.
+    pub fn targeted(&self, player: &Player) -> Option<u64> {
+        return self
+            .lst
+            .iter()
+            .find(|spawned| spawned.object.can_interact(player))
+            .map(|spawned| spawned.id);
+    }
+
+    /// Activates the checkpoint the player is standing on and keeps every
+    /// checkpoint's highlight in sync with the player's respawn point.
+    pub fn touch_checkpoints(&mut self, player: &mut Player, level_name: &str) {
+        for spawned in self.lst.iter_mut() {
+            if let Some(checkpoint) = downcast_mut::<Checkpoint>(spawned.object.as_mut()) {
+                let point = checkpoint.respawn_point(level_name);
+                if checkpoint.body.hitbox.overlaps(&player.body.hitbox) {
+                    player.checkpoint = Some(point.clone());
+                }
+                checkpoint.active = player.checkpoint.as_ref() == Some(&point);
+            }
+        }
+    }
+
+    /// Where the portal the player just stepped onto leads, if any.
+    pub fn touch_portals(&self, player: &Player) -> Option<WarpTarget> {
+        return self.lst.iter().find_map(|spawned| {
+            downcast::<Portal>(spawned.object.as_ref())
+                .and_then(|portal| portal.triggered(&player.body.hitbox))
+                .cloned()
+        });
+    }
+
+    /// Markers for objects that are invisible during play, drawn while the
+    /// editor or the hitbox overlay is open.
+    pub fn render_markers(&self, world: &World) {
+        for spawned in self.lst.iter() {
+            spawned.object.render_marker(world);
+        }
+    }
+
+    /// Draws the visible objects on the above layer, then every visible
+    /// object's indicators. Call after the overlay layer.
+    pub fn render_above(&self, world: &World) {
+        for spawned in self.visible(world) {
+            if spawned.object.render_layer() == RenderLayer::Above {
+                spawned.object.render(world);
+            }
+        }
+
+        for spawned in self.visible(world) {
+            spawned.object.render_indicator(world);
+        }
+    }
+
+    /// This frame's lights from the player's lantern and every emitting
+    /// object, leaving out any that don't reach the world view.
+    pub fn lights(&self, player: &Player, world: &World) -> Vec<Light> {
+        let view = world.view(0.0, 0.0);
+        let lantern = Player::LANTERN.light_at(player.body.hitbox.center());
+        return self
+            .lst
+            .iter()
+            .filter_map(|spawned| {
+                let emitter = spawned.object.light()?;
+                Some(emitter.light_at(spawned.object.body().hitbox.center()))
+            })
+            .chain(std::iter::once(lantern))
+            .filter(|light| light.reaches(&view))
+            .collect();
+    }
+
+    /// Debug overlay drawn over everything else while hitboxes are toggled on
+    /// in the editor, topped with a count of live objects of each kind.
+    pub fn render_debug(&self, player: &Player, world: &World) {
+        for spawned in self.lst.iter() {
+            spawned.object.render_debug(world);
+        }
+
+        for light in self.lights(player, world) {
+            light.render_debug(world);
+        }
+
+        let counts: Vec<String> = ObjectKind::ALL
+            .iter()
+            .map(|kind| {
+                let count = self
+                    .lst
+                    .iter()
+                    .filter(|spawned| spawned.object.kind() == *kind)
+                    .count();
+                format!("{kind:?}:{count}")
+            })
+            .collect();
+        draw_text(&counts.join(" "), 2.0, VIRTUAL_H - 4.0, 8.0, WHITE);
+    }
+
+    /// Draws `object` solid a pixel out each way, leaving a pulsing 1px
+    /// outline around it once it's drawn over.
+    fn render_outline(object: &dyn GameObject, world: &World) {
+        let pulse = ((get_time() as f32 * Self::OUTLINE_PULSE_RATE).sin() + 1.0) / 2.0;
+        let brightness = 0.7 + 0.3 * pulse;
+        let color = Color::new(
+            Self::OUTLINE_COLOR.r * brightness,
+            Self::OUTLINE_COLOR.g * brightness,
+            Self::OUTLINE_COLOR.b * brightness,
+            1.0,
+        );
+
+        for (x, y) in [(-1.0, 0.0), (1.0, 0.0), (0.0, -1.0), (0.0, 1.0)] {
+            let shifted = World {
+                x: world.x + x,
+                y: world.y + y,
+                ..world.clone()
+            };
+            draw_flashing(color, || object.render(&shifted));
+        }
+    }
+
+    /// Objects whose hitbox is within a tile of the world view, in list
+    /// order. Anything further out can't have a pixel on screen.
+    fn visible<'a>(&'a self, world: &World) -> impl Iterator<Item = &'a SpawnedObject> {
+        let view = world.view(TILE_SIZE, TILE_SIZE);
+        return self
+            .lst
+            .iter()
+            .filter(move |spawned| spawned.object.body().hitbox.overlaps(&view));
+    }
+
+    /// Draws the visible ground objects y-sorted in with `other_bodies`, the
+    /// level's tall `tiles` and the world-layer particles. Ties go to
+    /// objects first, then bodies, then tiles. The object with id
+    /// `targeted` gets an outline.
+    pub fn render(
+        &mut self,
+        other_bodies: &[&Body],
+        tiles: &[SortedTile],
+        particles: &ParticleSystem,
+        targeted: Option<u64>,
+        world: &World,
+    ) {
+        self.sort_drawables(other_bodies, tiles, particles, world);
+
+        for &(_, drawable) in self.draw_order.iter() {
+            match drawable {
+                Drawable::Object(i) => {
+                    let spawned = &self.lst[i];
+                    if targeted == Some(spawned.id) {
+                        Self::render_outline(spawned.object.as_ref(), world);
                     }
-                },
+                    spawned.object.render(world);
+                }
+                Drawable::Body(i) => other_bodies[i].render(world),
+                Drawable::Tile(i) => tiles[i].render(world),
+                Drawable::Particle(i) => particles.particle(i).render(world),
+            }
+        }
+    }
+
+    /// Fills `draw_order` with this frame's y-sorted pass for `render`.
+    fn sort_drawables(
+        &mut self,
+        other_bodies: &[&Body],
+        tiles: &[SortedTile],
+        particles: &ParticleSystem,
+        world: &World,
+    ) {
+        let view = world.view(TILE_SIZE, TILE_SIZE);
+        self.draw_order.clear();
+        self.draw_order.extend(
+            self.lst
+                .iter()
+                .enumerate()
+                .filter(|(_, spawned)| {
+                    spawned.object.render_layer() == RenderLayer::Ground
+                        && spawned.object.body().hitbox.overlaps(&view)
+                })
+                .map(|(i, spawned)| (spawned.object.body().get_y_sort_key(), Drawable::Object(i))),
+        );
+        self.draw_order.extend(
+            other_bodies
+                .iter()
+                .enumerate()
+                .map(|(i, body)| (body.get_y_sort_key(), Drawable::Body(i))),
+        );
+        self.draw_order.extend(
+            tiles
+                .iter()
+                .enumerate()
+                .map(|(i, tile)| (tile.y_sort_key(), Drawable::Tile(i))),
+        );
+        self.draw_order.extend(
+            particles
+                .world_particles()
+                .map(|(i, particle)| (particle.y_sort_key(), Drawable::Particle(i))),
+        );
+        self.draw_order.sort_by_key(|(key, _)| *key);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::{
+        asset_loading::tests::stand_in_tex,
+        chest::tests::chest_sheet,
+        door::tests::door_sheet,
+        enemies::tests::no_sprites,
+        items::tests::item_sheet,
+        levels::tests::{level, place},
+        player::tests::player_at,
+    };
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    /// Runs `future`, which mustn't wait on anything, like the next frame.
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        return match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("waited on something tests can't provide"),
+        };
+    }
+
+    /// What one update of an object left behind.
+    pub struct Updated {
+        pub status: ObjectStatus,
+        pub spawns: Vec<Box<dyn GameObject>>,
+        pub events: Vec<GameEvent>,
+    }
+
+    /// Updates `object` once, the way `LevelObjects` would.
+    pub fn update(object: &mut dyn GameObject, level: &Level, player: &Player, dt: f32) -> Updated {
+        let sheet = item_sheet();
+        let mut spawns = Vec::new();
+        let mut events = EventBus::new();
+        let status = object.update(&mut UpdateCtx {
+            level,
+            player,
+            item_sheet: &sheet,
+            dt,
+            spawns: &mut spawns,
+            events: &mut events,
+        });
+        return Updated {
+            status,
+            spawns,
+            events: events.drain(),
+        };
+    }
+
+    /// The one live object, as a `T`.
+    pub fn only_object<T: GameObject>(objects: &mut LevelObjects) -> &mut T {
+        assert_eq!(objects.lst.len(), 1);
+        return downcast_mut(objects.lst[0].object.as_mut()).unwrap();
+    }
+
+    /// The shipped tuning with stand-in sheets and no enemy sprites, so
+    /// nothing needs a window. NPCs still load their sprites.
+    pub fn object_assets() -> ObjectAssets {
+        return ObjectAssets {
+            item_sheet: item_sheet(),
+            chest_sheet: chest_sheet(),
+            door_sheet: door_sheet(),
+            enemy_sprites: no_sprites(),
+            enemy_stats: EnemyStatsTable::load().unwrap(),
+            breakable_drops: BreakableDropsTable::load().unwrap(),
+            npc_sprites: NpcSprites::new(),
+            sign_tex: stand_in_tex(),
+        };
+    }
+
+    /// Spawns `count` enemies of `r#type` on one point, all with the same
+    /// seed, and runs a second of updates with the player far away.
+    fn stack_and_tick(r#type: EnemyType, count: usize) -> Vec<Vec2> {
+        let level = level(&["............"; 12]);
+        let mut player = player_at(vec2(1000.0, 1000.0));
+        let mut world = World::new();
+        world.center_on(vec2(96.0, 96.0));
+        let mut events = EventBus::new();
+
+        let mut objects = LevelObjects::new(object_assets());
+        for _ in 0..count {
+            let stats = objects.assets.enemy_stats.get(r#type).clone();
+            let enemy = Enemy::new(r#type, stats, 96.0, 96.0, None, 0, None);
+            objects.spawn(Box::new(enemy));
+        }
+        for _ in 0..60 {
+            block_on(objects.update(&level, &mut player, &world, &mut events, FRAME));
+        }
+
+        return objects
+            .lst
+            .iter()
+            .map(|spawned| spawned.object.body().hitbox.center())
+            .collect();
+    }
+
+    #[test]
+    fn stacked_flocks_spread_out_within_a_second() {
+        let centers = stack_and_tick(EnemyType::PurpleBlob, 3);
+        assert_eq!(centers.len(), 3);
+        for (idx, a) in centers.iter().enumerate() {
+            for b in &centers[idx + 1..] {
+                assert!(a.distance(*b) >= Enemy::SIZE, "{centers:?}");
             }
         }
     }
+
+    #[test]
+    fn only_flocking_types_are_pushed_apart() {
+        let centers = stack_and_tick(EnemyType::CopperOrb, 3);
+        assert_eq!(centers.len(), 3);
+        assert!(
+            centers.iter().all(|center| *center == centers[0]),
+            "{centers:?}"
+        );
+    }
+
+    #[test]
+    fn the_y_sort_pass_reuses_its_buffer() {
+        let mut objects = LevelObjects::new(object_assets());
+        for y in [120.0, 40.0, 1000.0] {
+            let stats = objects.assets.enemy_stats.get(EnemyType::CopperOrb).clone();
+            let enemy = Enemy::new(EnemyType::CopperOrb, stats, 96.0, y, None, 0, None);
+            objects.spawn(Box::new(enemy));
+        }
+        let player = player_at(vec2(96.0, 80.0));
+        let particles = ParticleSystem::new();
+        let world = World::new();
+
+        objects.sort_drawables(&[&player.body], &[], &particles, &world);
+        let buffer = objects.draw_order.as_ptr();
+        let capacity = objects.draw_order.capacity();
+        objects.sort_drawables(&[&player.body], &[], &particles, &world);
+
+        assert_eq!(objects.draw_order.as_ptr(), buffer);
+        assert_eq!(objects.draw_order.capacity(), capacity);
+        let order: Vec<_> = objects
+            .draw_order
+            .iter()
+            .map(|(_, drawable)| match drawable {
+                Drawable::Object(i) => format!("object {i}"),
+                Drawable::Body(i) => format!("body {i}"),
+                Drawable::Tile(i) => format!("tile {i}"),
+                Drawable::Particle(i) => format!("particle {i}"),
+            })
+            .collect();
+        assert_eq!(order, ["object 1", "body 0", "object 0"]);
+    }
+
+    /// Pans the camera across `cols` columns and back `times` times, doing
+    /// the spawn bookkeeping the main loop does each frame.
+    fn sweep(level: &mut Level, objects: &mut LevelObjects, cols: usize, times: usize) {
+        let mut world = World::new();
+        let far = cols as f32 * TILE_SIZE;
+        for _ in 0..times {
+            for step in (0..=cols * 4).chain((0..=cols * 4).rev()) {
+                world.x = far * step as f32 / (cols * 4) as f32;
+                level.clear_listings(&objects.take_cleared());
+                level.release_spawns(&objects.despawn_distant(&world));
+                block_on(level.spawn_objects(&world, objects));
+                assert!(enemies(objects) <= 1, "duplicated at x {}", world.x);
+            }
+        }
+    }
+
+    /// How many enemies are live, leaving out anything they dropped.
+    fn enemies(objects: &LevelObjects) -> usize {
+        return objects
+            .lst
+            .iter()
+            .filter(|spawned| spawned.object.kind() == ObjectKind::Enemy)
+            .count();
+    }
+
+    /// A level wider than the sweep's view with a blob past the first
+    /// screen, which `sweep` over 40 columns pans into view and back out.
+    fn swept_level() -> Level {
+        let mut level = level(&[".".repeat(64).as_str(); 16]);
+        place(&mut level, 8, 30, ObjectType::Enemy(EnemyType::PurpleBlob));
+        return level;
+    }
+
+    /// Kills the enemy in `objects` and runs the update that removes it,
+    /// returning the events that update raised.
+    fn kill_enemy(level: &Level, objects: &mut LevelObjects) -> Vec<GameEvent> {
+        let enemy = downcast_mut::<Enemy>(objects.lst[0].object.as_mut()).unwrap();
+        enemy.take_damage(u32::MAX, Vec2::ZERO, 0.0);
+
+        let mut player = player_at(vec2(1000.0, 1000.0));
+        let mut world = World::new();
+        world.center_on(cell_center(8, 30));
+        let mut events = EventBus::new();
+        block_on(objects.update(level, &mut player, &world, &mut events, FRAME));
+        assert_eq!(enemies(objects), 0);
+        return events.drain();
+    }
+
+    /// How many of `events` are a PurpleBlob dying.
+    fn blob_deaths(events: &[GameEvent]) -> usize {
+        return events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    GameEvent::EnemyDied {
+                        kind: EnemyType::PurpleBlob,
+                        ..
+                    }
+                )
+            })
+            .count();
+    }
+
+    #[test]
+    fn killing_an_enemy_announces_it_once() {
+        let mut level = swept_level();
+        let mut objects = LevelObjects::new(object_assets());
+        sweep(&mut level, &mut objects, 40, 1);
+        let events = kill_enemy(&level, &mut objects);
+        assert_eq!(blob_deaths(&events), 1, "{events:?}");
+
+        let mut player = player_at(vec2(1000.0, 1000.0));
+        let mut world = World::new();
+        world.center_on(cell_center(8, 30));
+        let mut events = EventBus::new();
+        block_on(objects.update(&level, &mut player, &world, &mut events, FRAME));
+        let events = events.drain();
+        assert_eq!(blob_deaths(&events), 0, "{events:?}");
+    }
+
+    #[test]
+    fn sweeping_over_a_listing_spawns_it_once() {
+        let mut level = swept_level();
+        let mut objects = LevelObjects::new(object_assets());
+        sweep(&mut level, &mut objects, 40, 5);
+
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects.next_id, 1);
+        assert_eq!(level.spawn_counts(), (1, 1));
+    }
+
+    #[test]
+    fn killed_listings_stay_dead() {
+        let mut level = swept_level();
+        let mut objects = LevelObjects::new(object_assets());
+        sweep(&mut level, &mut objects, 40, 1);
+        kill_enemy(&level, &mut objects);
+
+        sweep(&mut level, &mut objects, 40, 3);
+        assert_eq!(enemies(&objects), 0);
+        assert_eq!(level.spawn_counts(), (1, 1));
+    }
+
+    #[test]
+    fn reset_forgets_what_was_spawned_and_killed() {
+        let mut level = swept_level();
+        let mut objects = LevelObjects::new(object_assets());
+        sweep(&mut level, &mut objects, 40, 1);
+        kill_enemy(&level, &mut objects);
+
+        // Going back to a checkpoint, as the main loop does.
+        objects.reset();
+        level.reset_spawns();
+        assert_eq!(objects.len(), 0);
+        assert!(objects.take_cleared().is_empty());
+        assert_eq!(level.spawn_counts(), (0, 1));
+
+        sweep(&mut level, &mut objects, 40, 3);
+        assert_eq!(objects.len(), 1);
+        assert_eq!(enemies(&objects), 1);
+    }
+
+    /// Dies on its third update, leaving a shell behind.
+    struct ShortLived {
+        body: Body,
+        updates: u32,
+    }
+
+    impl GameObject for ShortLived {
+        fn body(&self) -> &Body {
+            &self.body
+        }
+
+        fn kind(&self) -> ObjectKind {
+            ObjectKind::Breakable
+        }
+
+        fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+            self.updates += 1;
+            if self.updates < 3 {
+                return ObjectStatus::Alive;
+            }
+
+            let Vec2 { x, y } = self.body.hitbox.center();
+            let shell = ItemPickup::new(ItemId::Shell, x, y, ctx.item_sheet);
+            ctx.spawns.push(Box::new(shell));
+            return ObjectStatus::Dead;
+        }
+
+        fn render(&self, _world: &World) {}
+    }
+
+    #[test]
+    fn dead_objects_leave_after_their_update_and_their_loot_stays() {
+        let level = level(&["........"; 8]);
+        let mut player = player_at(vec2(1000.0, 1000.0));
+        let mut world = World::new();
+        world.center_on(vec2(64.0, 64.0));
+        let mut events = EventBus::new();
+
+        let mut objects = LevelObjects::new(object_assets());
+        let short_lived = ShortLived {
+            body: Body::new(64.0, 64.0, TILE_SIZE, TILE_SIZE, None),
+            updates: 0,
+        };
+        objects.push(Box::new(short_lived), Some(7), None, false);
+
+        let kinds = |objects: &LevelObjects| -> Vec<ObjectKind> {
+            return objects
+                .lst
+                .iter()
+                .map(|spawned| spawned.object.kind())
+                .collect();
+        };
+        for _ in 0..2 {
+            block_on(objects.update(&level, &mut player, &world, &mut events, FRAME));
+            assert_eq!(kinds(&objects), [ObjectKind::Breakable]);
+        }
+        for _ in 0..2 {
+            block_on(objects.update(&level, &mut player, &world, &mut events, FRAME));
+            assert_eq!(kinds(&objects), [ObjectKind::Item]);
+        }
+
+        let shell = downcast::<ItemPickup>(objects.lst[0].object.as_ref()).unwrap();
+        assert_eq!(shell.item, ItemId::Shell);
+        assert_eq!(shell.body.hitbox.center(), vec2(64.0, 64.0));
+        // Its listing is done for good, and the shell was never listed.
+        assert_eq!(objects.take_cleared(), [7]);
+        assert_eq!(objects.lst[0].listing, None);
+    }
 }
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use crate::{body::Body, levels::Level};
+
+/// Fades the overlay tiles over the player, like a roof or canopy they've
+/// walked under, so they stay in sight.
+pub struct OverlayFade {
+    /// Alpha of each overlay cell that isn't fully opaque, by `(row, col)`.
+    alphas: HashMap<(usize, usize), f32>,
+}
+
+impl OverlayFade {
+    /// Alpha of the tiles over the player once faded.
+    const FADED_ALPHA: f32 = 0.4;
+    const FADE_SECONDS: f32 = 0.2;
+
+    pub fn new() -> Self {
+        Self {
+            alphas: HashMap::new(),
+        }
+    }
+
+    /// Shows every overlay tile straight away, for a fresh level.
+    pub fn reset(&mut self) {
+        self.alphas.clear();
+    }
+
+    /// Eases the cluster of overlay tiles over `body` out and every other
+    /// tile back in.
+    pub fn update(&mut self, level: &Level, body: &Body, dt: f32) {
+        let covering = level.overlay_cluster(&body.hitbox);
+        for &cell in &covering {
+            self.alphas.entry(cell).or_insert(1.0);
+        }
+
+        let step = (1.0 - Self::FADED_ALPHA) / Self::FADE_SECONDS * dt;
+        self.alphas.retain(|cell, alpha| {
+            *alpha = match covering.contains(cell) {
+                true => (*alpha - step).max(Self::FADED_ALPHA),
+                false => (*alpha + step).min(1.0),
+            };
+            return *alpha < 1.0;
+        });
+    }
+
+    pub fn alphas(&self) -> &HashMap<(usize, usize), f32> {
+        &self.alphas
+    }
+}
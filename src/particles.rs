@@ -0,0 +1,210 @@
+use macroquad::{
+    color::{Color, WHITE},
+    math::{vec2, Rect, Vec2},
+    rand::gen_range,
+    shapes::draw_rectangle,
+    text::draw_text,
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+};
+
+use crate::{world::World, VIRTUAL_H, VIRTUAL_W};
+
+/// Where particles are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParticleLayer {
+    /// Y-sorted in with the bodies and objects.
+    World,
+    /// Over everything in the world, like sparkles on the HUD.
+    Above,
+}
+
+/// How an emitter's particles start out and change over their life.
+#[derive(Debug, Clone)]
+pub struct EmitterDesc {
+    /// Particles per call to `ParticleSystem::burst`.
+    pub burst: usize,
+    /// Seconds each particle lives, picked between the two.
+    pub lifetime: (f32, f32),
+    /// Starting speed, picked between the two.
+    pub speed: (f32, f32),
+    /// Angle in radians of the middle of the cone particles fly out in.
+    pub direction: f32,
+    /// Width in radians of that cone. A full turn sprays every way.
+    pub spread: f32,
+    /// Downward acceleration in pixels per second squared.
+    pub gravity: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+    /// Draw this part of a texture instead of a square.
+    pub sprite: Option<(Texture2D, Rect)>,
+    pub layer: ParticleLayer,
+}
+
+pub struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    gravity: f32,
+    start_color: Color,
+    end_color: Color,
+    start_size: f32,
+    end_size: f32,
+    sprite: Option<(Texture2D, Rect)>,
+    layer: ParticleLayer,
+}
+
+impl Particle {
+    fn new(desc: &EmitterDesc, position: Vec2) -> Self {
+        let half = desc.spread / 2.0;
+        let angle = desc.direction + gen_range(-half, half);
+        let speed = gen_range(desc.speed.0, desc.speed.1);
+        Self {
+            position,
+            velocity: Vec2::from_angle(angle) * speed,
+            age: 0.0,
+            lifetime: gen_range(desc.lifetime.0, desc.lifetime.1).max(0.01),
+            gravity: desc.gravity,
+            start_color: desc.start_color,
+            end_color: desc.end_color,
+            start_size: desc.start_size,
+            end_size: desc.end_size,
+            sprite: desc.sprite.clone(),
+            layer: desc.layer,
+        }
+    }
+
+    pub fn y_sort_key(&self) -> i32 {
+        (self.position.y * 100.0) as i32
+    }
+
+    pub fn render(&self, world: &World) {
+        let t = self.age / self.lifetime;
+        let size = self.start_size + (self.end_size - self.start_size) * t;
+        let color = Color::new(
+            self.start_color.r + (self.end_color.r - self.start_color.r) * t,
+            self.start_color.g + (self.end_color.g - self.start_color.g) * t,
+            self.start_color.b + (self.end_color.b - self.start_color.b) * t,
+            self.start_color.a + (self.end_color.a - self.start_color.a) * t,
+        );
+        let x = self.position.x - world.x - size / 2.0;
+        let y = self.position.y - world.y - size / 2.0;
+
+        match &self.sprite {
+            Some((texture, source)) => draw_texture_ex(
+                texture,
+                x,
+                y,
+                color,
+                DrawTextureParams {
+                    dest_size: Some(vec2(size, size)),
+                    source: Some(*source),
+                    ..Default::default()
+                },
+            ),
+            None => draw_rectangle(x, y, size, size, color),
+        }
+    }
+}
+
+/// Every live particle, in one pool allocated up front. Dead particles are
+/// swapped out, so nothing is allocated once the game is running.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    /// Sideways push on every particle, in pixels per second squared.
+    wind: f32,
+}
+
+impl ParticleSystem {
+    /// Past this many live particles, new ones are dropped.
+    const CAPACITY: usize = 4096;
+
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::with_capacity(Self::CAPACITY),
+            wind: 0.0,
+        }
+    }
+
+    pub fn set_wind(&mut self, wind: f32) {
+        self.wind = wind;
+    }
+
+    fn emit(&mut self, desc: &EmitterDesc, position: Vec2) {
+        if self.particles.len() < Self::CAPACITY {
+            self.particles.push(Particle::new(desc, position));
+        }
+    }
+
+    /// Emits `desc.burst` particles at `position` at once.
+    pub fn burst(&mut self, desc: &EmitterDesc, position: Vec2) {
+        for _ in 0..desc.burst {
+            self.emit(desc, position);
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let mut idx = 0;
+        while idx < self.particles.len() {
+            let particle = &mut self.particles[idx];
+            particle.age += dt;
+            if particle.age >= particle.lifetime {
+                self.particles.swap_remove(idx);
+                continue;
+            }
+
+            particle.velocity += vec2(self.wind, particle.gravity) * dt;
+            particle.position += particle.velocity * dt;
+            idx += 1;
+        }
+    }
+
+    /// Particles drawn y-sorted in with the world, with their index for
+    /// `particle`.
+    pub fn world_particles(&self) -> impl Iterator<Item = (usize, &Particle)> {
+        return self
+            .particles
+            .iter()
+            .enumerate()
+            .filter(|(_, particle)| particle.layer == ParticleLayer::World);
+    }
+
+    pub fn particle(&self, index: usize) -> &Particle {
+        return &self.particles[index];
+    }
+
+    /// Draws the above-layer particles. Call after the world is drawn.
+    pub fn render_above(&self, world: &World) {
+        for particle in self.particles.iter() {
+            if particle.layer == ParticleLayer::Above {
+                particle.render(world);
+            }
+        }
+    }
+
+    /// How many particles are live, for the debug overlay.
+    pub fn render_debug(&self) {
+        let text = format!("Particles:{}", self.particles.len());
+        draw_text(&text, VIRTUAL_W - 64.0, VIRTUAL_H - 20.0, 8.0, WHITE);
+    }
+}
+
+/// Sparks thrown every way, for testing and for things popping.
+pub fn spark_burst(layer: ParticleLayer) -> EmitterDesc {
+    EmitterDesc {
+        burst: 40,
+        lifetime: (0.3, 0.7),
+        speed: (30.0, 90.0),
+        direction: 0.0,
+        spread: std::f32::consts::TAU,
+        gravity: 120.0,
+        start_color: Color::new(1.0, 0.9, 0.5, 1.0),
+        end_color: Color::new(1.0, 0.4, 0.1, 0.0),
+        start_size: 2.0,
+        end_size: 0.5,
+        sprite: None,
+        layer,
+    }
+}
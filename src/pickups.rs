@@ -0,0 +1,186 @@
+use macroquad::{
+    color::{Color, GOLD, RED, WHITE, YELLOW},
+    math::{vec2, Vec2},
+    shapes::{draw_circle, draw_line, draw_triangle},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::Body,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    world::World,
+    TILE_SIZE,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    /// Adds one to the player's coin count.
+    Coin,
+    /// Restores one heart. Left alone while the player is at full health.
+    Heart,
+}
+
+impl PickupKind {
+    pub const ALL: [PickupKind; 2] = [PickupKind::Coin, PickupKind::Heart];
+
+    /// Whether the player would get anything out of it right now.
+    fn wanted_by(&self, player: &Player) -> bool {
+        match self {
+            PickupKind::Coin => true,
+            PickupKind::Heart => player.hp < player.max_hp,
+        }
+    }
+}
+
+enum PickupState {
+    Idle,
+    /// Flying at the player.
+    Seeking(Vec2),
+    /// Collected, showing the sparkle before it's removed.
+    Sparkling(f32),
+}
+
+/// A coin or heart sitting in the level. Once the player is close it flies
+/// straight at them, ignoring walls, and is collected on contact.
+pub struct Pickup {
+    pub body: Body,
+    kind: PickupKind,
+    state: PickupState,
+    time: f32,
+}
+
+impl Pickup {
+    pub const SIZE: f32 = 6.0;
+    const MAGNET_RADIUS: f32 = TILE_SIZE * 1.5;
+    const MAGNET_ACCELERATION: f32 = 600.0;
+    const BOB_HEIGHT: f32 = 1.5;
+    const BOB_SPEED: f32 = 3.0;
+    const SPARKLE_SECONDS: f32 = 0.25;
+    const SPARKLE_RADIUS: f32 = 6.0;
+
+    pub fn new(kind: PickupKind, x: f32, y: f32) -> Self {
+        Self {
+            body: Body::new(x, y, Self::SIZE, Self::SIZE, None),
+            kind,
+            state: PickupState::Idle,
+            time: 0.0,
+        }
+    }
+
+    fn render_coin(&self, center: Vec2) {
+        draw_circle(center.x, center.y, Self::SIZE / 2.0, GOLD);
+        draw_circle(center.x - 1.0, center.y - 1.0, 1.0, YELLOW);
+    }
+
+    fn render_heart(&self, center: Vec2) {
+        let r = Self::SIZE / 4.0;
+        let (x, y) = (center.x - Self::SIZE / 2.0, center.y - Self::SIZE / 2.0);
+        draw_circle(x + r, y + r, r, RED);
+        draw_circle(x + r * 3.0, y + r, r, RED);
+        draw_triangle(
+            vec2(x, y + r),
+            vec2(x + Self::SIZE, y + r),
+            vec2(x + Self::SIZE / 2.0, y + Self::SIZE),
+            RED,
+        );
+    }
+}
+
+impl GameObject for Pickup {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Pickup
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        self.time += ctx.dt;
+        let player = ctx.player;
+        let to_player = player.body.hitbox.center() - self.body.hitbox.center();
+
+        match self.state {
+            PickupState::Sparkling(time) => {
+                if time + ctx.dt >= Self::SPARKLE_SECONDS {
+                    return ObjectStatus::Dead;
+                }
+                self.state = PickupState::Sparkling(time + ctx.dt);
+            }
+            PickupState::Idle => {
+                if to_player.length() <= Self::MAGNET_RADIUS && self.kind.wanted_by(player) {
+                    self.state = PickupState::Seeking(Vec2::ZERO);
+                }
+            }
+            PickupState::Seeking(velocity) => {
+                let velocity =
+                    velocity + to_player.normalize_or_zero() * Self::MAGNET_ACCELERATION * ctx.dt;
+                let center = self.body.hitbox.center() + velocity * ctx.dt;
+                self.body.set_center(center);
+                self.state = match self.kind.wanted_by(player) {
+                    true => PickupState::Seeking(velocity),
+                    false => PickupState::Idle,
+                };
+            }
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    /// Keeps bobbing and finishes a sparkle, but stops seeking.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        self.time += dt;
+        if let PickupState::Sparkling(time) = self.state {
+            if time + dt >= Self::SPARKLE_SECONDS {
+                return ObjectStatus::Dead;
+            }
+            self.state = PickupState::Sparkling(time + dt);
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    fn touch_player(&mut self, player: &mut Player) {
+        if matches!(self.state, PickupState::Sparkling(_))
+            || !self.kind.wanted_by(player)
+            || !self.body.hitbox.overlaps(&player.body.hitbox)
+        {
+            return;
+        }
+
+        match self.kind {
+            PickupKind::Coin => player.coins += 1,
+            PickupKind::Heart => player.hp += 1,
+        }
+        self.state = PickupState::Sparkling(0.0);
+    }
+
+    fn render(&self, world: &World) {
+        let mut center = self.body.hitbox.center() - vec2(world.x, world.y);
+
+        if let PickupState::Sparkling(time) = self.state {
+            let progress = time / Self::SPARKLE_SECONDS;
+            let color = Color {
+                a: 1.0 - progress,
+                ..WHITE
+            };
+            for idx in 0..4 {
+                let direction = Vec2::from_angle(idx as f32 * std::f32::consts::FRAC_PI_2);
+                let inner = center + direction * Self::SPARKLE_RADIUS * progress * 0.5;
+                let outer = center + direction * Self::SPARKLE_RADIUS * progress;
+                draw_line(inner.x, inner.y, outer.x, outer.y, 1.0, color);
+            }
+            return;
+        }
+
+        if let PickupState::Idle = self.state {
+            center.y -= ((self.time * Self::BOB_SPEED).sin() + 1.0) * Self::BOB_HEIGHT;
+        }
+
+        match self.kind {
+            PickupKind::Coin => self.render_coin(center),
+            PickupKind::Heart => self.render_heart(center),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::{cell::OnceCell, collections::HashMap};
+
+use macroquad::{
+    color::Color,
+    math::{vec2, Rect},
+    text::{draw_text, measure_text},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::asset_loading::{load_tex_with_meta, AssetManageResult};
+
+#[derive(Serialize, Deserialize)]
+struct GlyphSerializable {
+    rect: [f32; 4],
+    advance: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PixelFontSerializable {
+    line_height: f32,
+    letter_spacing: f32,
+    /// Pixels from the top of a glyph down to where letters sit.
+    baseline: f32,
+    fallback: char,
+    /// Extra advance between pairs of letters, keyed by the pair.
+    #[serde(default)]
+    kerning: HashMap<String, f32>,
+    glyphs: HashMap<char, GlyphSerializable>,
+}
+
+#[derive(Clone, Copy)]
+struct Glyph {
+    source: Rect,
+    advance: f32,
+}
+
+/// A font drawn from a sheet of glyphs, so text lines up with the pixel
+/// art instead of being smoothed into it.
+struct PixelFont {
+    tex: Texture2D,
+    glyphs: HashMap<char, Glyph>,
+    /// Drawn for any character the sheet doesn't have.
+    fallback: Glyph,
+    kerning: HashMap<(char, char), f32>,
+    line_height: f32,
+    letter_spacing: f32,
+    baseline: f32,
+}
+
+impl PixelFont {
+    const PATH: &str = "assets/art/fonts/pixel.png";
+
+    async fn load() -> AssetManageResult<Self> {
+        let (serializable, tex): (PixelFontSerializable, _) =
+            load_tex_with_meta(Self::PATH).await?;
+
+        let glyphs: HashMap<char, Glyph> = serializable
+            .glyphs
+            .into_iter()
+            .map(|(letter, glyph)| {
+                let [x, y, w, h] = glyph.rect;
+                let glyph = Glyph {
+                    source: Rect::new(x, y, w, h),
+                    advance: glyph.advance,
+                };
+                return (letter, glyph);
+            })
+            .collect();
+        let kerning = serializable
+            .kerning
+            .into_iter()
+            .filter_map(|(pair, amount)| {
+                let mut letters = pair.chars();
+                let pair = (letters.next()?, letters.next()?);
+                return Some((pair, amount));
+            })
+            .collect();
+
+        return Ok(Self {
+            tex,
+            fallback: glyphs
+                .get(&serializable.fallback)
+                .copied()
+                .unwrap_or(Glyph {
+                    source: Rect::new(0.0, 0.0, 0.0, 0.0),
+                    advance: 0.0,
+                }),
+            glyphs,
+            kerning,
+            line_height: serializable.line_height,
+            letter_spacing: serializable.letter_spacing,
+            baseline: serializable.baseline,
+        });
+    }
+
+    fn glyph(&self, letter: char) -> Glyph {
+        return self.glyphs.get(&letter).copied().unwrap_or(self.fallback);
+    }
+
+    /// Each letter's glyph and how far along the line it starts, at scale 1.
+    fn layout<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (Glyph, f32)> + 'a {
+        let mut x = 0.0;
+        let mut previous = None;
+        return text.chars().map(move |letter| {
+            if let Some(previous) = previous {
+                x += self.letter_spacing;
+                x += self
+                    .kerning
+                    .get(&(previous, letter))
+                    .copied()
+                    .unwrap_or(0.0);
+            }
+            previous = Some(letter);
+
+            let glyph = self.glyph(letter);
+            let start = x;
+            x += glyph.advance;
+            return (glyph, start);
+        });
+    }
+
+    fn measure(&self, text: &str) -> f32 {
+        return self
+            .layout(text)
+            .last()
+            .map(|(glyph, start)| start + glyph.advance)
+            .unwrap_or(0.0);
+    }
+
+    fn draw(&self, text: &str, x: f32, y: f32, scale: f32, color: Color) {
+        let top = y - self.baseline * scale;
+        for (glyph, start) in self.layout(text) {
+            draw_texture_ex(
+                &self.tex,
+                (x + start * scale).round(),
+                top.round(),
+                color,
+                DrawTextureParams {
+                    source: Some(glyph.source),
+                    dest_size: Some(vec2(glyph.source.w, glyph.source.h) * scale),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+thread_local! {
+    static FONT: OnceCell<PixelFont> = const { OnceCell::new() };
+}
+
+/// Size of the built-in font used until the pixel font is loaded, or if it
+/// fails to.
+const FALLBACK_FONT_SIZE: f32 = 12.0;
+
+/// Loads the pixel font for the `*_pixel_text` functions. Until it's
+/// loaded they use the built-in font.
+pub async fn load_pixel_font() -> AssetManageResult<()> {
+    let font = PixelFont::load().await?;
+    FONT.with(|cell| {
+        let _ = cell.set(font);
+    });
+    return Ok(());
+}
+
+/// Draws `text` with its baseline at `y`, like `draw_text`.
+pub fn draw_pixel_text(text: &str, x: f32, y: f32, color: Color) {
+    draw_pixel_text_scaled(text, x, y, 1.0, color);
+}
+
+/// Draws `text` with every pixel `scale` pixels across, for text outside
+/// the virtual screen.
+pub fn draw_pixel_text_scaled(text: &str, x: f32, y: f32, scale: f32, color: Color) {
+    FONT.with(|cell| match cell.get() {
+        Some(font) => font.draw(text, x, y, scale, color),
+        None => {
+            draw_text(text, x, y, FALLBACK_FONT_SIZE * scale, color);
+        }
+    });
+}
+
+/// How wide `text` is at scale 1.
+pub fn measure_pixel_text(text: &str) -> f32 {
+    FONT.with(|cell| match cell.get() {
+        Some(font) => font.measure(text),
+        None => measure_text(text, None, FALLBACK_FONT_SIZE as u16, 1.0).width,
+    })
+}
+
+/// Distance between the baselines of two lines at scale 1.
+pub fn pixel_line_height() -> f32 {
+    FONT.with(|cell| match cell.get() {
+        Some(font) => font.line_height,
+        None => FALLBACK_FONT_SIZE,
+    })
+}
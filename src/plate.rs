@@ -0,0 +1,86 @@
+use macroquad::{
+    color::{DARKGRAY, GRAY, LIGHTGRAY},
+    math::Rect,
+    shapes::draw_rectangle,
+};
+
+use crate::{
+    body::Body,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+    TILE_SIZE,
+};
+
+/// Holds the gates sharing its `link_id` open while anything stands on it.
+/// A latching plate stays down after the first press.
+pub struct PressurePlate {
+    pub body: Body,
+    link_id: String,
+    latch: bool,
+    pressed: bool,
+    latched: bool,
+}
+
+impl PressurePlate {
+    const SIZE: f32 = TILE_SIZE * 0.75;
+
+    /// `latched` is whether a latching plate was already pressed this
+    /// session.
+    pub fn new(x: f32, y: f32, link_id: String, latch: bool, latched: bool) -> Self {
+        Self {
+            body: Body::new(x, y, Self::SIZE, Self::SIZE, None),
+            link_id,
+            latch,
+            pressed: false,
+            latched,
+        }
+    }
+
+    pub fn link_id(&self) -> &str {
+        &self.link_id
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.pressed || self.latched
+    }
+
+    /// Presses the plate if any of `bodies` overlaps it. Returns true the
+    /// moment a latching plate locks down.
+    pub fn press(&mut self, bodies: &[Rect]) -> bool {
+        self.pressed = bodies
+            .iter()
+            .any(|hitbox| hitbox.overlaps(&self.body.hitbox));
+        if self.pressed && self.latch && !self.latched {
+            self.latched = true;
+            return true;
+        }
+
+        return false;
+    }
+}
+
+impl GameObject for PressurePlate {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::PressurePlate
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn render(&self, world: &World) {
+        let x = self.body.screen_x(world);
+        let y = self.body.screen_y(world);
+        let (w, h) = (self.body.hitbox.w, self.body.hitbox.h);
+
+        draw_rectangle(x, y, w, h, DARKGRAY);
+        match self.is_down() {
+            true => draw_rectangle(x + 1.0, y + 2.0, w - 2.0, h - 3.0, GRAY),
+            false => draw_rectangle(x + 1.0, y, w - 2.0, h - 2.0, LIGHTGRAY),
+        }
+    }
+}
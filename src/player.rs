@@ -1,29 +1,333 @@
-use macroquad::math::vec2;
+use macroquad::{
+    logging::warn,
+    math::{vec2, Rect, Vec2},
+};
+use serde::Deserialize;
 
 use crate::{
-    asset_loading::AssetManageResult, input::Input, levels::Level, body::Body, sprites::Sprite,
+    asset_loading::{deserialize, AssetManageResult},
+    body::Body,
+    checkpoint::RespawnPoint,
+    input::Input,
+    inventory::Inventory,
+    levels::Level,
+    lights::LightEmitter,
+    player_state::{InputBuffer, PlayerState, StateContext},
+    sprites::Sprite,
+    stamina::{Stamina, StaminaConfig},
     world::World,
+    TILE_SIZE,
 };
 
+/// Gameplay tuning stored alongside the animation data in the player meta.
+/// Anything left out of the file keeps its default.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PlayerConfig {
+    pub walk_speed: f32,
+    pub sprint_speed: f32,
+    pub swim_speed: f32,
+    pub dash_speed: f32,
+    pub dash_seconds: f32,
+    pub attack_seconds: f32,
+    /// Damage and shove dealt to an enemy by one swing.
+    pub attack_damage: u32,
+    pub attack_knockback: f32,
+    pub hurt_seconds: f32,
+    pub hitbox_w: f32,
+    pub hitbox_h: f32,
+    pub max_hp: u32,
+    pub invincible_seconds: f32,
+    /// How long a new direction must be held before the player walks. Taps
+    /// shorter than this only turn in place.
+    pub turn_seconds: f32,
+    pub stamina: StaminaConfig,
+    pub input_buffer_seconds: f32,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            walk_speed: 60.0,
+            sprint_speed: 95.0,
+            swim_speed: 36.0,
+            dash_speed: 220.0,
+            dash_seconds: 0.15,
+            attack_seconds: 0.3,
+            attack_damage: 1,
+            attack_knockback: 160.0,
+            hurt_seconds: 0.2,
+            hitbox_w: 14.0,
+            hitbox_h: 12.0,
+            max_hp: 6,
+            invincible_seconds: 0.8,
+            turn_seconds: 0.08,
+            stamina: StaminaConfig::default(),
+            input_buffer_seconds: 0.15,
+        }
+    }
+}
+
+impl PlayerConfig {
+    /// Never fails: a missing or malformed file is reported and the defaults
+    /// are used instead.
+    fn load(path: &str) -> Self {
+        match deserialize(path) {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("Could not read player config from {path}: {err}. Using defaults.");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// One swing of the player's attack as seen by whatever it hits. `swing`
+/// changes every time a new attack starts, so a target can tell a fresh
+/// swing from the one that already hit it.
+pub struct Attack {
+    pub hitbox: Rect,
+    pub swing: u32,
+    pub damage: u32,
+    pub knockback: f32,
+}
+
 pub struct Player {
     pub body: Body,
+    pub inventory: Inventory,
+    pub hp: u32,
+    pub max_hp: u32,
+    pub coins: u32,
+    pub checkpoint: Option<RespawnPoint>,
+    pub stamina: Stamina,
+    pub state: PlayerState,
+    pub attack_box: Option<Rect>,
+    swing: u32,
+    input_buffer: InputBuffer,
+    shoreline_time: f32,
+    turn_time: Option<f32>,
+    invincible_time: f32,
+    config: PlayerConfig,
 }
 
 impl Player {
+    const META_PATH: &str = "assets/art/sprites/player.png.meta.json";
+    /// The small light the player always carries, for dark levels.
+    pub const LANTERN: LightEmitter = LightEmitter {
+        radius: TILE_SIZE * 3.0,
+        color: [255, 220, 170],
+        flicker: false,
+    };
+    const FLASH_SECONDS: f32 = 0.05;
+
     pub async fn new(world: &World) -> AssetManageResult<Self> {
-        Ok(Self {
+        let config = PlayerConfig::load(Self::META_PATH);
+        let sprite = Sprite::load_player().await?;
+        let center = vec2(world.w / 2.0, world.h / 2.0);
+        return Ok(Self::with_config(center, sprite, config));
+    }
+
+    fn with_config(center: Vec2, sprite: Sprite, config: PlayerConfig) -> Self {
+        Self {
             body: Body::new(
-                world.w / 2.0,
-                world.h / 2.0,
-                14.0,
-                12.0,
-                Some(Sprite::load_player().await?),
+                center.x,
+                center.y,
+                config.hitbox_w,
+                config.hitbox_h,
+                Some(sprite),
             ),
-        })
+            inventory: Inventory::new(),
+            hp: config.max_hp,
+            max_hp: config.max_hp,
+            coins: 0,
+            checkpoint: None,
+            stamina: Stamina::new(config.stamina.clone()),
+            state: PlayerState::Normal,
+            attack_box: None,
+            swing: 0,
+            input_buffer: InputBuffer::new(config.input_buffer_seconds),
+            shoreline_time: 0.0,
+            turn_time: None,
+            invincible_time: 0.0,
+            config,
+        }
+    }
+
+    fn state_context(&mut self) -> StateContext<'_> {
+        StateContext {
+            hitbox: self.body.hitbox,
+            facing: self.body.facing(),
+            stamina: &mut self.stamina,
+            attack_box: &mut self.attack_box,
+            buffer: &mut self.input_buffer,
+            shoreline_time: &mut self.shoreline_time,
+            turn_time: &mut self.turn_time,
+            config: &self.config,
+        }
+    }
+
+    /// The single place the player changes state, so `exit`/`enter` always
+    /// run in pairs. Illegal transitions are ignored.
+    pub fn transition(&mut self, next: PlayerState) {
+        if !self.state.can_transition_to(&next) {
+            return;
+        }
+
+        self.force_state(next);
+    }
+
+    fn force_state(&mut self, mut next: PlayerState) {
+        if let PlayerState::Attacking { .. } = next {
+            self.swing = self.swing.wrapping_add(1);
+        }
+
+        let mut previous = std::mem::replace(&mut self.state, PlayerState::Normal);
+        let mut ctx = self.state_context();
+        previous.exit(&mut ctx);
+        next.enter(&mut ctx);
+        self.state = next;
+    }
+
+    /// Whether hits are ignored right now: during the invincibility window
+    /// that follows every hit, and for the length of a dash.
+    pub fn is_invulnerable(&self) -> bool {
+        self.invincible_time > 0.0 || matches!(self.state, PlayerState::Dashing { .. })
+    }
+
+    /// Hurts the player and knocks them away from `source` with an impulse
+    /// of `knockback`. Ignored while the player is invulnerable.
+    pub fn take_damage(&mut self, amount: u32, source: Vec2, knockback: f32) {
+        if self.is_dead() || self.is_invulnerable() {
+            return;
+        }
+
+        self.hp = self.hp.saturating_sub(amount);
+        self.invincible_time = self.config.invincible_seconds;
+
+        let away = (self.body.hitbox.center() - source).normalize_or(-self.body.facing());
+        self.body.apply_impulse(away * knockback);
+        self.body.flash(Self::FLASH_SECONDS);
+
+        match self.hp == 0 {
+            true => self.transition(PlayerState::Dead),
+            false => self.transition(PlayerState::hurt(&self.config)),
+        }
+    }
+
+    /// The swing currently able to hit something, if the player is attacking.
+    pub fn attack(&self) -> Option<Attack> {
+        return self.attack_box.map(|hitbox| Attack {
+            hitbox,
+            swing: self.swing,
+            damage: self.config.attack_damage,
+            knockback: self.config.attack_knockback,
+        });
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hp == 0
+    }
+
+    pub fn respawn(&mut self, position: Vec2) {
+        self.hp = self.max_hp;
+        self.body.set_center(position);
+        self.body.clear_impulse();
+        self.input_buffer.clear();
+        self.invincible_time = 0.0;
+        self.force_state(PlayerState::Normal);
     }
 
     pub fn move_player(&mut self, level: &Level, input: &Input, dt: f32) {
-        let move_input = vec2(input.horizontal, input.vertical).normalize_or_zero();
-        self.body.r#move(move_input * 60.0, level, dt);
+        self.stamina.update(dt);
+        self.invincible_time = (self.invincible_time - dt).max(0.0);
+        self.input_buffer.record(input);
+
+        let mut state = std::mem::replace(&mut self.state, PlayerState::Normal);
+        let update = state.update(input, level, &mut self.state_context(), dt);
+        self.state = state;
+        self.input_buffer.tick(dt);
+
+        if input.jump && self.state == PlayerState::Normal {
+            self.body.jump();
+        }
+
+        if let Some(turn) = update.turn {
+            self.body.face(turn);
+        }
+        self.body.set_animation(update.animation);
+        self.body.r#move(update.delta, level, dt);
+
+        if let Some(next) = update.next {
+            self.transition(next);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{levels::tests::level, sprites::tests::sprite};
+
+    const FRAME: f32 = 0.01;
+
+    /// A player with the default config, standing at `center`.
+    pub fn player_at(center: Vec2) -> Player {
+        return Player::with_config(center, sprite(), PlayerConfig::default());
+    }
+
+    fn player() -> Player {
+        return player_at(vec2(40.0, 40.0));
+    }
+
+    fn pressing(horizontal: f32, vertical: f32) -> Input {
+        return Input {
+            horizontal,
+            vertical,
+            ..Input::default()
+        };
+    }
+
+    #[test]
+    fn tap_shorter_than_the_threshold_only_turns() {
+        let level = level(&[".....", ".....", ".....", ".....", "....."]);
+        let mut player = player();
+        let start = player.body.hitbox.center();
+
+        for _ in 0..5 {
+            player.move_player(&level, &pressing(1.0, 0.0), FRAME);
+        }
+        player.move_player(&level, &Input::default(), FRAME);
+
+        assert_eq!(player.body.hitbox.center(), start);
+        assert_eq!(player.body.facing(), vec2(1.0, 0.0));
+    }
+
+    #[test]
+    fn holding_past_the_threshold_walks() {
+        let level = level(&[".....", ".....", ".....", ".....", "....."]);
+        let mut player = player();
+        let start = player.body.hitbox.center();
+
+        let mut held = 0.0;
+        while player.body.hitbox.center() == start && held < 1.0 {
+            player.move_player(&level, &pressing(1.0, 0.0), FRAME);
+            held += FRAME;
+        }
+
+        // The frame the direction is pressed starts the turn without timing
+        // it, and the timer can land a hair under the threshold.
+        let threshold = player.config.turn_seconds;
+        assert!(held > threshold, "walked after {held}s");
+        assert!(held < threshold + FRAME * 3.5, "walked after {held}s");
+    }
+
+    #[test]
+    fn direction_already_faced_walks_at_once() {
+        let level = level(&[".....", ".....", ".....", ".....", "....."]);
+        let mut player = player();
+        let start = player.body.hitbox.center();
+
+        player.move_player(&level, &pressing(0.0, 1.0), FRAME);
+        assert!(player.body.hitbox.center().y > start.y);
     }
 }
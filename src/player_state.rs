@@ -0,0 +1,511 @@
+use macroquad::math::{vec2, Rect, Vec2};
+
+use crate::{
+    animator::{facing_for, Animation},
+    input::Input,
+    levels::Level,
+    player::PlayerConfig,
+    stamina::Stamina,
+};
+
+const ATTACK_REACH: f32 = 14.0;
+const SHORELINE_GRACE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BufferedAction {
+    Attack,
+    Dash,
+}
+
+/// Remembers the latest action press for a short window so it can fire as
+/// soon as a state that accepts it becomes active. Movement is never
+/// buffered.
+pub struct InputBuffer {
+    window: f32,
+    pending: Option<(BufferedAction, f32)>,
+}
+
+impl InputBuffer {
+    pub fn new(window: f32) -> Self {
+        Self {
+            window,
+            pending: None,
+        }
+    }
+
+    pub fn record(&mut self, input: &Input) {
+        if input.attack {
+            self.pending = Some((BufferedAction::Attack, 0.0));
+        } else if input.dash {
+            self.pending = Some((BufferedAction::Dash, 0.0));
+        }
+    }
+
+    pub fn take(&mut self) -> Option<BufferedAction> {
+        self.pending.take().map(|(action, _)| action)
+    }
+
+    /// Ages the pending press, dropping it once it outlives the window.
+    pub fn tick(&mut self, dt: f32) {
+        if let Some((_, ref mut age)) = self.pending {
+            *age += dt;
+            if *age > self.window {
+                self.pending = None;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pending = None;
+    }
+}
+
+/// The parts of the player a state is allowed to read or change.
+pub struct StateContext<'a> {
+    pub hitbox: Rect,
+    pub facing: Vec2,
+    pub stamina: &'a mut Stamina,
+    pub attack_box: &'a mut Option<Rect>,
+    pub buffer: &'a mut InputBuffer,
+    /// How long the body has been on the "other" side of the shoreline.
+    pub shoreline_time: &'a mut f32,
+    /// How long the current turn-in-place has been held, if one is running.
+    pub turn_time: &'a mut Option<f32>,
+    pub config: &'a PlayerConfig,
+}
+
+impl StateContext<'_> {
+    /// Returns true once the hitbox center has been in (or out of) water
+    /// for the whole grace period, so straddling the shore doesn't flicker.
+    fn crossed_shoreline(&mut self, level: &Level, want_water: bool, dt: f32) -> bool {
+        let center = self.hitbox.center();
+        if level.is_water_at(center.x, center.y) == want_water {
+            *self.shoreline_time += dt;
+        } else {
+            *self.shoreline_time = 0.0;
+        }
+
+        if *self.shoreline_time >= SHORELINE_GRACE {
+            *self.shoreline_time = 0.0;
+            return true;
+        }
+
+        return false;
+    }
+
+    /// Returns true while a freshly pressed direction should only turn the
+    /// player. Holding the direction already faced never waits.
+    fn turning_in_place(&mut self, move_input: Vec2, dt: f32) -> bool {
+        if move_input == Vec2::ZERO {
+            *self.turn_time = None;
+            return false;
+        }
+
+        if facing_for(move_input) != self.facing {
+            *self.turn_time = Some(0.0);
+            return true;
+        }
+
+        match self.turn_time {
+            Some(time) if *time < self.config.turn_seconds => {
+                *time += dt;
+                return true;
+            }
+            _ => {
+                *self.turn_time = None;
+                return false;
+            }
+        }
+    }
+}
+
+/// What a state wants to happen this frame.
+pub struct StateUpdate {
+    pub delta: Vec2,
+    pub animation: Animation,
+    pub next: Option<PlayerState>,
+    /// Face this way without moving.
+    pub turn: Option<Vec2>,
+}
+
+impl StateUpdate {
+    fn stay(delta: Vec2, animation: Animation) -> Self {
+        Self {
+            delta,
+            animation,
+            next: None,
+            turn: None,
+        }
+    }
+
+    fn switch(next: PlayerState, animation: Animation) -> Self {
+        Self {
+            delta: Vec2::ZERO,
+            animation,
+            next: Some(next),
+            turn: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerState {
+    Normal,
+    Dashing { direction: Vec2, time_left: f32 },
+    Attacking { time_left: f32 },
+    Swimming,
+    Hurt { time_left: f32 },
+    Dead,
+}
+
+impl PlayerState {
+    pub fn dashing(direction: Vec2, config: &PlayerConfig) -> Self {
+        PlayerState::Dashing {
+            direction,
+            time_left: config.dash_seconds,
+        }
+    }
+
+    pub fn attacking(config: &PlayerConfig) -> Self {
+        PlayerState::Attacking {
+            time_left: config.attack_seconds,
+        }
+    }
+
+    /// Directional input is ignored while hurt so walking doesn't cancel the
+    /// knockback.
+    pub fn hurt(config: &PlayerConfig) -> Self {
+        PlayerState::Hurt {
+            time_left: config.hurt_seconds,
+        }
+    }
+
+    /// Whether the state machine may leave `self` for `next`. Respawning
+    /// bypasses this, since nothing is allowed to leave `Dead` on its own.
+    pub fn can_transition_to(&self, next: &PlayerState) -> bool {
+        match (self, next) {
+            (PlayerState::Dead, _) => false,
+            (_, PlayerState::Dead) => true,
+            (_, PlayerState::Hurt { .. }) => true,
+            (_, PlayerState::Normal) => true,
+            (PlayerState::Normal, _) => true,
+            _ => false,
+        }
+    }
+
+    pub fn enter(&mut self, ctx: &mut StateContext) {
+        if let PlayerState::Attacking { .. } = self {
+            let center = ctx.hitbox.center() + ctx.facing * ATTACK_REACH;
+            *ctx.attack_box = Some(Rect::new(
+                center.x - ATTACK_REACH / 2.0,
+                center.y - ATTACK_REACH / 2.0,
+                ATTACK_REACH,
+                ATTACK_REACH,
+            ));
+        }
+    }
+
+    pub fn exit(&mut self, ctx: &mut StateContext) {
+        if let PlayerState::Attacking { .. } = self {
+            *ctx.attack_box = None;
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        input: &Input,
+        level: &Level,
+        ctx: &mut StateContext,
+        dt: f32,
+    ) -> StateUpdate {
+        match self {
+            PlayerState::Normal => {
+                let move_input = vec2(input.horizontal, input.vertical).normalize_or_zero();
+
+                if ctx.crossed_shoreline(level, true, dt) {
+                    return StateUpdate::switch(PlayerState::Swimming, Animation::Swim);
+                }
+
+                match ctx.buffer.take() {
+                    Some(BufferedAction::Attack) => {
+                        return StateUpdate::switch(
+                            PlayerState::attacking(ctx.config),
+                            Animation::Attack,
+                        );
+                    }
+                    Some(BufferedAction::Dash) if ctx.stamina.try_dash() => {
+                        let direction = match move_input == Vec2::ZERO {
+                            true => ctx.facing,
+                            false => move_input,
+                        };
+                        return StateUpdate::switch(
+                            PlayerState::dashing(direction, ctx.config),
+                            Animation::Walk,
+                        );
+                    }
+                    _ => {}
+                }
+
+                if ctx.turning_in_place(move_input, dt) {
+                    return StateUpdate {
+                        turn: Some(move_input),
+                        ..StateUpdate::stay(Vec2::ZERO, Animation::Walk)
+                    };
+                }
+
+                let speed = match input.sprint
+                    && move_input != Vec2::ZERO
+                    && ctx.stamina.drain_sprint(dt)
+                {
+                    true => ctx.config.sprint_speed,
+                    false => ctx.config.walk_speed,
+                };
+
+                return StateUpdate::stay(move_input * speed, Animation::Walk);
+            }
+            PlayerState::Dashing {
+                direction,
+                time_left,
+            } => {
+                *time_left -= dt;
+                let delta = *direction * ctx.config.dash_speed;
+                return match *time_left <= 0.0 {
+                    true => StateUpdate {
+                        delta,
+                        animation: Animation::Walk,
+                        next: Some(PlayerState::Normal),
+                        turn: None,
+                    },
+                    false => StateUpdate::stay(delta, Animation::Walk),
+                };
+            }
+            PlayerState::Attacking { time_left } => {
+                *time_left -= dt;
+                return match *time_left <= 0.0 {
+                    true => StateUpdate::switch(PlayerState::Normal, Animation::Walk),
+                    false => StateUpdate::stay(Vec2::ZERO, Animation::Attack),
+                };
+            }
+            PlayerState::Swimming => {
+                let move_input = vec2(input.horizontal, input.vertical).normalize_or_zero();
+
+                if ctx.crossed_shoreline(level, false, dt) {
+                    return StateUpdate::switch(PlayerState::Normal, Animation::Walk);
+                }
+
+                return StateUpdate::stay(move_input * ctx.config.swim_speed, Animation::Swim);
+            }
+            PlayerState::Hurt { time_left } => {
+                *time_left -= dt;
+                return match *time_left <= 0.0 {
+                    true => StateUpdate::switch(PlayerState::Normal, Animation::Walk),
+                    false => StateUpdate::stay(Vec2::ZERO, Animation::Hit),
+                };
+            }
+            PlayerState::Dead => StateUpdate::stay(Vec2::ZERO, Animation::Death),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::tests::level;
+
+    /// What a `StateContext` borrows, owned for the length of a test.
+    struct Parts {
+        stamina: Stamina,
+        attack_box: Option<Rect>,
+        buffer: InputBuffer,
+        shoreline_time: f32,
+        turn_time: Option<f32>,
+        config: PlayerConfig,
+    }
+
+    impl Parts {
+        fn new() -> Self {
+            let config = PlayerConfig::default();
+            Self {
+                stamina: Stamina::new(config.stamina.clone()),
+                attack_box: None,
+                buffer: InputBuffer::new(config.input_buffer_seconds),
+                shoreline_time: 0.0,
+                turn_time: None,
+                config,
+            }
+        }
+
+        fn ctx(&mut self, center: Vec2) -> StateContext<'_> {
+            StateContext {
+                hitbox: Rect::new(center.x - 7.0, center.y - 6.0, 14.0, 12.0),
+                facing: vec2(0.0, 1.0),
+                stamina: &mut self.stamina,
+                attack_box: &mut self.attack_box,
+                buffer: &mut self.buffer,
+                shoreline_time: &mut self.shoreline_time,
+                turn_time: &mut self.turn_time,
+                config: &self.config,
+            }
+        }
+    }
+
+    fn pressed(attack: bool, dash: bool) -> Input {
+        return Input {
+            attack,
+            dash,
+            ..Input::default()
+        };
+    }
+
+    #[test]
+    fn buffer_keeps_a_press_for_its_window() {
+        let mut buffer = InputBuffer::new(0.15);
+        buffer.record(&pressed(true, false));
+        buffer.tick(0.1);
+        buffer.tick(0.05);
+        assert_eq!(buffer.take(), Some(BufferedAction::Attack));
+        assert_eq!(buffer.take(), None);
+    }
+
+    #[test]
+    fn buffer_drops_a_press_past_its_window() {
+        let mut buffer = InputBuffer::new(0.15);
+        buffer.record(&pressed(false, true));
+        buffer.tick(0.1);
+        buffer.tick(0.1);
+        assert_eq!(buffer.take(), None);
+    }
+
+    #[test]
+    fn buffer_keeps_the_latest_press() {
+        let mut buffer = InputBuffer::new(0.15);
+        buffer.record(&pressed(true, false));
+        buffer.tick(0.1);
+        buffer.record(&pressed(false, true));
+        buffer.tick(0.1);
+        assert_eq!(buffer.take(), Some(BufferedAction::Dash));
+    }
+
+    #[test]
+    fn buffer_prefers_attack_and_ignores_no_press() {
+        let mut buffer = InputBuffer::new(0.15);
+        buffer.record(&pressed(true, true));
+        buffer.record(&Input::default());
+        assert_eq!(buffer.take(), Some(BufferedAction::Attack));
+
+        buffer.record(&pressed(false, true));
+        buffer.clear();
+        assert_eq!(buffer.take(), None);
+    }
+
+    fn all(config: &PlayerConfig) -> [PlayerState; 6] {
+        return [
+            PlayerState::Normal,
+            PlayerState::dashing(vec2(1.0, 0.0), config),
+            PlayerState::attacking(config),
+            PlayerState::Swimming,
+            PlayerState::hurt(config),
+            PlayerState::Dead,
+        ];
+    }
+
+    #[test]
+    fn transition_table() {
+        let config = PlayerConfig::default();
+        // Rows are from, columns to, in the order of `all`.
+        let table = [
+            [true, true, true, true, true, true],
+            [true, false, false, false, true, true],
+            [true, false, false, false, true, true],
+            [true, false, false, false, true, true],
+            [true, false, false, false, true, true],
+            [false, false, false, false, false, false],
+        ];
+
+        for (from, row) in all(&config).iter().zip(table) {
+            for (to, allowed) in all(&config).iter().zip(row) {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed,
+                    "{:?} to {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn attacking_holds_its_box_then_returns_to_normal() {
+        let level = level(&["...", "...", "..."]);
+        let mut parts = Parts::new();
+        let input = Input::default();
+        let mut state = PlayerState::attacking(&parts.config);
+
+        state.enter(&mut parts.ctx(vec2(24.0, 24.0)));
+        assert!(parts.attack_box.is_some());
+
+        let update = state.update(&input, &level, &mut parts.ctx(vec2(24.0, 24.0)), 0.2);
+        assert_eq!(update.next, None);
+        let update = state.update(&input, &level, &mut parts.ctx(vec2(24.0, 24.0)), 0.2);
+        assert_eq!(update.next, Some(PlayerState::Normal));
+
+        state.exit(&mut parts.ctx(vec2(24.0, 24.0)));
+        assert!(parts.attack_box.is_none());
+    }
+
+    #[test]
+    fn buffered_attack_starts_an_attack() {
+        let level = level(&["...", "...", "..."]);
+        let mut parts = Parts::new();
+        parts.buffer.record(&Input {
+            attack: true,
+            ..Input::default()
+        });
+
+        let update = PlayerState::Normal.update(
+            &Input::default(),
+            &level,
+            &mut parts.ctx(vec2(24.0, 24.0)),
+            0.016,
+        );
+        assert_eq!(update.next, Some(PlayerState::attacking(&parts.config)));
+    }
+
+    #[test]
+    fn dash_without_stamina_stays_normal() {
+        let level = level(&["...", "...", "..."]);
+        let mut parts = Parts::new();
+        parts.stamina.current = 0.0;
+        parts.buffer.record(&Input {
+            dash: true,
+            ..Input::default()
+        });
+
+        let update = PlayerState::Normal.update(
+            &Input::default(),
+            &level,
+            &mut parts.ctx(vec2(24.0, 24.0)),
+            0.016,
+        );
+        assert_eq!(update.next, None);
+    }
+
+    #[test]
+    fn water_switches_to_swimming_after_the_grace() {
+        let water = level(&["~~~", "~~~", "~~~"]);
+        let mut parts = Parts::new();
+        let input = Input::default();
+        let mut state = PlayerState::Normal;
+
+        let update = state.update(&input, &water, &mut parts.ctx(vec2(24.0, 24.0)), 0.1);
+        assert_eq!(update.next, None);
+        let update = state.update(&input, &water, &mut parts.ctx(vec2(24.0, 24.0)), 0.1);
+        assert_eq!(update.next, Some(PlayerState::Swimming));
+
+        state = PlayerState::Swimming;
+        let land = level(&["...", "...", "..."]);
+        let update = state.update(&input, &land, &mut parts.ctx(vec2(24.0, 24.0)), 0.25);
+        assert_eq!(update.next, Some(PlayerState::Normal));
+    }
+}
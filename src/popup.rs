@@ -0,0 +1,48 @@
+use macroquad::{
+    color::{Color, WHITE},
+    math::Vec2,
+    text::{draw_text, measure_text},
+};
+
+use crate::world::World;
+
+/// A short line of text that floats up from an object and fades out.
+pub struct Popup {
+    text: String,
+    time_left: f32,
+}
+
+impl Popup {
+    const SECONDS: f32 = 1.5;
+    const RISE: f32 = 8.0;
+    const FONT_SIZE: f32 = 8.0;
+
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            time_left: Self::SECONDS,
+        }
+    }
+
+    /// Returns false once the popup has faded out.
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.time_left -= dt;
+        return self.time_left > 0.0;
+    }
+
+    /// Draws the text centered above `anchor`, a world-space point.
+    pub fn render(&self, anchor: Vec2, world: &World) {
+        let progress = 1.0 - self.time_left / Self::SECONDS;
+        let width = measure_text(&self.text, None, Self::FONT_SIZE as u16, 1.0).width;
+        draw_text(
+            &self.text,
+            anchor.x - width / 2.0 - world.x,
+            anchor.y - 2.0 - progress * Self::RISE - world.y,
+            Self::FONT_SIZE,
+            Color {
+                a: 1.0 - progress,
+                ..WHITE
+            },
+        );
+    }
+}
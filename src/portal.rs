@@ -0,0 +1,74 @@
+use macroquad::{color::VIOLET, math::Rect, shapes::draw_circle_lines, time::get_time};
+
+use crate::{
+    body::Body,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+    TILE_SIZE,
+};
+
+/// Where a portal sends the player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarpTarget {
+    pub level: String,
+    pub anchor: String,
+}
+
+/// Sends the player to an anchor in another level when they step in.
+pub struct Portal {
+    pub body: Body,
+    target: WarpTarget,
+    /// Only armed once the player has been off the portal, so arriving on
+    /// top of one doesn't send them straight back.
+    armed: bool,
+}
+
+impl Portal {
+    pub fn new(x: f32, y: f32, target: WarpTarget) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE / 2.0, TILE_SIZE / 2.0, None),
+            target,
+            armed: false,
+        }
+    }
+
+    /// The target, if the player just stepped onto an armed portal.
+    pub fn triggered(&self, player_hitbox: &Rect) -> Option<&WarpTarget> {
+        return match self.armed && self.body.hitbox.overlaps(player_hitbox) {
+            true => Some(&self.target),
+            false => None,
+        };
+    }
+}
+
+impl GameObject for Portal {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Portal
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        if !self.body.hitbox.overlaps(&ctx.player.body.hitbox) {
+            self.armed = true;
+        }
+
+        return ObjectStatus::Alive;
+    }
+
+    fn render(&self, world: &World) {
+        let center = self.body.hitbox.center();
+        let pulse = (get_time() as f32 * 3.0).sin();
+        for ring in 0..3 {
+            draw_circle_lines(
+                center.x - world.x,
+                center.y - world.y,
+                TILE_SIZE / 2.0 - ring as f32 * 2.0 + pulse,
+                1.0,
+                VIOLET,
+            );
+        }
+    }
+}
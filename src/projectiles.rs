@@ -0,0 +1,182 @@
+use macroquad::{
+    color::{Color, ORANGE},
+    math::Vec2,
+    shapes::draw_circle,
+};
+
+use crate::{
+    body::Body,
+    levels::Level,
+    object::{GameObject, ObjectKind, ObjectStatus, RenderLayer, UpdateCtx},
+    player::Player,
+    world::World,
+};
+
+/// What a projectile does when it runs into a wall.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallBehavior {
+    Bounce,
+    Despawn,
+}
+
+/// A small body flying in a straight line. Projectiles only ever test
+/// against tiles and the player, never against other objects.
+pub struct Projectile {
+    pub body: Body,
+    pub damage: u32,
+    pub knockback: f32,
+    velocity: Vec2,
+    wall: WallBehavior,
+    time_left: f32,
+    done: bool,
+}
+
+impl Projectile {
+    const LIFETIME_SECONDS: f32 = 3.0;
+    const SIZE: f32 = 4.0;
+    const COLOR: Color = ORANGE;
+
+    pub fn new(
+        origin: Vec2,
+        velocity: Vec2,
+        damage: u32,
+        knockback: f32,
+        wall: WallBehavior,
+    ) -> Self {
+        Self {
+            body: Body::new(origin.x, origin.y, Self::SIZE, Self::SIZE, None).without_shadow(),
+            damage,
+            knockback,
+            velocity,
+            wall,
+            time_left: Self::LIFETIME_SECONDS,
+            done: false,
+        }
+    }
+
+    /// Whether the projectile has expired, hit a wall it doesn't bounce off,
+    /// or hit the player, and should be removed.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn hit(&mut self) {
+        self.done = true;
+    }
+
+    fn fly(&mut self, level: &Level, dt: f32) {
+        self.time_left -= dt;
+        if self.time_left <= 0.0 {
+            self.done = true;
+            return;
+        }
+
+        let result = self.body.r#move(self.velocity, level, dt);
+        if !result.blocked_x && !result.blocked_y {
+            return;
+        }
+
+        match self.wall {
+            WallBehavior::Bounce => {
+                if result.blocked_x {
+                    self.velocity.x = -self.velocity.x;
+                }
+                if result.blocked_y {
+                    self.velocity.y = -self.velocity.y;
+                }
+            }
+            WallBehavior::Despawn => self.done = true,
+        }
+    }
+}
+
+impl GameObject for Projectile {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Projectile
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx) -> ObjectStatus {
+        if !self.is_done() {
+            self.fly(ctx.level, ctx.dt);
+        }
+
+        return match self.is_done() {
+            true => ObjectStatus::Dead,
+            false => ObjectStatus::Alive,
+        };
+    }
+
+    /// Runs down the lifetime without flying.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        self.time_left -= dt;
+        return match self.time_left <= 0.0 {
+            true => ObjectStatus::Dead,
+            false => ObjectStatus::Alive,
+        };
+    }
+
+    fn touch_player(&mut self, player: &mut Player) {
+        if self.body.hitbox.overlaps(&player.body.hitbox) {
+            player.take_damage(self.damage, self.body.hitbox.center(), self.knockback);
+            self.hit();
+        }
+    }
+
+    /// Shots fly over everything, so the overlay never hides one coming.
+    fn render_layer(&self) -> RenderLayer {
+        RenderLayer::Above
+    }
+
+    fn render(&self, world: &World) {
+        let center = self.body.hitbox.center();
+        draw_circle(
+            center.x - world.x,
+            center.y - world.y,
+            Self::SIZE / 2.0,
+            Self::COLOR,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+
+    use super::*;
+    use crate::{levels::tests::level, object::tests::update, player::tests::player_at};
+
+    /// Fires a shot right at a wall four tiles away and flies it for a
+    /// second.
+    fn fire_at_wall(wall: WallBehavior) -> (Projectile, ObjectStatus) {
+        let level = level(&["......#....."; 5]);
+        let player = player_at(vec2(1000.0, 1000.0));
+        let mut shot = Projectile::new(vec2(40.0, 40.0), vec2(100.0, 0.0), 1, 0.0, wall);
+        let mut status = ObjectStatus::Alive;
+        for _ in 0..60 {
+            status = update(&mut shot, &level, &player, 1.0 / 60.0).status;
+            if status == ObjectStatus::Dead {
+                break;
+            }
+        }
+        return (shot, status);
+    }
+
+    #[test]
+    fn bouncing_shots_turn_back_at_walls() {
+        let (shot, status) = fire_at_wall(WallBehavior::Bounce);
+        assert_eq!(status, ObjectStatus::Alive);
+        assert_eq!(shot.velocity, vec2(-100.0, 0.0));
+        assert!(shot.body.hitbox.right() < 96.0);
+    }
+
+    #[test]
+    fn other_shots_break_on_walls() {
+        let (shot, status) = fire_at_wall(WallBehavior::Despawn);
+        assert_eq!(status, ObjectStatus::Dead);
+        assert!(shot.is_done());
+    }
+}
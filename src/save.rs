@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use macroquad::{
+    color::{Color, BROWN, GRAY, ORANGE, WHITE, YELLOW},
+    math::vec2,
+    shapes::{draw_rectangle, draw_rectangle_lines, draw_triangle},
+    text::{draw_text, measure_text},
+    time::get_time,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{deserialize, serialize, AssetManageError, AssetManageResult},
+    body::Body,
+    checkpoint::RespawnPoint,
+    data_dir::data_path,
+    input::Input,
+    items::ItemId,
+    levels::Level,
+    lights::LightEmitter,
+    object::{in_reach, GameObject, Interaction, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    world::World,
+    world_state::WorldState,
+    TILE_SIZE, VIRTUAL_H, VIRTUAL_W,
+};
+
+/// A campfire the player can save their game at.
+pub struct SavePoint {
+    pub body: Body,
+}
+
+impl SavePoint {
+    pub const LIGHT: LightEmitter = LightEmitter {
+        radius: TILE_SIZE * 4.0,
+        color: [255, 150, 60],
+        flicker: true,
+    };
+
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE * 0.75, TILE_SIZE * 0.5, None),
+        }
+    }
+}
+
+impl GameObject for SavePoint {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::SavePoint
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn can_interact(&self, player: &Player) -> bool {
+        return in_reach(player, &self.body.hitbox);
+    }
+
+    fn interact(&mut self, _player: &mut Player) -> Interaction {
+        Interaction::Save
+    }
+
+    fn light(&self) -> Option<&LightEmitter> {
+        Some(&Self::LIGHT)
+    }
+
+    fn render(&self, world: &World) {
+        let x = self.body.hitbox.center().x - world.x;
+        let y = self.body.hitbox.bottom() - world.y;
+
+        draw_rectangle(x - 6.0, y - 3.0, 12.0, 2.0, BROWN);
+        draw_rectangle(x - 4.0, y - 2.0, 8.0, 2.0, BROWN);
+
+        let flicker = (get_time() as f32 * 9.0).sin() * 1.5;
+        draw_triangle(
+            vec2(x - 4.0, y - 3.0),
+            vec2(x + 4.0, y - 3.0),
+            vec2(x, y - 11.0 - flicker),
+            ORANGE,
+        );
+        draw_triangle(
+            vec2(x - 2.0, y - 3.0),
+            vec2(x + 2.0, y - 3.0),
+            vec2(x, y - 7.0 + flicker),
+            YELLOW,
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedCheckpoint {
+    level: String,
+    position: (f32, f32),
+}
+
+/// Everything needed to put the player back where they saved.
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    level: String,
+    position: (f32, f32),
+    hp: u32,
+    coins: u32,
+    inventory: BTreeMap<ItemId, u32>,
+    /// Object states of every level visited, the saved one included.
+    world: WorldState,
+    checkpoint: Option<SavedCheckpoint>,
+}
+
+impl SaveData {
+    const FILE: &str = "save.json";
+
+    pub fn capture(player: &Player, level: &Level, world: &WorldState) -> Self {
+        let position = player.body.hitbox.center();
+        let mut world = world.clone();
+        world.store(level);
+
+        Self {
+            level: level.name().to_owned(),
+            position: (position.x, position.y),
+            hp: player.hp,
+            coins: player.coins,
+            inventory: player.inventory.items().clone(),
+            world,
+            checkpoint: player.checkpoint.as_ref().map(|point| SavedCheckpoint {
+                level: point.level.clone(),
+                position: (point.position.x, point.position.y),
+            }),
+        }
+    }
+
+    pub fn write(&self) -> AssetManageResult<()> {
+        return serialize(self, data_path(Self::FILE));
+    }
+
+    pub fn read() -> AssetManageResult<Self> {
+        return deserialize(data_path(Self::FILE));
+    }
+
+    /// What to tell the player when `read` fails.
+    pub fn describe_error(err: &AssetManageError) -> String {
+        match err {
+            AssetManageError::Io(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                "There is no saved game yet.".to_owned()
+            }
+            AssetManageError::Serde(_) => {
+                "The save file is damaged and can't be loaded.".to_owned()
+            }
+            err => format!("The save file couldn't be read: {err}"),
+        }
+    }
+
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    /// Replays the saved object states onto the freshly loaded level, and
+    /// puts the player and the rest of the world back as they were.
+    pub fn restore(self, player: &mut Player, level: &mut Level, world: &mut WorldState) {
+        *world = self.world;
+        world.restore(level);
+
+        player.respawn(vec2(self.position.0, self.position.1));
+        player.hp = self.hp.clamp(1, player.max_hp);
+        player.coins = self.coins;
+        player.inventory.set_items(self.inventory);
+        player.checkpoint = self.checkpoint.map(|point| RespawnPoint {
+            level: point.level,
+            position: vec2(point.position.0, point.position.1),
+        });
+    }
+}
+
+/// The yes/no box shown after interacting with a save point.
+pub struct SaveMenu {
+    yes: bool,
+}
+
+impl SaveMenu {
+    const W: f32 = 120.0;
+    const H: f32 = 44.0;
+
+    pub fn new() -> Self {
+        Self { yes: true }
+    }
+
+    /// Moves the selection and returns whether to save once it is
+    /// confirmed.
+    pub fn update(&mut self, input: &Input) -> Option<bool> {
+        if input.menu_up || input.menu_down {
+            self.yes = !self.yes;
+        }
+
+        return match input.confirm || input.interact {
+            true => Some(self.yes),
+            false => None,
+        };
+    }
+
+    pub fn render(&self) {
+        let x = (VIRTUAL_W - Self::W) / 2.0;
+        let y = (VIRTUAL_H - Self::H) / 2.0;
+        draw_rectangle(x, y, Self::W, Self::H, Color::from_rgba(20, 10, 30, 220));
+        draw_rectangle_lines(x, y, Self::W, Self::H, 1.0, WHITE);
+
+        Self::draw_centered("Save your game?", y + 13.0, WHITE);
+        for (idx, (label, selected)) in [("Yes", self.yes), ("No", !self.yes)].iter().enumerate() {
+            let line_y = y + 26.0 + idx as f32 * 13.0;
+            match selected {
+                true => Self::draw_centered(&format!("> {label} <"), line_y, WHITE),
+                false => Self::draw_centered(label, line_y, GRAY),
+            }
+        }
+    }
+
+    fn draw_centered(text: &str, y: f32, color: Color) {
+        let width = measure_text(text, None, 16, 1.0).width;
+        draw_text(text, (VIRTUAL_W - width) / 2.0, y, 16.0, color);
+    }
+}
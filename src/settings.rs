@@ -0,0 +1,350 @@
+use macroquad::{
+    color::{Color, GRAY, WHITE},
+    logging::warn,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loading::{deserialize, serialize, AssetManageError, AssetManageResult},
+    data_dir::data_path,
+    input::Input,
+    object::LevelObjects,
+    pixel_font::{draw_pixel_text, measure_pixel_text},
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+/// Colors the post-process can snap the world to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Palette {
+    /// Colors stay as they are.
+    #[default]
+    Off,
+    Handheld,
+    Dusk,
+    Sepia,
+}
+
+impl Palette {
+    const ALL: [Palette; 4] = [
+        Palette::Off,
+        Palette::Handheld,
+        Palette::Dusk,
+        Palette::Sepia,
+    ];
+
+    /// The four colors, darkest first, or `None` when off.
+    pub fn colors(&self) -> Option<[Color; 4]> {
+        let hex = match self {
+            Palette::Off => return None,
+            Palette::Handheld => [0x0f380f, 0x306230, 0x8bac0f, 0x9bbc0f],
+            Palette::Dusk => [0x2b0f54, 0xab1f65, 0xff4f69, 0xfff7f8],
+            Palette::Sepia => [0x2b1d0e, 0x6b4a2b, 0xb08d57, 0xf0e0c0],
+        };
+        return Some(hex.map(Color::from_hex));
+    }
+
+    fn step(&self, by: isize) -> Self {
+        let count = Self::ALL.len() as isize;
+        let idx = Self::ALL
+            .iter()
+            .position(|palette| palette == self)
+            .unwrap_or(0) as isize;
+        return Self::ALL[(idx + by).rem_euclid(count) as usize];
+    }
+}
+
+/// How the finished world is drawn to the window.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct PostProcessSettings {
+    #[serde(default)]
+    pub palette: Palette,
+    /// How dark the gaps between scanlines are, 0 to 1.
+    #[serde(default)]
+    pub scanlines: f32,
+    /// How far the screen bulges like a CRT, 0 to 1.
+    #[serde(default)]
+    pub curvature: f32,
+}
+
+impl PostProcessSettings {
+    /// Whether drawing with these changes anything.
+    pub fn is_active(&self) -> bool {
+        return self.palette != Palette::Off || self.scanlines > 0.0 || self.curvature > 0.0;
+    }
+}
+
+/// How frames are paced.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum PresentMode {
+    /// Wait for the display each frame. Only switched on or off at launch.
+    #[default]
+    Vsync,
+    /// Draw frames as fast as possible.
+    Uncapped,
+    /// Wait out the rest of each frame to hold this many a second.
+    Capped(u32),
+}
+
+impl PresentMode {
+    const ALL: [PresentMode; 6] = [
+        PresentMode::Vsync,
+        PresentMode::Uncapped,
+        PresentMode::Capped(30),
+        PresentMode::Capped(60),
+        PresentMode::Capped(120),
+        PresentMode::Capped(144),
+    ];
+
+    fn label(&self) -> String {
+        match self {
+            PresentMode::Vsync => "Vsync".to_owned(),
+            PresentMode::Uncapped => "Uncapped".to_owned(),
+            PresentMode::Capped(fps) => format!("Cap {fps}"),
+        }
+    }
+
+    fn step(&self, by: isize) -> Self {
+        let count = Self::ALL.len() as isize;
+        let idx = Self::ALL.iter().position(|mode| mode == self).unwrap_or(0) as isize;
+        return Self::ALL[(idx + by).rem_euclid(count) as usize];
+    }
+}
+
+/// How the camera follows between sub-pixels.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum CameraSmoothing {
+    /// Moves in whole sub-pixels. Steady, but slow pans step.
+    #[default]
+    Snapped,
+    /// Slides the finished world by what snapping left over, so slow pans
+    /// glide. Everything still lines up, as it all moves together.
+    Smooth,
+}
+
+impl CameraSmoothing {
+    fn toggled(&self) -> Self {
+        match self {
+            CameraSmoothing::Snapped => CameraSmoothing::Smooth,
+            CameraSmoothing::Smooth => CameraSmoothing::Snapped,
+        }
+    }
+}
+
+/// Player preferences, kept apart from the save so they hold across games.
+/// Anything missing from the file keeps its default.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub post_process: PostProcessSettings,
+    pub present_mode: PresentMode,
+    pub camera: CameraSmoothing,
+    /// Float the damage dealt up off enemies.
+    pub damage_numbers: bool,
+    /// Target pixels to a virtual one. Higher moves more smoothly, lower
+    /// is lighter on weak GPUs.
+    pub render_scale: u32,
+    /// Load everything in the asset manifest at startup, so levels change
+    /// without loading. On by default in release builds.
+    pub preload_assets: bool,
+    /// Save JSON levels from the editor gzipped.
+    pub compress_levels: bool,
+    /// Screens past the view that objects keep running before they only
+    /// sleep. Lower is lighter on weak CPUs.
+    pub sleep_screens: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            post_process: PostProcessSettings::default(),
+            present_mode: PresentMode::default(),
+            camera: CameraSmoothing::default(),
+            damage_numbers: true,
+            render_scale: 3,
+            preload_assets: !cfg!(debug_assertions),
+            compress_levels: false,
+            sleep_screens: LevelObjects::SLEEP_SCREENS,
+        }
+    }
+}
+
+impl Settings {
+    const MAX_RENDER_SCALE: u32 = 4;
+
+    /// The render scale, kept in range whatever the file says.
+    pub fn render_scale(&self) -> u32 {
+        return self.render_scale.clamp(1, Self::MAX_RENDER_SCALE);
+    }
+
+    /// Steps the view is snapped to per virtual pixel, one per target
+    /// pixel.
+    pub fn sub_pixels(&self) -> f32 {
+        return self.render_scale() as f32;
+    }
+}
+
+impl Settings {
+    const FILE: &str = "settings.json";
+
+    /// The saved settings, or the defaults if there are none yet or they
+    /// can't be read.
+    pub fn load() -> Self {
+        return match deserialize(data_path(Self::FILE)) {
+            Ok(settings) => settings,
+            Err(AssetManageError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(err) => {
+                warn!("Could not read the settings, using defaults: {}", err);
+                Self::default()
+            }
+        };
+    }
+
+    pub fn write(&self) -> AssetManageResult<()> {
+        return serialize(self, data_path(Self::FILE));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SettingsRow {
+    Palette,
+    Scanlines,
+    Curvature,
+    Frames,
+    Camera,
+    DamageNumbers,
+    RenderScale,
+}
+
+impl SettingsRow {
+    const ALL: [SettingsRow; 7] = [
+        SettingsRow::Palette,
+        SettingsRow::Scanlines,
+        SettingsRow::Curvature,
+        SettingsRow::Frames,
+        SettingsRow::Camera,
+        SettingsRow::DamageNumbers,
+        SettingsRow::RenderScale,
+    ];
+}
+
+/// The settings screen. Changes show straight away and are saved once it
+/// closes.
+pub struct SettingsMenu {
+    selected: usize,
+    /// Whether the game started with vsync, which can't change until the
+    /// next launch.
+    launched_with_vsync: bool,
+}
+
+impl SettingsMenu {
+    const W: f32 = 180.0;
+    const H: f32 = 132.0;
+    /// How much one press moves a slider.
+    const STEP: f32 = 0.1;
+
+    pub fn new(launched_with_vsync: bool) -> Self {
+        Self {
+            selected: 0,
+            launched_with_vsync,
+        }
+    }
+
+    /// Moves the selection and changes the selected setting. Returns false
+    /// once the menu is closed.
+    pub fn update(&mut self, input: &Input, settings: &mut Settings) -> bool {
+        let count = SettingsRow::ALL.len();
+        if input.menu_up {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.menu_down {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        let by = match (input.menu_left, input.menu_right) {
+            (true, false) => -1,
+            (false, true) => 1,
+            _ => 0,
+        };
+        if by != 0 {
+            let post_process = &mut settings.post_process;
+            let slide = |value: f32| (value + by as f32 * Self::STEP).clamp(0.0, 1.0);
+            match SettingsRow::ALL[self.selected] {
+                SettingsRow::Palette => post_process.palette = post_process.palette.step(by),
+                SettingsRow::Scanlines => post_process.scanlines = slide(post_process.scanlines),
+                SettingsRow::Curvature => post_process.curvature = slide(post_process.curvature),
+                SettingsRow::Frames => settings.present_mode = settings.present_mode.step(by),
+                SettingsRow::Camera => settings.camera = settings.camera.toggled(),
+                SettingsRow::DamageNumbers => settings.damage_numbers = !settings.damage_numbers,
+                SettingsRow::RenderScale => {
+                    let scale = settings.render_scale() as isize + by;
+                    settings.render_scale =
+                        scale.clamp(1, Settings::MAX_RENDER_SCALE as isize) as u32;
+                }
+            }
+        }
+
+        return !(input.toggle_settings || input.confirm);
+    }
+
+    pub fn render(&self, settings: &Settings) {
+        let x = (VIRTUAL_W - Self::W) / 2.0;
+        let y = (VIRTUAL_H - Self::H) / 2.0;
+        draw_rectangle(x, y, Self::W, Self::H, Color::from_rgba(20, 10, 30, 220));
+        draw_rectangle_lines(x, y, Self::W, Self::H, 1.0, WHITE);
+
+        let title = "Settings";
+        draw_pixel_text(
+            title,
+            (VIRTUAL_W - measure_pixel_text(title)) / 2.0,
+            y + 14.0,
+            WHITE,
+        );
+
+        let post_process = &settings.post_process;
+        for (idx, row) in SettingsRow::ALL.iter().enumerate() {
+            let (label, value) = match row {
+                SettingsRow::Palette => ("Palette", format!("{:?}", post_process.palette)),
+                SettingsRow::Scanlines => ("Scanlines", percent(post_process.scanlines)),
+                SettingsRow::Curvature => ("Curvature", percent(post_process.curvature)),
+                SettingsRow::Frames => ("Frames", settings.present_mode.label()),
+                SettingsRow::Camera => ("Camera", format!("{:?}", settings.camera)),
+                SettingsRow::DamageNumbers => {
+                    let value = match settings.damage_numbers {
+                        true => "On",
+                        false => "Off",
+                    };
+                    ("Damage numbers", value.to_owned())
+                }
+                SettingsRow::RenderScale => {
+                    ("Render scale", format!("{}x", settings.render_scale()))
+                }
+            };
+            let color = match idx == self.selected {
+                true => WHITE,
+                false => GRAY,
+            };
+
+            let line_y = y + 32.0 + idx as f32 * 12.0;
+            draw_pixel_text(label, x + 12.0, line_y, color);
+            let value = match idx == self.selected {
+                true => format!("< {value} >"),
+                false => value,
+            };
+            let value_x = x + Self::W - 12.0 - measure_pixel_text(&value);
+            draw_pixel_text(&value, value_x, line_y, color);
+        }
+
+        if (settings.present_mode == PresentMode::Vsync) != self.launched_with_vsync {
+            let note = "Vsync changes on restart";
+            let note_x = (VIRTUAL_W - measure_pixel_text(note)) / 2.0;
+            draw_pixel_text(note, note_x, y + Self::H - 8.0, GRAY);
+        }
+    }
+}
+
+fn percent(value: f32) -> String {
+    return format!("{}%", (value * 100.0).round());
+}
@@ -0,0 +1,313 @@
+use std::cell::OnceCell;
+
+use macroquad::{
+    color::{Color, WHITE},
+    logging::{error, warn},
+    material::{gl_use_default_material, gl_use_material, load_material, Material, MaterialParams},
+    miniquad::{
+        BlendFactor, BlendState, BlendValue, Equation, PipelineParams, ShaderSource, UniformDesc,
+        UniformType,
+    },
+};
+
+use crate::{settings::PostProcessSettings, VIRTUAL_H};
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}"#;
+
+/// Blends every opaque pixel toward `flash.rgb` by `flash.a`, keeping the
+/// sprite's own alpha so the silhouette stays intact.
+const FLASH_FRAGMENT: &str = r#"#version 100
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform sampler2D Texture;
+uniform lowp vec4 flash;
+
+void main() {
+    lowp vec4 base = texture2D(Texture, uv) * color;
+    gl_FragColor = vec4(mix(base.rgb, flash.rgb, flash.a), base.a);
+}"#;
+
+/// Draws the texture as it is, for materials where the blend state does
+/// the work.
+const PLAIN_FRAGMENT: &str = r#"#version 100
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = texture2D(Texture, uv) * color;
+}"#;
+
+/// The finished world on its way to the window: optionally bent like a
+/// CRT, snapped to the nearest of four palette colors and striped with
+/// scanlines.
+const POST_PROCESS_FRAGMENT: &str = r#"#version 100
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform sampler2D Texture;
+uniform lowp vec4 palette0;
+uniform lowp vec4 palette1;
+uniform lowp vec4 palette2;
+uniform lowp vec4 palette3;
+uniform lowp float quantize;
+uniform lowp float scanlines;
+uniform mediump float curvature;
+uniform mediump float lines;
+
+lowp vec3 nearest(lowp vec3 rgb) {
+    lowp vec3 best = palette0.rgb;
+    mediump float best_dist = distance(rgb, palette0.rgb);
+    mediump float dist = distance(rgb, palette1.rgb);
+    if (dist < best_dist) { best = palette1.rgb; best_dist = dist; }
+    dist = distance(rgb, palette2.rgb);
+    if (dist < best_dist) { best = palette2.rgb; best_dist = dist; }
+    dist = distance(rgb, palette3.rgb);
+    if (dist < best_dist) { best = palette3.rgb; }
+    return best;
+}
+
+void main() {
+    mediump vec2 pos = uv * 2.0 - 1.0;
+    pos += pos * (pos.yx * pos.yx) * curvature * 0.25;
+    pos = pos * 0.5 + 0.5;
+    if (pos.x < 0.0 || pos.x > 1.0 || pos.y < 0.0 || pos.y > 1.0) {
+        gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    lowp vec4 base = texture2D(Texture, pos) * color;
+    lowp vec3 rgb = base.rgb;
+    if (quantize > 0.5) {
+        rgb = nearest(rgb);
+    }
+    mediump float stripe = abs(sin(pos.y * lines * 3.14159));
+    rgb *= mix(1.0, 0.4 + 0.6 * stripe, scanlines);
+    gl_FragColor = vec4(rgb, base.a);
+}"#;
+
+thread_local! {
+    static POST_PROCESS_MATERIAL: OnceCell<Option<Material>> = const { OnceCell::new() };
+    static FLASH_MATERIAL: OnceCell<Option<Material>> = const { OnceCell::new() };
+    static MULTIPLY_MATERIAL: OnceCell<Option<Material>> = const { OnceCell::new() };
+    static ADDITIVE_MATERIAL: OnceCell<Option<Material>> = const { OnceCell::new() };
+}
+
+fn flash_material() -> Option<Material> {
+    FLASH_MATERIAL.with(|cell| {
+        cell.get_or_init(|| {
+            let material = load_material(
+                ShaderSource::Glsl {
+                    vertex: VERTEX,
+                    fragment: FLASH_FRAGMENT,
+                },
+                MaterialParams {
+                    uniforms: vec![UniformDesc::new("flash", UniformType::Float4)],
+                    pipeline_params: PipelineParams {
+                        color_blend: Some(BlendState::new(
+                            Equation::Add,
+                            BlendFactor::Value(BlendValue::SourceAlpha),
+                            BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                        )),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            );
+
+            match material {
+                Ok(material) => Some(material),
+                Err(err) => {
+                    warn!("Flash shader failed to compile: {:?}", err);
+                    None
+                }
+            }
+        })
+        .clone()
+    })
+}
+
+/// A material that draws textures as they are, combined with what is
+/// already there by `blend`.
+fn blend_material(name: &str, blend: BlendState) -> Option<Material> {
+    let material = load_material(
+        ShaderSource::Glsl {
+            vertex: VERTEX,
+            fragment: PLAIN_FRAGMENT,
+        },
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend: Some(blend),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    match material {
+        Ok(material) => Some(material),
+        Err(err) => {
+            warn!("{} shader failed to compile: {:?}", name, err);
+            None
+        }
+    }
+}
+
+fn post_process_material() -> Option<Material> {
+    POST_PROCESS_MATERIAL.with(|cell| {
+        cell.get_or_init(|| {
+            let material = load_material(
+                ShaderSource::Glsl {
+                    vertex: VERTEX,
+                    fragment: POST_PROCESS_FRAGMENT,
+                },
+                MaterialParams {
+                    uniforms: vec![
+                        UniformDesc::new("palette0", UniformType::Float4),
+                        UniformDesc::new("palette1", UniformType::Float4),
+                        UniformDesc::new("palette2", UniformType::Float4),
+                        UniformDesc::new("palette3", UniformType::Float4),
+                        UniformDesc::new("quantize", UniformType::Float1),
+                        UniformDesc::new("scanlines", UniformType::Float1),
+                        UniformDesc::new("curvature", UniformType::Float1),
+                        UniformDesc::new("lines", UniformType::Float1),
+                    ],
+                    ..Default::default()
+                },
+            );
+
+            match material {
+                Ok(material) => Some(material),
+                Err(err) => {
+                    error!(
+                        "Post-process shader failed to compile, drawing without it: {:?}",
+                        err
+                    );
+                    None
+                }
+            }
+        })
+        .clone()
+    })
+}
+
+fn multiply_material() -> Option<Material> {
+    MULTIPLY_MATERIAL.with(|cell| {
+        cell.get_or_init(|| {
+            blend_material(
+                "Multiply",
+                BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::DestinationColor),
+                    BlendFactor::Zero,
+                ),
+            )
+        })
+        .clone()
+    })
+}
+
+fn additive_material() -> Option<Material> {
+    ADDITIVE_MATERIAL.with(|cell| {
+        cell.get_or_init(|| {
+            blend_material(
+                "Additive",
+                BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::SourceAlpha),
+                    BlendFactor::One,
+                ),
+            )
+        })
+        .clone()
+    })
+}
+
+/// Runs `draw` with everything it draws added onto what is already there,
+/// weighted by its alpha. Draws nothing if the shader is unavailable.
+pub fn draw_additive(draw: impl FnOnce()) {
+    let Some(material) = additive_material() else {
+        return;
+    };
+
+    gl_use_material(&material);
+    draw();
+    gl_use_default_material();
+}
+
+/// Runs `draw` with everything it draws multiplied into what is already
+/// there, so white leaves it alone and black blacks it out. Draws nothing
+/// if the shader is unavailable.
+pub fn draw_multiplied(draw: impl FnOnce()) {
+    let Some(material) = multiply_material() else {
+        return;
+    };
+
+    gl_use_material(&material);
+    draw();
+    gl_use_default_material();
+}
+
+/// Runs `draw`, a blit of the finished world, through the post-process set
+/// up by `settings`. Falls back to drawing it plain if the shader is
+/// unavailable.
+pub fn draw_post_processed(settings: &PostProcessSettings, draw: impl FnOnce()) {
+    let material = match settings.is_active() {
+        true => post_process_material(),
+        false => None,
+    };
+    let Some(material) = material else {
+        draw();
+        return;
+    };
+
+    let colors = settings.palette.colors();
+    for (idx, color) in colors.unwrap_or([WHITE; 4]).iter().enumerate() {
+        material.set_uniform(
+            &format!("palette{idx}"),
+            [color.r, color.g, color.b, color.a],
+        );
+    }
+    let quantize: f32 = match colors {
+        Some(_) => 1.0,
+        None => 0.0,
+    };
+    material.set_uniform("quantize", quantize);
+    material.set_uniform("scanlines", settings.scanlines);
+    material.set_uniform("curvature", settings.curvature);
+    material.set_uniform("lines", VIRTUAL_H);
+    gl_use_material(&material);
+    draw();
+    gl_use_default_material();
+}
+
+/// Runs `draw` with everything it draws tinted solid `color`. Falls back to
+/// drawing untinted if the shader is unavailable.
+pub fn draw_flashing(color: Color, draw: impl FnOnce()) {
+    let Some(material) = flash_material() else {
+        draw();
+        return;
+    };
+
+    material.set_uniform("flash", [color.r, color.g, color.b, color.a]);
+    gl_use_material(&material);
+    draw();
+    gl_use_default_material();
+}
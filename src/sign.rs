@@ -0,0 +1,72 @@
+use macroquad::{
+    color::WHITE,
+    texture::{draw_texture, Texture2D},
+};
+
+use crate::{
+    asset_loading::{load_tex, AssetManageResult},
+    body::Body,
+    dialogue::DialoguePage,
+    object::{in_reach, GameObject, Interaction, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    world::World,
+};
+
+/// A post with some writing on it, read through the dialogue box.
+pub struct Sign {
+    pub body: Body,
+    text: String,
+    tex: Texture2D,
+}
+
+impl Sign {
+    const PATH: &str = "assets/art/sprites/sign.png";
+    const SIZE: f32 = 12.0;
+
+    pub async fn load_tex() -> AssetManageResult<Texture2D> {
+        return load_tex(Self::PATH).await;
+    }
+
+    pub fn new(x: f32, y: f32, text: String, tex: &Texture2D) -> Self {
+        Self {
+            body: Body::new(x, y, Self::SIZE, Self::SIZE, None),
+            text,
+            tex: tex.clone(),
+        }
+    }
+}
+
+impl GameObject for Sign {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Sign
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn can_interact(&self, player: &Player) -> bool {
+        return !self.text.trim().is_empty() && in_reach(player, &self.body.hitbox);
+    }
+
+    fn interact(&mut self, _player: &mut Player) -> Interaction {
+        return Interaction::Talk(vec![DialoguePage {
+            speaker: None,
+            portrait: None,
+            text: self.text.clone(),
+        }]);
+    }
+
+    fn render(&self, world: &World) {
+        draw_texture(
+            &self.tex,
+            self.body.hitbox.center().x - self.tex.width() / 2.0 - world.x,
+            self.body.hitbox.bottom() - self.tex.height() - world.y,
+            WHITE,
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use macroquad::math::Vec2;
+
+/// Buckets indices by position so "who is near this point" only looks at a
+/// handful of cells instead of everything in the level.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// `cell_size` should be at least the largest query radius, since
+    /// queries only look one cell out.
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        let cell = (position / self.cell_size).floor();
+        return (cell.x as i32, cell.y as i32);
+    }
+
+    pub fn insert(&mut self, idx: usize, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(idx);
+    }
+
+    /// Every index in the cell containing `position` and its eight
+    /// neighbours.
+    pub fn near(&self, position: Vec2) -> impl Iterator<Item = usize> + '_ {
+        let (col, row) = self.cell(position);
+        return (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (col + dx, row + dy)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied();
+    }
+}
@@ -0,0 +1,140 @@
+use macroquad::{
+    color::{Color, MAGENTA},
+    math::{vec2, Rect, Vec2},
+    rand::RandGenerator,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    text::draw_text,
+};
+
+use crate::{
+    body::Body,
+    enemies::{Enemy, EnemyType},
+    levels::Level,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+    TILE_SIZE,
+};
+
+/// Keeps up to `population` enemies of one type alive inside a rectangle of
+/// cells, replacing each one that dies after `respawn_delay` seconds.
+pub struct SpawnRegion {
+    pub body: Body,
+    enemy: EnemyType,
+    population: usize,
+    respawn_delay: f32,
+    timer: f32,
+    /// How many were alive last tick, to notice deaths.
+    last_alive: usize,
+    rng: RandGenerator,
+}
+
+impl SpawnRegion {
+    /// Spawns never land closer than this to the player.
+    const PLAYER_CLEARANCE: f32 = TILE_SIZE * 3.0;
+    /// Random cells tried per spawn before giving up until the next frame.
+    const ATTEMPTS: u32 = 8;
+
+    /// `area` is the world-space rectangle of cells the region covers.
+    /// `seed` drives where enemies spawn.
+    pub fn new(
+        area: Rect,
+        enemy: EnemyType,
+        population: usize,
+        respawn_delay: f32,
+        seed: u64,
+    ) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+
+        let center = area.center();
+        Self {
+            body: Body::new(center.x, center.y, area.w, area.h, None),
+            enemy,
+            population,
+            respawn_delay,
+            timer: 0.0,
+            last_alive: 0,
+            rng,
+        }
+    }
+
+    /// Returns true when an enemy should be spawned this frame. `alive` is
+    /// how many of this region's enemies are still in the level. The region
+    /// fills up straight away, then waits out the delay after each death.
+    pub fn tick(&mut self, alive: usize, dt: f32) -> bool {
+        if alive < self.last_alive {
+            self.timer = self.respawn_delay;
+        }
+        self.last_alive = alive;
+        self.timer = (self.timer - dt).max(0.0);
+
+        return alive < self.population && self.timer <= 0.0;
+    }
+
+    /// Where and what to spawn for the enemy `tick` asked for: a random
+    /// free cell in the region away from the player, or `None` if none
+    /// turned up this time.
+    pub fn spawn_request(&self, level: &Level, player: Vec2) -> Option<(EnemyType, Vec2, u64)> {
+        let hitbox = self.body.hitbox;
+        let rows = (hitbox.h / TILE_SIZE) as usize;
+        let cols = (hitbox.w / TILE_SIZE) as usize;
+
+        for _ in 0..Self::ATTEMPTS {
+            let row = self.rng.gen_range(0, rows);
+            let col = self.rng.gen_range(0, cols);
+            let center =
+                vec2(hitbox.x, hitbox.y) + vec2(col as f32 + 0.5, row as f32 + 0.5) * TILE_SIZE;
+            if center.distance(player) < Self::PLAYER_CLEARANCE {
+                continue;
+            }
+
+            let probe = Body::new(center.x, center.y, Enemy::SIZE, Enemy::SIZE, None);
+            if !probe.overlaps_solid(level) {
+                let seed = (self.rng.rand() as u64) << 32 | self.rng.rand() as u64;
+                return Some((self.enemy, center, seed));
+            }
+        }
+
+        return None;
+    }
+}
+
+impl GameObject for SpawnRegion {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::SpawnRegion
+    }
+
+    /// Spawning needs the level's object list, so `LevelObjects` drives it
+    /// through `tick` instead.
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    /// Runs the respawn delay down without spawning.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        self.timer = (self.timer - dt).max(0.0);
+        return ObjectStatus::Alive;
+    }
+
+    /// Regions are invisible during play.
+    fn render(&self, _world: &World) {}
+
+    fn render_marker(&self, world: &World) {
+        let x = self.body.screen_x(world);
+        let y = self.body.screen_y(world);
+        let (w, h) = (self.body.hitbox.w, self.body.hitbox.h);
+        draw_rectangle(x, y, w, h, Color { a: 0.15, ..MAGENTA });
+        draw_rectangle_lines(x, y, w, h, 1.0, MAGENTA);
+        draw_text(
+            &format!("{}/{}", self.last_alive, self.population),
+            x + 1.0,
+            y + 10.0,
+            8.0,
+            MAGENTA,
+        );
+    }
+}
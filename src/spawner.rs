@@ -0,0 +1,144 @@
+use macroquad::{
+    color::MAGENTA,
+    math::{vec2, Vec2},
+    shapes::draw_rectangle_lines,
+    text::draw_text,
+};
+
+use crate::{
+    body::Body,
+    enemies::{Enemy, EnemyType},
+    levels::Level,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    world::World,
+    TILE_SIZE,
+};
+
+/// Produces `count` enemies over time, one every `interval` seconds, keeping
+/// at most `max_alive` of them in the level at once.
+pub struct Spawner {
+    pub body: Body,
+    enemy: EnemyType,
+    count: usize,
+    interval: f32,
+    max_alive: usize,
+    produced: usize,
+    timer: f32,
+}
+
+impl Spawner {
+    /// How far from the spawner, in tiles, a spawn position may be.
+    const SEARCH_TILES: i32 = 2;
+
+    pub fn new(
+        x: f32,
+        y: f32,
+        enemy: EnemyType,
+        count: usize,
+        interval: f32,
+        max_alive: usize,
+    ) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE, TILE_SIZE, None),
+            enemy,
+            count,
+            interval,
+            max_alive,
+            produced: 0,
+            timer: interval,
+        }
+    }
+
+    /// Returns true when an enemy should be spawned this frame. `alive` is
+    /// how many of this spawner's enemies are still in the level.
+    pub fn tick(&mut self, alive: usize, dt: f32) -> bool {
+        if self.produced >= self.count || alive >= self.max_alive {
+            return false;
+        }
+
+        self.timer -= dt;
+        if self.timer > 0.0 {
+            return false;
+        }
+
+        self.timer = self.interval;
+        self.produced += 1;
+        return true;
+    }
+
+    /// The free cell center nearest to the cell `offset` cells away from the
+    /// spawner, searching no further than `SEARCH_TILES` from the spawner.
+    /// `size` is the hitbox size of the enemy being placed.
+    fn spawn_position(&self, level: &Level, offset: (i32, i32), size: Vec2) -> Option<Vec2> {
+        let origin = self.body.hitbox.center();
+        let wanted = origin + vec2(offset.0 as f32, offset.1 as f32) * TILE_SIZE;
+
+        let mut candidates = Vec::new();
+        for row in -Self::SEARCH_TILES..=Self::SEARCH_TILES {
+            for col in -Self::SEARCH_TILES..=Self::SEARCH_TILES {
+                candidates.push(origin + vec2(col as f32, row as f32) * TILE_SIZE);
+            }
+        }
+        candidates.sort_by(|a, b| a.distance(wanted).total_cmp(&b.distance(wanted)));
+
+        return candidates.into_iter().find(|center| {
+            let probe = Body::new(center.x, center.y, size.x, size.y, None);
+            !probe.overlaps_solid(level)
+        });
+    }
+
+    /// Where and what to spawn for the enemy `tick` just asked for, or
+    /// `None` if there is no free cell nearby. Spawns go round the spawner's
+    /// four sides in turn.
+    pub fn spawn_request(&self, level: &Level) -> Option<(EnemyType, Vec2, u64)> {
+        const SIDES: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+        let side = SIDES[(self.produced - 1) % SIDES.len()];
+        let size = vec2(Enemy::SIZE, Enemy::SIZE);
+        let position = self.spawn_position(level, side, size)?;
+
+        let cell = (self.body.hitbox.center() / TILE_SIZE).floor();
+        let seed = ((cell.y as u64) << 32 | cell.x as u64) ^ (self.produced as u64).rotate_left(48);
+        return Some((self.enemy, position, seed));
+    }
+}
+
+impl GameObject for Spawner {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::Spawner
+    }
+
+    /// Spawning needs the level's object list, so `LevelObjects` drives it
+    /// through `tick` instead.
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    /// Runs the timer down without spawning, so a spawner that was due
+    /// spawns once it wakes.
+    fn sleep(&mut self, dt: f32) -> ObjectStatus {
+        if self.produced < self.count {
+            self.timer = (self.timer - dt).max(0.0);
+        }
+        return ObjectStatus::Alive;
+    }
+
+    /// Spawners are invisible during play.
+    fn render(&self, _world: &World) {}
+
+    fn render_marker(&self, world: &World) {
+        let x = self.body.screen_x(world);
+        let y = self.body.screen_y(world);
+        draw_rectangle_lines(x, y, TILE_SIZE, TILE_SIZE, 1.0, MAGENTA);
+        draw_text(
+            &format!("{}/{}", self.produced, self.count),
+            x + 1.0,
+            y + 10.0,
+            8.0,
+            MAGENTA,
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use macroquad::{
+    color::{Color, DARKGRAY, GRAY, GREEN, LIGHTGRAY, RED},
+    math::vec2,
+    shapes::{draw_rectangle, draw_triangle},
+    time::get_time,
+};
+
+use crate::{
+    body::Body,
+    object::{GameObject, ObjectKind, ObjectStatus, UpdateCtx},
+    player::Player,
+    world::World,
+    TILE_SIZE,
+};
+
+enum SpikePhase {
+    Retracted,
+    /// Poking out as a warning just before the spikes extend.
+    Rising,
+    Extended,
+}
+
+/// Spikes that cycle out and back in every `period` seconds, hurting the
+/// player only while extended. Every trap runs off the same clock, so traps
+/// with stepped `offset`s ripple in sequence.
+pub struct SpikeTrap {
+    pub body: Body,
+    period: f32,
+    offset: f32,
+}
+
+impl SpikeTrap {
+    /// Portion of the cycle spent extended, at the end of the cycle.
+    const EXTENDED_FRACTION: f32 = 0.4;
+    /// Portion of the cycle spent rising, just before extending.
+    const RISING_FRACTION: f32 = 0.1;
+    const DAMAGE: u32 = 1;
+    const KNOCKBACK: f32 = 120.0;
+    const SPIKES: usize = 3;
+
+    pub fn new(x: f32, y: f32, period: f32, offset: f32) -> Self {
+        Self {
+            body: Body::new(x, y, TILE_SIZE * 0.75, TILE_SIZE * 0.75, None),
+            period: period.max(0.1),
+            offset,
+        }
+    }
+
+    /// How far through its cycle the trap is, from 0 to 1.
+    fn cycle(&self) -> f32 {
+        return ((get_time() as f32 + self.offset) / self.period).rem_euclid(1.0);
+    }
+
+    fn phase(&self) -> SpikePhase {
+        let cycle = self.cycle();
+        if cycle >= 1.0 - Self::EXTENDED_FRACTION {
+            return SpikePhase::Extended;
+        }
+        if cycle >= 1.0 - Self::EXTENDED_FRACTION - Self::RISING_FRACTION {
+            return SpikePhase::Rising;
+        }
+        return SpikePhase::Retracted;
+    }
+}
+
+impl GameObject for SpikeTrap {
+    fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::SpikeTrap
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx) -> ObjectStatus {
+        ObjectStatus::Alive
+    }
+
+    fn touch_player(&mut self, player: &mut Player) {
+        if matches!(self.phase(), SpikePhase::Extended)
+            && self.body.hitbox.overlaps(&player.body.hitbox)
+        {
+            player.take_damage(Self::DAMAGE, self.body.hitbox.center(), Self::KNOCKBACK);
+        }
+    }
+
+    fn render(&self, world: &World) {
+        let x = self.body.screen_x(world);
+        let y = self.body.screen_y(world);
+        let (w, h) = (self.body.hitbox.w, self.body.hitbox.h);
+        draw_rectangle(x, y, w, h, DARKGRAY);
+
+        let height = match self.phase() {
+            SpikePhase::Retracted => return,
+            SpikePhase::Rising => h * 0.25,
+            SpikePhase::Extended => h * 0.75,
+        };
+        let gap = w / Self::SPIKES as f32;
+        let base = y + h * 0.85;
+        for spike in 0..Self::SPIKES {
+            let left = x + spike as f32 * gap;
+            draw_triangle(
+                vec2(left + 1.0, base),
+                vec2(left + gap - 1.0, base),
+                vec2(left + gap / 2.0, base - height),
+                LIGHTGRAY,
+            );
+        }
+    }
+
+    /// A bar filling over one cycle, red during the extended window, for
+    /// lining up sequences of traps.
+    fn render_marker(&self, world: &World) {
+        let x = self.body.screen_x(world);
+        let y = self.body.screen_y(world) + self.body.hitbox.h + 1.0;
+        let w = self.body.hitbox.w;
+
+        draw_rectangle(x, y, w, 2.0, GRAY);
+        draw_rectangle(
+            x + w * (1.0 - Self::EXTENDED_FRACTION),
+            y,
+            w * Self::EXTENDED_FRACTION,
+            2.0,
+            Color { a: 0.5, ..RED },
+        );
+        let color = match self.phase() {
+            SpikePhase::Extended => RED,
+            _ => GREEN,
+        };
+        draw_rectangle(x, y, w * self.cycle(), 1.0, color);
+    }
+}
@@ -1,9 +1,12 @@
 use macroquad::texture::Texture2D;
 use serde::{Deserialize, Serialize};
 
-use crate::asset_loading::{load_tex_with_meta, AssetManageResult};
+use crate::asset_loading::{
+    clear_problem, first_missing, load_tex_with_meta, meta_path, placeholder_tex, report_problem,
+    AssetManageResult,
+};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct SpriteFrameSpan {
     pub start_frame: usize,
     pub number_of_frames: usize,
@@ -21,10 +24,16 @@ impl Default for SpriteFrameSpan {
 }
 
 #[derive(Serialize, Deserialize)]
-struct SpriteSerializable {
+pub struct SpriteSerializable {
     pub up: SpriteFrameSpan,
     pub down: SpriteFrameSpan,
     pub side: SpriteFrameSpan,
+    pub attack: Option<SpriteFrameSpan>,
+    pub swim: Option<SpriteFrameSpan>,
+    pub hit: Option<SpriteFrameSpan>,
+    pub death: Option<SpriteFrameSpan>,
+    pub disguise: Option<SpriteFrameSpan>,
+    pub reveal: Option<SpriteFrameSpan>,
     pub frames: Vec<(f32, f32)>,
     pub frame_w: f32,
     pub frame_h: f32,
@@ -36,6 +45,12 @@ impl Default for SpriteSerializable {
             up: SpriteFrameSpan::default(),
             down: SpriteFrameSpan::default(),
             side: SpriteFrameSpan::default(),
+            attack: None,
+            swim: None,
+            hit: None,
+            death: None,
+            disguise: None,
+            reveal: None,
             frames: Vec::new(),
             frame_w: 0.0,
             frame_h: 0.0,
@@ -43,18 +58,25 @@ impl Default for SpriteSerializable {
     }
 }
 
+#[derive(Clone)]
 pub struct Sprite {
     pub tex: Texture2D,
     pub up: SpriteFrameSpan,
     pub down: SpriteFrameSpan,
     pub side: SpriteFrameSpan,
+    pub attack: Option<SpriteFrameSpan>,
+    pub swim: Option<SpriteFrameSpan>,
+    pub hit: Option<SpriteFrameSpan>,
+    pub death: Option<SpriteFrameSpan>,
+    pub disguise: Option<SpriteFrameSpan>,
+    pub reveal: Option<SpriteFrameSpan>,
     pub frames: Vec<(f32, f32)>,
     pub frame_w: f32,
     pub frame_h: f32,
 }
 
 impl Sprite {
-    const PATH: &str = "assets/art/sprites";
+    pub const DIR: &str = "assets/art/sprites";
 
     async fn load(serializable: SpriteSerializable, tex: Texture2D) -> Self {
         Self {
@@ -62,15 +84,129 @@ impl Sprite {
             up: serializable.up,
             down: serializable.down,
             side: serializable.side,
+            attack: serializable.attack,
+            swim: serializable.swim,
+            hit: serializable.hit,
+            death: serializable.death,
+            disguise: serializable.disguise,
+            reveal: serializable.reveal,
             frames: serializable.frames,
             frame_w: serializable.frame_w,
             frame_h: serializable.frame_h,
         }
     }
 
-    pub async fn load_player() -> AssetManageResult<Sprite> {
-        let path = format!("{}/player.png", Self::PATH);
+    pub fn player_path() -> String {
+        return format!("{}/player.png", Self::DIR);
+    }
+
+    /// Loads the sprite sheet at `path`. If it or its meta is missing it
+    /// loads as a one-frame checkerboard, and the problem is reported.
+    async fn load_or_placeholder(path: &str) -> AssetManageResult<Sprite> {
+        let meta = meta_path(path);
+        if let Some(missing) = first_missing(&[path, &meta]) {
+            report_problem(path, format!("{missing} is missing"));
+            return Ok(Self::placeholder());
+        }
+
         let (serializable, tex) = load_tex_with_meta(path).await?;
+        clear_problem(path);
         return Ok(Self::load(serializable, tex).await);
     }
+
+    /// A checkerboard tile with every animation showing it.
+    fn placeholder() -> Sprite {
+        let tex = placeholder_tex();
+        let span = SpriteFrameSpan {
+            start_frame: 0,
+            number_of_frames: 1,
+            duration_seconds: 1.0,
+        };
+        Self {
+            up: span.clone(),
+            down: span.clone(),
+            side: span,
+            attack: None,
+            swim: None,
+            hit: None,
+            death: None,
+            disguise: None,
+            reveal: None,
+            frames: vec![(0.0, 0.0)],
+            frame_w: tex.width(),
+            frame_h: tex.height(),
+            tex,
+        }
+    }
+
+    pub async fn load_player() -> AssetManageResult<Sprite> {
+        return Self::load_or_placeholder(&Self::player_path()).await;
+    }
+
+    pub async fn load_npc(name: &str) -> AssetManageResult<Sprite> {
+        let path = format!("{}/npcs/{}.png", Self::DIR, name);
+        return Self::load_or_placeholder(&path).await;
+    }
+
+    pub async fn load_enemy(name: &str) -> AssetManageResult<Sprite> {
+        let path = format!("{}/enemies/{}.png", Self::DIR, name);
+        return Self::load_or_placeholder(&path).await;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{asset_loading::tests::stand_in_tex, object::tests::block_on};
+
+    /// A sprite of a single frame, for bodies in tests that need to face
+    /// somewhere.
+    pub fn sprite() -> Sprite {
+        let span = SpriteFrameSpan {
+            start_frame: 0,
+            number_of_frames: 1,
+            duration_seconds: 1.0,
+        };
+        return Sprite {
+            tex: stand_in_tex(),
+            up: span.clone(),
+            down: span.clone(),
+            side: span,
+            attack: None,
+            swim: None,
+            hit: None,
+            death: None,
+            disguise: None,
+            reveal: None,
+            frames: vec![(0.0, 0.0)],
+            frame_w: 16.0,
+            frame_h: 16.0,
+        };
+    }
+
+    #[test]
+    fn sprite_builds_from_its_meta() {
+        let walk = SpriteFrameSpan {
+            start_frame: 2,
+            number_of_frames: 4,
+            duration_seconds: 0.5,
+        };
+        let meta = SpriteSerializable {
+            down: walk.clone(),
+            attack: Some(walk),
+            frames: vec![(0.0, 0.0); 6],
+            frame_w: 16.0,
+            frame_h: 24.0,
+            ..SpriteSerializable::default()
+        };
+        let json = serde_json::to_string(&meta).unwrap();
+        let meta: SpriteSerializable = serde_json::from_str(&json).unwrap();
+
+        let sprite = block_on(Sprite::load(meta, stand_in_tex()));
+        assert_eq!(sprite.down.start_frame, 2);
+        assert_eq!(sprite.attack.map(|span| span.number_of_frames), Some(4));
+        assert!(sprite.swim.is_none());
+        assert_eq!(sprite.frames.len(), 6);
+        assert_eq!((sprite.frame_w, sprite.frame_h), (16.0, 24.0));
+    }
 }
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StaminaConfig {
+    pub max: f32,
+    pub sprint_drain_per_sec: f32,
+    pub dash_cost: f32,
+    pub regen_per_sec: f32,
+    pub regen_delay_seconds: f32,
+}
+
+impl Default for StaminaConfig {
+    fn default() -> Self {
+        Self {
+            max: 100.0,
+            sprint_drain_per_sec: 25.0,
+            dash_cost: 20.0,
+            regen_per_sec: 35.0,
+            regen_delay_seconds: 0.7,
+        }
+    }
+}
+
+pub struct Stamina {
+    pub current: f32,
+    pub config: StaminaConfig,
+    regen_delay: f32,
+}
+
+impl Stamina {
+    pub fn new(config: StaminaConfig) -> Self {
+        Self {
+            current: config.max,
+            config,
+            regen_delay: 0.0,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.current >= self.config.max
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current / self.config.max
+    }
+
+    fn spend(&mut self, amount: f32) {
+        self.current = (self.current - amount).max(0.0);
+        self.regen_delay = self.config.regen_delay_seconds;
+    }
+
+    /// Drains stamina for one frame of sprinting. Returns false (spending
+    /// nothing) once the meter is empty.
+    pub fn drain_sprint(&mut self, dt: f32) -> bool {
+        if self.current <= 0.0 {
+            return false;
+        }
+
+        self.spend(self.config.sprint_drain_per_sec * dt);
+        return true;
+    }
+
+    /// Pays for a dash up front. Returns false (spending nothing) if the
+    /// meter is below the dash cost.
+    pub fn try_dash(&mut self) -> bool {
+        if self.current < self.config.dash_cost {
+            return false;
+        }
+
+        self.spend(self.config.dash_cost);
+        return true;
+    }
+
+    /// Regenerates once the regen delay since the last spend has elapsed.
+    /// Anything spent during the delay restarts it.
+    pub fn update(&mut self, dt: f32) {
+        if self.regen_delay > 0.0 {
+            self.regen_delay -= dt;
+            return;
+        }
+
+        self.current = (self.current + self.config.regen_per_sec * dt).min(self.config.max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamina() -> Stamina {
+        return Stamina::new(StaminaConfig::default());
+    }
+
+    #[test]
+    fn sprinting_drains_until_empty() {
+        let mut stamina = stamina();
+        assert!(stamina.drain_sprint(1.0));
+        assert_eq!(stamina.current, 75.0);
+
+        for _ in 0..3 {
+            assert!(stamina.drain_sprint(1.0));
+        }
+        assert_eq!(stamina.current, 0.0);
+        assert!(!stamina.drain_sprint(1.0));
+    }
+
+    #[test]
+    fn dash_needs_its_full_cost() {
+        let mut stamina = stamina();
+        stamina.current = 25.0;
+        assert!(stamina.try_dash());
+        assert_eq!(stamina.current, 5.0);
+        assert!(!stamina.try_dash());
+        assert_eq!(stamina.current, 5.0);
+    }
+
+    #[test]
+    fn regen_waits_out_the_delay() {
+        let mut stamina = stamina();
+        stamina.try_dash();
+
+        stamina.update(0.5);
+        assert_eq!(stamina.current, 80.0);
+        stamina.update(0.3);
+        assert_eq!(stamina.current, 80.0);
+        stamina.update(0.2);
+        assert_eq!(stamina.current, 87.0);
+    }
+
+    #[test]
+    fn regen_stops_at_max() {
+        let mut stamina = stamina();
+        stamina.try_dash();
+        stamina.update(1.0);
+        stamina.update(10.0);
+        assert!(stamina.is_full());
+        assert_eq!(stamina.current, 100.0);
+    }
+
+    #[test]
+    fn spending_during_the_delay_restarts_it() {
+        let mut stamina = stamina();
+        stamina.try_dash();
+        stamina.update(0.6);
+        stamina.drain_sprint(0.0);
+
+        stamina.update(0.6);
+        assert_eq!(stamina.current, 80.0);
+        stamina.update(0.2);
+        assert_eq!(stamina.current, 80.0);
+        stamina.update(0.2);
+        assert!(stamina.current > 80.0);
+    }
+}
@@ -0,0 +1,86 @@
+use std::cell::Cell;
+
+use macroquad::{
+    color::Color,
+    math::Rect,
+    models::{draw_mesh, Mesh, Vertex},
+    texture::Texture2D,
+};
+
+thread_local! {
+    static DRAWS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// How many meshes tile batches have drawn since this was last called.
+pub fn take_draw_count() -> usize {
+    return DRAWS.take();
+}
+
+fn draw_counted(mesh: &Mesh) {
+    draw_mesh(mesh);
+    DRAWS.set(DRAWS.get() + 1);
+}
+
+/// Tile quads gathered by texture, so a layer costs one draw per tileset
+/// instead of one per tile.
+pub struct TileBatch {
+    meshes: Vec<Mesh>,
+}
+
+impl TileBatch {
+    /// Quads per mesh. Keeps each draw inside macroquad's vertex and index
+    /// limits; a fuller mesh is drawn early and started again.
+    const MAX_QUADS: usize = 800;
+
+    pub fn new() -> Self {
+        Self { meshes: Vec::new() }
+    }
+
+    /// Adds a quad showing `source` of `tex` at `x`, `y`, the same as
+    /// `draw_texture_ex` would with no `dest_size`.
+    pub fn push(&mut self, tex: &Texture2D, x: f32, y: f32, source: Rect, color: Color) {
+        let idx = match self
+            .meshes
+            .iter()
+            .position(|mesh| mesh.texture.as_ref() == Some(tex))
+        {
+            Some(idx) => idx,
+            None => {
+                self.meshes.push(Mesh {
+                    vertices: Vec::new(),
+                    indices: Vec::new(),
+                    texture: Some(tex.clone()),
+                });
+                self.meshes.len() - 1
+            }
+        };
+
+        let mesh = &mut self.meshes[idx];
+        if mesh.vertices.len() / 4 >= Self::MAX_QUADS {
+            draw_counted(mesh);
+            mesh.vertices.clear();
+            mesh.indices.clear();
+        }
+
+        let (tex_w, tex_h) = (tex.width(), tex.height());
+        let (u0, v0) = (source.x / tex_w, source.y / tex_h);
+        let (u1, v1) = (source.right() / tex_w, source.bottom() / tex_h);
+        let (x1, y1) = (x + source.w, y + source.h);
+
+        let first = mesh.vertices.len() as u16;
+        mesh.vertices.extend([
+            Vertex::new(x, y, 0.0, u0, v0, color),
+            Vertex::new(x1, y, 0.0, u1, v0, color),
+            Vertex::new(x1, y1, 0.0, u1, v1, color),
+            Vertex::new(x, y1, 0.0, u0, v1, color),
+        ]);
+        mesh.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|corner| first + corner));
+    }
+
+    pub fn draw(&self) {
+        for mesh in &self.meshes {
+            draw_counted(mesh);
+        }
+    }
+}
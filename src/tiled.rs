@@ -0,0 +1,425 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    asset_loading::{AssetManageError, AssetManageResult, Versioned},
+    levels::{LevelSerializable, TilePointer, TileVec},
+    object::{ObjectListing, ObjectType},
+    tilesets::{tile_at_pos, TileAsset},
+    weather::WeatherKind,
+    TILE_SIZE,
+};
+
+/// A map exported from Tiled as JSON, with only what the importer reads.
+#[derive(Deserialize)]
+struct TiledMap {
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    width: usize,
+    height: usize,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Deserialize)]
+struct TiledLayer {
+    #[serde(default)]
+    name: String,
+    r#type: String,
+    /// Tile ids row by row, or a base64 string when the map isn't saved
+    /// as CSV.
+    #[serde(default)]
+    data: Value,
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+    /// Children of a group layer.
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+}
+
+#[derive(Deserialize)]
+struct TiledObject {
+    #[serde(default)]
+    id: u32,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    /// Called `class` by Tiled 1.9.
+    #[serde(default, alias = "class")]
+    r#type: String,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+    /// Set on tile objects, which Tiled places by their bottom edge.
+    #[serde(default)]
+    gid: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct TiledProperty {
+    name: String,
+    value: Value,
+}
+
+#[derive(Deserialize)]
+struct TiledTileset {
+    firstgid: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    image: Option<String>,
+    /// Set instead of the rest when the tileset is kept in its own file.
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+}
+
+/// A Tiled map brought over as a level.
+pub struct TiledImport {
+    pub level: LevelSerializable,
+    /// What couldn't be brought over, with how many times.
+    pub skipped: Vec<String>,
+}
+
+/// Bits Tiled sets on a tile id when the tile is flipped or rotated.
+const FLIP_BITS: u32 = 0xf000_0000;
+
+/// Reads the Tiled JSON map `json`, from `file`, as a level drawn with the
+/// tilesets in `known`. Tile layers named background, object and overlay
+/// fill the matching layers, and objects take their type from a `type`
+/// property holding an object type as the level file writes it.
+///
+/// Maps the game can't lay out at all are an error. Anything else it can't
+/// bring over is left out and listed in `skipped`.
+pub fn import_tiled(
+    json: &[u8],
+    file: &str,
+    known: &HashMap<String, Vec<TileAsset>>,
+) -> AssetManageResult<TiledImport> {
+    let map: TiledMap = serde_json::from_slice(json)?;
+
+    let mut unsupported = Vec::new();
+    if map.orientation != "orthogonal" {
+        unsupported.push(format!("{} orientation", map.orientation));
+    }
+    if map.infinite {
+        unsupported.push("an infinite map".to_owned());
+    }
+    if map.tilewidth != TILE_SIZE as u32 || map.tileheight != TILE_SIZE as u32 {
+        unsupported.push(format!("{}x{} tiles", map.tilewidth, map.tileheight));
+    }
+    if !unsupported.is_empty() {
+        return Err(AssetManageError::Unsupported {
+            file: file.to_owned(),
+            features: unsupported,
+        });
+    }
+
+    let mut import = TiledImporter {
+        map: &map,
+        known,
+        background_layer: empty_layer(map.height, map.width),
+        object_layer: empty_layer(map.height, map.width),
+        overlay_layer: empty_layer(map.height, map.width),
+        objects: Vec::new(),
+        skipped: BTreeMap::new(),
+    };
+    for layer in &map.layers {
+        import.layer(layer);
+    }
+
+    return Ok(TiledImport {
+        level: LevelSerializable {
+            version: LevelSerializable::VERSION,
+            background_layer: import.background_layer,
+            object_layer: import.object_layer,
+            overlay_layer: import.overlay_layer,
+            rows: map.height,
+            cols: map.width,
+            objects: import.objects,
+            indoors: false,
+            darkness: 0.0,
+            weather: WeatherKind::default(),
+        },
+        skipped: import
+            .skipped
+            .into_iter()
+            .map(|(what, count)| match count {
+                1 => what,
+                count => format!("{what} (x{count})"),
+            })
+            .collect(),
+    });
+}
+
+fn empty_layer(rows: usize, cols: usize) -> TileVec {
+    return vec![vec![None; cols]; rows];
+}
+
+struct TiledImporter<'a> {
+    map: &'a TiledMap,
+    known: &'a HashMap<String, Vec<TileAsset>>,
+    background_layer: TileVec,
+    object_layer: TileVec,
+    overlay_layer: TileVec,
+    objects: Vec<ObjectListing>,
+    skipped: BTreeMap<String, usize>,
+}
+
+impl TiledImporter<'_> {
+    fn skip(&mut self, what: String) {
+        *self.skipped.entry(what).or_default() += 1;
+    }
+
+    fn layer(&mut self, layer: &TiledLayer) {
+        match layer.r#type.as_str() {
+            "tilelayer" => self.tile_layer(layer),
+            "objectgroup" => {
+                for object in &layer.objects {
+                    self.object(&layer.name, object);
+                }
+            }
+            "group" => {
+                for child in &layer.layers {
+                    self.layer(child);
+                }
+            }
+            other => self.skip(format!("{other} {:?}, not tiles or objects", layer.name)),
+        }
+    }
+
+    fn tile_layer(&mut self, layer: &TiledLayer) {
+        let name = layer.name.to_lowercase();
+        if !["background", "object", "overlay"].contains(&name.as_str()) {
+            self.skip(format!(
+                "tile layer {:?}, not background, object or overlay",
+                layer.name
+            ));
+            return;
+        }
+        let Some(data) = layer.data.as_array() else {
+            self.skip(format!("tile layer {:?} isn't saved as CSV", layer.name));
+            return;
+        };
+
+        let (rows, cols) = (self.map.height, self.map.width);
+        let mut cells = empty_layer(rows, cols);
+        for (idx, gid) in data.iter().enumerate() {
+            let gid = gid.as_u64().unwrap_or_default() as u32;
+            let (row, col) = (idx / layer.width.max(1), idx % layer.width.max(1));
+            if gid == 0 || row >= rows || col >= cols {
+                continue;
+            }
+            cells[row][col] = self.tile(gid);
+        }
+
+        match name.as_str() {
+            "background" => self.background_layer = cells,
+            "object" => self.object_layer = cells,
+            _ => self.overlay_layer = cells,
+        }
+    }
+
+    /// The game's tile for the Tiled tile id `gid`.
+    fn tile(&mut self, gid: u32) -> Option<TilePointer> {
+        if gid & FLIP_BITS != 0 {
+            self.skip("flipped or rotated tiles".to_owned());
+            return None;
+        }
+
+        let tileset = self
+            .map
+            .tilesets
+            .iter()
+            .filter(|tileset| tileset.firstgid <= gid)
+            .max_by_key(|tileset| tileset.firstgid)?;
+        if let Some(source) = &tileset.source {
+            self.skip(format!("tiles from external tileset {source:?}, embed it"));
+            return None;
+        }
+
+        let name = tileset
+            .image
+            .as_deref()
+            .and_then(|image| Path::new(image).file_stem())
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let Some(tiles) = self.known.get(name) else {
+            self.skip(format!(
+                "tiles from tileset {:?}, not one of the game's",
+                tileset.name
+            ));
+            return None;
+        };
+
+        let local = gid - tileset.firstgid;
+        let columns = tileset.columns.max(1);
+        let step = TILE_SIZE as u32 + tileset.spacing;
+        let x = (tileset.margin + local % columns * step) as f32;
+        let y = (tileset.margin + local / columns * step) as f32;
+        return match tile_at_pos(tiles, x, y) {
+            Some(idx) => Some(TilePointer(name.to_owned(), idx)),
+            None => {
+                self.skip(format!("tiles of {name} not set up in the game"));
+                None
+            }
+        };
+    }
+
+    fn object(&mut self, layer: &str, object: &TiledObject) {
+        let r#type = object
+            .properties
+            .iter()
+            .find(|property| property.name == "type")
+            .and_then(|property| property.value.as_str())
+            .unwrap_or(&object.r#type);
+
+        let Some(r#type) = ObjectType::parse(r#type) else {
+            self.skip(format!(
+                "object {} on {layer:?} with unknown type {type:?}",
+                object.id
+            ));
+            return;
+        };
+
+        let top = match object.gid {
+            Some(_) => object.y - object.height,
+            None => object.y,
+        };
+        let col = ((object.x + object.width / 2.0) / TILE_SIZE).floor();
+        let row = ((top + object.height / 2.0) / TILE_SIZE).floor();
+        if col < 0.0 || row < 0.0 || col >= self.map.width as f32 || row >= self.map.height as f32 {
+            self.skip(format!("object {} on {layer:?} outside the map", object.id));
+            return;
+        }
+
+        self.objects
+            .push(ObjectListing::new(0, row as usize, col as usize, r#type));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilesets::TileLayer;
+
+    fn tile(x: f32, y: f32) -> TileAsset {
+        return TileAsset {
+            x,
+            y,
+            auto_rule: None,
+            layer: TileLayer::Background,
+            group: None,
+            collision_matrix: None,
+            surface: None,
+            water: false,
+            animation: None,
+            y_sort: false,
+            sort_offset: 0.0,
+        };
+    }
+
+    /// The game knows the first two tiles of the beach sheet.
+    fn known() -> HashMap<String, Vec<TileAsset>> {
+        let beach = vec![tile(0.0, 0.0), tile(16.0, 0.0)];
+        return HashMap::from([("beach".to_owned(), beach)]);
+    }
+
+    fn import(path: &str) -> AssetManageResult<TiledImport> {
+        return import_tiled(&std::fs::read(path).unwrap(), path, &known());
+    }
+
+    fn beach(idx: usize) -> Option<TilePointer> {
+        return Some(TilePointer("beach".to_owned(), idx));
+    }
+
+    #[test]
+    fn imports_tile_layers_by_name() {
+        let level = import("tests/fixtures/tiled_map.tmj").unwrap().level;
+        assert_eq!((level.rows, level.cols), (2, 3));
+        assert_eq!(
+            level.background_layer,
+            [
+                vec![beach(0), beach(1), beach(0)],
+                vec![beach(1), beach(0), None]
+            ]
+        );
+        // Found inside its group.
+        assert_eq!(
+            level.object_layer,
+            [vec![None, None, None], vec![None, None, beach(1)]]
+        );
+        assert_eq!(level.overlay_layer, [vec![None; 3], vec![None; 3]]);
+    }
+
+    #[test]
+    fn imports_objects_by_type() {
+        let level = import("tests/fixtures/tiled_map.tmj").unwrap().level;
+        let objects: Vec<(usize, usize, String)> = level
+            .objects
+            .iter()
+            .map(|listing| (listing.row(), listing.col(), listing.r#type().label()))
+            .collect();
+        assert_eq!(
+            objects,
+            [
+                (0, 0, "Checkpoint".to_owned()),
+                (1, 2, "Enemy:PurpleBlob".to_owned()),
+                // Tile objects are placed by their bottom edge.
+                (1, 1, "SavePoint".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_what_it_skipped() {
+        let skipped = import("tests/fixtures/tiled_map.tmj").unwrap().skipped;
+        for expected in [
+            "flipped or rotated tiles",
+            "tiles from tileset \"Mystery\", not one of the game's",
+            "tiles of beach not set up in the game",
+            "tile layer \"decor\", not background, object or overlay",
+            "imagelayer \"sky\", not tiles or objects",
+            "object 4 on \"things\" with unknown type \"Dragon\"",
+            "object 5 on \"things\" outside the map",
+        ] {
+            assert!(skipped.iter().any(|what| what == expected), "{skipped:?}");
+        }
+        assert_eq!(skipped.len(), 7);
+    }
+
+    #[test]
+    fn refuses_maps_it_cant_lay_out() {
+        let path = "tests/fixtures/tiled_isometric.tmj";
+        match import(path) {
+            Err(AssetManageError::Unsupported { file, features }) => {
+                assert_eq!(file, path);
+                assert_eq!(
+                    features,
+                    ["isometric orientation", "an infinite map", "32x16 tiles"]
+                );
+            }
+            Err(err) => panic!("expected the map to be unsupported, got {err}"),
+            Ok(_) => panic!("imported an isometric map"),
+        }
+    }
+}
@@ -1,50 +1,106 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use macroquad::texture::{Image, Texture2D};
+use macroquad::{
+    math::Rect,
+    texture::{Image, Texture2D},
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
-    asset_loading::{load_tex_with_meta, AssetManageResult},
+    asset_loading::{
+        clear_problem, create_default_meta, deserialize_versioned, fill_field, first_missing,
+        load_tex, meta_path, placeholder_tex, report_problem, AssetManageError, AssetManageResult,
+        Versioned,
+    },
+    asset_root::AssetRoot,
     TILE_COLLISION_SECTIONS, TILE_SIZE,
 };
 
+#[derive(Clone)]
 pub struct TilesetAsset {
     pub tex: Texture2D,
     pub tiles: Vec<TileAsset>,
+    /// Where the meta is saved, worked out from the image's path when it
+    /// loads rather than kept in the meta.
     pub meta_path: PathBuf,
+    /// Stands in for a tileset whose image or meta is missing.
+    pub placeholder: bool,
 }
 
+/// Drawn for tiles a tileset doesn't have, as when it's a placeholder.
+static MISSING_TILE: TileAsset = TileAsset {
+    x: 0.0,
+    y: 0.0,
+    auto_rule: None,
+    layer: TileLayer::Background,
+    group: None,
+    collision_matrix: None,
+    surface: None,
+    water: false,
+    animation: None,
+    y_sort: false,
+    sort_offset: 0.0,
+};
+
 impl TilesetAsset {
-    fn new(serializable: TilesetAssetSerializable, tex: Texture2D) -> TilesetAsset {
+    fn new(serializable: TilesetAssetSerializable, tex: Texture2D, meta: String) -> TilesetAsset {
         TilesetAsset {
             tex,
             tiles: serializable.tiles,
-            meta_path: serializable.meta_path,
+            meta_path: PathBuf::from(meta),
+            placeholder: false,
         }
     }
 
+    /// The tiles as the meta keeps them.
+    pub fn to_serializable(&self) -> TilesetAssetSerializable {
+        return TilesetAssetSerializable {
+            version: TilesetAssetSerializable::VERSION,
+            tiles: self.tiles.clone(),
+        };
+    }
+
+    pub const DIR: &str = "assets/art/tiles";
+
+    /// Where the texture of the tileset `tile_asset` is kept.
+    pub fn path(tile_asset: &str) -> String {
+        return format!("{}/{}.png", Self::DIR, tile_asset);
+    }
+
+    /// Loads the tileset `tile_asset`. If its image or meta is missing it
+    /// loads as a checkerboard with no tiles, so levels using it still load,
+    /// and the problem is reported.
     pub async fn load(tile_asset: &str) -> AssetManageResult<Self> {
-        let path = format!("assets/art/tiles/{}.png", tile_asset);
-        let (serializable, tex) = load_tex_with_meta(path).await?;
+        let path = Self::path(tile_asset);
+        let meta = meta_path(&path);
+        create_default_meta::<TilesetAssetSerializable>(&path)?;
+        if let Some(missing) = first_missing(&[&path, &meta]) {
+            report_problem(&path, format!("{missing} is missing"));
+            return Ok(TilesetAsset {
+                tex: placeholder_tex(),
+                tiles: Vec::new(),
+                meta_path: PathBuf::from(meta),
+                placeholder: true,
+            });
+        }
+
+        let tex = load_tex(&path).await?;
+        let serializable = deserialize_versioned(&meta)?;
+        clear_problem(&path);
+        return Ok(Self::new(serializable, tex, meta));
+    }
 
-        return Ok(Self::new(serializable, tex));
+    /// The tile at `idx`, or a checkerboard tile if there's no such tile.
+    pub fn tile(&self, idx: usize) -> &TileAsset {
+        return self.tiles.get(idx).unwrap_or(&MISSING_TILE);
     }
 
     pub fn get_tile_at_pos(&self, x: f32, y: f32) -> Option<usize> {
-        return self
-            .tiles
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, t)| {
-                let a = x - t.x;
-                let b = y - t.y;
-                if a == 0.0 && b == 0.0 {
-                    Some(idx)
-                } else {
-                    None
-                }
-            })
-            .last();
+        return tile_at_pos(&self.tiles, x, y);
     }
 
     fn is_section_transparent(
@@ -82,7 +138,7 @@ impl TilesetAsset {
                 if let Some(tile) = self.get_tile_at_pos(x, y) {
                     let tile = self.tiles.get_mut(tile).expect("Tile should exist");
                     if let TileLayer::Object = tile.layer {
-                        if let None = tile.collision_matrix {
+                        if tile.collision_matrix.is_none() {
                             tile.collision_matrix = Some(CollisionMatrix::new());
                         }
                     }
@@ -100,6 +156,11 @@ impl TilesetAsset {
                             layer: TileLayer::Object,
                             group: None,
                             collision_matrix: Some(CollisionMatrix::new()),
+                            surface: None,
+                            water: false,
+                            animation: None,
+                            y_sort: false,
+                            sort_offset: 0.0,
                         });
                     }
                 }
@@ -108,10 +169,81 @@ impl TilesetAsset {
     }
 }
 
+/// The last of `tiles` drawn from the sheet at `x`, `y`.
+pub fn tile_at_pos(tiles: &[TileAsset], x: f32, y: f32) -> Option<usize> {
+    return tiles.iter().rposition(|tile| tile.x == x && tile.y == y);
+}
+
+/// The tile tables of every tileset the game has, by name, read without
+/// loading their textures. Importers match other editors' tilesets
+/// against them.
+pub fn known_tile_tables() -> AssetManageResult<HashMap<String, Vec<TileAsset>>> {
+    let mut known = HashMap::new();
+    for entry in std::fs::read_dir(AssetRoot::resolve(TilesetAsset::DIR))? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("png") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+
+        let meta_path = meta_path(&path.to_string_lossy());
+        if Path::new(&meta_path).exists() {
+            let meta: TilesetAssetSerializable = deserialize_versioned(meta_path)?;
+            known.insert(name.to_owned(), meta.tiles);
+        }
+    }
+    return Ok(known);
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TilesetAssetSerializable {
+    pub version: u32,
     pub tiles: Vec<TileAsset>,
-    pub meta_path: PathBuf,
+}
+
+/// A tileset with no tiles cut yet.
+impl Default for TilesetAssetSerializable {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            tiles: Vec::new(),
+        }
+    }
+}
+
+impl Versioned for TilesetAssetSerializable {
+    const VERSION: u32 = 2;
+
+    fn migrate(from: u32, doc: &mut Value) -> AssetManageResult<()> {
+        match from {
+            // Before versions, tiles could leave out everything added since
+            // the first tilesets.
+            0 => {
+                for tile in doc["tiles"].as_array_mut().into_iter().flatten() {
+                    fill_field(tile, "surface", None::<SurfaceKind>)?;
+                    fill_field(tile, "water", false)?;
+                    fill_field(tile, "animation", None::<TileAnimation>)?;
+                    fill_field(tile, "y_sort", false)?;
+                    fill_field(tile, "sort_offset", 0.0)?;
+                }
+            }
+            // Metas kept their own path, which broke once the project
+            // moved. It's worked out from the image's path now.
+            1 => {
+                if let Some(fields) = doc.as_object_mut() {
+                    fields.remove("meta_path");
+                }
+            }
+            _ => {
+                return Err(AssetManageError::Missing(format!(
+                    "tileset migration from version {from}"
+                )))
+            }
+        }
+        return Ok(());
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -186,6 +318,40 @@ impl CollisionMatrix {
             matrix: [[true, true, true], [true, true, true], [true, true, true]],
         }
     }
+
+    /// A solid section that is the only solid one in its column, i.e. an
+    /// obstacle one section high that can be hopped over.
+    pub fn is_low(&self, row: usize, col: usize) -> bool {
+        return self.matrix[row][col]
+            && (0..TILE_COLLISION_SECTIONS as usize)
+                .filter(|r| self.matrix[*r][col])
+                .count()
+                == 1;
+    }
+}
+
+/// What a tile feels like to walk on, used to pick footstep effects.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SurfaceKind {
+    Sand,
+    Grass,
+    Stone,
+}
+
+/// Cycles a tile through `frames` sheet tiles, starting with its own and
+/// running rightward, at `fps` frames per second.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct TileAnimation {
+    pub frames: usize,
+    pub fps: f32,
+}
+
+impl TileAnimation {
+    /// Which frame shows at `time` seconds on the animation clock. Every
+    /// tile with the same animation is on the same frame.
+    pub fn frame(&self, time: f32) -> usize {
+        return (time * self.fps) as usize % self.frames.max(1);
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -196,4 +362,140 @@ pub struct TileAsset {
     pub layer: TileLayer,
     pub group: Option<u8>,
     pub collision_matrix: Option<CollisionMatrix>,
+    pub surface: Option<SurfaceKind>,
+    pub water: bool,
+    pub animation: Option<TileAnimation>,
+    /// Drawn sorted in with bodies by its bottom edge, for trees and poles
+    /// that bodies can walk behind. Only applies on the object layer.
+    pub y_sort: bool,
+    /// Pixels to move the sort line down from the tile's bottom edge.
+    pub sort_offset: f32,
+}
+
+impl TileAsset {
+    /// Where in the sheet the tile is drawn from at `time` seconds on the
+    /// animation clock.
+    pub fn source(&self, time: f32) -> Rect {
+        let frame = self.animation.map_or(0, |animation| animation.frame(time));
+        return Rect::new(
+            self.x + frame as f32 * TILE_SIZE,
+            self.y,
+            TILE_SIZE,
+            TILE_SIZE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_loading::{
+        deserialize, serialize,
+        tests::{scratch_dir, stand_in_tex},
+    };
+
+    #[test]
+    fn unversioned_tilesets_gain_what_was_added_since() {
+        let meta: TilesetAssetSerializable =
+            deserialize_versioned("tests/fixtures/tileset_v0.json").unwrap();
+        assert_eq!(meta.version, TilesetAssetSerializable::VERSION);
+        let tile = &meta.tiles[0];
+        assert_eq!((tile.x, tile.y, tile.group), (16.0, 0.0, Some(2)));
+        assert!(tile.collision_matrix.as_ref().unwrap().is_low(2, 0));
+        assert_eq!(tile.surface, None);
+        assert!(!tile.water && !tile.y_sort);
+        assert!(tile.animation.is_none());
+        assert_eq!(tile.sort_offset, 0.0);
+    }
+
+    #[test]
+    fn version_one_tilesets_keep_their_tiles() {
+        let meta: TilesetAssetSerializable =
+            deserialize_versioned("tests/fixtures/tileset_v1.json").unwrap();
+        assert_eq!(meta.version, TilesetAssetSerializable::VERSION);
+        let tile = &meta.tiles[0];
+        assert_eq!(tile.surface, Some(SurfaceKind::Sand));
+        assert!(tile.water);
+        assert_eq!(tile.animation.map(|animation| animation.frames), Some(3));
+    }
+
+    #[test]
+    fn tilesets_from_a_newer_game_are_refused() {
+        let err = deserialize_versioned::<TilesetAssetSerializable, _>(
+            "tests/fixtures/tileset_too_new.json",
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(
+            err,
+            AssetManageError::TooNew {
+                version: 3,
+                supported: 2,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("only reads up to version 2"));
+    }
+
+    #[test]
+    fn tileset_builds_from_its_meta() {
+        let tile = TileAsset {
+            x: 16.0,
+            y: 32.0,
+            auto_rule: Some(TileAutoRule::from_array([true; 8])),
+            layer: TileLayer::Object,
+            group: Some(1),
+            collision_matrix: Some(CollisionMatrix::new()),
+            surface: Some(SurfaceKind::Stone),
+            water: false,
+            animation: Some(TileAnimation {
+                frames: 2,
+                fps: 4.0,
+            }),
+            y_sort: true,
+            sort_offset: 2.0,
+        };
+        let meta = TilesetAssetSerializable {
+            tiles: vec![tile],
+            ..TilesetAssetSerializable::default()
+        };
+        let json = serde_json::to_string(&meta).unwrap();
+        let meta: TilesetAssetSerializable = serde_json::from_str(&json).unwrap();
+
+        let tileset = TilesetAsset::new(meta, stand_in_tex(), "test.json".to_owned());
+        assert!(!tileset.placeholder);
+        assert_eq!(tileset.get_tile_at_pos(16.0, 32.0), Some(0));
+        let tile = tileset.tile(0);
+        assert_eq!(tile.surface, Some(SurfaceKind::Stone));
+        assert_eq!(tile.source(0.3), Rect::new(32.0, 32.0, 16.0, 16.0));
+        let rule = tile.auto_rule.as_ref().unwrap();
+        assert_eq!(rule.cmp(&TileAutoRule::from_array([true; 8])), Some(8));
+
+        // Tiles past the end draw as the placeholder.
+        assert_eq!(tileset.tile(5).x, 0.0);
+        assert_eq!(tileset.to_serializable().tiles.len(), 1);
+    }
+
+    #[test]
+    fn saved_metas_leave_out_where_they_are_kept() {
+        // An old meta that still names where it was kept.
+        let old: TilesetAssetSerializable =
+            deserialize_versioned("tests/fixtures/tileset_v1.json").unwrap();
+        let meta = scratch_dir("tileset_meta_path")
+            .join("sand.png.meta.json")
+            .to_string_lossy()
+            .into_owned();
+        let tileset = TilesetAsset::new(old, stand_in_tex(), meta.clone());
+        serialize(&tileset.to_serializable(), &tileset.meta_path).unwrap();
+
+        let saved: serde_json::Value = deserialize(&meta).unwrap();
+        assert!(saved.get("meta_path").is_none());
+        assert!(!std::fs::read_to_string(&meta).unwrap().contains("moved"));
+
+        let reloaded: TilesetAssetSerializable = deserialize_versioned(&meta).unwrap();
+        let reloaded = TilesetAsset::new(reloaded, stand_in_tex(), meta.clone());
+        assert_eq!(reloaded.meta_path, PathBuf::from(&meta));
+        assert_eq!(reloaded.tiles.len(), 1);
+        assert!(reloaded.tiles[0].water);
+    }
 }
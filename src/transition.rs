@@ -0,0 +1,125 @@
+use macroquad::{
+    color::{Color, BLACK},
+    shapes::draw_rectangle,
+    window::{screen_height, screen_width},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionStyle {
+    /// The whole screen darkens at once.
+    Fade,
+    /// Black sweeps in from the left and clears off to the right.
+    Wipe,
+}
+
+enum TransitionState {
+    Clear,
+    Out { time: f32, seconds: f32 },
+    Covered,
+    In { time: f32, seconds: f32 },
+}
+
+/// Covers the finished frame, editor panel included, while levels load or
+/// scenes change. Start it out, wait for `is_covered` to do the swap
+/// behind it, then start it back in.
+pub struct Transition {
+    style: TransitionStyle,
+    state: TransitionState,
+}
+
+impl Transition {
+    /// How long a level change takes to cover and uncover the screen.
+    pub const LEVEL_OUT_SECONDS: f32 = 0.4;
+    pub const LEVEL_IN_SECONDS: f32 = 0.6;
+
+    pub fn new() -> Self {
+        Self {
+            style: TransitionStyle::Fade,
+            state: TransitionState::Clear,
+        }
+    }
+
+    /// Starts covering the screen over `seconds`.
+    pub fn start_out(&mut self, style: TransitionStyle, seconds: f32) {
+        self.style = style;
+        self.state = TransitionState::Out { time: 0.0, seconds };
+    }
+
+    /// Starts uncovering the screen over `seconds`, in the style it was
+    /// covered with. Starting from a clear screen snaps it black first.
+    pub fn start_in(&mut self, seconds: f32) {
+        self.state = TransitionState::In { time: 0.0, seconds };
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.state = match self.state {
+            TransitionState::Out { time, seconds } => match time + dt >= seconds {
+                true => TransitionState::Covered,
+                false => TransitionState::Out {
+                    time: time + dt,
+                    seconds,
+                },
+            },
+            TransitionState::In { time, seconds } => match time + dt >= seconds {
+                true => TransitionState::Clear,
+                false => TransitionState::In {
+                    time: time + dt,
+                    seconds,
+                },
+            },
+            TransitionState::Clear => TransitionState::Clear,
+            TransitionState::Covered => TransitionState::Covered,
+        };
+    }
+
+    /// Whether the screen is fully black, so whatever is behind it can
+    /// change without being seen.
+    pub fn is_covered(&self) -> bool {
+        matches!(self.state, TransitionState::Covered)
+    }
+
+    /// Whether any of the screen is covered.
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, TransitionState::Clear)
+    }
+
+    /// How far the screen is covered, from 0 to 1.
+    fn coverage(&self) -> f32 {
+        let coverage = match self.state {
+            TransitionState::Clear => 0.0,
+            TransitionState::Out { time, seconds } => time / seconds,
+            TransitionState::Covered => 1.0,
+            TransitionState::In { time, seconds } => 1.0 - time / seconds,
+        };
+        return coverage.clamp(0.0, 1.0);
+    }
+
+    /// Draws over the whole window. Call after the render target is drawn
+    /// to the screen.
+    pub fn render(&self) {
+        let coverage = self.coverage();
+        if coverage <= 0.0 {
+            return;
+        }
+
+        let (w, h) = (screen_width(), screen_height());
+        match self.style {
+            TransitionStyle::Fade => draw_rectangle(
+                0.0,
+                0.0,
+                w,
+                h,
+                Color {
+                    a: coverage,
+                    ..BLACK
+                },
+            ),
+            TransitionStyle::Wipe => match self.state {
+                TransitionState::In { .. } => {
+                    draw_rectangle(w * (1.0 - coverage), 0.0, w * coverage, h, BLACK)
+                }
+                _ => draw_rectangle(0.0, 0.0, w * coverage, h, BLACK),
+            },
+        }
+    }
+}
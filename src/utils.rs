@@ -46,6 +46,27 @@ pub async fn alert(text: &str) {
     }
 }
 
+/// Asks a yes or no question, answered with the buttons, or Enter for yes
+/// and Escape for no.
+pub async fn confirm(text: &str) -> bool {
+    next_frame().await;
+
+    loop {
+        set_default_camera();
+        clear_background(DARKGRAY);
+        root_ui().label(None, text);
+
+        if root_ui().button(None, "Yes") || is_key_pressed(KeyCode::Enter) {
+            return true;
+        }
+        if root_ui().button(None, "No") || is_key_pressed(KeyCode::Escape) {
+            return false;
+        }
+
+        next_frame().await;
+    }
+}
+
 pub fn splitter() {
     root_ui().label(None, &"-".repeat(20))
 }
@@ -0,0 +1,343 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    asset_loading::{asset_exists, deserialize, deserialize_versioned, meta_path, read_asset},
+    asset_root::AssetRoot,
+    level_file::LevelFormat,
+    levels::LevelSerializable,
+    manifest::AssetManifest,
+    object::ObjectType,
+    sprites::{Sprite, SpriteFrameSpan, SpriteSerializable},
+    tilesets::{TilesetAsset, TilesetAssetSerializable},
+};
+
+/// Checks that every level, tileset and sprite sheet in the manifest, or in
+/// the asset folders when there's no manifest, reads and that they agree
+/// with each other. Only reads files, so it runs without a window. Returns
+/// what's wrong, a line each.
+pub fn validate_assets() -> Vec<String> {
+    let mut problems = Vec::new();
+    let manifest = match AssetManifest::load() {
+        Some(manifest) => manifest,
+        None => match AssetManifest::scan() {
+            Ok(manifest) => manifest,
+            Err(err) => return vec![format!("Could not list the assets: {err}")],
+        },
+    };
+
+    let mut tile_counts = HashMap::new();
+    for name in &manifest.tilesets {
+        tile_count(name, &mut tile_counts, &mut problems);
+    }
+
+    let mut levels = HashMap::new();
+    for name in &manifest.levels {
+        let path = LevelFormat::find(name);
+        match LevelFormat::of(&path).read(&path) {
+            Ok(level) => {
+                check_level(name, &level, &mut tile_counts, &mut problems);
+                levels.insert(name.clone(), level);
+            }
+            Err(err) => problems.push(format!("{path}: {err}")),
+        }
+    }
+    for (name, level) in &levels {
+        check_portals(name, level, &levels, &mut problems);
+    }
+
+    for sprite in &manifest.sprites {
+        check_sprite(sprite, &mut problems);
+    }
+
+    problems.sort();
+    return problems;
+}
+
+/// Runs `validate_assets` for `--validate`, printing a report. Returns the
+/// exit code, nonzero if anything is broken.
+pub fn run_cli() -> i32 {
+    if let Err(err) = AssetRoot::install() {
+        eprintln!("Could not validate: {err}");
+        return 2;
+    }
+
+    let problems = validate_assets();
+    if problems.is_empty() {
+        println!("No asset problems");
+        return 0;
+    }
+
+    eprintln!("Asset problems ({}):", problems.len());
+    for problem in &problems {
+        eprintln!("  {problem}");
+    }
+    return 1;
+}
+
+/// The start of every PNG file.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// What's wrong with the image at `path`, if anything. Only looks at the
+/// header, so a PNG broken further in still passes.
+fn check_image(path: &str) -> Option<String> {
+    if !asset_exists(path) {
+        return Some(format!("{path} is missing"));
+    }
+    return match read_asset(path) {
+        Ok(bytes) if bytes.starts_with(&PNG_SIGNATURE) => None,
+        Ok(_) => Some(format!("{path} is not a PNG")),
+        Err(err) => Some(format!("{path}: {err}")),
+    };
+}
+
+/// How many tiles the tileset `name` has, reading it the first time it's
+/// asked for. `None` if it can't be read, which is reported once.
+fn tile_count(
+    name: &str,
+    counts: &mut HashMap<String, Option<usize>>,
+    problems: &mut Vec<String>,
+) -> Option<usize> {
+    if let Some(count) = counts.get(name) {
+        return *count;
+    }
+
+    let path = TilesetAsset::path(name);
+    let meta = meta_path(&path);
+    let count = match check_image(&path) {
+        Some(problem) => {
+            problems.push(problem);
+            None
+        }
+        None if !asset_exists(&meta) => {
+            problems.push(format!("{meta} is missing"));
+            None
+        }
+        None => match deserialize_versioned::<TilesetAssetSerializable, _>(&meta) {
+            Ok(tileset) => Some(tileset.tiles.len()),
+            Err(err) => {
+                problems.push(format!("{meta}: {err}"));
+                None
+            }
+        },
+    };
+    counts.insert(name.to_owned(), count);
+    return count;
+}
+
+/// Checks the level `name`'s layers are its size and point at tiles that
+/// exist, and that its objects sit inside it.
+fn check_level(
+    name: &str,
+    level: &LevelSerializable,
+    tile_counts: &mut HashMap<String, Option<usize>>,
+    problems: &mut Vec<String>,
+) {
+    let layers = [
+        ("background", &level.background_layer),
+        ("object", &level.object_layer),
+        ("overlay", &level.overlay_layer),
+    ];
+    for (layer_name, layer) in layers {
+        let sized = layer.len() == level.rows && layer.iter().all(|row| row.len() == level.cols);
+        if !sized {
+            problems.push(format!(
+                "Level {name}: the {layer_name} layer isn't {} by {}",
+                level.rows, level.cols
+            ));
+        }
+
+        // Reported once per tileset and layer, not per cell.
+        let mut reported = HashSet::new();
+        for (row, cells) in layer.iter().enumerate() {
+            for (col, pointer) in cells.iter().enumerate() {
+                let Some(pointer) = pointer else {
+                    continue;
+                };
+                let Some(count) = tile_count(&pointer.0, tile_counts, problems) else {
+                    if reported.insert(pointer.0.clone()) {
+                        problems.push(format!(
+                            "Level {name}: the {layer_name} layer uses tileset {}, which can't be read",
+                            pointer.0
+                        ));
+                    }
+                    continue;
+                };
+                if pointer.1 >= count && reported.insert(pointer.0.clone()) {
+                    problems.push(format!(
+                        "Level {name}: the {layer_name} layer uses tile {} of {} at row {row}, column {col}, which only has {count}",
+                        pointer.1, pointer.0
+                    ));
+                }
+            }
+        }
+    }
+
+    for object in &level.objects {
+        if object.row() >= level.rows || object.col() >= level.cols {
+            problems.push(format!(
+                "Level {name}: {:?} at row {}, column {} is outside the level",
+                object.r#type(),
+                object.row(),
+                object.col()
+            ));
+        }
+
+        let sprite = match object.r#type() {
+            ObjectType::Enemy(enemy)
+            | ObjectType::Spawner { enemy, .. }
+            | ObjectType::SpawnRegion { enemy, .. } => {
+                Some(format!("{}/enemies/{}.png", Sprite::DIR, enemy.file_name()))
+            }
+            ObjectType::Npc { sprite, .. } => Some(format!("{}/npcs/{sprite}.png", Sprite::DIR)),
+            _ => None,
+        };
+        if let Some(sprite) = sprite.filter(|sprite| !asset_exists(sprite)) {
+            problems.push(format!(
+                "Level {name}: an object at row {}, column {} uses {sprite}, which is missing",
+                object.row(),
+                object.col()
+            ));
+        }
+    }
+}
+
+/// Checks the portals in the level `name` lead to anchors that exist. Levels
+/// outside `levels` are only checked for being there.
+fn check_portals(
+    name: &str,
+    level: &LevelSerializable,
+    levels: &HashMap<String, LevelSerializable>,
+    problems: &mut Vec<String>,
+) {
+    for object in &level.objects {
+        let ObjectType::Portal {
+            target_level,
+            target_anchor,
+        } = object.r#type()
+        else {
+            continue;
+        };
+
+        let Some(target) = levels.get(target_level) else {
+            if !asset_exists(LevelFormat::find(target_level)) {
+                problems.push(format!(
+                    "Level {name}: a portal leads to level {target_level}, which is missing"
+                ));
+            }
+            continue;
+        };
+        let has_anchor = target.objects.iter().any(|object| {
+            matches!(object.r#type(), ObjectType::Anchor { name } if name == target_anchor)
+        });
+        if !has_anchor {
+            problems.push(format!(
+                "Level {name}: a portal leads to anchor {target_anchor} in {target_level}, which has no such anchor"
+            ));
+        }
+    }
+}
+
+/// Checks the sheet `sprite`, a path under the sprites folder, and its
+/// meta. Character sheets must also only use frames they have.
+fn check_sprite(sprite: &str, problems: &mut Vec<String>) {
+    let path = format!("{}/{sprite}", Sprite::DIR);
+    if let Some(problem) = check_image(&path) {
+        problems.push(problem);
+        return;
+    }
+
+    // The object sheets each have their own meta, or none, and only need
+    // to read.
+    let meta = meta_path(&path);
+    let is_character =
+        sprite == "player.png" || sprite.starts_with("enemies/") || sprite.starts_with("npcs/");
+    if !is_character {
+        if asset_exists(&meta) {
+            if let Err(err) = deserialize::<serde_json::Value, _>(&meta) {
+                problems.push(format!("{meta}: {err}"));
+            }
+        }
+        return;
+    }
+    if !asset_exists(&meta) {
+        problems.push(format!("{meta} is missing"));
+        return;
+    }
+
+    let serializable: SpriteSerializable = match deserialize(&meta) {
+        Ok(serializable) => serializable,
+        Err(err) => {
+            problems.push(format!("{meta}: {err}"));
+            return;
+        }
+    };
+    let spans: [(&str, Option<&SpriteFrameSpan>); 9] = [
+        ("up", Some(&serializable.up)),
+        ("down", Some(&serializable.down)),
+        ("side", Some(&serializable.side)),
+        ("attack", serializable.attack.as_ref()),
+        ("swim", serializable.swim.as_ref()),
+        ("hit", serializable.hit.as_ref()),
+        ("death", serializable.death.as_ref()),
+        ("disguise", serializable.disguise.as_ref()),
+        ("reveal", serializable.reveal.as_ref()),
+    ];
+    for (span_name, span) in spans {
+        let Some(span) = span else {
+            continue;
+        };
+        if span.start_frame + span.number_of_frames > serializable.frames.len() {
+            problems.push(format!(
+                "{meta}: the {span_name} animation runs past the last of its {} frames",
+                serializable.frames.len()
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_root::tests::use_root;
+
+    #[test]
+    fn shipped_assets_are_valid() {
+        assert_eq!(validate_assets(), Vec::<String>::new());
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "embedded-assets",
+        ignore = "the built-in manifest shadows the fixture's folders"
+    )]
+    fn broken_assets_list_each_problem() {
+        use_root("tests/fixtures/broken_assets");
+        let problems = validate_assets();
+
+        let expected = [
+            "Level broken: Anchor { name: \"lost\" } at row 5, column 0 is outside the level",
+            "Level broken: a portal leads to anchor dock in harbor, which has no such anchor",
+            "Level broken: a portal leads to level nowhere, which is missing",
+            "Level broken: an object at row 0, column 1 uses assets/art/sprites/npcs/stranger.png, which is missing",
+            "Level broken: the background layer isn't 2 by 2",
+            "Level broken: the object layer uses tile 5 of tiny at row 0, column 0, which only has 2",
+            "Level broken: the object layer uses tileset gone, which can't be read",
+            "assets/art/sprites/npcs/liar.png.meta.json: the side animation runs past the last of its 2 frames",
+            "assets/art/tiles/fake.png is not a PNG",
+            "assets/art/tiles/gone.png is missing",
+        ];
+        assert_eq!(problems, expected);
+    }
+
+    #[test]
+    fn each_tileset_is_read_once() {
+        use_root("tests/fixtures/broken_assets");
+        let mut counts = HashMap::new();
+        let mut problems = Vec::new();
+
+        assert_eq!(tile_count("tiny", &mut counts, &mut problems), Some(2));
+        assert_eq!(tile_count("gone", &mut counts, &mut problems), None);
+        assert_eq!(tile_count("gone", &mut counts, &mut problems), None);
+        assert_eq!(problems, ["assets/art/tiles/gone.png is missing"]);
+    }
+}
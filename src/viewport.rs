@@ -0,0 +1,69 @@
+use macroquad::{
+    input::mouse_position,
+    math::{vec2, Rect, Vec2},
+    window::{screen_height, screen_width},
+};
+
+use crate::{VIRTUAL_H, VIRTUAL_W};
+
+/// Where the virtual screen lands in the window. It's centered with black
+/// bars on the sides that don't fit.
+pub struct Viewport {
+    /// Scale by any amount instead of whole multiples. Fills more of the
+    /// window, but pixels come out uneven.
+    stretch: bool,
+    dest: Rect,
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        let mut viewport = Self {
+            stretch: false,
+            dest: Rect::new(0.0, 0.0, VIRTUAL_W, VIRTUAL_H),
+        };
+        viewport.update();
+        return viewport;
+    }
+
+    pub fn toggle_stretch(&mut self) {
+        self.stretch = !self.stretch;
+        self.update();
+    }
+
+    /// Fits the virtual screen to the window's current size. Call once a
+    /// frame before reading input.
+    pub fn update(&mut self) {
+        let fit = (screen_width() / VIRTUAL_W).min(screen_height() / VIRTUAL_H);
+        let scale = match self.stretch {
+            true => fit,
+            false => fit.floor().max(1.0),
+        };
+
+        let (w, h) = (VIRTUAL_W * scale, VIRTUAL_H * scale);
+        self.dest = Rect::new(
+            ((screen_width() - w) / 2.0).round(),
+            ((screen_height() - h) / 2.0).round(),
+            w.round(),
+            h.round(),
+        );
+    }
+
+    /// The window area the virtual screen is drawn to.
+    pub fn dest(&self) -> Rect {
+        self.dest
+    }
+
+    /// Maps a point in window pixels to virtual-screen pixels. Points over
+    /// the bars land outside `0..VIRTUAL_W` and `0..VIRTUAL_H`.
+    pub fn to_virtual(&self, screen: Vec2) -> Vec2 {
+        return vec2(
+            (screen.x - self.dest.x) / self.dest.w * VIRTUAL_W,
+            (screen.y - self.dest.y) / self.dest.h * VIRTUAL_H,
+        );
+    }
+
+    /// The mouse in virtual-screen pixels.
+    pub fn mouse(&self) -> Vec2 {
+        return self.to_virtual(mouse_position().into());
+    }
+}
@@ -0,0 +1,179 @@
+use macroquad::{
+    color::Color,
+    math::{vec2, Vec2},
+    rand::gen_range,
+    shapes::{draw_line, draw_rectangle},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    particles::{EmitterDesc, ParticleLayer, ParticleSystem},
+    world::World,
+    VIRTUAL_H, VIRTUAL_W,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    HeavyRain,
+}
+
+impl WeatherKind {
+    pub const ALL: [WeatherKind; 3] = [
+        WeatherKind::Clear,
+        WeatherKind::Rain,
+        WeatherKind::HeavyRain,
+    ];
+
+    /// How hard it rains, from 0 for dry to 1 for a downpour.
+    fn intensity(&self) -> f32 {
+        match self {
+            WeatherKind::Clear => 0.0,
+            WeatherKind::Rain => 0.4,
+            WeatherKind::HeavyRain => 1.0,
+        }
+    }
+}
+
+/// A falling raindrop, in screen space.
+struct Streak {
+    position: Vec2,
+    speed: f32,
+}
+
+/// Rain streaking down the screen, splashing on the ground, darkening the
+/// scene and blowing other particles sideways. Changing the weather eases
+/// between the two over `FADE_SECONDS`.
+pub struct Weather {
+    kind: WeatherKind,
+    /// Intensity when the current change started.
+    from: f32,
+    fade: f32,
+    streaks: Vec<Streak>,
+    /// Splashes due but not yet made, carried between frames.
+    owed_splashes: f32,
+}
+
+impl Weather {
+    const FADE_SECONDS: f32 = 3.0;
+    /// Streaks on screen in a downpour; lighter rain shows fewer of them.
+    const MAX_STREAKS: usize = 240;
+    const STREAK_LENGTH: f32 = 6.0;
+    const SPLASHES_PER_SECOND: f32 = 40.0;
+    /// Sideways push at full intensity, in pixels per second squared.
+    const WIND: f32 = -40.0;
+    /// Sideways drift of the streaks per pixel fallen, at full intensity.
+    const SLANT: f32 = -0.25;
+    /// Darkness laid over the scene at full intensity.
+    const GLOOM: f32 = 0.25;
+
+    pub fn new(kind: WeatherKind) -> Self {
+        let streaks = (0..Self::MAX_STREAKS)
+            .map(|_| Streak {
+                position: vec2(gen_range(0.0, VIRTUAL_W), gen_range(0.0, VIRTUAL_H)),
+                speed: gen_range(220.0, 320.0),
+            })
+            .collect();
+
+        Self {
+            kind,
+            from: kind.intensity(),
+            fade: Self::FADE_SECONDS,
+            streaks,
+            owed_splashes: 0.0,
+        }
+    }
+
+    /// Starts easing into `kind`, from however hard it is raining now.
+    pub fn set(&mut self, kind: WeatherKind) {
+        if kind == self.kind {
+            return;
+        }
+        self.from = self.intensity();
+        self.kind = kind;
+        self.fade = 0.0;
+    }
+
+    fn intensity(&self) -> f32 {
+        let t = (self.fade / Self::FADE_SECONDS).min(1.0);
+        return self.from + (self.kind.intensity() - self.from) * t;
+    }
+
+    /// Moves the rain along, splashes some drops on the ground in view and
+    /// sets the wind for `particles`.
+    pub fn update(&mut self, particles: &mut ParticleSystem, world: &World, dt: f32) {
+        self.fade += dt;
+        let intensity = self.intensity();
+        particles.set_wind(Self::WIND * intensity);
+
+        for streak in self.streaks.iter_mut() {
+            let fall = streak.speed * dt;
+            streak.position += vec2(Self::SLANT * intensity * fall, fall);
+            if streak.position.y > VIRTUAL_H {
+                streak.position = vec2(gen_range(0.0, VIRTUAL_W), -Self::STREAK_LENGTH);
+            }
+            streak.position.x = streak.position.x.rem_euclid(VIRTUAL_W);
+        }
+
+        self.owed_splashes += Self::SPLASHES_PER_SECOND * intensity * dt;
+        while self.owed_splashes >= 1.0 {
+            self.owed_splashes -= 1.0;
+            let position = vec2(
+                world.x + gen_range(0.0, world.w),
+                world.y + gen_range(0.0, world.h),
+            );
+            particles.burst(&Self::splash(), position);
+        }
+    }
+
+    fn splash() -> EmitterDesc {
+        EmitterDesc {
+            burst: 3,
+            lifetime: (0.15, 0.3),
+            speed: (15.0, 30.0),
+            direction: -std::f32::consts::FRAC_PI_2,
+            spread: 2.0,
+            gravity: 200.0,
+            start_color: Color::new(0.75, 0.85, 1.0, 0.8),
+            end_color: Color::new(0.75, 0.85, 1.0, 0.0),
+            start_size: 1.0,
+            end_size: 1.0,
+            sprite: None,
+            layer: ParticleLayer::World,
+        }
+    }
+
+    /// Darkens the scene and draws the streaks. Call after the overlay
+    /// layer and before the HUD and editor.
+    pub fn render(&self) {
+        let intensity = self.intensity();
+        if intensity <= 0.0 {
+            return;
+        }
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            VIRTUAL_W,
+            VIRTUAL_H,
+            Color::new(0.05, 0.05, 0.15, Self::GLOOM * intensity),
+        );
+
+        let color = Color::new(0.7, 0.8, 1.0, 0.5);
+        let shown = (Self::MAX_STREAKS as f32 * intensity) as usize;
+        let tail = vec2(-Self::SLANT * intensity, -1.0) * Self::STREAK_LENGTH;
+        for streak in self.streaks.iter().take(shown) {
+            let end = streak.position + tail;
+            draw_line(
+                streak.position.x,
+                streak.position.y,
+                end.x,
+                end.y,
+                0.5,
+                color,
+            );
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use crate::{SUB_PIX_LEVEL, VIRTUAL_H, VIRTUAL_W};
+use macroquad::math::{Rect, Vec2};
 
-#[derive(Debug)]
+use crate::{VIRTUAL_H, VIRTUAL_W};
+
+#[derive(Debug, Clone)]
 pub struct World {
     pub x: f32,
     pub y: f32,
@@ -18,8 +20,26 @@ impl World {
         }
     }
 
-    pub fn rounded(&self) -> Self {
-        let sub = 1.0 / SUB_PIX_LEVEL;
+    pub fn center_on(&mut self, point: Vec2) {
+        self.x = point.x - self.w / 2.0;
+        self.y = point.y - self.h / 2.0;
+    }
+
+    /// The view grown by `margin_x` on the left and right and `margin_y`
+    /// on the top and bottom.
+    pub fn view(&self, margin_x: f32, margin_y: f32) -> Rect {
+        return Rect::new(
+            self.x - margin_x,
+            self.y - margin_y,
+            self.w + margin_x * 2.0,
+            self.h + margin_y * 2.0,
+        );
+    }
+
+    /// The view snapped to `sub_pixels` steps per pixel, to match the
+    /// render target's pixels.
+    pub fn rounded(&self, sub_pixels: f32) -> Self {
+        let sub = 1.0 / sub_pixels;
         Self {
             x: (self.x / sub).round() * sub,
             y: (self.y / sub).round() * sub,
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::levels::Level;
+
+/// What became of a listing's object since its level was loaded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ObjectState {
+    /// A chest or door opened, a plate latched, or a lever left flipped.
+    Opened,
+    /// An enemy killed, an item collected, or a prop broken.
+    Cleared,
+}
+
+/// The object states of every level visited this session, by level name, so
+/// a level left through a portal is as it was when the player comes back.
+/// A fresh game starts with none.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WorldState {
+    levels: BTreeMap<String, BTreeMap<u64, ObjectState>>,
+    /// Which cells of each level have been seen, for the map's fog.
+    #[serde(default)]
+    explored: BTreeMap<String, Vec<u64>>,
+}
+
+impl WorldState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `level`'s object states and explored cells, replacing any
+    /// recorded before.
+    pub fn store(&mut self, level: &Level) {
+        self.levels
+            .insert(level.name().to_owned(), level.object_states());
+        self.explored
+            .insert(level.name().to_owned(), level.explored().to_vec());
+    }
+
+    /// Puts back what was recorded for `level`, if it was visited before.
+    pub fn restore(&self, level: &mut Level) {
+        if let Some(states) = self.levels.get(level.name()) {
+            level.restore_object_states(states);
+        }
+        if let Some(explored) = self.explored.get(level.name()) {
+            level.set_explored(explored.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+
+    use super::*;
+    use crate::{
+        levels::tests::{named_level, place},
+        object::{
+            tests::{block_on, object_assets},
+            Interaction, LevelObjects, ObjectType,
+        },
+        player::{tests::player_at, Player},
+        world::World,
+    };
+
+    /// The harbor as freshly loaded, with a chest just below the top-left
+    /// corner.
+    fn fresh_harbor() -> Level {
+        let mut harbor = named_level("harbor", &["....", "....", "...."]);
+        place(&mut harbor, 1, 1, ObjectType::Chest { contents: vec![] });
+        return harbor;
+    }
+
+    /// A player right above the harbor's chest, facing it.
+    fn player() -> Player {
+        return player_at(vec2(24.0, 8.0));
+    }
+
+    /// Spawns `level`'s objects and says whether its chest can still be
+    /// opened.
+    fn chest_is_closed(level: &mut Level) -> bool {
+        let mut objects = LevelObjects::new(object_assets());
+        block_on(level.spawn_objects(&World::new(), &mut objects));
+        assert_eq!(objects.len(), 1);
+        return objects.targeted(&player()).is_some();
+    }
+
+    /// The harbor after its chest was opened.
+    fn looted_harbor() -> Level {
+        let mut harbor = fresh_harbor();
+        let mut objects = LevelObjects::new(object_assets());
+        block_on(harbor.spawn_objects(&World::new(), &mut objects));
+        match objects.interact(&mut player()) {
+            Some((Interaction::Looted, Some(listing_id))) => harbor.mark_opened(listing_id),
+            _ => panic!("the chest didn't open"),
+        }
+        return harbor;
+    }
+
+    #[test]
+    fn opened_chests_stay_open_after_leaving_and_coming_back() {
+        let mut world_state = WorldState::new();
+        let harbor = looted_harbor();
+
+        // Out through a portal to the cave, and back, as `warp_to` does.
+        world_state.store(&harbor);
+        let mut cave = named_level("cave", &["...."]);
+        world_state.restore(&mut cave);
+        world_state.store(&cave);
+        let mut harbor = fresh_harbor();
+        world_state.restore(&mut harbor);
+
+        assert!(!chest_is_closed(&mut harbor));
+    }
+
+    #[test]
+    fn world_state_survives_the_save_file() {
+        let mut world_state = WorldState::new();
+        let mut harbor = looted_harbor();
+        harbor.explore(&World::new().view(0.0, 0.0));
+        world_state.store(&harbor);
+
+        let saved = serde_json::to_string(&world_state).unwrap();
+        let read: WorldState = serde_json::from_str(&saved).unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), saved);
+
+        let mut harbor = fresh_harbor();
+        read.restore(&mut harbor);
+        assert!(!chest_is_closed(&mut harbor));
+        assert!(!harbor.explored().is_empty());
+    }
+
+    #[test]
+    fn fresh_games_start_with_every_chest_closed() {
+        let mut world_state = WorldState::new();
+        world_state.store(&looted_harbor());
+
+        // A new game starts from a new world state, not the last one.
+        let world_state = WorldState::new();
+        let mut harbor = fresh_harbor();
+        world_state.restore(&mut harbor);
+        assert!(harbor.object_states().is_empty());
+        assert!(chest_is_closed(&mut harbor));
+    }
+}